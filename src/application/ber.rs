@@ -0,0 +1,310 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, row, Table};
+use crate::util::*;
+
+pub const CLASS_UNIVERSAL: u8 = 0;
+pub const CLASS_APPLICATION: u8 = 1;
+pub const CLASS_CONTEXT: u8 = 2;
+
+pub type Tag = u32;
+
+pub const BOOLEAN: Tag = 1;
+pub const INTEGER: Tag = 2;
+pub const BIT_STRING: Tag = 3;
+pub const OCTET_STRING: Tag = 4;
+pub const NULL: Tag = 5;
+pub const OBJECT_IDENTIFIER: Tag = 6;
+pub const REAL: Tag = 9;
+pub const ENUMERATED: Tag = 10;
+pub const UTF8_STRING: Tag = 12;
+pub const SEQUENCE: Tag = 16;
+pub const SET: Tag = 17;
+pub const NUMERIC_STRING: Tag = 18;
+pub const PRINTABLE_STRING: Tag = 19;
+pub const T61_STRING: Tag = 20;
+pub const IA5_STRING: Tag = 22;
+pub const UTC_TIME: Tag = 23;
+pub const GENERALIZED_TIME: Tag = 24;
+pub const VISIBLE_STRING: Tag = 26;
+pub const BMP_STRING: Tag = 30;
+
+/// A single ASN.1 BER encoded element: its tag and the bytes of its value.
+#[derive(Debug, Clone)]
+pub struct Element<'a> {
+    class: u8,
+    constructed: bool,
+    tag: u32,
+    value: &'a [u8],
+}
+
+impl<'a> Element<'a> {
+    /// Decodes the element at the start of the data, returning it and the data after it.
+    pub fn parse(data: &'a [u8]) -> Option<(Self, &'a [u8])> {
+        let (&identifier, mut rest) = data.split_first()?;
+        let mut tag = (identifier & 0x1f) as u32;
+        if tag == 0x1f {
+            // High tag numbers continue in base 128 while the top bit is set
+            tag = 0;
+            loop {
+                let (&byte, next) = rest.split_first()?;
+                rest = next;
+                tag = tag.checked_mul(128)? | (byte & 0x7f) as u32;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+        }
+
+        let (&first, next) = rest.split_first()?;
+        rest = next;
+        let length = if first & 0x80 == 0 {
+            first as usize
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4 || rest.len() < count {
+                return None;
+            }
+            let length = rest[..count].iter().fold(0, |length, byte| length << 8 | *byte as usize);
+            rest = &rest[count..];
+            length
+        };
+        if rest.len() < length {
+            return None;
+        }
+
+        Some((Self {
+            class: identifier >> 6,
+            constructed: identifier & 0x20 != 0,
+            tag,
+            value: &rest[..length],
+        }, &rest[length..]))
+    }
+
+    /// Decodes all consecutive elements in the data, stopping at the first malformed one.
+    pub fn parse_all(data: &'a [u8]) -> Vec<Self> {
+        let mut elements = Vec::new();
+        let mut rest = data;
+        while let Some((element, next)) = Self::parse(rest) {
+            elements.push(element);
+            rest = next;
+        }
+        elements
+    }
+
+    /// The elements inside a constructed element.
+    pub fn children(&self) -> Vec<Self> {
+        if self.constructed {
+            Self::parse_all(self.value)
+        } else {
+            Vec::new()
+        }
+    }
+
+    #[inline]
+    pub fn class(&self) -> u8 {
+        self.class
+    }
+
+    #[inline]
+    pub fn constructed(&self) -> bool {
+        self.constructed
+    }
+
+    #[inline]
+    pub fn tag(&self) -> u32 {
+        self.tag
+    }
+
+    #[inline]
+    pub fn value(&self) -> &'a [u8] {
+        self.value
+    }
+
+    /// Whether this is the universal element with the given tag.
+    #[inline]
+    pub fn is_universal(&self, tag: Tag) -> bool {
+        self.class == CLASS_UNIVERSAL && self.tag == tag
+    }
+
+    /// Whether this is the context specific element with the given tag.
+    #[inline]
+    pub fn is_context(&self, tag: u32) -> bool {
+        self.class == CLASS_CONTEXT && self.tag == tag
+    }
+
+    /// The value as a two's complement integer.
+    pub fn integer(&self) -> Option<i64> {
+        if self.value.is_empty() || self.value.len() > 8 {
+            return None;
+        }
+        let sign = if self.value[0] & 0x80 != 0 { -1_i64 } else { 0 };
+        Some(self.value.iter().fold(sign, |value, byte| value << 8 | *byte as i64))
+    }
+
+    /// The value as an unsigned integer.
+    pub fn unsigned(&self) -> Option<u64> {
+        let value = match self.value {
+            // A leading zero keeps the sign bit clear
+            [0, rest @ ..] => rest,
+            value => value
+        };
+        if value.len() > 8 {
+            return None;
+        }
+        Some(value.iter().fold(0, |value, byte| value << 8 | *byte as u64))
+    }
+
+    /// The value as text, replacing invalid UTF-8.
+    pub fn string(&self) -> String {
+        String::from_utf8_lossy(self.value).into_owned()
+    }
+
+    /// The value as a dotted object identifier.
+    pub fn oid(&self) -> Option<String> {
+        let mut arcs = Vec::new();
+        let mut arc: u64 = 0;
+        for byte in self.value {
+            arc = arc.checked_mul(128)? | (byte & 0x7f) as u64;
+            if byte & 0x80 == 0 {
+                arcs.push(arc);
+                arc = 0;
+            }
+        }
+        if arc != 0 || arcs.is_empty() {
+            return None;
+        }
+        // The first subidentifier packs the first two arcs, and may take several bytes when the
+        // first arc is 2
+        let first = (arcs[0] / 40).min(2);
+        arcs.splice(0..1, [first, arcs[0] - first * 40]);
+        let arcs: Vec<String> = arcs.iter().map(|arc| arc.to_string()).collect();
+        Some(arcs.join("."))
+    }
+
+    /// The value as an IEEE 754 float, as used for floating point MMS data.
+    pub fn float(&self) -> Option<f64> {
+        // The first byte holds the exponent width
+        match self.value {
+            [8, rest @ ..] if rest.len() == 4 => Some(f32::from_bits(tou32(rest)) as f64),
+            [11, rest @ ..] if rest.len() == 8 => Some(f64::from_bits(tou64(rest))),
+            _ => None
+        }
+    }
+
+    /// The name of the tag: the type for universal tags and the class and number otherwise.
+    pub fn tag_name(&self) -> String {
+        match (self.class, self.tag) {
+            (CLASS_UNIVERSAL, BOOLEAN) => String::from("boolean"),
+            (CLASS_UNIVERSAL, INTEGER) => String::from("integer"),
+            (CLASS_UNIVERSAL, BIT_STRING) => String::from("bit_string"),
+            (CLASS_UNIVERSAL, OCTET_STRING) => String::from("octet_string"),
+            (CLASS_UNIVERSAL, NULL) => String::from("null"),
+            (CLASS_UNIVERSAL, OBJECT_IDENTIFIER) => String::from("object_identifier"),
+            (CLASS_UNIVERSAL, REAL) => String::from("real"),
+            (CLASS_UNIVERSAL, ENUMERATED) => String::from("enumerated"),
+            (CLASS_UNIVERSAL, UTF8_STRING) => String::from("utf8_string"),
+            (CLASS_UNIVERSAL, SEQUENCE) => String::from("sequence"),
+            (CLASS_UNIVERSAL, SET) => String::from("set"),
+            (CLASS_UNIVERSAL, NUMERIC_STRING) => String::from("numeric_string"),
+            (CLASS_UNIVERSAL, PRINTABLE_STRING) => String::from("printable_string"),
+            (CLASS_UNIVERSAL, T61_STRING) => String::from("t61_string"),
+            (CLASS_UNIVERSAL, IA5_STRING) => String::from("ia5_string"),
+            (CLASS_UNIVERSAL, UTC_TIME) => String::from("utc_time"),
+            (CLASS_UNIVERSAL, GENERALIZED_TIME) => String::from("generalized_time"),
+            (CLASS_UNIVERSAL, VISIBLE_STRING) => String::from("visible_string"),
+            (CLASS_UNIVERSAL, BMP_STRING) => String::from("bmp_string"),
+            (CLASS_UNIVERSAL, tag) => format!("[universal {}]", tag),
+            (CLASS_APPLICATION, tag) => format!("[application {}]", tag),
+            (CLASS_CONTEXT, tag) => format!("[context {}]", tag),
+            (_, tag) => format!("[private {}]", tag)
+        }
+    }
+
+    /// The value as its universal type reads, as text when an octet string holds some and as
+    /// hex otherwise.
+    pub fn describe(&self) -> String {
+        let text = || {
+            (!self.value.is_empty() && self.value.iter().all(|byte| byte.is_ascii_graphic() || *byte == b' '))
+                .then(|| self.string())
+        };
+        let value = match (self.class, self.tag) {
+            (CLASS_UNIVERSAL, BOOLEAN) => self.value.first().map(|value| (*value != 0).to_string()),
+            (CLASS_UNIVERSAL, INTEGER | ENUMERATED) => self.integer().map(|value| value.to_string()),
+            (CLASS_UNIVERSAL, NULL) => Some(String::new()),
+            (CLASS_UNIVERSAL, OBJECT_IDENTIFIER) => self.oid(),
+            (CLASS_UNIVERSAL, UTF8_STRING | NUMERIC_STRING | PRINTABLE_STRING | T61_STRING | IA5_STRING | UTC_TIME
+                | GENERALIZED_TIME | VISIBLE_STRING) => Some(self.string()),
+            (CLASS_UNIVERSAL, BMP_STRING) => {
+                let units: Vec<u16> = self.value.chunks_exact(2).map(tou16).collect();
+                Some(String::from_utf16_lossy(&units))
+            }
+            // The first byte counts the unused bits at the end
+            (CLASS_UNIVERSAL, BIT_STRING) => self.value.split_first()
+                .map(|(unused, bits)| format!("{} ({} unused bits)", hex(bits), unused)),
+            _ => text()
+        };
+        value.unwrap_or_else(|| hex(self.value))
+    }
+
+    fn add_rows(&self, table: &mut Table, indent: usize) {
+        let name = format!("{}{}", " ".repeat(indent * 2), self.tag_name());
+        if self.constructed {
+            table.add_row(row![name, format!("{} bytes", self.value.len())]);
+            for child in self.children() {
+                child.add_rows(table, indent + 1);
+            }
+        } else {
+            table.add_row(row![name, self.describe()]);
+        }
+    }
+}
+
+/// The element as a tree, one row per element with the children indented below their parent.
+impl Display for Element<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        self.add_rows(&mut table, 0);
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(data: &[u8]) -> Element<'_> {
+        let (element, rest) = Element::parse(data).unwrap();
+        assert!(rest.is_empty());
+        element
+    }
+
+    #[test]
+    fn parses_x690_encodings() {
+        // Long form length, and a high tag number in the application class
+        let data = [&[0x5f, 0x81, 0x00, 0x81, 0x80][..], &[0; 128]].concat();
+        let parsed = element(&data);
+        assert_eq!((parsed.class, parsed.constructed, parsed.tag), (CLASS_APPLICATION, false, 128));
+        assert_eq!(parsed.value.len(), 128);
+        assert!(Element::parse(&[0x04, 0x82, 0x01]).is_none());
+        assert!(Element::parse(&[0x04, 0x05, 0x00]).is_none());
+
+        assert_eq!(element(&[0x02, 0x01, 0x7f]).integer(), Some(127));
+        assert_eq!(element(&[0x02, 0x02, 0x00, 0x80]).integer(), Some(128));
+        assert_eq!(element(&[0x02, 0x01, 0x80]).integer(), Some(-128));
+        assert_eq!(element(&[0x02, 0x02, 0xff, 0x7f]).integer(), Some(-129));
+    }
+
+    #[test]
+    fn decodes_object_identifiers() {
+        assert_eq!(element(&[0x06, 0x06, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d]).oid().as_deref(), Some("1.2.840.113549"));
+        assert_eq!(element(&[0x06, 0x03, 0x55, 0x04, 0x03]).oid().as_deref(), Some("2.5.4.3"));
+        // The example of X.690 section 8.19.5, whose first subidentifier takes two bytes
+        assert_eq!(element(&[0x06, 0x03, 0x88, 0x37, 0x03]).oid().as_deref(), Some("2.999.3"));
+        assert_eq!(element(&[0x06, 0x02, 0x2b, 0x86]).oid(), None);
+    }
+}
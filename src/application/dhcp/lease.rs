@@ -0,0 +1,214 @@
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use std::time::Duration;
+use prettytable::{format, row, Table};
+use crate::application::dhcp::{self, DhcpMessage};
+use crate::application::dhcp::v6::{self, Dhcpv6Message};
+
+/// The part a message plays in getting an address, the same for both versions: a solicit is a
+/// discover, an advertise an offer, a renew or rebind a request and a reply an ack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Discover,
+    Offer,
+    Request,
+    Ack,
+    Nak,
+}
+
+/// One exchange of a client for an address, from the discover to the ack when all were seen.
+#[derive(Debug)]
+pub struct Lease {
+    client: String,
+    version: u8,
+    /// The transaction of the latest message, which DHCPv6 changes between advertise and request
+    transaction_id: u32,
+    address: Option<String>,
+    server: Option<String>,
+    lease_time: Option<u32>,
+    discovered: Option<Duration>,
+    offered: Option<Duration>,
+    requested: Option<Duration>,
+    /// When the server acknowledged the request, or refused it
+    answered: Option<(Duration, bool)>,
+}
+
+/// The address assignments seen in the capture, per client and transaction.
+#[derive(Debug, Default)]
+pub struct Leases {
+    leases: Vec<Lease>,
+}
+
+fn milliseconds(from: Option<Duration>, to: Option<Duration>) -> String {
+    match (from, to) {
+        (Some(from), Some(to)) => format!("{:.3} ms", to.saturating_sub(from).as_secs_f64() * 1000.0),
+        _ => String::new()
+    }
+}
+
+impl Lease {
+    pub fn state(&self) -> &'static str {
+        match (self.answered, self.requested, self.offered) {
+            (Some((_, true)), _, _) => "bound",
+            (Some((_, false)), _, _) => "refused",
+            (None, Some(_), _) => "requested",
+            (None, None, Some(_)) => "offered",
+            _ => "discovering"
+        }
+    }
+
+    fn started(&self) -> Option<Duration> {
+        self.discovered
+            .or(self.offered)
+            .or(self.requested)
+            .or(self.answered.map(|(answered, _)| answered))
+    }
+
+    fn step(&mut self, step: Step, timestamp: Duration) {
+        match step {
+            Step::Discover => self.discovered = Some(timestamp),
+            Step::Offer => self.offered = Some(timestamp),
+            Step::Request => self.requested = Some(timestamp),
+            Step::Ack => self.answered = Some((timestamp, true)),
+            Step::Nak => self.answered = Some((timestamp, false)),
+        }
+    }
+}
+
+impl Leases {
+    /// Records a DHCPv4 message sent from the source address.
+    pub fn update_v4(&mut self, message: &DhcpMessage, source: Option<IpAddr>, timestamp: Duration) {
+        let step = match message.message_type() {
+            Some(dhcp::DISCOVER) => Step::Discover,
+            Some(dhcp::OFFER) => Step::Offer,
+            Some(dhcp::REQUEST) => Step::Request,
+            // An ack to an inform gives no address
+            Some(dhcp::ACK) if !message.yiaddr().is_unspecified() => Step::Ack,
+            Some(dhcp::NAK) => Step::Nak,
+            _ => return
+        };
+        let lease = self.lease(message.client(), 4, message.xid(), step);
+        lease.step(step, timestamp);
+        match step {
+            Step::Offer | Step::Ack => lease.address = Some(message.yiaddr().to_string()),
+            Step::Request if lease.address.is_none() => {
+                let address = message.requested_address().unwrap_or(message.ciaddr());
+                if !address.is_unspecified() {
+                    lease.address = Some(address.to_string());
+                }
+            }
+            _ => ()
+        }
+        if step != Step::Discover {
+            let server = message.server_identifier().map(IpAddr::V4);
+            // The client only names the server it picked, the server is the source of what it sends
+            let server = match step {
+                Step::Request => server,
+                _ => server.or(source)
+            };
+            if let Some(server) = server {
+                lease.server = Some(server.to_string());
+            }
+        }
+        if let Some(lease_time) = message.lease_time() {
+            lease.lease_time = Some(lease_time);
+        }
+    }
+
+    /// Records a DHCPv6 message, already taken out of the relay messages around it.
+    pub fn update_v6(&mut self, message: &Dhcpv6Message, timestamp: Duration) {
+        let Some(client_id) = message.client_id() else {
+            return;
+        };
+        let leases = message.leases();
+        let step = match message.msg_type() {
+            v6::SOLICIT => Step::Discover,
+            v6::ADVERTISE => Step::Offer,
+            v6::REQUEST | v6::RENEW | v6::REBIND => Step::Request,
+            v6::REPLY if message.status() != 0 => Step::Nak,
+            v6::REPLY if !leases.is_empty() => Step::Ack,
+            _ => return
+        };
+        let client = match v6::duid_link_address(client_id) {
+            Some(address) => address.to_string(),
+            None => v6::duid_name(client_id)
+        };
+        let lease = self.lease(client, 6, message.transaction_id(), step);
+        lease.step(step, timestamp);
+        if !leases.is_empty() {
+            let addresses: Vec<String> = leases.iter().map(|(address, _)| address.clone()).collect();
+            lease.address = Some(addresses.join(", "));
+            if step != Step::Request {
+                lease.lease_time = leases.iter().map(|(_, valid)| *valid).min();
+            }
+        }
+        if let Some(server_id) = message.server_id() {
+            lease.server = Some(v6::duid_name(server_id));
+        }
+    }
+
+    /// The exchange a message belongs to: the one of its transaction or, for a request, the one
+    /// the client was offered an address in. A new exchange otherwise.
+    fn lease(&mut self, client: String, version: u8, transaction_id: u32, step: Step) -> &mut Lease {
+        let same_client = |lease: &Lease| lease.client == client && lease.version == version;
+        let position = self.leases.iter().rposition(|lease| same_client(lease) && lease.transaction_id == transaction_id)
+            .or_else(|| match step {
+                Step::Request => self.leases.iter().rposition(|lease| {
+                    same_client(lease) && lease.offered.is_some() && lease.requested.is_none() && lease.answered.is_none()
+                }),
+                _ => None
+            });
+        let position = position.unwrap_or_else(|| {
+            self.leases.push(Lease {
+                client: client.clone(),
+                version,
+                transaction_id,
+                address: None,
+                server: None,
+                lease_time: None,
+                discovered: None,
+                offered: None,
+                requested: None,
+                answered: None,
+            });
+            self.leases.len() - 1
+        });
+        let lease = &mut self.leases[position];
+        lease.transaction_id = transaction_id;
+        lease
+    }
+}
+
+impl Display for Leases {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.set_titles(row![
+            "client", "version", "transaction", "address", "server", "lease_time", "state", "started",
+            "offer_delay", "ack_delay", "total"
+        ]);
+        for lease in self.leases.iter() {
+            let answered = lease.answered.map(|(answered, _)| answered);
+            table.add_row(row![
+                lease.client,
+                lease.version,
+                format!("0x{:x}", lease.transaction_id),
+                lease.address.as_deref().unwrap_or(""),
+                lease.server.as_deref().unwrap_or(""),
+                match lease.lease_time {
+                    Some(u32::MAX) => String::from("infinite"),
+                    Some(lease_time) => format!("{} s", lease_time),
+                    None => String::new()
+                },
+                lease.state(),
+                lease.started().map(|started| format!("{:.3}", started.as_secs_f64())).unwrap_or_default(),
+                milliseconds(lease.discovered, lease.offered),
+                milliseconds(lease.requested, answered),
+                milliseconds(lease.started(), answered)
+            ]);
+        }
+
+        table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
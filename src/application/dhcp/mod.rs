@@ -0,0 +1,374 @@
+pub mod v6;
+pub mod lease;
+
+use std::fmt::{Display, Formatter};
+use std::net::Ipv4Addr;
+use pnet::util::MacAddr;
+use prettytable::{format, row, table};
+use crate::util::*;
+
+pub const SERVER_PORT: u16 = 67;
+pub const CLIENT_PORT: u16 = 68;
+
+pub const MAGIC_COOKIE: u32 = 0x63825363;
+
+/// The BOOTP header before the magic cookie and the options.
+pub const HEADER_LENGTH: usize = 236;
+
+pub type MessageType = u8;
+
+pub const DISCOVER: MessageType = 1;
+pub const OFFER: MessageType = 2;
+pub const REQUEST: MessageType = 3;
+pub const DECLINE: MessageType = 4;
+pub const ACK: MessageType = 5;
+pub const NAK: MessageType = 6;
+pub const RELEASE: MessageType = 7;
+pub const INFORM: MessageType = 8;
+
+pub type OptionCode = u8;
+
+pub const OPTION_PAD: OptionCode = 0;
+pub const OPTION_SUBNET_MASK: OptionCode = 1;
+pub const OPTION_ROUTER: OptionCode = 3;
+pub const OPTION_DNS_SERVERS: OptionCode = 6;
+pub const OPTION_HOST_NAME: OptionCode = 12;
+pub const OPTION_DOMAIN_NAME: OptionCode = 15;
+pub const OPTION_BROADCAST_ADDRESS: OptionCode = 28;
+pub const OPTION_NTP_SERVERS: OptionCode = 42;
+pub const OPTION_REQUESTED_ADDRESS: OptionCode = 50;
+pub const OPTION_LEASE_TIME: OptionCode = 51;
+pub const OPTION_OVERLOAD: OptionCode = 52;
+pub const OPTION_MESSAGE_TYPE: OptionCode = 53;
+pub const OPTION_SERVER_IDENTIFIER: OptionCode = 54;
+pub const OPTION_PARAMETER_REQUEST_LIST: OptionCode = 55;
+pub const OPTION_MESSAGE: OptionCode = 56;
+pub const OPTION_MAX_MESSAGE_SIZE: OptionCode = 57;
+pub const OPTION_RENEWAL_TIME: OptionCode = 58;
+pub const OPTION_REBINDING_TIME: OptionCode = 59;
+pub const OPTION_VENDOR_CLASS: OptionCode = 60;
+pub const OPTION_CLIENT_IDENTIFIER: OptionCode = 61;
+pub const OPTION_TFTP_SERVER: OptionCode = 66;
+pub const OPTION_BOOTFILE: OptionCode = 67;
+pub const OPTION_RELAY_AGENT_INFORMATION: OptionCode = 82;
+pub const OPTION_CLASSLESS_STATIC_ROUTE: OptionCode = 121;
+pub const OPTION_END: OptionCode = 255;
+
+/// A DHCPv4 message: the BOOTP header followed by the options.
+#[derive(Debug, Clone)]
+pub struct DhcpMessage {
+    op: u8,
+    htype: u8,
+    hlen: u8,
+    hops: u8,
+    xid: u32,
+    secs: u16,
+    flags: u16,
+    ciaddr: Ipv4Addr,
+    yiaddr: Ipv4Addr,
+    siaddr: Ipv4Addr,
+    giaddr: Ipv4Addr,
+    chaddr: [u8; 16],
+    sname: String,
+    file: String,
+    options: Vec<(OptionCode, Vec<u8>)>,
+}
+
+/// Splits options up to the end option, skipping the padding. Option lengths that run past the
+/// end are cut short.
+fn options(mut data: &[u8], options: &mut Vec<(OptionCode, Vec<u8>)>) {
+    while let Some(&code) = data.first() {
+        match code {
+            OPTION_PAD => data = &data[1..],
+            OPTION_END => break,
+            _ => {
+                let length = data.get(1).copied().unwrap_or_default() as usize;
+                let end = (2 + length).min(data.len());
+                options.push((code, Vec::from(&data[2.min(end)..end])));
+                data = &data[end..];
+            }
+        }
+    }
+}
+
+/// A null terminated field of the header.
+fn text(data: &[u8]) -> String {
+    let end = data.iter().position(|byte| *byte == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
+fn ipv4(data: &[u8]) -> Ipv4Addr {
+    Ipv4Addr::new(data[0], data[1], data[2], data[3])
+}
+
+fn addresses(data: &[u8]) -> String {
+    let addresses: Vec<String> = data.chunks_exact(4).map(|address| ipv4(address).to_string()).collect();
+    addresses.join(", ")
+}
+
+pub fn message_type_name(message_type: MessageType) -> String {
+    match message_type {
+        DISCOVER => String::from("discover"),
+        OFFER => String::from("offer"),
+        REQUEST => String::from("request"),
+        DECLINE => String::from("decline"),
+        ACK => String::from("ack"),
+        NAK => String::from("nak"),
+        RELEASE => String::from("release"),
+        INFORM => String::from("inform"),
+        9 => String::from("forcerenew"),
+        10 => String::from("leasequery"),
+        11 => String::from("leaseunassigned"),
+        12 => String::from("leaseunknown"),
+        13 => String::from("leaseactive"),
+        _ => String::from("unidentified")
+    }
+}
+
+pub fn option_name(code: OptionCode) -> String {
+    match code {
+        OPTION_SUBNET_MASK => String::from("subnet_mask"),
+        2 => String::from("time_offset"),
+        OPTION_ROUTER => String::from("router"),
+        OPTION_DNS_SERVERS => String::from("dns_servers"),
+        OPTION_HOST_NAME => String::from("host_name"),
+        OPTION_DOMAIN_NAME => String::from("domain_name"),
+        26 => String::from("interface_mtu"),
+        OPTION_BROADCAST_ADDRESS => String::from("broadcast_address"),
+        OPTION_NTP_SERVERS => String::from("ntp_servers"),
+        43 => String::from("vendor_specific"),
+        44 => String::from("netbios_name_servers"),
+        OPTION_REQUESTED_ADDRESS => String::from("requested_address"),
+        OPTION_LEASE_TIME => String::from("lease_time"),
+        OPTION_OVERLOAD => String::from("overload"),
+        OPTION_MESSAGE_TYPE => String::from("message_type"),
+        OPTION_SERVER_IDENTIFIER => String::from("server_identifier"),
+        OPTION_PARAMETER_REQUEST_LIST => String::from("parameter_request_list"),
+        OPTION_MESSAGE => String::from("message"),
+        OPTION_MAX_MESSAGE_SIZE => String::from("max_message_size"),
+        OPTION_RENEWAL_TIME => String::from("renewal_time"),
+        OPTION_REBINDING_TIME => String::from("rebinding_time"),
+        OPTION_VENDOR_CLASS => String::from("vendor_class"),
+        OPTION_CLIENT_IDENTIFIER => String::from("client_identifier"),
+        OPTION_TFTP_SERVER => String::from("tftp_server"),
+        OPTION_BOOTFILE => String::from("bootfile"),
+        80 => String::from("rapid_commit"),
+        81 => String::from("client_fqdn"),
+        OPTION_RELAY_AGENT_INFORMATION => String::from("relay_agent_information"),
+        119 => String::from("domain_search"),
+        OPTION_CLASSLESS_STATIC_ROUTE => String::from("classless_static_route"),
+        _ => format!("{}", code)
+    }
+}
+
+fn relay_agent_suboption_name(code: u8) -> String {
+    match code {
+        1 => String::from("circuit_id"),
+        2 => String::from("remote_id"),
+        5 => String::from("link_selection"),
+        9 => String::from("vendor_specific"),
+        11 => String::from("server_identifier_override"),
+        _ => format!("{}", code)
+    }
+}
+
+/// Readable text, or hex when it is not.
+fn text_or_hex(data: &[u8]) -> String {
+    if !data.is_empty() && data.iter().all(|byte| byte.is_ascii_graphic() || *byte == b' ') {
+        String::from_utf8_lossy(data).into_owned()
+    } else {
+        hex(data)
+    }
+}
+
+/// The routes of option 121, each a prefix length, the significant bytes of the destination
+/// and the router.
+fn classless_routes(mut data: &[u8]) -> Option<String> {
+    let mut routes = Vec::new();
+    while let Some(&length) = data.first() {
+        let significant = (length as usize).div_ceil(8);
+        if length > 32 || data.len() < 1 + significant + 4 {
+            return None;
+        }
+        let mut destination = [0u8; 4];
+        destination[..significant].copy_from_slice(&data[1..1 + significant]);
+        let router = ipv4(&data[1 + significant..]);
+        routes.push(format!("{}/{} via {}", Ipv4Addr::from(destination), length, router));
+        data = &data[1 + significant + 4..];
+    }
+    Some(routes.join(", "))
+}
+
+/// The value of an option as it reads for its code, hex when it is malformed.
+pub fn option_value(code: OptionCode, data: &[u8]) -> String {
+    let value = match (code, data.len()) {
+        (OPTION_SUBNET_MASK | OPTION_BROADCAST_ADDRESS | OPTION_REQUESTED_ADDRESS | OPTION_SERVER_IDENTIFIER, 4) => {
+            Some(ipv4(data).to_string())
+        }
+        (OPTION_ROUTER | OPTION_DNS_SERVERS | OPTION_NTP_SERVERS | 44, length) if length > 0 && length.is_multiple_of(4) => {
+            Some(addresses(data))
+        }
+        (OPTION_LEASE_TIME | OPTION_RENEWAL_TIME | OPTION_REBINDING_TIME, 4) => Some(match tou32(data) {
+            u32::MAX => String::from("infinite"),
+            seconds => format!("{} s", seconds)
+        }),
+        (OPTION_MESSAGE_TYPE, 1) => Some(format!("{} ({})", message_type_name(data[0]), data[0])),
+        (OPTION_MAX_MESSAGE_SIZE | 26, 2) => Some(tou16(data).to_string()),
+        (OPTION_OVERLOAD, 1) => Some(match data[0] {
+            1 => String::from("file"),
+            2 => String::from("sname"),
+            3 => String::from("file, sname"),
+            other => format!("{}", other)
+        }),
+        (OPTION_PARAMETER_REQUEST_LIST, _) => {
+            let names: Vec<String> = data.iter().map(|code| option_name(*code)).collect();
+            Some(names.join(", "))
+        }
+        (OPTION_HOST_NAME | OPTION_DOMAIN_NAME | OPTION_MESSAGE | OPTION_VENDOR_CLASS | OPTION_TFTP_SERVER | OPTION_BOOTFILE, _) => {
+            Some(String::from_utf8_lossy(data).into_owned())
+        }
+        // A hardware type and address, or any other identifier after a type of 0
+        (OPTION_CLIENT_IDENTIFIER, 7) if data[0] == 1 => {
+            Some(format!("ethernet {}", MacAddr::new(data[1], data[2], data[3], data[4], data[5], data[6])))
+        }
+        (OPTION_RELAY_AGENT_INFORMATION, _) => {
+            let mut suboptions = Vec::new();
+            let mut rest = data;
+            while rest.len() >= 2 {
+                let end = (2 + rest[1] as usize).min(rest.len());
+                suboptions.push(format!("{}={}", relay_agent_suboption_name(rest[0]), text_or_hex(&rest[2..end])));
+                rest = &rest[end..];
+            }
+            Some(suboptions.join(", "))
+        }
+        (OPTION_CLASSLESS_STATIC_ROUTE, _) => classless_routes(data),
+        _ => None
+    };
+    value.unwrap_or_else(|| hex(data))
+}
+
+impl DhcpMessage {
+    /// Parses a message, None when it is too short or has no magic cookie.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < HEADER_LENGTH + 4 || tou32(&data[HEADER_LENGTH..HEADER_LENGTH + 4]) != MAGIC_COOKIE {
+            return None;
+        }
+        let mut message = Self {
+            op: data[0],
+            htype: data[1],
+            hlen: data[2],
+            hops: data[3],
+            xid: tou32(&data[4..8]),
+            secs: tou16(&data[8..10]),
+            flags: tou16(&data[10..12]),
+            ciaddr: ipv4(&data[12..16]),
+            yiaddr: ipv4(&data[16..20]),
+            siaddr: ipv4(&data[20..24]),
+            giaddr: ipv4(&data[24..28]),
+            chaddr: data[28..44].try_into().unwrap(),
+            sname: String::new(),
+            file: String::new(),
+            options: Vec::new(),
+        };
+        options(&data[HEADER_LENGTH + 4..], &mut message.options);
+
+        // The file and sname fields may carry more options instead of their own values
+        let overload = message.option(OPTION_OVERLOAD).and_then(|value| value.first()).copied().unwrap_or_default();
+        let (sname, file) = (&data[44..108], &data[108..236]);
+        if overload & 0x01 != 0 {
+            options(file, &mut message.options);
+        } else {
+            message.file = text(file);
+        }
+        if overload & 0x02 != 0 {
+            options(sname, &mut message.options);
+        } else {
+            message.sname = text(sname);
+        }
+        Some(message)
+    }
+
+    #[inline]
+    pub fn is_request(&self) -> bool {
+        self.op == 1
+    }
+
+    #[inline]
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+
+    #[inline]
+    pub fn ciaddr(&self) -> Ipv4Addr {
+        self.ciaddr
+    }
+
+    #[inline]
+    pub fn yiaddr(&self) -> Ipv4Addr {
+        self.yiaddr
+    }
+
+    /// The client hardware address, as a MAC address for ethernet.
+    pub fn client(&self) -> String {
+        let length = (self.hlen as usize).min(self.chaddr.len());
+        match (self.htype, length) {
+            (1, 6) => MacAddr::new(self.chaddr[0], self.chaddr[1], self.chaddr[2], self.chaddr[3], self.chaddr[4], self.chaddr[5]).to_string(),
+            _ => hex(&self.chaddr[..length])
+        }
+    }
+
+    pub fn option(&self, code: OptionCode) -> Option<&[u8]> {
+        self.options.iter().find(|(known, _)| *known == code).map(|(_, value)| value.as_slice())
+    }
+
+    /// The DHCP message type, None for plain BOOTP.
+    pub fn message_type(&self) -> Option<MessageType> {
+        self.option(OPTION_MESSAGE_TYPE).and_then(|value| value.first()).copied()
+    }
+
+    pub fn server_identifier(&self) -> Option<Ipv4Addr> {
+        self.option(OPTION_SERVER_IDENTIFIER).filter(|value| value.len() == 4).map(ipv4)
+    }
+
+    pub fn requested_address(&self) -> Option<Ipv4Addr> {
+        self.option(OPTION_REQUESTED_ADDRESS).filter(|value| value.len() == 4).map(ipv4)
+    }
+
+    /// The lease time in seconds, u32::MAX standing for an infinite lease.
+    pub fn lease_time(&self) -> Option<u32> {
+        self.option(OPTION_LEASE_TIME).filter(|value| value.len() == 4).map(tou32)
+    }
+}
+
+impl Display for DhcpMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["op", if self.is_request() { "request" } else { "reply" }],
+            ["htype", self.htype],
+            ["hops", self.hops],
+            ["xid", format!("0x{:08x}", self.xid)],
+            ["secs", self.secs],
+            ["flags", if self.flags & 0x8000 != 0 { "broadcast" } else { "" }],
+            ["ciaddr", self.ciaddr],
+            ["yiaddr", self.yiaddr],
+            ["siaddr", self.siaddr],
+            ["giaddr", self.giaddr],
+            ["chaddr", self.client()]
+        );
+        if !self.sname.is_empty() {
+            table.add_row(row!["sname", self.sname]);
+        }
+        if !self.file.is_empty() {
+            table.add_row(row!["file", self.file]);
+        }
+        for (code, value) in self.options.iter() {
+            table.add_row(row![format!("{} ({})", option_name(*code), code), option_value(*code, value)]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,399 @@
+use std::fmt::{Display, Formatter};
+use std::net::Ipv6Addr;
+use pnet::util::MacAddr;
+use prettytable::{format, row, table, Table};
+use crate::util::*;
+
+pub const SERVER_PORT: u16 = 547;
+
+pub type MessageType = u8;
+
+pub const SOLICIT: MessageType = 1;
+pub const ADVERTISE: MessageType = 2;
+pub const REQUEST: MessageType = 3;
+pub const CONFIRM: MessageType = 4;
+pub const RENEW: MessageType = 5;
+pub const REBIND: MessageType = 6;
+pub const REPLY: MessageType = 7;
+pub const RELEASE: MessageType = 8;
+pub const DECLINE: MessageType = 9;
+pub const RECONFIGURE: MessageType = 10;
+pub const INFORMATION_REQUEST: MessageType = 11;
+pub const RELAY_FORW: MessageType = 12;
+pub const RELAY_REPL: MessageType = 13;
+
+pub type OptionCode = u16;
+
+pub const OPTION_CLIENTID: OptionCode = 1;
+pub const OPTION_SERVERID: OptionCode = 2;
+pub const OPTION_IA_NA: OptionCode = 3;
+pub const OPTION_IA_TA: OptionCode = 4;
+pub const OPTION_IAADDR: OptionCode = 5;
+pub const OPTION_ORO: OptionCode = 6;
+pub const OPTION_PREFERENCE: OptionCode = 7;
+pub const OPTION_ELAPSED_TIME: OptionCode = 8;
+pub const OPTION_RELAY_MSG: OptionCode = 9;
+pub const OPTION_UNICAST: OptionCode = 12;
+pub const OPTION_STATUS_CODE: OptionCode = 13;
+pub const OPTION_RAPID_COMMIT: OptionCode = 14;
+pub const OPTION_INTERFACE_ID: OptionCode = 18;
+pub const OPTION_DNS_SERVERS: OptionCode = 23;
+pub const OPTION_DOMAIN_LIST: OptionCode = 24;
+pub const OPTION_IA_PD: OptionCode = 25;
+pub const OPTION_IAPREFIX: OptionCode = 26;
+pub const OPTION_SNTP_SERVERS: OptionCode = 31;
+pub const OPTION_INFORMATION_REFRESH_TIME: OptionCode = 32;
+pub const OPTION_REMOTE_ID: OptionCode = 37;
+
+pub const DUID_LLT: u16 = 1;
+pub const DUID_EN: u16 = 2;
+pub const DUID_LL: u16 = 3;
+pub const DUID_UUID: u16 = 4;
+
+/// A DHCPv6 message, either sent between client and server or wrapped by a relay agent.
+#[derive(Debug, Clone)]
+pub struct Dhcpv6Message {
+    msg_type: MessageType,
+    /// The transaction id, for messages between client and server
+    transaction_id: u32,
+    /// The hop count, link address and peer address, for relay messages
+    relay: Option<(u8, Ipv6Addr, Ipv6Addr)>,
+    options: Vec<Dhcpv6Option>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dhcpv6Option {
+    code: OptionCode,
+    data: Vec<u8>,
+}
+
+fn dhcpv6_options(mut data: &[u8]) -> Vec<Dhcpv6Option> {
+    let mut options = Vec::new();
+    while data.len() >= 4 {
+        let end = (4 + tou16(&data[2..4]) as usize).min(data.len());
+        options.push(Dhcpv6Option {
+            code: tou16(&data[0..2]),
+            data: Vec::from(&data[4..end]),
+        });
+        data = &data[end..];
+    }
+    options
+}
+
+fn ipv6(data: &[u8]) -> Ipv6Addr {
+    Ipv6Addr::from(tou128(&data[..16]))
+}
+
+fn lifetime(seconds: u32) -> String {
+    match seconds {
+        u32::MAX => String::from("infinite"),
+        seconds => format!("{} s", seconds)
+    }
+}
+
+pub fn message_type_name(msg_type: MessageType) -> String {
+    match msg_type {
+        SOLICIT => String::from("solicit"),
+        ADVERTISE => String::from("advertise"),
+        REQUEST => String::from("request"),
+        CONFIRM => String::from("confirm"),
+        RENEW => String::from("renew"),
+        REBIND => String::from("rebind"),
+        REPLY => String::from("reply"),
+        RELEASE => String::from("release"),
+        DECLINE => String::from("decline"),
+        RECONFIGURE => String::from("reconfigure"),
+        INFORMATION_REQUEST => String::from("information_request"),
+        RELAY_FORW => String::from("relay_forw"),
+        RELAY_REPL => String::from("relay_repl"),
+        _ => String::from("unidentified")
+    }
+}
+
+pub fn option_name(code: OptionCode) -> String {
+    match code {
+        OPTION_CLIENTID => String::from("client_id"),
+        OPTION_SERVERID => String::from("server_id"),
+        OPTION_IA_NA => String::from("ia_na"),
+        OPTION_IA_TA => String::from("ia_ta"),
+        OPTION_IAADDR => String::from("iaaddr"),
+        OPTION_ORO => String::from("option_request"),
+        OPTION_PREFERENCE => String::from("preference"),
+        OPTION_ELAPSED_TIME => String::from("elapsed_time"),
+        OPTION_RELAY_MSG => String::from("relay_message"),
+        11 => String::from("auth"),
+        OPTION_UNICAST => String::from("unicast"),
+        OPTION_STATUS_CODE => String::from("status_code"),
+        OPTION_RAPID_COMMIT => String::from("rapid_commit"),
+        15 => String::from("user_class"),
+        16 => String::from("vendor_class"),
+        17 => String::from("vendor_options"),
+        OPTION_INTERFACE_ID => String::from("interface_id"),
+        19 => String::from("reconfigure_message"),
+        20 => String::from("reconfigure_accept"),
+        OPTION_DNS_SERVERS => String::from("dns_servers"),
+        OPTION_DOMAIN_LIST => String::from("domain_list"),
+        OPTION_IA_PD => String::from("ia_pd"),
+        OPTION_IAPREFIX => String::from("iaprefix"),
+        OPTION_SNTP_SERVERS => String::from("sntp_servers"),
+        OPTION_INFORMATION_REFRESH_TIME => String::from("information_refresh_time"),
+        OPTION_REMOTE_ID => String::from("remote_id"),
+        39 => String::from("client_fqdn"),
+        56 => String::from("ntp_server"),
+        82 => String::from("sol_max_rt"),
+        _ => format!("{}", code)
+    }
+}
+
+pub fn status_name(status: u16) -> String {
+    match status {
+        0 => String::from("success"),
+        1 => String::from("unspec_fail"),
+        2 => String::from("no_addrs_avail"),
+        3 => String::from("no_binding"),
+        4 => String::from("not_on_link"),
+        5 => String::from("use_multicast"),
+        6 => String::from("no_prefix_avail"),
+        _ => String::from("unidentified")
+    }
+}
+
+/// The link layer address of a DUID made from one, as a MAC address for ethernet.
+pub fn duid_link_address(duid: &[u8]) -> Option<MacAddr> {
+    let address = match (duid.len() >= 4).then(|| tou16(&duid[0..2]))? {
+        DUID_LLT => duid.get(8..)?,
+        DUID_LL => duid.get(4..)?,
+        _ => return None
+    };
+    match (tou16(&duid[2..4]), address) {
+        (1, &[a, b, c, d, e, f]) => Some(MacAddr::new(a, b, c, d, e, f)),
+        _ => None
+    }
+}
+
+pub fn duid_name(duid: &[u8]) -> String {
+    if duid.len() < 2 {
+        return hex(duid);
+    }
+    let link_address = || duid_link_address(duid).map(|address| address.to_string());
+    match tou16(&duid[0..2]) {
+        DUID_LLT if duid.len() >= 8 => format!(
+            "llt {} time {}",
+            link_address().unwrap_or_else(|| hex(&duid[8..])),
+            tou32(&duid[4..8])
+        ),
+        DUID_EN if duid.len() >= 6 => format!("en {} {}", tou32(&duid[2..6]), hex(&duid[6..])),
+        DUID_LL if duid.len() >= 4 => format!("ll {}", link_address().unwrap_or_else(|| hex(&duid[4..]))),
+        DUID_UUID => format!("uuid {}", hex(&duid[2..])),
+        _ => hex(duid)
+    }
+}
+
+/// Domain names encoded as DNS labels, without compression.
+fn domain_names(mut data: &[u8]) -> Option<String> {
+    let mut names = Vec::new();
+    let mut labels = Vec::new();
+    while let Some(&length) = data.first() {
+        if length == 0 {
+            names.push(labels.join("."));
+            labels.clear();
+        } else {
+            labels.push(String::from_utf8_lossy(data.get(1..1 + length as usize)?).into_owned());
+        }
+        data = &data[1 + length as usize..];
+    }
+    if !labels.is_empty() {
+        names.push(labels.join("."));
+    }
+    Some(names.join(", "))
+}
+
+impl Dhcpv6Option {
+    /// Where the options nested in an option start, for the options that hold others.
+    fn children_offset(&self) -> Option<usize> {
+        let offset = match self.code {
+            OPTION_IA_NA | OPTION_IA_PD => 12,
+            OPTION_IA_TA => 4,
+            OPTION_IAADDR => 24,
+            OPTION_IAPREFIX => 25,
+            _ => return None
+        };
+        (self.data.len() >= offset).then_some(offset)
+    }
+
+    /// The options of an identity association, an address or a prefix.
+    pub fn children(&self) -> Vec<Dhcpv6Option> {
+        match self.children_offset() {
+            Some(offset) => dhcpv6_options(&self.data[offset..]),
+            None => Vec::new()
+        }
+    }
+
+    /// The address of an IAADDR, or the prefix of an IAPREFIX with its length, and the valid lifetime.
+    pub fn lease(&self) -> Option<(String, u32)> {
+        match (self.code, self.children_offset()) {
+            (OPTION_IAADDR, Some(_)) => Some((ipv6(&self.data).to_string(), tou32(&self.data[20..24]))),
+            (OPTION_IAPREFIX, Some(_)) => Some((format!("{}/{}", ipv6(&self.data[9..25]), self.data[8]), tou32(&self.data[4..8]))),
+            _ => None
+        }
+    }
+
+    pub fn value(&self) -> String {
+        let data = self.data.as_slice();
+        let value = match (self.code, data.len()) {
+            (OPTION_CLIENTID | OPTION_SERVERID, _) => Some(duid_name(data)),
+            (OPTION_IA_NA | OPTION_IA_PD, length) if length >= 12 => Some(format!(
+                "iaid {:08x} t1 {} t2 {}",
+                tou32(&data[0..4]),
+                lifetime(tou32(&data[4..8])),
+                lifetime(tou32(&data[8..12]))
+            )),
+            (OPTION_IA_TA, length) if length >= 4 => Some(format!("iaid {:08x}", tou32(&data[0..4]))),
+            (OPTION_IAADDR, length) if length >= 24 => Some(format!(
+                "{} preferred {} valid {}",
+                ipv6(data),
+                lifetime(tou32(&data[16..20])),
+                lifetime(tou32(&data[20..24]))
+            )),
+            (OPTION_IAPREFIX, length) if length >= 25 => Some(format!(
+                "{}/{} preferred {} valid {}",
+                ipv6(&data[9..25]),
+                data[8],
+                lifetime(tou32(&data[0..4])),
+                lifetime(tou32(&data[4..8]))
+            )),
+            (OPTION_ORO, length) if length.is_multiple_of(2) => {
+                let names: Vec<String> = data.chunks_exact(2).map(|code| option_name(tou16(code))).collect();
+                Some(names.join(", "))
+            }
+            (OPTION_PREFERENCE, 1) => Some(data[0].to_string()),
+            // Elapsed time counts hundredths of a second
+            (OPTION_ELAPSED_TIME, 2) => Some(format!("{} ms", tou16(data) as u32 * 10)),
+            (OPTION_RELAY_MSG, length) if length > 0 => Some(message_type_name(data[0])),
+            (OPTION_UNICAST, 16) => Some(ipv6(data).to_string()),
+            (OPTION_STATUS_CODE, length) if length >= 2 => {
+                let status = tou16(&data[0..2]);
+                Some(format!("{} ({}) {}", status_name(status), status, String::from_utf8_lossy(&data[2..])).trim_end().to_string())
+            }
+            (OPTION_RAPID_COMMIT, 0) => Some(String::new()),
+            (OPTION_INTERFACE_ID | OPTION_REMOTE_ID, _) if !data.is_empty() && data.iter().all(|byte| byte.is_ascii_graphic()) => {
+                Some(String::from_utf8_lossy(data).into_owned())
+            }
+            (OPTION_DNS_SERVERS | OPTION_SNTP_SERVERS, length) if length.is_multiple_of(16) => {
+                let addresses: Vec<String> = data.chunks_exact(16).map(|address| ipv6(address).to_string()).collect();
+                Some(addresses.join(", "))
+            }
+            (OPTION_DOMAIN_LIST, _) => domain_names(data),
+            (OPTION_INFORMATION_REFRESH_TIME, 4) => Some(lifetime(tou32(data))),
+            _ => None
+        };
+        value.unwrap_or_else(|| hex(data))
+    }
+
+    fn add_rows(&self, table: &mut Table, indent: usize) {
+        let name = format!("{}{} ({})", " ".repeat(indent * 2), option_name(self.code), self.code);
+        table.add_row(row![name, self.value()]);
+        for child in self.children() {
+            child.add_rows(table, indent + 1);
+        }
+    }
+}
+
+impl Dhcpv6Message {
+    /// Parses a message, None when it is too short for its header.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        let msg_type = *data.first()?;
+        let message = match msg_type {
+            RELAY_FORW | RELAY_REPL if data.len() >= 34 => Self {
+                msg_type,
+                transaction_id: 0,
+                relay: Some((data[1], ipv6(&data[2..18]), ipv6(&data[18..34]))),
+                options: dhcpv6_options(&data[34..]),
+            },
+            RELAY_FORW | RELAY_REPL => return None,
+            _ if data.len() >= 4 => Self {
+                msg_type,
+                transaction_id: tou32(&[0, data[1], data[2], data[3]]),
+                relay: None,
+                options: dhcpv6_options(&data[4..]),
+            },
+            _ => return None
+        };
+        Some(message)
+    }
+
+    #[inline]
+    pub fn msg_type(&self) -> MessageType {
+        self.msg_type
+    }
+
+    #[inline]
+    pub fn transaction_id(&self) -> u32 {
+        self.transaction_id
+    }
+
+    #[inline]
+    pub fn is_relay(&self) -> bool {
+        self.relay.is_some()
+    }
+
+    pub fn option(&self, code: OptionCode) -> Option<&Dhcpv6Option> {
+        self.options.iter().find(|option| option.code == code)
+    }
+
+    /// The message a relay agent wraps.
+    pub fn relay_message(&self) -> Option<Dhcpv6Message> {
+        self.relay?;
+        Self::new(&self.option(OPTION_RELAY_MSG)?.data)
+    }
+
+    pub fn client_id(&self) -> Option<&[u8]> {
+        self.option(OPTION_CLIENTID).map(|option| option.data.as_slice())
+    }
+
+    pub fn server_id(&self) -> Option<&[u8]> {
+        self.option(OPTION_SERVERID).map(|option| option.data.as_slice())
+    }
+
+    /// The status of the message as a whole, success when it has none.
+    pub fn status(&self) -> u16 {
+        self.option(OPTION_STATUS_CODE)
+            .filter(|option| option.data.len() >= 2)
+            .map(|option| tou16(&option.data[0..2]))
+            .unwrap_or_default()
+    }
+
+    /// The addresses and prefixes of the identity associations, with their valid lifetimes.
+    pub fn leases(&self) -> Vec<(String, u32)> {
+        self.options.iter()
+            .filter(|option| matches!(option.code, OPTION_IA_NA | OPTION_IA_TA | OPTION_IA_PD))
+            .flat_map(|option| option.children())
+            .filter_map(|option| option.lease())
+            .collect()
+    }
+}
+
+impl Display for Dhcpv6Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(["msg_type", format!("{} ({})", message_type_name(self.msg_type), self.msg_type)]);
+        match self.relay {
+            Some((hop_count, link_address, peer_address)) => {
+                table.add_row(row!["hop_count", hop_count]);
+                table.add_row(row!["link_address", link_address]);
+                table.add_row(row!["peer_address", peer_address]);
+            }
+            None => {
+                table.add_row(row!["transaction_id", format!("0x{:06x}", self.transaction_id)]);
+            }
+        }
+        for option in self.options.iter() {
+            option.add_rows(&mut table, 0);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table, row, Table};
+use crate::util::*;
+
+pub const FLAG_REQUEST: u8 = 0x80;
+pub const FLAG_PROXIABLE: u8 = 0x40;
+pub const FLAG_ERROR: u8 = 0x20;
+pub const FLAG_RETRANSMITTED: u8 = 0x10;
+
+pub const AVP_FLAG_VENDOR: u8 = 0x80;
+pub const AVP_FLAG_MANDATORY: u8 = 0x40;
+
+/// A Diameter message header followed by its attribute value pairs.
+#[derive(Debug)]
+pub struct DiameterMessage {
+    version: u8,
+    length: u32,
+    flags: u8,
+    command_code: u32,
+    application_id: u32,
+    hop_by_hop_id: u32,
+    end_to_end_id: u32,
+    avps: Vec<Avp>,
+}
+
+#[derive(Debug)]
+pub struct Avp {
+    code: u32,
+    flags: u8,
+    vendor_id: Option<u32>,
+    data: Vec<u8>,
+}
+
+/// Splits attribute value pairs, each padded to 4 bytes.
+fn avps(mut data: &[u8]) -> Vec<Avp> {
+    let mut avps = Vec::new();
+    while data.len() >= 8 {
+        let flags = data[4];
+        let length = (tou32(&[0, data[5], data[6], data[7]]) as usize).clamp(8, data.len());
+        let (vendor_id, start) = if flags & AVP_FLAG_VENDOR != 0 && length >= 12 {
+            (Some(tou32(&data[8..12])), 12)
+        } else {
+            (None, 8)
+        };
+        avps.push(Avp {
+            code: tou32(&data[0..4]),
+            flags,
+            vendor_id,
+            data: Vec::from(&data[start..length]),
+        });
+        data = &data[(length.div_ceil(4) * 4).min(data.len())..];
+    }
+    avps
+}
+
+impl DiameterMessage {
+    #[inline]
+    pub fn is_request(&self) -> bool {
+        self.flags & FLAG_REQUEST != 0
+    }
+
+    pub fn command_name(&self) -> &'static str {
+        match self.command_code {
+            257 => "capabilities-exchange",
+            258 => "re-auth",
+            271 => "accounting",
+            272 => "credit-control",
+            274 => "abort-session",
+            275 => "session-termination",
+            280 => "device-watchdog",
+            282 => "disconnect-peer",
+            316 => "update-location",
+            317 => "cancel-location",
+            318 => "authentication-information",
+            319 => "insert-subscriber-data",
+            321 => "purge-ue",
+            _ => "unidentified"
+        }
+    }
+}
+
+/// The length of the header before the attribute value pairs.
+const HEADER_LENGTH: usize = 20;
+
+impl DiameterMessage {
+    /// Parses a message, None when the data is shorter than its header.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < HEADER_LENGTH {
+            return None;
+        }
+        let length = tou32(&[0, data[1], data[2], data[3]]);
+        Some(Self {
+            version: data[0],
+            length,
+            flags: data[4],
+            command_code: tou32(&[0, data[5], data[6], data[7]]),
+            application_id: tou32(&data[8..12]),
+            hop_by_hop_id: tou32(&data[12..16]),
+            end_to_end_id: tou32(&data[16..20]),
+            avps: avps(&data[HEADER_LENGTH..(length as usize).clamp(HEADER_LENGTH, data.len())]),
+        })
+    }
+}
+
+impl Avp {
+    pub fn name(&self) -> &'static str {
+        match (self.vendor_id, self.code) {
+            (None, 1) => "user-name",
+            (None, 257) => "host-ip-address",
+            (None, 258) => "auth-application-id",
+            (None, 259) => "acct-application-id",
+            (None, 260) => "vendor-specific-application-id",
+            (None, 263) => "session-id",
+            (None, 264) => "origin-host",
+            (None, 265) => "supported-vendor-id",
+            (None, 266) => "vendor-id",
+            (None, 267) => "firmware-revision",
+            (None, 268) => "result-code",
+            (None, 269) => "product-name",
+            (None, 273) => "disconnect-cause",
+            (None, 277) => "auth-session-state",
+            (None, 278) => "origin-state-id",
+            (None, 279) => "failed-avp",
+            (None, 281) => "error-message",
+            (None, 283) => "destination-realm",
+            (None, 293) => "destination-host",
+            (None, 296) => "origin-realm",
+            (None, 297) => "experimental-result",
+            (None, 298) => "experimental-result-code",
+            (None, 415) => "cc-request-number",
+            (None, 416) => "cc-request-type",
+            (None, 443) => "subscription-id",
+            (None, 444) => "subscription-id-data",
+            (None, 450) => "subscription-id-type",
+            (None, 456) => "multiple-services-credit-control",
+            (Some(10415), 1407) => "visited-plmn-id",
+            (Some(10415), 1032) => "rat-type",
+            _ => "unidentified"
+        }
+    }
+
+    /// Whether the AVP holds other AVPs rather than a plain value.
+    pub fn is_grouped(&self) -> bool {
+        matches!((self.vendor_id, self.code), (None, 260 | 279 | 297 | 443 | 456))
+    }
+
+    /// The AVPs of a grouped AVP.
+    pub fn children(&self) -> Vec<Avp> {
+        if self.is_grouped() { avps(&self.data) } else { Vec::new() }
+    }
+
+    /// The value as text when it reads as such, as a number for 4 and 8 byte values, or as hex.
+    pub fn value(&self) -> String {
+        let data = self.data.as_slice();
+        match (self.vendor_id, self.code, data.len()) {
+            // Address AVPs start with their address family
+            (None, 257, 6) => format!("{}.{}.{}.{}", data[2], data[3], data[4], data[5]),
+            (None, 257, 18) => std::net::Ipv6Addr::from(tou128(&data[2..18])).to_string(),
+            _ if !data.is_empty() && data.iter().all(|byte| byte.is_ascii_graphic() || *byte == b' ') => {
+                String::from_utf8_lossy(data).into_owned()
+            }
+            (_, _, 4) => tou32(data).to_string(),
+            (_, _, 8) => tou64(data).to_string(),
+            _ => hex(data)
+        }
+    }
+
+    fn add_rows(&self, table: &mut Table, indent: usize) {
+        let flags = format!(
+            "{}{}",
+            if self.flags & AVP_FLAG_VENDOR != 0 { "V" } else { "" },
+            if self.flags & AVP_FLAG_MANDATORY != 0 { "M" } else { "" }
+        );
+        let code = match self.vendor_id {
+            Some(vendor_id) => format!("{}{} {}:{} [{}]", " ".repeat(indent * 2), self.name(), vendor_id, self.code, flags),
+            None => format!("{}{} {} [{}]", " ".repeat(indent * 2), self.name(), self.code, flags)
+        };
+        if self.is_grouped() {
+            table.add_row(row![code, ""]);
+            for child in self.children() {
+                child.add_rows(table, indent + 1);
+            }
+        } else {
+            table.add_row(row![code, self.value()]);
+        }
+    }
+}
+
+impl Display for DiameterMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let flags = format!(
+            "{}{}{}{}",
+            if self.flags & FLAG_REQUEST != 0 { "R" } else { "" },
+            if self.flags & FLAG_PROXIABLE != 0 { "P" } else { "" },
+            if self.flags & FLAG_ERROR != 0 { "E" } else { "" },
+            if self.flags & FLAG_RETRANSMITTED != 0 { "T" } else { "" }
+        );
+        let kind = if self.is_request() { "request" } else { "answer" };
+
+        let mut table = table!(
+            ["version", self.version],
+            ["length", self.length],
+            ["flags", flags],
+            ["command_code", format!("{} ({} {})", self.command_code, self.command_name(), kind)],
+            ["application_id", self.application_id],
+            ["hop_by_hop_id", format!("{:08X}", self.hop_by_hop_id)],
+            ["end_to_end_id", format!("{:08X}", self.end_to_end_id)]
+        );
+        for avp in self.avps.iter() {
+            avp.add_rows(&mut table, 0);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt::{Display, Formatter};
 use std::ops::Deref;
-use prettytable::{format, table, row, cell};
+use prettytable::{format, table};
 use crate::util::tou16;
 
 #[derive(Debug)]
@@ -12,6 +12,7 @@ pub struct DNSQuery {
     authority_rr: u16,
     additional_rr: u16,
     query_requests: Vec<DNSQueryRequest>,
+    #[allow(dead_code)]
     query_answers: Vec<DNSQueryAnswer>
 }
 
@@ -23,6 +24,7 @@ struct DNSQueryRequest {
 }
 
 #[derive(Debug)]
+#[allow(dead_code)]
 struct DNSQueryAnswer {
     name: u16,
     answer_type: u16,
@@ -42,7 +44,7 @@ impl Display for DNSQueryRequest {
         table.set_format(*format::consts::FORMAT_CLEAN);
         table.get_format().padding(10, 10);
 
-        writeln!(f, "{}", table);
+        writeln!(f, "{}", table).unwrap();
         Ok(())
     }
 }
@@ -52,7 +54,7 @@ impl DNSQueryRequest {
         let mut name = String::new();
         let mut length = 0;
         while data[length] != 0 {
-            name += String::from_utf8_lossy(&data[length+1 as usize..(data[length]+length as u8+1) as usize]).deref();
+            name += String::from_utf8_lossy(&data[length+1..(data[length]+length as u8+1) as usize]).deref();
             name += ".";
             length += (data[length]+1) as usize;
         }
@@ -68,7 +70,7 @@ impl From<&[u8]> for DNSQuery {
     fn from(data: &[u8]) -> Self {
         let mut data_pointer = &data[12..];
         let mut answers = Vec::new();
-        for i in 0..(tou16(&data[4..6])) as usize {
+        for _ in 0..(tou16(&data[4..6])) as usize {
             let combo = DNSQueryRequest::from(data_pointer);
             answers.push(combo.0);
             data_pointer = combo.1;
@@ -102,7 +104,7 @@ impl Display for DNSQuery {
         table.get_format().padding(5, 5);
         writeln!(f, "{}", table).unwrap();
         for request in self.query_requests.iter().enumerate() {
-            writeln!(f, "QUERY REQUEST {}", request.0);
+            writeln!(f, "QUERY REQUEST {}", request.0).unwrap();
             writeln!(f, "{}", request.1).unwrap();
         }
         Ok(())
@@ -0,0 +1,152 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use crate::application::http::HttpMessage;
+use crate::application::tftp::TftpTransfer;
+
+const HTTP_INDEX: &str = "index.csv";
+const HTTP_COLUMNS: &str = "file,request_frame,response_first_frame,response_last_frame,host,path,status,content_type,size";
+const TFTP_INDEX: &str = "tftp_index.csv";
+const TFTP_COLUMNS: &str = "file,client,server,remote_file,direction,mode,size";
+
+/// Writes the objects a protocol transferred, such as the bodies of HTTP responses, to a
+/// directory, with an index of where they came from.
+#[derive(Debug)]
+pub struct ObjectExporter {
+    directory: PathBuf,
+    index: File,
+}
+
+/// The usual file extension of a content type, ignoring its parameters.
+fn extension(content_type: &str) -> &'static str {
+    let content_type = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    match content_type.as_str() {
+        "text/html" => "html",
+        "text/plain" => "txt",
+        "text/css" => "css",
+        "text/csv" => "csv",
+        "text/javascript" | "application/javascript" | "application/x-javascript" => "js",
+        "application/json" => "json",
+        "text/xml" | "application/xml" => "xml",
+        "application/pdf" => "pdf",
+        "application/zip" => "zip",
+        "application/gzip" => "gz",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/svg+xml" => "svg",
+        "image/webp" => "webp",
+        "image/x-icon" | "image/vnd.microsoft.icon" => "ico",
+        _ => "bin"
+    }
+}
+
+/// Quotes a CSV field when it holds separators, quotes or line breaks.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+impl ObjectExporter {
+    /// Creates the directory if needed and starts the index of the HTTP objects.
+    pub fn http(directory: &Path) -> io::Result<Self> {
+        Self::new(directory, HTTP_INDEX, HTTP_COLUMNS)
+    }
+
+    /// Creates the directory if needed and starts the index of the files sent over TFTP.
+    pub fn tftp(directory: &Path) -> io::Result<Self> {
+        Self::new(directory, TFTP_INDEX, TFTP_COLUMNS)
+    }
+
+    fn new(directory: &Path, index: &str, columns: &str) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        let mut index = File::create(directory.join(index))?;
+        writeln!(index, "{}", columns)?;
+        Ok(Self {
+            directory: PathBuf::from(directory),
+            index,
+        })
+    }
+
+    /// A file name from the last segment of the path, with an extension from the content type
+    /// when it has none, numbered when already taken.
+    fn file_name(&self, path: &str, content_type: &str) -> String {
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let name: String = path.rsplit('/').next().unwrap_or_default().chars()
+            .map(|c| if c.is_ascii_alphanumeric() || "._-".contains(c) { c } else { '_' })
+            .collect();
+        let name = name.trim_start_matches('.');
+        let (stem, extension) = match name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() && !extension.is_empty() => (String::from(stem), String::from(extension)),
+            _ if name.is_empty() => (String::from("index"), String::from(extension(content_type))),
+            _ => (String::from(name), String::from(extension(content_type)))
+        };
+
+        let mut file_name = format!("{}.{}", stem, extension);
+        let mut number = 1;
+        while self.directory.join(&file_name).exists() {
+            file_name = format!("{}({}).{}", stem, number, extension);
+            number += 1;
+        }
+        file_name
+    }
+
+    /// Writes the body of a response, returning the name of the file. Responses without a body
+    /// are skipped.
+    pub fn export(&mut self, response: &HttpMessage) -> io::Result<Option<String>> {
+        let Some(request) = response.request() else {
+            return Ok(None);
+        };
+        if response.body().is_empty() {
+            return Ok(None);
+        }
+        let content_type = response.header("Content-Type").unwrap_or_default();
+        let file_name = self.file_name(request.target(), content_type);
+        fs::write(self.directory.join(&file_name), response.body())?;
+
+        let (first_frame, last_frame) = response.frames();
+        let fields = [
+            file_name.clone(),
+            request.frame().to_string(),
+            first_frame.to_string(),
+            last_frame.to_string(),
+            String::from(request.host().unwrap_or_default()),
+            String::from(request.target()),
+            response.status().map(|status| status.to_string()).unwrap_or_default(),
+            String::from(content_type),
+            response.body().len().to_string(),
+        ];
+        self.index(&fields)?;
+        Ok(Some(file_name))
+    }
+
+    /// Writes the file of a completed transfer, returning its name. Failed transfers are skipped.
+    pub fn export_tftp(&mut self, transfer: &TftpTransfer) -> io::Result<Option<String>> {
+        if transfer.error().is_some() {
+            return Ok(None);
+        }
+        let file_name = self.file_name(&transfer.file_name().replace('\\', "/"), "");
+        let contents = transfer.contents();
+        fs::write(self.directory.join(&file_name), &contents)?;
+
+        let fields = [
+            file_name.clone(),
+            transfer.client().to_string(),
+            transfer.server().to_string(),
+            String::from(transfer.file_name()),
+            String::from(if transfer.is_write() { "write" } else { "read" }),
+            String::from(transfer.mode()),
+            contents.len().to_string(),
+        ];
+        self.index(&fields)?;
+        Ok(Some(file_name))
+    }
+
+    fn index(&mut self, fields: &[String]) -> io::Result<()> {
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        writeln!(self.index, "{}", fields.join(","))
+    }
+}
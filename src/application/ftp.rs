@@ -0,0 +1,177 @@
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use prettytable::{format, row, Table};
+use crate::application::line::{self, Command, Reply, ReplyReader};
+
+pub const PORT: u16 = 21;
+
+/// Longest part of a listing kept to print when its data connection ends.
+const MAXIMUM_LISTING: usize = 1 << 16;
+
+/// Commands that move a file or a listing over a data connection.
+const TRANSFER_COMMANDS: [&str; 7] = ["RETR", "STOR", "STOU", "APPE", "LIST", "NLST", "MLSD"];
+
+pub const REPLY_PASSIVE: u16 = 227;
+pub const REPLY_EXTENDED_PASSIVE: u16 = 229;
+pub const REPLY_SECURITY_EXCHANGE_COMPLETE: u16 = 234;
+
+#[derive(Debug, Clone)]
+pub enum FtpMessage {
+    Command(Command),
+    Reply(Reply),
+}
+
+/// The control connection: the commands of the client and the replies of the server.
+#[derive(Debug, Default)]
+pub struct FtpConversation {
+    buffers: [Vec<u8>; 2],
+    replies: ReplyReader,
+    /// The last command sent, which a reply answers
+    command: Option<Command>,
+    /// The last command naming what a data connection carries
+    transfer: Option<Command>,
+    encrypted: bool,
+}
+
+/// A data connection the control connection of a stream announced with PASV, EPSV, PORT or
+/// EPRT.
+#[derive(Debug)]
+pub struct FtpDataTransfer {
+    control: usize,
+    command: Option<Command>,
+    length: usize,
+    listing: Vec<u8>,
+}
+
+/// The numbers of the "h1,h2,h3,h4,p1,p2" form PASV and PORT give an address and port in.
+fn numbers(text: &str) -> Option<SocketAddr> {
+    let numbers = text.split(|c: char| !c.is_ascii_digit() && c != ',')
+        .find(|part| part.matches(',').count() == 5)?;
+    let numbers: Vec<u8> = numbers.split(',').map(|number| number.parse::<u8>().ok()).collect::<Option<_>>()?;
+    let address = Ipv4Addr::new(numbers[0], numbers[1], numbers[2], numbers[3]);
+    Some(SocketAddr::new(IpAddr::V4(address), (numbers[4] as u16) << 8 | numbers[5] as u16))
+}
+
+/// The endpoint a PORT command asks the server to connect to.
+pub fn port_endpoint(argument: &str) -> Option<SocketAddr> {
+    numbers(argument)
+}
+
+/// The endpoint an EPRT command asks the server to connect to, such as "|2|::1|6275|".
+pub fn extended_port_endpoint(argument: &str) -> Option<SocketAddr> {
+    let delimiter = argument.chars().next()?;
+    let fields: Vec<&str> = argument.split(delimiter).collect();
+    let [_, _, address, port, ..] = fields.as_slice() else {
+        return None;
+    };
+    Some(SocketAddr::new(address.parse().ok()?, port.parse().ok()?))
+}
+
+/// The endpoint a reply to PASV or EPSV tells the client to connect to, EPSV only giving the
+/// port of the server.
+pub fn passive_endpoint(reply: &Reply, server: IpAddr) -> Option<SocketAddr> {
+    match reply.code() {
+        REPLY_PASSIVE => numbers(reply.text()),
+        REPLY_EXTENDED_PASSIVE => {
+            let (_, port) = reply.text().split_once("|||")?;
+            let (port, _) = port.split_once('|')?;
+            Some(SocketAddr::new(server, port.parse().ok()?))
+        }
+        _ => None
+    }
+}
+
+impl FtpConversation {
+    /// Adds the data one frame brought to a direction, 0 for the client, and returns the
+    /// commands and replies it completed.
+    pub fn push(&mut self, direction: usize, data: &[u8]) -> Vec<FtpMessage> {
+        let mut messages = Vec::new();
+        if self.encrypted {
+            return messages;
+        }
+        self.buffers[direction].extend_from_slice(data);
+        while let Some(line) = line::take_line(&mut self.buffers[direction]) {
+            if direction == 0 {
+                let command = Command::new(&line);
+                if TRANSFER_COMMANDS.contains(&command.verb()) {
+                    self.transfer = Some(command.clone());
+                }
+                self.command = Some(command.clone());
+                messages.push(FtpMessage::Command(command));
+            } else if let Some(reply) = self.replies.push(&line) {
+                let auth = self.command.as_ref().is_some_and(|command| command.verb() == "AUTH");
+                if auth && reply.code() == REPLY_SECURITY_EXCHANGE_COMPLETE {
+                    self.encrypted = true;
+                }
+                messages.push(FtpMessage::Reply(reply));
+            }
+            if self.encrypted {
+                break;
+            }
+        }
+        messages
+    }
+
+    /// Whether the control connection switched to TLS after AUTH.
+    #[inline]
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    #[inline]
+    pub fn transfer(&self) -> Option<&Command> {
+        self.transfer.as_ref()
+    }
+}
+
+impl FtpDataTransfer {
+    pub fn new(control: usize) -> Self {
+        Self {
+            control,
+            command: None,
+            length: 0,
+            listing: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn control(&self) -> usize {
+        self.control
+    }
+
+    /// Adds data sent over the connection.
+    pub fn push(&mut self, data: &[u8]) {
+        self.length += data.len();
+        let room = MAXIMUM_LISTING.saturating_sub(self.listing.len());
+        self.listing.extend_from_slice(&data[..data.len().min(room)]);
+    }
+
+    /// Names the transfer after the command the control connection gave for it.
+    pub fn finish(&mut self, command: Option<&Command>) {
+        self.command = command.cloned();
+    }
+
+    fn is_listing(&self) -> bool {
+        self.command.as_ref().is_some_and(|command| ["LIST", "NLST", "MLSD"].contains(&command.verb()))
+    }
+}
+
+impl Display for FtpDataTransfer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.add_row(row!["control_stream", self.control]);
+        if let Some(command) = &self.command {
+            table.add_row(row!["command", format!("{} {}", command.verb(), command.argument()).trim_end()]);
+        }
+        table.add_row(row!["length", format!("{} bytes", self.length)]);
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        if self.is_listing() {
+            writeln!(f, "{}", String::from_utf8_lossy(&self.listing)).unwrap();
+        }
+        Ok(())
+    }
+}
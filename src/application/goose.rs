@@ -0,0 +1,189 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table, row};
+use crate::application::ber::{self, Element};
+use crate::util::*;
+
+/// An IEC 61850 GOOSE message, carried directly in an ethernet frame.
+#[derive(Debug, Default)]
+pub struct GooseMessage {
+    appid: u16,
+    length: u16,
+    gocb_ref: String,
+    time_allowed_to_live: u64,
+    dat_set: String,
+    go_id: Option<String>,
+    t: Option<UtcTime>,
+    st_num: u64,
+    sq_num: u64,
+    simulation: bool,
+    conf_rev: u64,
+    nds_com: bool,
+    num_dat_set_entries: u64,
+    all_data: Vec<Data>,
+}
+
+/// An MMS style timestamp: seconds since the epoch, a binary fraction of a second and the time
+/// quality flags.
+#[derive(Debug, Clone, Copy)]
+pub struct UtcTime {
+    seconds: u32,
+    fraction: u32,
+    quality: u8,
+}
+
+impl UtcTime {
+    fn new(data: &[u8]) -> Option<Self> {
+        if data.len() != 8 {
+            return None;
+        }
+        Some(Self {
+            seconds: tou32(&data[0..4]),
+            fraction: tou32(&[0, data[4], data[5], data[6]]),
+            quality: data[7],
+        })
+    }
+}
+
+impl Display for UtcTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // The fraction is 24 bits of a second
+        let nanoseconds = (self.fraction as u64 * 1_000_000_000) >> 24;
+        write!(f, "{}.{:09} (quality {:02X})", self.seconds, nanoseconds, self.quality)
+    }
+}
+
+/// A value of the data set, one of the MMS Data choices.
+#[derive(Debug)]
+pub enum Data {
+    Boolean(bool),
+    BitString(Vec<u8>, u8),
+    Integer(i64),
+    Unsigned(u64),
+    Float(f64),
+    OctetString(Vec<u8>),
+    VisibleString(String),
+    UtcTime(UtcTime),
+    Structure(Vec<Data>),
+    Array(Vec<Data>),
+    Unknown(u32, Vec<u8>),
+}
+
+impl From<&Element<'_>> for Data {
+    fn from(element: &Element) -> Self {
+        let unknown = || Data::Unknown(element.tag(), Vec::from(element.value()));
+        match element.tag() {
+            1 => Data::Array(element.children().iter().map(Data::from).collect()),
+            2 => Data::Structure(element.children().iter().map(Data::from).collect()),
+            3 => Data::Boolean(element.value().first().is_some_and(|value| *value != 0)),
+            4 => match element.value().split_first() {
+                Some((unused, bits)) => Data::BitString(Vec::from(bits), *unused),
+                None => unknown()
+            },
+            5 => element.integer().map(Data::Integer).unwrap_or_else(unknown),
+            6 => element.unsigned().map(Data::Unsigned).unwrap_or_else(unknown),
+            7 => element.float().map(Data::Float).unwrap_or_else(unknown),
+            9 => Data::OctetString(Vec::from(element.value())),
+            10 => Data::VisibleString(element.string()),
+            17 => UtcTime::new(element.value()).map(Data::UtcTime).unwrap_or_else(unknown),
+            _ => unknown()
+        }
+    }
+}
+
+impl Display for Data {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let list = |values: &[Data]| values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(", ");
+        match self {
+            Data::Boolean(value) => write!(f, "{}", value),
+            Data::BitString(bits, unused) => {
+                let bits = bits.iter().map(|byte| format!("{:08b}", byte)).collect::<String>();
+                write!(f, "{}", &bits[..bits.len().saturating_sub(*unused as usize)])
+            }
+            Data::Integer(value) => write!(f, "{}", value),
+            Data::Unsigned(value) => write!(f, "{}", value),
+            Data::Float(value) => write!(f, "{}", value),
+            Data::OctetString(value) => write!(f, "{:02X?}", value),
+            Data::VisibleString(value) => write!(f, "\"{}\"", value),
+            Data::UtcTime(value) => write!(f, "{}", value),
+            Data::Structure(values) => write!(f, "{{{}}}", list(values)),
+            Data::Array(values) => write!(f, "[{}]", list(values)),
+            Data::Unknown(tag, value) => write!(f, "<tag {}> {:02X?}", tag, value)
+        }
+    }
+}
+
+impl GooseMessage {
+    /// Parses a message, or returns `None` if it ends inside the header before the goosePdu.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+        let mut message = Self {
+            appid: tou16(&data[0..2]),
+            length: tou16(&data[2..4]),
+            ..Default::default()
+        };
+
+        // The goosePdu is [APPLICATION 1], right after the two reserved fields
+        let Some((pdu, _)) = Element::parse(&data[8..]) else {
+            return Some(message);
+        };
+        if pdu.class() != ber::CLASS_APPLICATION || pdu.tag() != 1 {
+            return Some(message);
+        }
+        for field in pdu.children() {
+            if field.class() != ber::CLASS_CONTEXT {
+                continue;
+            }
+            match field.tag() {
+                0 => message.gocb_ref = field.string(),
+                1 => message.time_allowed_to_live = field.unsigned().unwrap_or_default(),
+                2 => message.dat_set = field.string(),
+                3 => message.go_id = Some(field.string()),
+                4 => message.t = UtcTime::new(field.value()),
+                5 => message.st_num = field.unsigned().unwrap_or_default(),
+                6 => message.sq_num = field.unsigned().unwrap_or_default(),
+                7 => message.simulation = field.value().first().is_some_and(|value| *value != 0),
+                8 => message.conf_rev = field.unsigned().unwrap_or_default(),
+                9 => message.nds_com = field.value().first().is_some_and(|value| *value != 0),
+                10 => message.num_dat_set_entries = field.unsigned().unwrap_or_default(),
+                11 => message.all_data = field.children().iter().map(Data::from).collect(),
+                _ => ()
+            }
+        }
+        Some(message)
+    }
+}
+
+impl Display for GooseMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["appid", format!("{:04X}", self.appid)],
+            ["length", self.length],
+            ["gocb_ref", self.gocb_ref],
+            ["time_allowed_to_live", format!("{} ms", self.time_allowed_to_live)],
+            ["dat_set", self.dat_set]
+        );
+        if let Some(go_id) = self.go_id.as_ref() {
+            table.add_row(row!["go_id", go_id]);
+        }
+        if let Some(t) = self.t {
+            table.add_row(row!["t", t]);
+        }
+        table.add_row(row!["st_num", self.st_num]);
+        table.add_row(row!["sq_num", self.sq_num]);
+        table.add_row(row!["simulation", self.simulation]);
+        table.add_row(row!["conf_rev", self.conf_rev]);
+        table.add_row(row!["nds_com", self.nds_com]);
+        table.add_row(row!["num_dat_set_entries", self.num_dat_set_entries]);
+        for (index, data) in self.all_data.iter().enumerate() {
+            table.add_row(row![format!("all_data[{}]", index), data]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
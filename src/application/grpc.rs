@@ -0,0 +1,170 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, row, table};
+use crate::application::http;
+use crate::application::http2::Http2Message;
+use crate::application::protobuf::ProtobufMessage;
+use crate::application::protobuf::descriptor::DescriptorSet;
+use crate::util::*;
+
+pub const CONTENT_TYPE: &str = "application/grpc";
+
+/// Every message is preceded by a compressed flag and a four byte length.
+pub const PREFIX_LENGTH: usize = 5;
+
+/// A length-prefixed message of a gRPC request or response body.
+#[derive(Debug, Clone)]
+pub struct GrpcMessage {
+    compressed: bool,
+    length: u32,
+    /// The message after undoing the compression named by grpc-encoding
+    data: Vec<u8>,
+    decoding_error: Option<String>,
+    protobuf: Option<ProtobufMessage>,
+}
+
+/// The outcome of a call, from the trailers or, for a response without a body, the headers.
+#[derive(Debug, Clone)]
+pub struct GrpcStatus {
+    code: u32,
+    message: Option<String>,
+}
+
+/// Whether the content type is gRPC, whatever the message format that follows the plus sign.
+pub fn is_grpc(content_type: &str) -> bool {
+    let media_type = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    media_type == CONTENT_TYPE || media_type.starts_with("application/grpc+")
+}
+
+pub fn status_name(code: u32) -> String {
+    match code {
+        0 => String::from("ok"),
+        1 => String::from("cancelled"),
+        2 => String::from("unknown"),
+        3 => String::from("invalid_argument"),
+        4 => String::from("deadline_exceeded"),
+        5 => String::from("not_found"),
+        6 => String::from("already_exists"),
+        7 => String::from("permission_denied"),
+        8 => String::from("resource_exhausted"),
+        9 => String::from("failed_precondition"),
+        10 => String::from("aborted"),
+        11 => String::from("out_of_range"),
+        12 => String::from("unimplemented"),
+        13 => String::from("internal"),
+        14 => String::from("unavailable"),
+        15 => String::from("data_loss"),
+        16 => String::from("unauthenticated"),
+        _ => String::from("unidentified")
+    }
+}
+
+/// Undoes the percent-encoding grpc-message is sent with.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1..i + 3).and_then(|digits| std::str::from_utf8(digits).ok())) {
+            (b'%', Some(digits)) if u8::from_str_radix(digits, 16).is_ok() => {
+                decoded.push(u8::from_str_radix(digits, 16).unwrap());
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Splits a body into its messages, decoding them as the named protobuf type. Returns the bytes
+/// of a message cut short by the end of the body along with them.
+pub fn messages<'a>(body: &'a [u8], encoding: Option<&str>, descriptors: &DescriptorSet, type_name: Option<&str>) -> (Vec<GrpcMessage>, &'a [u8]) {
+    let mut messages = Vec::new();
+    let mut rest = body;
+    while rest.len() >= PREFIX_LENGTH {
+        let length = tou32(&rest[1..5]);
+        let Some(data) = rest.get(PREFIX_LENGTH..PREFIX_LENGTH + length as usize) else {
+            break;
+        };
+        let compressed = rest[0] & 0x01 != 0;
+        let (data, decoding_error) = match (compressed, encoding) {
+            (false, _) => (Vec::from(data), None),
+            (true, Some(encoding)) => match http::decode_content(data, encoding) {
+                Ok(data) => (data, None),
+                Err(e) => (Vec::from(data), Some(e))
+            },
+            (true, None) => (Vec::from(data), Some(String::from("compressed without a grpc-encoding")))
+        };
+        let protobuf = match decoding_error {
+            None => ProtobufMessage::new(&data, descriptors, type_name),
+            Some(_) => None
+        };
+        messages.push(GrpcMessage {
+            compressed,
+            length,
+            data,
+            decoding_error,
+            protobuf,
+        });
+        rest = &rest[PREFIX_LENGTH + length as usize..];
+    }
+    (messages, rest)
+}
+
+impl Display for GrpcMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["compressed", self.compressed],
+            ["length", format!("{} bytes", self.length)]
+        );
+        if self.compressed {
+            table.add_row(row!["decoded_length", format!("{} bytes", self.data.len())]);
+        }
+        if let Some(error) = &self.decoding_error {
+            table.add_row(row!["decoding_error", error]);
+        }
+        match &self.protobuf {
+            Some(protobuf) => table.add_row(row!["type", protobuf.type_name().unwrap_or("unidentified")]),
+            None => table.add_row(row!["data", hex(&self.data)])
+        };
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        if let Some(protobuf) = &self.protobuf {
+            write!(f, "{}", protobuf).unwrap();
+        }
+        Ok(())
+    }
+}
+
+impl GrpcStatus {
+    pub fn new(message: &Http2Message) -> Option<Self> {
+        let (code, text) = match message.trailer("grpc-status") {
+            Some(code) => (code, message.trailer("grpc-message")),
+            None => (message.header("grpc-status")?, message.header("grpc-message"))
+        };
+        Some(Self {
+            code: code.trim().parse().ok()?,
+            message: text.map(percent_decode),
+        })
+    }
+}
+
+impl Display for GrpcStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(["status", format!("{} ({})", status_name(self.code), self.code)]);
+        if let Some(message) = &self.message {
+            table.add_row(row!["message", message]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::time::Duration;
+use flate2::read::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+use prettytable::{format, table, row};
+
+pub const PORTS: [u16; 3] = [80, 8000, 8080];
+
+const METHODS: [&str; 9] = ["GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH"];
+
+#[derive(Debug, Clone)]
+pub enum StartLine {
+    Request { method: String, target: String, version: String },
+    Response { version: String, status: u16, reason: String },
+}
+
+/// A request or response, with its body put back together and decompressed.
+#[derive(Debug, Clone)]
+pub struct HttpMessage {
+    start_line: StartLine,
+    headers: Vec<(String, String)>,
+    /// The body as sent, after undoing the chunked transfer coding
+    raw_body: Vec<u8>,
+    /// The body after undoing the content codings
+    body: Vec<u8>,
+    chunked: bool,
+    decoding_error: Option<String>,
+    /// The frames holding the first and the last byte of the message
+    frames: (usize, usize),
+    /// For a response, the request it answers
+    request: Option<RequestSummary>,
+    /// For a response, the time from the end of the request to the start of the response
+    response_time: Option<Duration>,
+}
+
+/// What a response needs to know about its request.
+#[derive(Debug, Clone)]
+pub struct RequestSummary {
+    method: String,
+    target: String,
+    host: Option<String>,
+    completed: Duration,
+    /// The frame that completed the request
+    frame: usize,
+}
+
+impl RequestSummary {
+    #[inline]
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    #[inline]
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    #[inline]
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+}
+
+impl HttpMessage {
+    #[inline]
+    pub fn is_request(&self) -> bool {
+        matches!(self.start_line, StartLine::Request { .. })
+    }
+
+    /// The value of the first header with the name, compared case insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The decoded body.
+    #[inline]
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    #[inline]
+    pub fn request(&self) -> Option<&RequestSummary> {
+        self.request.as_ref()
+    }
+
+    #[inline]
+    pub fn frames(&self) -> (usize, usize) {
+        self.frames
+    }
+
+    #[inline]
+    pub fn status(&self) -> Option<u16> {
+        match self.start_line {
+            StartLine::Response { status, .. } => Some(status),
+            StartLine::Request { .. } => None
+        }
+    }
+
+    /// Whether the body reads as text worth printing.
+    fn is_text(&self) -> bool {
+        is_text(self.header("Content-Type").unwrap_or_default(), &self.body)
+    }
+}
+
+/// Whether a body of the content type reads as text worth printing.
+pub fn is_text(content_type: &str, body: &[u8]) -> bool {
+    let content_type = content_type.to_ascii_lowercase();
+    let textual = content_type.starts_with("text/")
+        || ["json", "xml", "javascript", "x-www-form-urlencoded"].iter().any(|kind| content_type.contains(kind));
+    textual && std::str::from_utf8(body).is_ok()
+}
+
+/// Undoes the content codings, listed in the order they were applied.
+pub fn decode_content(body: &[u8], codings: &str) -> Result<Vec<u8>, String> {
+    let mut body = Vec::from(body);
+    for coding in codings.split(',').map(|coding| coding.trim().to_ascii_lowercase()).rev() {
+        let mut decoded = Vec::new();
+        let result = match coding.as_str() {
+            "gzip" | "x-gzip" => MultiGzDecoder::new(body.as_slice()).read_to_end(&mut decoded),
+            // Some servers send raw deflate data instead of the zlib format the name stands for
+            "deflate" => ZlibDecoder::new(body.as_slice()).read_to_end(&mut decoded).or_else(|_| {
+                decoded.clear();
+                DeflateDecoder::new(body.as_slice()).read_to_end(&mut decoded)
+            }),
+            "br" => brotli::Decompressor::new(body.as_slice(), 4096).read_to_end(&mut decoded),
+            "identity" | "" => continue,
+            other => return Err(format!("unsupported content coding {}", other))
+        };
+        result.map_err(|e| format!("{} decoding failed: {}", coding, e))?;
+        body = decoded;
+    }
+    Ok(body)
+}
+
+enum Parse {
+    Incomplete,
+    Invalid,
+    /// The message, its header length and the length of the whole message
+    Complete(StartLine, Vec<(String, String)>, Vec<u8>, bool, usize),
+}
+
+fn parse_start_line(line: &str, response: bool) -> Option<StartLine> {
+    let mut parts = line.splitn(3, ' ');
+    if response {
+        let version = parts.next()?;
+        if !version.starts_with("HTTP/1.") {
+            return None;
+        }
+        Some(StartLine::Response {
+            version: String::from(version),
+            status: parts.next()?.parse().ok()?,
+            reason: String::from(parts.next().unwrap_or_default()),
+        })
+    } else {
+        let method = parts.next()?;
+        let target = parts.next()?;
+        let version = parts.next()?;
+        if !METHODS.contains(&method) || !version.starts_with("HTTP/1.") {
+            return None;
+        }
+        Some(StartLine::Request {
+            method: String::from(method),
+            target: String::from(target),
+            version: String::from(version),
+        })
+    }
+}
+
+/// Undoes the chunked transfer coding, returning the body and the length it took, or None
+/// until the last chunk and the trailer are in.
+fn dechunk(data: &[u8]) -> Option<Result<(Vec<u8>, usize), ()>> {
+    let mut body = Vec::new();
+    let mut offset = 0;
+    loop {
+        let line_end = offset + data[offset..].windows(2).position(|window| window == b"\r\n")?;
+        let line = std::str::from_utf8(&data[offset..line_end]).ok();
+        let size = line.and_then(|line| usize::from_str_radix(line.split(';').next()?.trim(), 16).ok());
+        let Some(size) = size else {
+            return Some(Err(()));
+        };
+        offset = line_end + 2;
+        if size == 0 {
+            // Skip the trailer fields up to the empty line
+            loop {
+                let line_end = offset + data[offset..].windows(2).position(|window| window == b"\r\n")?;
+                let empty = line_end == offset;
+                offset = line_end + 2;
+                if empty {
+                    return Some(Ok((body, offset)));
+                }
+            }
+        }
+        if data.len() < offset + size + 2 {
+            return None;
+        }
+        body.extend_from_slice(&data[offset..offset + size]);
+        offset += size + 2;
+    }
+}
+
+/// Parses the message at the start of the buffer. Responses need to know whether they answer a
+/// HEAD request, and bodies delimited by the end of the connection whether it ended.
+fn parse(buffer: &[u8], response: bool, head: bool, finished: bool) -> Parse {
+    let Some(header_end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") else {
+        // Give up on data that does not even start like a message
+        let plausible = |candidate: &[u8]| {
+            let length = buffer.len().min(candidate.len());
+            buffer[..length] == candidate[..length]
+        };
+        let plausible = if response {
+            plausible(b"HTTP/1.")
+        } else {
+            METHODS.iter().any(|method| plausible(format!("{} ", method).as_bytes()))
+        };
+        return if plausible { Parse::Incomplete } else { Parse::Invalid };
+    };
+    let head_text = String::from_utf8_lossy(&buffer[..header_end]);
+    let mut lines = head_text.split("\r\n");
+    let Some(start_line) = lines.next().and_then(|line| parse_start_line(line, response)) else {
+        return Parse::Invalid;
+    };
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in lines {
+        if line.starts_with(' ') || line.starts_with('\t') {
+            // A folded continuation of the previous field
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((String::from(name.trim()), String::from(value.trim())));
+        }
+    }
+    let header = |name: &str| headers.iter().find(|(known, _)| known.eq_ignore_ascii_case(name)).map(|(_, value)| value.clone());
+
+    let body_start = header_end + 4;
+    let rest = &buffer[body_start..];
+    let no_body = match start_line {
+        StartLine::Response { status, .. } => head || (100..200).contains(&status) || status == 204 || status == 304,
+        StartLine::Request { .. } => false
+    };
+    let chunked = header("Transfer-Encoding")
+        .is_some_and(|codings| codings.to_ascii_lowercase().trim_end().ends_with("chunked"));
+    let content_length = header("Content-Length").and_then(|length| length.trim().parse::<usize>().ok());
+
+    let (body, length) = if no_body {
+        (Vec::new(), 0)
+    } else if chunked {
+        match dechunk(rest) {
+            Some(Ok(result)) => result,
+            Some(Err(())) => return Parse::Invalid,
+            None if finished => (Vec::from(rest), rest.len()),
+            None => return Parse::Incomplete
+        }
+    } else if let Some(length) = content_length {
+        if rest.len() < length && !finished {
+            return Parse::Incomplete;
+        }
+        let length = length.min(rest.len());
+        (Vec::from(&rest[..length]), length)
+    } else if response {
+        // Without a length, the body runs until the server closes the connection
+        if !finished {
+            return Parse::Incomplete;
+        }
+        (Vec::from(rest), rest.len())
+    } else {
+        (Vec::new(), 0)
+    };
+    Parse::Complete(start_line, headers, body, chunked, body_start + length)
+}
+
+/// One direction of an HTTP connection, waiting for messages to complete.
+#[derive(Debug, Default)]
+struct HttpStream {
+    buffer: Vec<u8>,
+    /// When and in which frame the first byte of the buffer was captured
+    started: Option<(Duration, usize)>,
+    /// Whether the data stopped being HTTP after an upgrade
+    abandoned: bool,
+}
+
+/// Both directions of an HTTP connection, pairing pipelined responses with their requests in order.
+#[derive(Debug, Default)]
+pub struct HttpConversation {
+    streams: [HttpStream; 2],
+    /// Requests waiting for their response, oldest first
+    pending: VecDeque<RequestSummary>,
+}
+
+impl HttpConversation {
+    /// Adds the data one frame brought to a direction, 0 for requests and 1 for responses, and
+    /// returns the messages it completed.
+    pub fn push(&mut self, direction: usize, data: &[u8], finished: bool, timestamp: Duration, frame: usize) -> Vec<HttpMessage> {
+        let response = direction == 1;
+        let stream = &mut self.streams[direction];
+        if stream.abandoned {
+            return Vec::new();
+        }
+        if stream.buffer.is_empty() && !data.is_empty() {
+            stream.started = Some((timestamp, frame));
+        }
+        stream.buffer.extend_from_slice(data);
+
+        let mut messages = Vec::new();
+        while !stream.buffer.is_empty() {
+            let head = response && self.pending.front().is_some_and(|request| request.method == "HEAD");
+            let (start_line, headers, raw_body, chunked, length) = match parse(&stream.buffer, response, head, finished) {
+                Parse::Complete(start_line, headers, body, chunked, length) => (start_line, headers, body, chunked, length),
+                Parse::Incomplete => break,
+                // Most likely the capture started in the middle of a message
+                Parse::Invalid => {
+                    stream.buffer.clear();
+                    break;
+                }
+            };
+            stream.buffer.drain(..length);
+            let (started, first_frame) = stream.started.unwrap_or((timestamp, frame));
+            stream.started = Some((timestamp, frame));
+
+            let (body, decoding_error) = match headers.iter().find(|(name, _)| name.eq_ignore_ascii_case("Content-Encoding")) {
+                Some((_, codings)) => match decode_content(&raw_body, codings) {
+                    Ok(body) => (body, None),
+                    Err(e) => (raw_body.clone(), Some(e))
+                },
+                None => (raw_body.clone(), None)
+            };
+            let mut message = HttpMessage {
+                start_line,
+                headers,
+                raw_body,
+                body,
+                chunked,
+                decoding_error,
+                frames: (first_frame, frame),
+                request: None,
+                response_time: None,
+            };
+            match &message.start_line {
+                StartLine::Request { method, target, .. } => {
+                    self.pending.push_back(RequestSummary {
+                        method: method.clone(),
+                        target: target.clone(),
+                        host: message.header("Host").map(String::from),
+                        completed: timestamp,
+                        frame,
+                    });
+                }
+                // Interim responses leave the request waiting for its final response
+                StartLine::Response { status, .. } if (100..200).contains(status) => {
+                    if *status == 101 {
+                        stream.abandoned = true;
+                        self.streams[0].abandoned = true;
+                        messages.push(message);
+                        break;
+                    }
+                }
+                StartLine::Response { .. } => {
+                    if let Some(request) = self.pending.pop_front() {
+                        message.response_time = Some(started.saturating_sub(request.completed));
+                        message.request = Some(request);
+                    }
+                }
+            }
+            messages.push(message);
+        }
+        messages
+    }
+}
+
+impl Display for HttpMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = match &self.start_line {
+            StartLine::Request { method, target, version } => table!(
+                ["method", method],
+                ["target", target],
+                ["version", version]
+            ),
+            StartLine::Response { version, status, reason } => table!(
+                ["version", version],
+                ["status", status],
+                ["reason", reason]
+            )
+        };
+        for (name, value) in self.headers.iter() {
+            table.add_row(row![name.to_ascii_lowercase(), value]);
+        }
+        table.add_row(row!["body_length", format!(
+            "{} bytes{}", self.raw_body.len(), if self.chunked { " (chunked)" } else { "" }
+        )]);
+        if self.body.len() != self.raw_body.len() || self.header("Content-Encoding").is_some() {
+            table.add_row(row!["decoded_length", format!("{} bytes", self.body.len())]);
+        }
+        if let Some(error) = &self.decoding_error {
+            table.add_row(row!["decoding_error", error]);
+        }
+        table.add_row(row!["frames", format!("{}-{}", self.frames.0, self.frames.1)]);
+        if let Some(request) = &self.request {
+            table.add_row(row!["request", format!("{} {} (frame {})", request.method, request.target, request.frame)]);
+        }
+        if let Some(response_time) = self.response_time {
+            table.add_row(row!["response_time", format!("{:.3} ms", response_time.as_secs_f64() * 1000.0)]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        if !self.body.is_empty() && self.is_text() {
+            writeln!(f, "{}", String::from_utf8_lossy(&self.body)).unwrap();
+        }
+        Ok(())
+    }
+}
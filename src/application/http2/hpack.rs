@@ -0,0 +1,285 @@
+use std::collections::VecDeque;
+
+/// The header fields every HPACK table starts with, RFC 7541 appendix A.
+const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""), (":method", "GET"), (":method", "POST"), (":path", "/"), (":path", "/index.html"),
+    (":scheme", "http"), (":scheme", "https"), (":status", "200"), (":status", "204"), (":status", "206"),
+    (":status", "304"), (":status", "400"), (":status", "404"), (":status", "500"), ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"), ("accept-language", ""), ("accept-ranges", ""), ("accept", ""),
+    ("access-control-allow-origin", ""), ("age", ""), ("allow", ""), ("authorization", ""),
+    ("cache-control", ""), ("content-disposition", ""), ("content-encoding", ""), ("content-language", ""),
+    ("content-length", ""), ("content-location", ""), ("content-range", ""), ("content-type", ""),
+    ("cookie", ""), ("date", ""), ("etag", ""), ("expect", ""), ("expires", ""), ("from", ""), ("host", ""),
+    ("if-match", ""), ("if-modified-since", ""), ("if-none-match", ""), ("if-range", ""),
+    ("if-unmodified-since", ""), ("last-modified", ""), ("link", ""), ("location", ""), ("max-forwards", ""),
+    ("proxy-authenticate", ""), ("proxy-authorization", ""), ("range", ""), ("referer", ""), ("refresh", ""),
+    ("retry-after", ""), ("server", ""), ("set-cookie", ""), ("strict-transport-security", ""),
+    ("transfer-encoding", ""), ("user-agent", ""), ("vary", ""), ("via", ""), ("www-authenticate", "")
+];
+
+/// The code and bit length of every symbol of the HPACK Huffman code, RFC 7541 appendix B. The
+/// last one is the end of string marker.
+const HUFFMAN_CODES: [(u32, u8); 257] = [
+    (0x1ff8, 13), (0x7fffd8, 23), (0xfffffe2, 28), (0xfffffe3, 28), (0xfffffe4, 28), (0xfffffe5, 28),
+    (0xfffffe6, 28), (0xfffffe7, 28), (0xfffffe8, 28), (0xffffea, 24), (0x3ffffffc, 30), (0xfffffe9, 28),
+    (0xfffffea, 28), (0x3ffffffd, 30), (0xfffffeb, 28), (0xfffffec, 28), (0xfffffed, 28), (0xfffffee, 28),
+    (0xfffffef, 28), (0xffffff0, 28), (0xffffff1, 28), (0xffffff2, 28), (0x3ffffffe, 30), (0xffffff3, 28),
+    (0xffffff4, 28), (0xffffff5, 28), (0xffffff6, 28), (0xffffff7, 28), (0xffffff8, 28), (0xffffff9, 28),
+    (0xffffffa, 28), (0xffffffb, 28), (0x14, 6), (0x3f8, 10), (0x3f9, 10), (0xffa, 12),
+    (0x1ff9, 13), (0x15, 6), (0xf8, 8), (0x7fa, 11), (0x3fa, 10), (0x3fb, 10),
+    (0xf9, 8), (0x7fb, 11), (0xfa, 8), (0x16, 6), (0x17, 6), (0x18, 6),
+    (0x0, 5), (0x1, 5), (0x2, 5), (0x19, 6), (0x1a, 6), (0x1b, 6),
+    (0x1c, 6), (0x1d, 6), (0x1e, 6), (0x1f, 6), (0x5c, 7), (0xfb, 8),
+    (0x7ffc, 15), (0x20, 6), (0xffb, 12), (0x3fc, 10), (0x1ffa, 13), (0x21, 6),
+    (0x5d, 7), (0x5e, 7), (0x5f, 7), (0x60, 7), (0x61, 7), (0x62, 7),
+    (0x63, 7), (0x64, 7), (0x65, 7), (0x66, 7), (0x67, 7), (0x68, 7),
+    (0x69, 7), (0x6a, 7), (0x6b, 7), (0x6c, 7), (0x6d, 7), (0x6e, 7),
+    (0x6f, 7), (0x70, 7), (0x71, 7), (0x72, 7), (0xfc, 8), (0x73, 7),
+    (0xfd, 8), (0x1ffb, 13), (0x7fff0, 19), (0x1ffc, 13), (0x3ffc, 14), (0x22, 6),
+    (0x7ffd, 15), (0x3, 5), (0x23, 6), (0x4, 5), (0x24, 6), (0x5, 5),
+    (0x25, 6), (0x26, 6), (0x27, 6), (0x6, 5), (0x74, 7), (0x75, 7),
+    (0x28, 6), (0x29, 6), (0x2a, 6), (0x7, 5), (0x2b, 6), (0x76, 7),
+    (0x2c, 6), (0x8, 5), (0x9, 5), (0x2d, 6), (0x77, 7), (0x78, 7),
+    (0x79, 7), (0x7a, 7), (0x7b, 7), (0x7ffe, 15), (0x7fc, 11), (0x3ffd, 14),
+    (0x1ffd, 13), (0xffffffc, 28), (0xfffe6, 20), (0x3fffd2, 22), (0xfffe7, 20), (0xfffe8, 20),
+    (0x3fffd3, 22), (0x3fffd4, 22), (0x3fffd5, 22), (0x7fffd9, 23), (0x3fffd6, 22), (0x7fffda, 23),
+    (0x7fffdb, 23), (0x7fffdc, 23), (0x7fffdd, 23), (0x7fffde, 23), (0xffffeb, 24), (0x7fffdf, 23),
+    (0xffffec, 24), (0xffffed, 24), (0x3fffd7, 22), (0x7fffe0, 23), (0xffffee, 24), (0x7fffe1, 23),
+    (0x7fffe2, 23), (0x7fffe3, 23), (0x7fffe4, 23), (0x1fffdc, 21), (0x3fffd8, 22), (0x7fffe5, 23),
+    (0x3fffd9, 22), (0x7fffe6, 23), (0x7fffe7, 23), (0xffffef, 24), (0x3fffda, 22), (0x1fffdd, 21),
+    (0xfffe9, 20), (0x3fffdb, 22), (0x3fffdc, 22), (0x7fffe8, 23), (0x7fffe9, 23), (0x1fffde, 21),
+    (0x7fffea, 23), (0x3fffdd, 22), (0x3fffde, 22), (0xfffff0, 24), (0x1fffdf, 21), (0x3fffdf, 22),
+    (0x7fffeb, 23), (0x7fffec, 23), (0x1fffe0, 21), (0x1fffe1, 21), (0x3fffe0, 22), (0x1fffe2, 21),
+    (0x7fffed, 23), (0x3fffe1, 22), (0x7fffee, 23), (0x7fffef, 23), (0xfffea, 20), (0x3fffe2, 22),
+    (0x3fffe3, 22), (0x3fffe4, 22), (0x7ffff0, 23), (0x3fffe5, 22), (0x3fffe6, 22), (0x7ffff1, 23),
+    (0x3ffffe0, 26), (0x3ffffe1, 26), (0xfffeb, 20), (0x7fff1, 19), (0x3fffe7, 22), (0x7ffff2, 23),
+    (0x3fffe8, 22), (0x1ffffec, 25), (0x3ffffe2, 26), (0x3ffffe3, 26), (0x3ffffe4, 26), (0x7ffffde, 27),
+    (0x7ffffdf, 27), (0x3ffffe5, 26), (0xfffff1, 24), (0x1ffffed, 25), (0x7fff2, 19), (0x1fffe3, 21),
+    (0x3ffffe6, 26), (0x7ffffe0, 27), (0x7ffffe1, 27), (0x3ffffe7, 26), (0x7ffffe2, 27), (0xfffff2, 24),
+    (0x1fffe4, 21), (0x1fffe5, 21), (0x3ffffe8, 26), (0x3ffffe9, 26), (0xffffffd, 28), (0x7ffffe3, 27),
+    (0x7ffffe4, 27), (0x7ffffe5, 27), (0xfffec, 20), (0xfffff3, 24), (0xfffed, 20), (0x1fffe6, 21),
+    (0x3fffe9, 22), (0x1fffe7, 21), (0x1fffe8, 21), (0x7ffff3, 23), (0x3fffea, 22), (0x3fffeb, 22),
+    (0x1ffffee, 25), (0x1ffffef, 25), (0xfffff4, 24), (0xfffff5, 24), (0x3ffffea, 26), (0x7ffff4, 23),
+    (0x3ffffeb, 26), (0x7ffffe6, 27), (0x3ffffec, 26), (0x3ffffed, 26), (0x7ffffe7, 27), (0x7ffffe8, 27),
+    (0x7ffffe9, 27), (0x7ffffea, 27), (0x7ffffeb, 27), (0xffffffe, 28), (0x7ffffec, 27), (0x7ffffed, 27),
+    (0x7ffffee, 27), (0x7ffffef, 27), (0x7fffff0, 27), (0x3ffffee, 26), (0x3fffffff, 30)
+];
+
+/// Each entry of the dynamic table counts this much on top of its name and value.
+const ENTRY_OVERHEAD: usize = 32;
+
+/// The HPACK state of one direction of a connection: the dynamic table the header blocks of that
+/// direction add to.
+#[derive(Debug)]
+pub struct HpackDecoder {
+    /// Newest entry first
+    table: VecDeque<(String, String)>,
+    size: usize,
+    max_size: usize,
+}
+
+impl Default for HpackDecoder {
+    fn default() -> Self {
+        Self {
+            table: VecDeque::new(),
+            size: 0,
+            max_size: 4096,
+        }
+    }
+}
+
+/// Decodes an integer with an N bit prefix, returning it and the bytes it took.
+fn integer(data: &[u8], prefix: u8) -> Result<(usize, usize), String> {
+    let mask = (1u16 << prefix) as usize - 1;
+    let first = *data.first().ok_or("truncated integer")? as usize & mask;
+    if first < mask {
+        return Ok((first, 1));
+    }
+    let mut value = mask;
+    for (index, byte) in data.iter().enumerate().skip(1) {
+        let shift = 7 * (index - 1);
+        if shift > 28 {
+            return Err(String::from("integer too large"));
+        }
+        value += ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    Err(String::from("truncated integer"))
+}
+
+/// Decodes a string literal, Huffman coded or not, returning it and the bytes it took.
+fn string(data: &[u8]) -> Result<(String, usize), String> {
+    let huffman = data.first().ok_or("truncated string")? & 0x80 != 0;
+    let (length, offset) = integer(data, 7)?;
+    let bytes = data.get(offset..offset + length).ok_or("truncated string")?;
+    let bytes = if huffman { decode_huffman(bytes)? } else { Vec::from(bytes) };
+    Ok((String::from_utf8_lossy(&bytes).into_owned(), offset + length))
+}
+
+fn decode_huffman(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::with_capacity(data.len() * 8 / 5);
+    let (mut code, mut length) = (0u32, 0u8);
+    for byte in data {
+        for bit in (0..8).rev() {
+            code = code << 1 | ((byte >> bit) & 1) as u32;
+            length += 1;
+            // No code is shorter than 5 bits
+            if length < 5 {
+                continue;
+            }
+            if let Some(symbol) = HUFFMAN_CODES.iter().position(|known| *known == (code, length)) {
+                if symbol == 256 {
+                    return Err(String::from("end of string in huffman data"));
+                }
+                decoded.push(symbol as u8);
+                (code, length) = (0, 0);
+            } else if length >= 30 {
+                return Err(String::from("invalid huffman code"));
+            }
+        }
+    }
+    // What is left must be a prefix of the end of string marker, all ones
+    if length > 7 || code != (1 << length) - 1 {
+        return Err(String::from("invalid huffman padding"));
+    }
+    Ok(decoded)
+}
+
+impl HpackDecoder {
+    /// The entry at a 1-based index of the static then the dynamic table.
+    fn entry(&self, index: usize) -> Result<(String, String), String> {
+        match index {
+            0 => Err(String::from("index 0")),
+            index if index <= STATIC_TABLE.len() => {
+                let (name, value) = STATIC_TABLE[index - 1];
+                Ok((String::from(name), String::from(value)))
+            }
+            index => self.table.get(index - STATIC_TABLE.len() - 1)
+                .cloned()
+                .ok_or_else(|| format!("index {} beyond the dynamic table", index))
+        }
+    }
+
+    fn insert(&mut self, name: &str, value: &str) {
+        let size = name.len() + value.len() + ENTRY_OVERHEAD;
+        self.table.push_front((String::from(name), String::from(value)));
+        self.size += size;
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.size > self.max_size {
+            match self.table.pop_back() {
+                Some((name, value)) => self.size -= name.len() + value.len() + ENTRY_OVERHEAD,
+                None => self.size = 0
+            }
+        }
+    }
+
+    /// Decodes a complete header block, updating the dynamic table along the way.
+    pub fn decode(&mut self, block: &[u8]) -> Result<Vec<(String, String)>, String> {
+        let mut headers = Vec::new();
+        let mut data = block;
+        while let Some(&first) = data.first() {
+            let consumed = if first & 0x80 != 0 {
+                // Indexed header field
+                let (index, consumed) = integer(data, 7)?;
+                headers.push(self.entry(index)?);
+                consumed
+            } else if first & 0xe0 == 0x20 {
+                // Dynamic table size update
+                let (max_size, consumed) = integer(data, 5)?;
+                self.max_size = max_size;
+                self.evict();
+                consumed
+            } else {
+                // Literal header field, indexed with a 6 bit prefix, otherwise never or not
+                // indexed with a 4 bit prefix
+                let indexing = first & 0xc0 == 0x40;
+                let (index, mut consumed) = integer(data, if indexing { 6 } else { 4 })?;
+                let name = if index == 0 {
+                    let (name, length) = string(&data[consumed..])?;
+                    consumed += length;
+                    name
+                } else {
+                    self.entry(index)?.0
+                };
+                let (value, length) = string(&data[consumed..])?;
+                consumed += length;
+                if indexing {
+                    self.insert(&name, &value);
+                }
+                headers.push((name, value));
+                consumed
+            };
+            data = &data[consumed..];
+        }
+        Ok(headers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unhex;
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(name, value)| (String::from(*name), String::from(*value))).collect()
+    }
+
+    #[test]
+    fn decodes_rfc7541_integers() {
+        // RFC 7541 appendix C.1
+        assert_eq!(integer(&[0x0a], 5), Ok((10, 1)));
+        assert_eq!(integer(&[0x1f, 0x9a, 0x0a], 5), Ok((1337, 3)));
+        assert_eq!(integer(&[0x2a], 8), Ok((42, 1)));
+        assert!(integer(&[0x1f, 0x9a], 5).is_err());
+    }
+
+    #[test]
+    fn decodes_rfc7541_huffman_requests() {
+        // RFC 7541 appendix C.4, three requests sharing the dynamic table
+        let mut decoder = HpackDecoder::default();
+        let block = unhex("828684418cf1e3c2e5f23a6ba0ab90f4ff").unwrap();
+        assert_eq!(decoder.decode(&block).unwrap(), headers(&[
+            (":method", "GET"), (":scheme", "http"), (":path", "/"), (":authority", "www.example.com"),
+        ]));
+        assert_eq!(decoder.size, 57);
+
+        let block = unhex("828684be5886a8eb10649cbf").unwrap();
+        assert_eq!(decoder.decode(&block).unwrap(), headers(&[
+            (":method", "GET"), (":scheme", "http"), (":path", "/"), (":authority", "www.example.com"),
+            ("cache-control", "no-cache"),
+        ]));
+        assert_eq!(decoder.size, 110);
+
+        let block = unhex("828785bf408825a849e95ba97d7f8925a849e95bb8e8b4bf").unwrap();
+        assert_eq!(decoder.decode(&block).unwrap(), headers(&[
+            (":method", "GET"), (":scheme", "https"), (":path", "/index.html"), (":authority", "www.example.com"),
+            ("custom-key", "custom-value"),
+        ]));
+        assert_eq!(decoder.size, 164);
+        assert_eq!(decoder.table.front(), Some(&(String::from("custom-key"), String::from("custom-value"))));
+    }
+
+    #[test]
+    fn decodes_rfc7541_huffman_response_with_eviction() {
+        // RFC 7541 appendix C.6.1, with the dynamic table limited to 256 bytes
+        let mut decoder = HpackDecoder { max_size: 256, ..HpackDecoder::default() };
+        let block = unhex(concat!(
+            "488264025885aec3771a4b6196d07abe941054d444a8200595040b8166e082a62d1bff",
+            "6e919d29ad171863c78f0b97c8e9ae82ae43d3"
+        )).unwrap();
+        assert_eq!(decoder.decode(&block).unwrap(), headers(&[
+            (":status", "302"), ("cache-control", "private"), ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+            ("location", "https://www.example.com"),
+        ]));
+        assert_eq!(decoder.size, 222);
+        // The padding is at most 7 bits of the end of string marker
+        assert_eq!(decode_huffman(&[0x1f]), Ok(b"a".to_vec()));
+        assert!(decode_huffman(&[0x18]).is_err());
+        assert!(decode_huffman(&[0x1f, 0xff]).is_err());
+    }
+}
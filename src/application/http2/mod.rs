@@ -0,0 +1,578 @@
+pub mod hpack;
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use prettytable::{format, table, row};
+use crate::application::http;
+use crate::application::http2::hpack::HpackDecoder;
+use crate::util::*;
+
+/// What a client sends first on every HTTP/2 connection.
+pub const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+pub type FrameType = u8;
+
+pub const DATA: FrameType = 0;
+pub const HEADERS: FrameType = 1;
+pub const PRIORITY: FrameType = 2;
+pub const RST_STREAM: FrameType = 3;
+pub const SETTINGS: FrameType = 4;
+pub const PUSH_PROMISE: FrameType = 5;
+pub const PING: FrameType = 6;
+pub const GOAWAY: FrameType = 7;
+pub const WINDOW_UPDATE: FrameType = 8;
+pub const CONTINUATION: FrameType = 9;
+
+pub const FLAG_END_STREAM: u8 = 0x01;
+pub const FLAG_ACK: u8 = 0x01;
+pub const FLAG_END_HEADERS: u8 = 0x04;
+pub const FLAG_PADDED: u8 = 0x08;
+pub const FLAG_PRIORITY: u8 = 0x20;
+
+#[derive(Debug, Clone)]
+pub struct Http2Frame {
+    frame_type: FrameType,
+    flags: u8,
+    stream_id: u32,
+    payload: Vec<u8>,
+}
+
+impl Http2Frame {
+    /// Decodes the frame at the start of the data, returning it and its length. None until the
+    /// whole frame is there.
+    pub fn parse(data: &[u8]) -> Option<(Self, usize)> {
+        let header = data.get(..9)?;
+        let length = tou32(&[0, header[0], header[1], header[2]]) as usize;
+        let payload = data.get(9..9 + length)?;
+        Some((Self {
+            frame_type: header[3],
+            flags: header[4],
+            stream_id: tou32(&header[5..9]) & 0x7fffffff,
+            payload: Vec::from(payload),
+        }, 9 + length))
+    }
+
+    #[inline]
+    pub fn frame_type(&self) -> FrameType {
+        self.frame_type
+    }
+
+    #[inline]
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+
+    #[inline]
+    pub fn stream_id(&self) -> u32 {
+        self.stream_id
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.frame_type {
+            DATA => "data",
+            HEADERS => "headers",
+            PRIORITY => "priority",
+            RST_STREAM => "rst_stream",
+            SETTINGS => "settings",
+            PUSH_PROMISE => "push_promise",
+            PING => "ping",
+            GOAWAY => "goaway",
+            WINDOW_UPDATE => "window_update",
+            CONTINUATION => "continuation",
+            _ => "unidentified"
+        }
+    }
+
+    /// The fields that come before the data or the header block, after the pad length.
+    fn prefix_length(&self) -> usize {
+        match self.frame_type {
+            HEADERS if self.has_flag(FLAG_PRIORITY) => 5,
+            PUSH_PROMISE => 4,
+            _ => 0
+        }
+    }
+
+    /// The data or header block fragment, without the padding and the fields before it.
+    pub fn content(&self) -> &[u8] {
+        let padded = matches!(self.frame_type, DATA | HEADERS | PUSH_PROMISE) && self.has_flag(FLAG_PADDED);
+        let (start, padding) = match (padded, self.payload.first()) {
+            (true, Some(padding)) => (1, *padding as usize),
+            _ => (0, 0)
+        };
+        let start = start + self.prefix_length();
+        let end = self.payload.len().saturating_sub(padding);
+        self.payload.get(start..end).unwrap_or_default()
+    }
+
+    /// The stream a push promise reserves.
+    pub fn promised_stream(&self) -> Option<u32> {
+        if self.frame_type != PUSH_PROMISE {
+            return None;
+        }
+        let start = if self.has_flag(FLAG_PADDED) { 1 } else { 0 };
+        self.payload.get(start..start + 4).map(|id| tou32(id) & 0x7fffffff)
+    }
+
+    fn flag_names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        match self.frame_type {
+            DATA | HEADERS if self.has_flag(FLAG_END_STREAM) => names.push("end_stream"),
+            SETTINGS | PING if self.has_flag(FLAG_ACK) => names.push("ack"),
+            _ => ()
+        }
+        if matches!(self.frame_type, HEADERS | PUSH_PROMISE | CONTINUATION) && self.has_flag(FLAG_END_HEADERS) {
+            names.push("end_headers");
+        }
+        if matches!(self.frame_type, DATA | HEADERS | PUSH_PROMISE) && self.has_flag(FLAG_PADDED) {
+            names.push("padded");
+        }
+        if self.frame_type == HEADERS && self.has_flag(FLAG_PRIORITY) {
+            names.push("priority");
+        }
+        names
+    }
+}
+
+fn setting_name(setting: u16) -> String {
+    match setting {
+        1 => String::from("header_table_size"),
+        2 => String::from("enable_push"),
+        3 => String::from("max_concurrent_streams"),
+        4 => String::from("initial_window_size"),
+        5 => String::from("max_frame_size"),
+        6 => String::from("max_header_list_size"),
+        8 => String::from("enable_connect_protocol"),
+        9 => String::from("no_rfc7540_priorities"),
+        setting => format!("{:04X}", setting)
+    }
+}
+
+pub fn error_name(error: u32) -> String {
+    match error {
+        0x0 => String::from("no_error"),
+        0x1 => String::from("protocol_error"),
+        0x2 => String::from("internal_error"),
+        0x3 => String::from("flow_control_error"),
+        0x4 => String::from("settings_timeout"),
+        0x5 => String::from("stream_closed"),
+        0x6 => String::from("frame_size_error"),
+        0x7 => String::from("refused_stream"),
+        0x8 => String::from("cancel"),
+        0x9 => String::from("compression_error"),
+        0xa => String::from("connect_error"),
+        0xb => String::from("enhance_your_calm"),
+        0xc => String::from("inadequate_security"),
+        0xd => String::from("http_1_1_required"),
+        error => format!("{:08X}", error)
+    }
+}
+
+impl Display for Http2Frame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["type", self.name()],
+            ["flags", self.flag_names().join(", ")],
+            ["stream_id", self.stream_id],
+            ["length", self.payload.len()]
+        );
+        let payload = self.payload.as_slice();
+        match self.frame_type {
+            SETTINGS => for setting in payload.chunks_exact(6) {
+                table.add_row(row![setting_name(tou16(&setting[0..2])), tou32(&setting[2..6])]);
+            },
+            WINDOW_UPDATE if payload.len() >= 4 => {
+                table.add_row(row!["increment", tou32(&payload[0..4]) & 0x7fffffff]);
+            }
+            RST_STREAM if payload.len() >= 4 => {
+                table.add_row(row!["error_code", error_name(tou32(&payload[0..4]))]);
+            }
+            GOAWAY if payload.len() >= 8 => {
+                table.add_row(row!["last_stream_id", tou32(&payload[0..4]) & 0x7fffffff]);
+                table.add_row(row!["error_code", error_name(tou32(&payload[4..8]))]);
+                if payload.len() > 8 {
+                    table.add_row(row!["debug_data", String::from_utf8_lossy(&payload[8..])]);
+                }
+            }
+            PING => {
+                table.add_row(row!["opaque_data", hex(payload)]);
+            }
+            PUSH_PROMISE => if let Some(promised) = self.promised_stream() {
+                table.add_row(row!["promised_stream_id", promised]);
+            },
+            _ => ()
+        }
+        // Priority frames and prioritised headers carry the dependency and weight
+        let priority = match self.frame_type {
+            PRIORITY => payload.get(0..5),
+            HEADERS if self.has_flag(FLAG_PRIORITY) => {
+                let start = if self.has_flag(FLAG_PADDED) { 1 } else { 0 };
+                payload.get(start..start + 5)
+            }
+            _ => None
+        };
+        if let Some(priority) = priority {
+            let exclusive = if priority[0] & 0x80 != 0 { " (exclusive)" } else { "" };
+            table.add_row(row!["dependency", format!("{}{}", tou32(&priority[0..4]) & 0x7fffffff, exclusive)]);
+            table.add_row(row!["weight", priority[4] as u16 + 1]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+/// A request or response put back together from the frames of its stream.
+#[derive(Debug, Clone)]
+pub struct Http2Message {
+    stream_id: u32,
+    request: bool,
+    headers: Vec<(String, String)>,
+    trailers: Vec<(String, String)>,
+    /// The body as sent
+    raw_body: Vec<u8>,
+    /// The body after undoing the content codings
+    body: Vec<u8>,
+    decoding_error: Option<String>,
+    /// The frames holding the first and the last byte of the message
+    frames: (usize, usize),
+    /// For a response, the method and path of the request it answers
+    request_line: Option<String>,
+    /// For a response, the time from the end of the request to the start of the response
+    response_time: Option<Duration>,
+}
+
+impl Http2Message {
+    #[inline]
+    pub fn stream_id(&self) -> u32 {
+        self.stream_id
+    }
+
+    #[inline]
+    pub fn is_request(&self) -> bool {
+        self.request
+    }
+
+    /// The first header field with the name, pseudo-headers included.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn trailer(&self, name: &str) -> Option<&str> {
+        self.trailers.iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[inline]
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// The method and path of the request, or of the request a response answers.
+    pub fn request_line(&self) -> Option<String> {
+        if self.request {
+            Some(format!("{} {}", self.header(":method")?, self.header(":path").unwrap_or_default()))
+        } else {
+            self.request_line.clone()
+        }
+    }
+
+    /// The path of the request, or of the request a response answers.
+    pub fn path(&self) -> Option<&str> {
+        if self.request {
+            self.header(":path")
+        } else {
+            self.request_line.as_deref()?.split_once(' ').map(|(_, path)| path)
+        }
+    }
+}
+
+impl Display for Http2Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(["stream_id", self.stream_id]);
+        for (name, value) in self.headers.iter() {
+            table.add_row(row![name, value]);
+        }
+        table.add_row(row!["body_length", format!("{} bytes", self.raw_body.len())]);
+        if self.body.len() != self.raw_body.len() || self.header("content-encoding").is_some() {
+            table.add_row(row!["decoded_length", format!("{} bytes", self.body.len())]);
+        }
+        if let Some(error) = &self.decoding_error {
+            table.add_row(row!["decoding_error", error]);
+        }
+        for (name, value) in self.trailers.iter() {
+            table.add_row(row![format!("trailer {}", name), value]);
+        }
+        table.add_row(row!["frames", format!("{}-{}", self.frames.0, self.frames.1)]);
+        if let (false, Some(request_line)) = (self.request, &self.request_line) {
+            table.add_row(row!["request", request_line]);
+        }
+        if let Some(response_time) = self.response_time {
+            table.add_row(row!["response_time", format!("{:.3} ms", response_time.as_secs_f64() * 1000.0)]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        if !self.body.is_empty() && http::is_text(self.header("content-type").unwrap_or_default(), &self.body) {
+            writeln!(f, "{}", String::from_utf8_lossy(&self.body)).unwrap();
+        }
+        Ok(())
+    }
+}
+
+/// A decoded header block, with the stream it belongs to.
+#[derive(Debug, Clone)]
+pub struct Http2Headers {
+    stream_id: u32,
+    fields: Vec<(String, String)>,
+}
+
+impl Display for Http2Headers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(["stream_id", self.stream_id]);
+        for (name, value) in self.fields.iter() {
+            table.add_row(row![name, value]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+/// What the bytes added to a connection brought, in order.
+#[derive(Debug)]
+pub enum Http2Event {
+    Preface,
+    Frame(Http2Frame),
+    Headers(Http2Headers),
+    Message(Http2Message),
+    /// The direction can no longer be followed
+    Error(String),
+}
+
+/// A header block waiting for its CONTINUATION frames.
+#[derive(Debug)]
+struct HeaderBlock {
+    stream_id: u32,
+    promised_stream: Option<u32>,
+    end_stream: bool,
+    fragments: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+struct Http2Direction {
+    buffer: Vec<u8>,
+    /// Set once the client connection preface went by, or from the start for the server
+    preface: bool,
+    decoder: HpackDecoder,
+    header_block: Option<HeaderBlock>,
+    abandoned: bool,
+}
+
+/// A message still missing frames.
+#[derive(Debug, Default)]
+struct PartialMessage {
+    headers: Vec<(String, String)>,
+    trailers: Vec<(String, String)>,
+    body: Vec<u8>,
+    /// When and in which frame the message started
+    started: Option<(Duration, usize)>,
+}
+
+#[derive(Debug, Default)]
+struct Http2Stream {
+    /// The request and the response in progress
+    messages: [Option<PartialMessage>; 2],
+    /// The request line and when the request ended
+    request: Option<(String, Duration)>,
+}
+
+/// Both directions of an HTTP/2 connection, direction 0 being the client.
+#[derive(Debug)]
+pub struct Http2Connection {
+    directions: [Http2Direction; 2],
+    streams: HashMap<u32, Http2Stream>,
+}
+
+impl Default for Http2Connection {
+    fn default() -> Self {
+        let mut connection = Self {
+            directions: Default::default(),
+            streams: HashMap::new(),
+        };
+        connection.directions[1].preface = true;
+        connection
+    }
+}
+
+impl Http2Connection {
+    /// Adds the data one segment brought to a direction and returns what it completed.
+    pub fn push(&mut self, direction: usize, data: &[u8], timestamp: Duration, frame: usize) -> Vec<Http2Event> {
+        let mut events = Vec::new();
+        let state = &mut self.directions[direction];
+        if state.abandoned {
+            return events;
+        }
+        state.buffer.extend_from_slice(data);
+        if !state.preface {
+            if state.buffer.len() < PREFACE.len() {
+                if !PREFACE.starts_with(&state.buffer) {
+                    state.abandoned = true;
+                    events.push(Http2Event::Error(String::from("missing connection preface")));
+                }
+                return events;
+            }
+            if !state.buffer.starts_with(PREFACE) {
+                state.abandoned = true;
+                events.push(Http2Event::Error(String::from("missing connection preface")));
+                return events;
+            }
+            state.buffer.drain(..PREFACE.len());
+            state.preface = true;
+            events.push(Http2Event::Preface);
+        }
+
+        while let Some((http2_frame, length)) = Http2Frame::parse(&self.directions[direction].buffer) {
+            self.directions[direction].buffer.drain(..length);
+            events.push(Http2Event::Frame(http2_frame.clone()));
+            self.frame(direction, &http2_frame, timestamp, frame, &mut events);
+            if self.directions[direction].abandoned {
+                self.directions[direction].buffer.clear();
+                break;
+            }
+        }
+        events
+    }
+
+    fn frame(&mut self, direction: usize, http2_frame: &Http2Frame, timestamp: Duration, frame: usize, events: &mut Vec<Http2Event>) {
+        let stream_id = http2_frame.stream_id();
+        match http2_frame.frame_type() {
+            HEADERS | PUSH_PROMISE => {
+                self.directions[direction].header_block = Some(HeaderBlock {
+                    stream_id,
+                    promised_stream: http2_frame.promised_stream(),
+                    end_stream: http2_frame.frame_type() == HEADERS && http2_frame.has_flag(FLAG_END_STREAM),
+                    fragments: Vec::from(http2_frame.content()),
+                });
+            }
+            CONTINUATION => match self.directions[direction].header_block.as_mut() {
+                Some(block) if block.stream_id == stream_id => block.fragments.extend_from_slice(http2_frame.content()),
+                _ => {
+                    events.push(Http2Event::Error(format!("continuation of no header block on stream {}", stream_id)));
+                    return;
+                }
+            },
+            DATA => {
+                let message = self.streams.entry(stream_id).or_default().messages[direction].get_or_insert_with(Default::default);
+                message.started.get_or_insert((timestamp, frame));
+                message.body.extend_from_slice(http2_frame.content());
+                if http2_frame.has_flag(FLAG_END_STREAM) {
+                    events.extend(self.complete(stream_id, direction, direction == 0, timestamp, frame).map(Http2Event::Message));
+                }
+                return;
+            }
+            RST_STREAM => {
+                self.streams.remove(&stream_id);
+                return;
+            }
+            _ => return
+        }
+        if !http2_frame.has_flag(FLAG_END_HEADERS) {
+            return;
+        }
+
+        // The header block is complete
+        let Some(block) = self.directions[direction].header_block.take() else {
+            return;
+        };
+        let headers = match self.directions[direction].decoder.decode(&block.fragments) {
+            Ok(headers) => headers,
+            Err(e) => {
+                // Without the header block the dynamic table of the direction is lost
+                self.directions[direction].abandoned = true;
+                events.push(Http2Event::Error(format!("header block on stream {}: {}", stream_id, e)));
+                return;
+            }
+        };
+        events.push(Http2Event::Headers(Http2Headers {
+            stream_id: block.promised_stream.unwrap_or(stream_id),
+            fields: headers.clone(),
+        }));
+
+        if let Some(promised_stream) = block.promised_stream {
+            // The server makes up the request it will answer on the promised stream
+            let request = self.streams.entry(promised_stream).or_default().messages[0].insert(PartialMessage::default());
+            request.headers = headers;
+            request.started = Some((timestamp, frame));
+            events.extend(self.complete(promised_stream, 0, true, timestamp, frame).map(Http2Event::Message));
+            return;
+        }
+
+        let message = self.streams.entry(stream_id).or_default().messages[direction].get_or_insert_with(Default::default);
+        message.started.get_or_insert((timestamp, frame));
+        let informational = headers.iter().any(|(name, value)| name == ":status" && value.starts_with('1'));
+        if message.headers.is_empty() {
+            message.headers = headers;
+        } else {
+            message.trailers = headers;
+        }
+        if informational || block.end_stream {
+            events.extend(self.complete(stream_id, direction, direction == 0, timestamp, frame).map(Http2Event::Message));
+        }
+    }
+
+    /// Finishes the message of a direction of a stream, once its last frame went by.
+    fn complete(&mut self, stream_id: u32, direction: usize, request: bool, timestamp: Duration, frame: usize) -> Option<Http2Message> {
+        let stream = self.streams.get_mut(&stream_id)?;
+        let message = stream.messages[direction].take()?;
+        // Nothing more is expected on the stream once the response is over, an interim 1xx
+        // response leaving the final one to come
+        let informational = message.headers.iter().any(|(name, value)| name == ":status" && value.starts_with('1'));
+        let closed = !request && !informational && stream.messages.iter().all(Option::is_none);
+        let (started, first_frame) = message.started.unwrap_or((timestamp, frame));
+        let content_encoding = message.headers.iter()
+            .find(|(name, _)| name == "content-encoding")
+            .map(|(_, value)| value.as_str());
+        let (body, decoding_error) = match content_encoding {
+            Some(codings) => match http::decode_content(&message.body, codings) {
+                Ok(body) => (body, None),
+                Err(e) => (message.body.clone(), Some(e))
+            },
+            None => (message.body.clone(), None)
+        };
+
+        let mut message = Http2Message {
+            stream_id,
+            request,
+            headers: message.headers,
+            trailers: message.trailers,
+            raw_body: message.body,
+            body,
+            decoding_error,
+            frames: (first_frame, frame),
+            request_line: None,
+            response_time: None,
+        };
+        if request {
+            stream.request = message.request_line().map(|request_line| (request_line, timestamp));
+        } else if let Some((request_line, completed)) = &stream.request {
+            message.request_line = Some(request_line.clone());
+            message.response_time = started.checked_sub(*completed);
+        }
+        if closed {
+            self.streams.remove(&stream_id);
+        }
+        Some(message)
+    }
+}
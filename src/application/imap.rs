@@ -0,0 +1,225 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, row, Table};
+
+pub const PORT: u16 = 143;
+
+/// Largest literal waited for, longer ones being taken for garbage.
+const MAXIMUM_LITERAL: usize = 1 << 26;
+
+const STATUSES: [&str; 5] = ["OK", "NO", "BAD", "PREAUTH", "BYE"];
+
+#[derive(Debug, Clone)]
+pub enum ImapMessage {
+    Command(ImapCommand),
+    Response(ImapResponse),
+    /// A line of the client answering a continuation request during AUTHENTICATE
+    Continuation(String),
+}
+
+/// A tagged command, the literals in its arguments left out.
+#[derive(Debug, Clone)]
+pub struct ImapCommand {
+    tag: String,
+    name: String,
+    arguments: String,
+    literals: usize,
+}
+
+/// A line of the server: untagged data or status, a continuation request, or the tagged status
+/// completing a command.
+#[derive(Debug, Clone)]
+pub struct ImapResponse {
+    /// The tag of the command completed, "*" for untagged and "+" for continuation requests
+    tag: String,
+    /// The name of the command a tagged response completes
+    command: Option<String>,
+    status: Option<String>,
+    text: String,
+    literals: usize,
+}
+
+/// The commands of the client, pipelined or not, and the responses of the server.
+#[derive(Debug, Default)]
+pub struct ImapConversation {
+    buffers: [Vec<u8>; 2],
+    /// The tags and names of the commands not completed yet
+    pending: Vec<(String, String)>,
+    /// The tag of the AUTHENTICATE command in progress
+    authenticating: Option<String>,
+    encrypted: bool,
+}
+
+/// The length of the literal announced at the end of a line, as "{12}" or "{12+}".
+fn literal_length(line: &str) -> Option<usize> {
+    let (_, length) = line.strip_suffix('}')?.rsplit_once('{')?;
+    let length = length.strip_suffix(['+', '-']).unwrap_or(length);
+    length.parse().ok().filter(|length| *length <= MAXIMUM_LITERAL)
+}
+
+/// Takes the first complete line off the buffer, with the literals it announces, and returns
+/// its text without them and the number of bytes they took.
+fn take_line(buffer: &mut Vec<u8>) -> Option<(String, usize)> {
+    let mut text = String::new();
+    let mut literals = 0;
+    let mut position = 0;
+    loop {
+        let end = position + buffer[position..].iter().position(|byte| *byte == b'\n')?;
+        let line = &buffer[position..end];
+        let line = String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(line));
+        text.push_str(&line);
+        match literal_length(&line) {
+            Some(length) if buffer.len() >= end + 1 + length => {
+                literals += length;
+                position = end + 1 + length;
+            }
+            Some(_) => return None,
+            None => {
+                buffer.drain(..=end);
+                return Some((text, literals));
+            }
+        }
+    }
+}
+
+impl ImapCommand {
+    fn new(line: &str, literals: usize) -> Self {
+        let (tag, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let (name, arguments) = rest.split_once(' ').unwrap_or((rest, ""));
+        let mut name = name.to_ascii_uppercase();
+        let mut arguments = String::from(arguments);
+        // UID prefixes the command it applies to
+        if name == "UID" {
+            let (command, rest) = arguments.split_once(' ').unwrap_or((&arguments, ""));
+            name = format!("UID {}", command.to_ascii_uppercase());
+            arguments = String::from(rest);
+        }
+        Self {
+            tag: String::from(tag),
+            name,
+            arguments,
+            literals,
+        }
+    }
+}
+
+impl Display for ImapCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.add_row(row!["tag", self.tag]);
+        table.add_row(row!["command", self.name]);
+        if !self.arguments.is_empty() {
+            table.add_row(row!["arguments", self.arguments]);
+        }
+        if self.literals > 0 {
+            table.add_row(row!["literals", format!("{} bytes", self.literals)]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl ImapResponse {
+    fn new(line: &str, literals: usize) -> Self {
+        let (tag, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let (word, text) = rest.split_once(' ').unwrap_or((rest, ""));
+        let status = (tag != "+" && STATUSES.contains(&word.to_ascii_uppercase().as_str()))
+            .then(|| word.to_ascii_uppercase());
+        Self {
+            tag: String::from(tag),
+            command: None,
+            text: String::from(if status.is_some() { text } else { rest }),
+            status,
+            literals,
+        }
+    }
+
+    /// Whether the response completes a command rather than coming before its completion.
+    #[inline]
+    pub fn is_tagged(&self) -> bool {
+        self.tag != "*" && self.tag != "+"
+    }
+}
+
+impl Display for ImapResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.add_row(row!["tag", match self.tag.as_str() {
+            "*" => String::from("* (untagged)"),
+            "+" => String::from("+ (continuation)"),
+            tag => String::from(tag)
+        }]);
+        if let Some(command) = &self.command {
+            table.add_row(row!["command", command]);
+        }
+        if let Some(status) = &self.status {
+            table.add_row(row!["status", status]);
+        }
+        if !self.text.is_empty() {
+            table.add_row(row!["text", self.text]);
+        }
+        if self.literals > 0 {
+            table.add_row(row!["literals", format!("{} bytes", self.literals)]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl ImapConversation {
+    /// Adds the data one frame brought to a direction, 0 for the client, and returns the
+    /// commands and responses it completed.
+    pub fn push(&mut self, direction: usize, data: &[u8]) -> Vec<ImapMessage> {
+        let mut messages = Vec::new();
+        if self.encrypted {
+            return messages;
+        }
+        self.buffers[direction].extend_from_slice(data);
+        while let Some((line, literals)) = take_line(&mut self.buffers[direction]) {
+            if direction == 0 {
+                if self.authenticating.is_some() {
+                    messages.push(ImapMessage::Continuation(line));
+                    continue;
+                }
+                let command = ImapCommand::new(&line, literals);
+                if command.name == "AUTHENTICATE" {
+                    self.authenticating = Some(command.tag.clone());
+                }
+                self.pending.push((command.tag.clone(), command.name.clone()));
+                messages.push(ImapMessage::Command(command));
+                continue;
+            }
+
+            let mut response = ImapResponse::new(&line, literals);
+            if response.is_tagged() {
+                if let Some(position) = self.pending.iter().position(|(tag, _)| *tag == response.tag) {
+                    response.command = Some(self.pending.remove(position).1);
+                }
+                if self.authenticating.as_ref() == Some(&response.tag) {
+                    self.authenticating = None;
+                }
+                if response.command.as_deref() == Some("STARTTLS") && response.status.as_deref() == Some("OK") {
+                    self.encrypted = true;
+                }
+            }
+            messages.push(ImapMessage::Response(response));
+            if self.encrypted {
+                break;
+            }
+        }
+        messages
+    }
+
+    /// Whether the connection switched to TLS after STARTTLS.
+    #[inline]
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+}
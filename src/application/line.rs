@@ -0,0 +1,144 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, row, Table};
+
+/// A command of the line based protocols, FTP, SMTP and POP3: a verb and its argument.
+#[derive(Debug, Clone)]
+pub struct Command {
+    verb: String,
+    argument: String,
+}
+
+/// A reply with a three digit code, as FTP and SMTP servers send, spanning several lines when
+/// the first has a hyphen after the code.
+#[derive(Debug, Clone)]
+pub struct Reply {
+    code: u16,
+    lines: Vec<String>,
+}
+
+/// Collects the lines of a reply until its last one.
+#[derive(Debug, Default)]
+pub struct ReplyReader {
+    reply: Option<Reply>,
+}
+
+/// Takes the first complete line off the buffer, without its line end.
+pub fn take_line(buffer: &mut Vec<u8>) -> Option<String> {
+    let end = buffer.iter().position(|byte| *byte == b'\n')?;
+    let line: Vec<u8> = buffer.drain(..=end).collect();
+    let line = line.strip_suffix(b"\r\n").or_else(|| line.strip_suffix(b"\n")).unwrap_or(&line);
+    Some(String::from_utf8_lossy(line).into_owned())
+}
+
+pub fn class_name(code: u16) -> String {
+    match code / 100 {
+        1 => String::from("positive_preliminary"),
+        2 => String::from("positive_completion"),
+        3 => String::from("positive_intermediate"),
+        4 => String::from("transient_negative"),
+        5 => String::from("permanent_negative"),
+        _ => String::from("unidentified")
+    }
+}
+
+impl Command {
+    pub fn new(line: &str) -> Self {
+        let (verb, argument) = line.split_once(' ').unwrap_or((line, ""));
+        Self {
+            verb: verb.to_ascii_uppercase(),
+            argument: String::from(argument),
+        }
+    }
+
+    #[inline]
+    pub fn verb(&self) -> &str {
+        &self.verb
+    }
+
+    #[inline]
+    pub fn argument(&self) -> &str {
+        &self.argument
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.add_row(row!["command", self.verb]);
+        if !self.argument.is_empty() {
+            table.add_row(row!["argument", self.argument]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl Reply {
+    #[inline]
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// The text of the first line.
+    pub fn text(&self) -> &str {
+        self.lines.first().map(String::as_str).unwrap_or_default()
+    }
+}
+
+impl Display for Reply {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.add_row(row!["code", format!("{} ({})", self.code, class_name(self.code))]);
+        for line in self.lines.iter() {
+            table.add_row(row!["text", line]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl ReplyReader {
+    /// Adds a line, returning the reply it ends. Lines that start no reply are dropped.
+    pub fn push(&mut self, line: &str) -> Option<Reply> {
+        let code = line.get(..3)
+            .filter(|code| code.bytes().all(|byte| byte.is_ascii_digit()))
+            .and_then(|code| code.parse().ok());
+        let separator = line.as_bytes().get(3).copied();
+        let text = || String::from(line.get(4..).unwrap_or_default());
+        match self.reply.as_mut() {
+            // The last line repeats the code followed by a space
+            Some(reply) if code == Some(reply.code) && separator != Some(b'-') => {
+                reply.lines.push(text());
+                self.reply.take()
+            }
+            Some(reply) if code == Some(reply.code) => {
+                reply.lines.push(text());
+                None
+            }
+            Some(reply) => {
+                reply.lines.push(String::from(line));
+                None
+            }
+            None => {
+                let reply = Reply {
+                    code: code?,
+                    lines: vec![text()],
+                };
+                if separator == Some(b'-') {
+                    self.reply = Some(reply);
+                    None
+                } else {
+                    Some(reply)
+                }
+            }
+        }
+    }
+}
@@ -1 +1,25 @@
-pub mod dns;
\ No newline at end of file
+pub mod dns;
+pub mod ber;
+pub mod wol;
+pub mod goose;
+pub mod diameter;
+pub mod tls;
+pub mod x509;
+pub mod http;
+pub mod http2;
+pub mod protobuf;
+pub mod grpc;
+pub mod dhcp;
+pub mod ntp;
+pub mod ptp;
+pub mod snmp;
+pub mod syslog;
+pub mod tftp;
+pub mod telnet;
+pub mod line;
+pub mod ftp;
+pub mod smtp;
+pub mod pop3;
+pub mod imap;
+pub mod ssh;
+pub mod export;
@@ -0,0 +1,237 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+use prettytable::{format, row, table};
+use crate::util::*;
+
+pub const PORT: u16 = 123;
+
+pub const HEADER_LENGTH: usize = 48;
+
+/// Seconds from the NTP epoch, 1900, to the Unix epoch, 1970.
+pub const UNIX_EPOCH: u64 = 2_208_988_800;
+
+pub type Mode = u8;
+
+pub const MODE_SYMMETRIC_ACTIVE: Mode = 1;
+pub const MODE_SYMMETRIC_PASSIVE: Mode = 2;
+pub const MODE_CLIENT: Mode = 3;
+pub const MODE_SERVER: Mode = 4;
+pub const MODE_BROADCAST: Mode = 5;
+pub const MODE_CONTROL: Mode = 6;
+pub const MODE_PRIVATE: Mode = 7;
+
+/// A 64 bit NTP timestamp: seconds since 1900 and a binary fraction of a second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NtpTimestamp(u64);
+
+/// An NTP packet header, with whatever extension fields and authenticator follow it left raw.
+#[derive(Debug, Clone)]
+pub struct NtpPacket {
+    leap: u8,
+    version: u8,
+    mode: Mode,
+    stratum: u8,
+    poll: i8,
+    precision: i8,
+    /// Round trip delay to the reference clock, in 16.16 fixed point seconds
+    root_delay: u32,
+    root_dispersion: u32,
+    reference_id: [u8; 4],
+    reference_timestamp: NtpTimestamp,
+    origin_timestamp: NtpTimestamp,
+    receive_timestamp: NtpTimestamp,
+    transmit_timestamp: NtpTimestamp,
+    trailer: Vec<u8>,
+}
+
+/// The clock offset and round trip delay of a client request and the server response to it.
+#[derive(Debug, Clone)]
+pub struct NtpExchange {
+    client: IpAddr,
+    server: IpAddr,
+    /// How far the client clock is behind the server, in seconds
+    offset: f64,
+    delay: f64,
+}
+
+/// The requests waiting for their response: client, server and the transmit timestamp the
+/// response echoes.
+#[derive(Debug, Default)]
+pub struct NtpExchanges {
+    requests: HashSet<(IpAddr, IpAddr, NtpTimestamp)>,
+}
+
+pub fn mode_name(mode: Mode) -> String {
+    match mode {
+        MODE_SYMMETRIC_ACTIVE => String::from("symmetric_active"),
+        MODE_SYMMETRIC_PASSIVE => String::from("symmetric_passive"),
+        MODE_CLIENT => String::from("client"),
+        MODE_SERVER => String::from("server"),
+        MODE_BROADCAST => String::from("broadcast"),
+        MODE_CONTROL => String::from("control"),
+        MODE_PRIVATE => String::from("private"),
+        _ => String::from("reserved")
+    }
+}
+
+pub fn leap_name(leap: u8) -> String {
+    match leap {
+        0 => String::from("no_warning"),
+        1 => String::from("last_minute_61_seconds"),
+        2 => String::from("last_minute_59_seconds"),
+        _ => String::from("unsynchronized")
+    }
+}
+
+/// A 16.16 fixed point number of seconds in milliseconds.
+fn short_format(value: u32) -> String {
+    format!("{:.3} ms", value as f64 / 65536.0 * 1000.0)
+}
+
+impl NtpTimestamp {
+    /// The capture time of a packet, which the capture took since the Unix epoch.
+    pub fn from_capture(timestamp: Duration) -> Self {
+        // The seconds wrap around at the end of each era, the first in 2036
+        let seconds = (timestamp.as_secs() + UNIX_EPOCH) & 0xffffffff;
+        let fraction = ((timestamp.subsec_nanos() as u64) << 32) / 1_000_000_000;
+        Self((seconds << 32) | fraction)
+    }
+
+    #[inline]
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// The seconds from the other timestamp to this one, negative when it is later.
+    pub fn since(&self, other: NtpTimestamp) -> f64 {
+        (self.0 as i128 - other.0 as i128) as f64 / 4294967296.0
+    }
+}
+
+impl Display for NtpTimestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // Timestamps before 1968 have wrapped around into the era starting in 2036
+        let mut seconds = (self.0 >> 32) as i64 - UNIX_EPOCH as i64;
+        if self.0 >> 63 == 0 {
+            seconds += 1 << 32;
+        }
+        let nanoseconds = ((self.0 & 0xffffffff) * 1_000_000_000) >> 32;
+        write!(f, "{}", utc_time(seconds, nanoseconds as u32))
+    }
+}
+
+impl NtpPacket {
+    /// Parses a packet, None when it is shorter than the header.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < HEADER_LENGTH {
+            return None;
+        }
+        Some(Self {
+            leap: data[0] >> 6,
+            version: (data[0] >> 3) & 0x07,
+            mode: data[0] & 0x07,
+            stratum: data[1],
+            poll: data[2] as i8,
+            precision: data[3] as i8,
+            root_delay: tou32(&data[4..8]),
+            root_dispersion: tou32(&data[8..12]),
+            reference_id: data[12..16].try_into().unwrap(),
+            reference_timestamp: NtpTimestamp(tou64(&data[16..24])),
+            origin_timestamp: NtpTimestamp(tou64(&data[24..32])),
+            receive_timestamp: NtpTimestamp(tou64(&data[32..40])),
+            transmit_timestamp: NtpTimestamp(tou64(&data[40..48])),
+            trailer: Vec::from(&data[HEADER_LENGTH..]),
+        })
+    }
+
+    /// The reference id: a kiss code for stratum 0, the name of the reference clock for stratum 1
+    /// and the address of the upstream server above, an IPv6 one showing as its hash.
+    pub fn reference_id(&self) -> String {
+        match self.stratum {
+            0 | 1 => {
+                let end = self.reference_id.iter().position(|byte| *byte == 0).unwrap_or(4);
+                String::from_utf8_lossy(&self.reference_id[..end]).into_owned()
+            }
+            _ => Ipv4Addr::from(self.reference_id).to_string()
+        }
+    }
+}
+
+impl Display for NtpPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["leap", leap_name(self.leap)],
+            ["version", self.version],
+            ["mode", format!("{} ({})", mode_name(self.mode), self.mode)],
+            ["stratum", self.stratum],
+            ["poll", format!("{} ({} s)", self.poll, 2f64.powi(self.poll as i32))],
+            ["precision", format!("{} ({:.9} s)", self.precision, 2f64.powi(self.precision as i32))],
+            ["root_delay", short_format(self.root_delay)],
+            ["root_dispersion", short_format(self.root_dispersion)],
+            ["reference_id", self.reference_id()],
+            ["reference_timestamp", self.reference_timestamp],
+            ["origin_timestamp", self.origin_timestamp],
+            ["receive_timestamp", self.receive_timestamp],
+            ["transmit_timestamp", self.transmit_timestamp]
+        );
+        if !self.trailer.is_empty() {
+            table.add_row(row!["extensions", hex(&self.trailer)]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl NtpExchanges {
+    /// Records a client request, or completes the exchange a server response answers. The
+    /// capture time of the response stands for the time the client received it, which holds
+    /// when capturing on the client.
+    pub fn update(&mut self, packet: &NtpPacket, source: IpAddr, destination: IpAddr, timestamp: Duration) -> Option<NtpExchange> {
+        match packet.mode {
+            MODE_CLIENT | MODE_SYMMETRIC_ACTIVE if !packet.transmit_timestamp.is_zero() => {
+                self.requests.insert((source, destination, packet.transmit_timestamp));
+                None
+            }
+            MODE_SERVER | MODE_SYMMETRIC_PASSIVE => {
+                if !self.requests.remove(&(destination, source, packet.origin_timestamp)) {
+                    return None;
+                }
+                let (t1, t2, t3) = (packet.origin_timestamp, packet.receive_timestamp, packet.transmit_timestamp);
+                let t4 = NtpTimestamp::from_capture(timestamp);
+                Some(NtpExchange {
+                    client: destination,
+                    server: source,
+                    offset: (t2.since(t1) + t3.since(t4)) / 2.0,
+                    delay: t4.since(t1) - t3.since(t2),
+                })
+            }
+            _ => None
+        }
+    }
+}
+
+impl Display for NtpExchange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["client", self.client],
+            ["server", self.server],
+            ["offset", format!("{:.3} ms", self.offset * 1000.0)],
+            ["delay", format!("{:.3} ms", self.delay * 1000.0)]
+        );
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
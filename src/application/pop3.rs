@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use prettytable::{format, row, Table};
+use crate::application::line::{self, Command};
+
+pub const PORT: u16 = 110;
+
+#[derive(Debug, Clone)]
+pub enum Pop3Message {
+    Command(Command),
+    Response(Pop3Response),
+}
+
+/// A status line, "+OK" or "-ERR" and its text, with the lines that follow it for the commands
+/// answered with several.
+#[derive(Debug, Clone)]
+pub struct Pop3Response {
+    status: String,
+    text: String,
+    /// The command answered, none for the greeting
+    command: Option<Command>,
+    lines: Vec<String>,
+}
+
+/// The commands of the client and the responses of the server, each answering the oldest
+/// command still waiting.
+#[derive(Debug, Default)]
+pub struct Pop3Conversation {
+    buffers: [Vec<u8>; 2],
+    pending: VecDeque<Command>,
+    /// The response whose lines are still coming
+    response: Option<Pop3Response>,
+    /// Whether the server asked for the next step of SASL authentication, which the client
+    /// answers with a line that is no command
+    continuation: bool,
+    encrypted: bool,
+}
+
+impl Pop3Response {
+    /// Whether the lines are a message, too long to print.
+    fn is_message(&self) -> bool {
+        self.command.as_ref().is_some_and(|command| ["RETR", "TOP"].contains(&command.verb()))
+    }
+}
+
+impl Display for Pop3Response {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        if let Some(command) = &self.command {
+            table.add_row(row!["command", format!("{} {}", command.verb(), command.argument()).trim_end()]);
+        }
+        table.add_row(row!["status", self.status]);
+        if !self.text.is_empty() {
+            table.add_row(row!["text", self.text]);
+        }
+        if self.is_message() {
+            let length: usize = self.lines.iter().map(|line| line.len() + 2).sum();
+            table.add_row(row!["length", format!("{} bytes", length)]);
+        } else {
+            for line in self.lines.iter() {
+                table.add_row(row!["line", line]);
+            }
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl Pop3Conversation {
+    /// Adds the data one frame brought to a direction, 0 for the client, and returns the
+    /// commands and responses it completed.
+    pub fn push(&mut self, direction: usize, data: &[u8]) -> Vec<Pop3Message> {
+        let mut messages = Vec::new();
+        if self.encrypted {
+            return messages;
+        }
+        self.buffers[direction].extend_from_slice(data);
+        while let Some(line) = line::take_line(&mut self.buffers[direction]) {
+            if direction == 0 {
+                let command = Command::new(&line);
+                if !std::mem::take(&mut self.continuation) {
+                    self.pending.push_back(command.clone());
+                }
+                messages.push(Pop3Message::Command(command));
+                continue;
+            }
+            if let Some(response) = self.response.as_mut() {
+                // The lines end with a single dot, other leading dots are doubled
+                if line == "." {
+                    messages.push(Pop3Message::Response(self.response.take().unwrap()));
+                } else {
+                    response.lines.push(String::from(line.strip_prefix('.').unwrap_or(&line)));
+                }
+                continue;
+            }
+            let (status, text) = line.split_once(' ').unwrap_or((&line, ""));
+            // A continuation of SASL authentication answers no command yet
+            self.continuation = status == "+";
+            let command = if self.continuation { None } else { self.pending.pop_front() };
+            let ok = status == "+OK";
+            let multiline = ok && command.as_ref().is_some_and(|command| match command.verb() {
+                "RETR" | "TOP" | "CAPA" => true,
+                "LIST" | "UIDL" => command.argument().is_empty(),
+                _ => false
+            });
+            let stls = command.as_ref().is_some_and(|command| command.verb() == "STLS");
+            let response = Pop3Response {
+                status: String::from(status),
+                text: String::from(text),
+                command,
+                lines: Vec::new(),
+            };
+            if multiline {
+                self.response = Some(response);
+            } else {
+                messages.push(Pop3Message::Response(response));
+            }
+            if ok && stls {
+                self.encrypted = true;
+                break;
+            }
+        }
+        messages
+    }
+
+    /// Whether the connection switched to TLS after STLS.
+    #[inline]
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+}
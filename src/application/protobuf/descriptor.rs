@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use crate::application::protobuf::{self, FieldType};
+
+/// A field of a message type.
+#[derive(Debug, Clone)]
+pub struct FieldDescriptor {
+    name: String,
+    number: u32,
+    field_type: FieldType,
+    /// The full name of the message or enum type, for fields of such types
+    type_name: Option<String>,
+}
+
+/// A message type with its fields.
+#[derive(Debug, Clone)]
+pub struct MessageDescriptor {
+    fields: Vec<FieldDescriptor>,
+}
+
+/// The message types, enums and services of the `FileDescriptorSet` files written by
+/// `protoc --descriptor_set_out`. Types go by their full name, without the leading dot.
+#[derive(Debug, Default)]
+pub struct DescriptorSet {
+    messages: HashMap<String, MessageDescriptor>,
+    enums: HashMap<String, HashMap<i32, String>>,
+    /// The input and output types of the methods, by the path gRPC calls them on
+    methods: HashMap<String, (String, String)>,
+}
+
+fn full_name(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        String::from(name)
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+/// Type names in descriptors are absolute once protoc resolved them, starting with a dot.
+fn type_name(name: &str) -> String {
+    String::from(name.strip_prefix('.').unwrap_or(name))
+}
+
+impl FieldDescriptor {
+    fn new(data: &[u8]) -> Option<Self> {
+        let mut field = Self {
+            name: String::new(),
+            number: 0,
+            field_type: 0,
+            type_name: None,
+        };
+        for value in protobuf::decode(data)? {
+            match value.number() {
+                1 => field.name = value.string()?,
+                3 => field.number = u32::try_from(value.unsigned()?).ok()?,
+                5 => field.field_type = FieldType::try_from(value.unsigned()?).ok()?,
+                6 => field.type_name = Some(type_name(&value.string()?)),
+                _ => ()
+            }
+        }
+        Some(field)
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn field_type(&self) -> FieldType {
+        self.field_type
+    }
+
+    #[inline]
+    pub fn type_name(&self) -> Option<&str> {
+        self.type_name.as_deref()
+    }
+}
+
+impl MessageDescriptor {
+    pub fn field(&self, number: u32) -> Option<&FieldDescriptor> {
+        self.fields.iter().find(|field| field.number == number)
+    }
+}
+
+impl DescriptorSet {
+    /// Loads and merges the descriptor set files.
+    pub fn load(paths: &[PathBuf]) -> io::Result<Self> {
+        let mut descriptors = Self::default();
+        for path in paths {
+            let data = fs::read(path)?;
+            let files = protobuf::decode(&data).ok_or_else(|| io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{}: not a descriptor set", path.display())
+            ))?;
+            for file in files.iter().filter(|file| file.number() == 1) {
+                file.bytes().and_then(|file| descriptors.add_file(file)).ok_or_else(|| io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{}: malformed file descriptor", path.display())
+                ))?;
+            }
+        }
+        Ok(descriptors)
+    }
+
+    /// Adds the types of a `FileDescriptorProto`.
+    fn add_file(&mut self, data: &[u8]) -> Option<()> {
+        let fields = protobuf::decode(data)?;
+        let package = fields.iter()
+            .find(|field| field.number() == 2)
+            .and_then(|field| field.string())
+            .unwrap_or_default();
+        for field in fields.iter() {
+            match field.number() {
+                4 => self.add_message(&package, field.bytes()?)?,
+                5 => self.add_enum(&package, field.bytes()?)?,
+                6 => self.add_service(&package, field.bytes()?)?,
+                _ => ()
+            }
+        }
+        Some(())
+    }
+
+    /// Adds a `DescriptorProto` with the types nested in it.
+    fn add_message(&mut self, scope: &str, data: &[u8]) -> Option<()> {
+        let fields = protobuf::decode(data)?;
+        let name = fields.iter().find(|field| field.number() == 1)?.string()?;
+        let name = full_name(scope, &name);
+        let mut message = MessageDescriptor { fields: Vec::new() };
+        for field in fields.iter() {
+            match field.number() {
+                2 => message.fields.push(FieldDescriptor::new(field.bytes()?)?),
+                3 => self.add_message(&name, field.bytes()?)?,
+                4 => self.add_enum(&name, field.bytes()?)?,
+                _ => ()
+            }
+        }
+        self.messages.insert(name, message);
+        Some(())
+    }
+
+    /// Adds an `EnumDescriptorProto`.
+    fn add_enum(&mut self, scope: &str, data: &[u8]) -> Option<()> {
+        let fields = protobuf::decode(data)?;
+        let name = fields.iter().find(|field| field.number() == 1)?.string()?;
+        let mut values = HashMap::new();
+        for value in fields.iter().filter(|field| field.number() == 2) {
+            let value = protobuf::decode(value.bytes()?)?;
+            let name = value.iter().find(|field| field.number() == 1)?.string()?;
+            // Negative numbers take all ten bytes of a varint
+            let number = value.iter()
+                .find(|field| field.number() == 2)
+                .and_then(|field| field.unsigned())
+                .unwrap_or_default() as i64 as i32;
+            values.insert(number, name);
+        }
+        self.enums.insert(full_name(scope, &name), values);
+        Some(())
+    }
+
+    /// Adds the methods of a `ServiceDescriptorProto`.
+    fn add_service(&mut self, package: &str, data: &[u8]) -> Option<()> {
+        let fields = protobuf::decode(data)?;
+        let name = fields.iter().find(|field| field.number() == 1)?.string()?;
+        let service = full_name(package, &name);
+        for method in fields.iter().filter(|field| field.number() == 2) {
+            let method = protobuf::decode(method.bytes()?)?;
+            let string = |number: u32| method.iter().find(|field| field.number() == number).and_then(|field| field.string());
+            let path = format!("/{}/{}", service, string(1)?);
+            self.methods.insert(path, (type_name(&string(2)?), type_name(&string(3)?)));
+        }
+        Some(())
+    }
+
+    pub fn message(&self, name: &str) -> Option<&MessageDescriptor> {
+        self.messages.get(name.strip_prefix('.').unwrap_or(name))
+    }
+
+    /// The name of a value of an enum type.
+    pub fn enum_value(&self, name: &str, number: i32) -> Option<&str> {
+        self.enums.get(name.strip_prefix('.').unwrap_or(name))?
+            .get(&number)
+            .map(String::as_str)
+    }
+
+    /// The input and output types of the method called on a path such as `/package.Service/Method`.
+    pub fn method(&self, path: &str) -> Option<(&str, &str)> {
+        self.methods.get(path).map(|(input, output)| (input.as_str(), output.as_str()))
+    }
+}
@@ -0,0 +1,447 @@
+pub mod descriptor;
+
+use std::fmt::{Display, Formatter};
+use prettytable::{format, row, Table};
+use crate::application::protobuf::descriptor::{DescriptorSet, FieldDescriptor};
+use crate::util::*;
+
+pub type WireType = u8;
+
+pub const VARINT: WireType = 0;
+pub const FIXED64: WireType = 1;
+pub const LENGTH_DELIMITED: WireType = 2;
+pub const START_GROUP: WireType = 3;
+pub const END_GROUP: WireType = 4;
+pub const FIXED32: WireType = 5;
+
+/// The types of the fields of a descriptor, as numbered in descriptor.proto.
+pub type FieldType = u32;
+
+pub const TYPE_DOUBLE: FieldType = 1;
+pub const TYPE_FLOAT: FieldType = 2;
+pub const TYPE_INT64: FieldType = 3;
+pub const TYPE_UINT64: FieldType = 4;
+pub const TYPE_INT32: FieldType = 5;
+pub const TYPE_FIXED64: FieldType = 6;
+pub const TYPE_FIXED32: FieldType = 7;
+pub const TYPE_BOOL: FieldType = 8;
+pub const TYPE_STRING: FieldType = 9;
+pub const TYPE_GROUP: FieldType = 10;
+pub const TYPE_MESSAGE: FieldType = 11;
+pub const TYPE_BYTES: FieldType = 12;
+pub const TYPE_UINT32: FieldType = 13;
+pub const TYPE_ENUM: FieldType = 14;
+pub const TYPE_SFIXED32: FieldType = 15;
+pub const TYPE_SFIXED64: FieldType = 16;
+pub const TYPE_SINT32: FieldType = 17;
+pub const TYPE_SINT64: FieldType = 18;
+
+/// Groups nested deeper than this are taken for garbage rather than followed, and messages
+/// nested deeper are shown in hexadecimal rather than expanded.
+const MAX_DEPTH: usize = 64;
+
+/// The value of a field as the wire format has it, before any schema is applied.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(Vec<u8>),
+    Group(Vec<Field>),
+    Fixed32(u32),
+}
+
+/// A field of an encoded message.
+#[derive(Debug, Clone)]
+pub struct Field {
+    number: u32,
+    value: Value,
+}
+
+/// An encoded message rendered field by field, by name when its type is known from a descriptor
+/// set and by field number and wire type otherwise.
+#[derive(Debug, Clone)]
+pub struct ProtobufMessage {
+    type_name: Option<String>,
+    /// The path of every field from the top of the message, its type and its value
+    rows: Vec<(String, String, String)>,
+}
+
+/// Reads a base 128 varint, returning it with the number of bytes it took.
+pub fn varint(data: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, byte) in data.iter().enumerate().take(10) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Decodes the fields of a message, None when the bytes are not a well formed message.
+pub fn decode(data: &[u8]) -> Option<Vec<Field>> {
+    let (fields, length) = decode_fields(data, None, 0)?;
+    (length == data.len()).then_some(fields)
+}
+
+/// Decodes a message nested in a field of a message at some depth, None once too deep.
+fn nested(data: &[u8], depth: usize) -> Option<Vec<Field>> {
+    if depth >= MAX_DEPTH {
+        return None;
+    }
+    decode(data)
+}
+
+/// Decodes fields up to the end of the data, or up to the end of the group when in one.
+fn decode_fields(data: &[u8], group: Option<u32>, depth: usize) -> Option<(Vec<Field>, usize)> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let (key, length) = varint(&data[offset..])?;
+        offset += length;
+        let number = u32::try_from(key >> 3).ok().filter(|number| *number != 0)?;
+        let value = match (key & 0x07) as WireType {
+            VARINT => {
+                let (value, length) = varint(&data[offset..])?;
+                offset += length;
+                Value::Varint(value)
+            }
+            FIXED64 => {
+                let value = data.get(offset..offset + 8)?;
+                offset += 8;
+                Value::Fixed64(u64::from_le_bytes(value.try_into().unwrap()))
+            }
+            LENGTH_DELIMITED => {
+                let (length, prefix) = varint(&data[offset..])?;
+                offset += prefix;
+                let value = data.get(offset..offset.checked_add(usize::try_from(length).ok()?)?)?;
+                offset += value.len();
+                Value::Bytes(Vec::from(value))
+            }
+            START_GROUP => {
+                let (group, length) = decode_fields(&data[offset..], Some(number), depth + 1)?;
+                offset += length;
+                Value::Group(group)
+            }
+            END_GROUP => return (group == Some(number)).then_some((fields, offset)),
+            FIXED32 => {
+                let value = data.get(offset..offset + 4)?;
+                offset += 4;
+                Value::Fixed32(u32::from_le_bytes(value.try_into().unwrap()))
+            }
+            _ => return None
+        };
+        fields.push(Field { number, value });
+    }
+    // A group must be closed before the data runs out
+    group.is_none().then_some((fields, offset))
+}
+
+pub fn wire_type_name(wire_type: WireType) -> String {
+    match wire_type {
+        VARINT => String::from("varint"),
+        FIXED64 => String::from("fixed64"),
+        LENGTH_DELIMITED => String::from("length_delimited"),
+        START_GROUP => String::from("start_group"),
+        END_GROUP => String::from("end_group"),
+        FIXED32 => String::from("fixed32"),
+        _ => format!("{}", wire_type)
+    }
+}
+
+pub fn field_type_name(field_type: FieldType) -> String {
+    match field_type {
+        TYPE_DOUBLE => String::from("double"),
+        TYPE_FLOAT => String::from("float"),
+        TYPE_INT64 => String::from("int64"),
+        TYPE_UINT64 => String::from("uint64"),
+        TYPE_INT32 => String::from("int32"),
+        TYPE_FIXED64 => String::from("fixed64"),
+        TYPE_FIXED32 => String::from("fixed32"),
+        TYPE_BOOL => String::from("bool"),
+        TYPE_STRING => String::from("string"),
+        TYPE_GROUP => String::from("group"),
+        TYPE_MESSAGE => String::from("message"),
+        TYPE_BYTES => String::from("bytes"),
+        TYPE_UINT32 => String::from("uint32"),
+        TYPE_ENUM => String::from("enum"),
+        TYPE_SFIXED32 => String::from("sfixed32"),
+        TYPE_SFIXED64 => String::from("sfixed64"),
+        TYPE_SINT32 => String::from("sint32"),
+        TYPE_SINT64 => String::from("sint64"),
+        _ => format!("{}", field_type)
+    }
+}
+
+fn zigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Text worth showing as such: valid UTF-8 without control characters other than whitespace.
+fn printable(data: &[u8]) -> Option<&str> {
+    std::str::from_utf8(data).ok()
+        .filter(|text| !text.is_empty() && text.chars().all(|c| !c.is_control() || c.is_ascii_whitespace()))
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        String::from(name)
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+impl Field {
+    #[inline]
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    pub fn wire_type(&self) -> WireType {
+        match self.value {
+            Value::Varint(_) => VARINT,
+            Value::Fixed64(_) => FIXED64,
+            Value::Bytes(_) => LENGTH_DELIMITED,
+            Value::Group(_) => START_GROUP,
+            Value::Fixed32(_) => FIXED32,
+        }
+    }
+
+    /// The value of a varint field.
+    pub fn unsigned(&self) -> Option<u64> {
+        match self.value {
+            Value::Varint(value) => Some(value),
+            _ => None
+        }
+    }
+
+    /// The value of a length delimited field.
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match &self.value {
+            Value::Bytes(value) => Some(value),
+            _ => None
+        }
+    }
+
+    /// The value of a length delimited field read as UTF-8.
+    pub fn string(&self) -> Option<String> {
+        self.bytes().map(|value| String::from_utf8_lossy(value).into_owned())
+    }
+}
+
+/// The value of a scalar packed in a repeated field, with the number of bytes it took.
+fn packed_value(data: &[u8], field_type: FieldType, descriptors: &DescriptorSet, type_name: Option<&str>) -> Option<(String, usize)> {
+    let value = match field_type {
+        TYPE_DOUBLE | TYPE_FIXED64 | TYPE_SFIXED64 => {
+            let value = u64::from_le_bytes(data.get(..8)?.try_into().unwrap());
+            (scalar(&Value::Fixed64(value), field_type, descriptors, type_name)?, 8)
+        }
+        TYPE_FLOAT | TYPE_FIXED32 | TYPE_SFIXED32 => {
+            let value = u32::from_le_bytes(data.get(..4)?.try_into().unwrap());
+            (scalar(&Value::Fixed32(value), field_type, descriptors, type_name)?, 4)
+        }
+        _ => {
+            let (value, length) = varint(data)?;
+            (scalar(&Value::Varint(value), field_type, descriptors, type_name)?, length)
+        }
+    };
+    Some(value)
+}
+
+/// The value of a scalar field as its declared type reads it, None when the wire type does not
+/// fit the declared one.
+fn scalar(value: &Value, field_type: FieldType, descriptors: &DescriptorSet, type_name: Option<&str>) -> Option<String> {
+    let text = match (value, field_type) {
+        (Value::Varint(value), TYPE_INT64 | TYPE_INT32) => format!("{}", *value as i64),
+        (Value::Varint(value), TYPE_UINT64 | TYPE_UINT32) => format!("{}", value),
+        (Value::Varint(value), TYPE_SINT64 | TYPE_SINT32) => format!("{}", zigzag(*value)),
+        (Value::Varint(value), TYPE_BOOL) => format!("{}", *value != 0),
+        (Value::Varint(value), TYPE_ENUM) => {
+            let number = *value as i64 as i32;
+            match type_name.and_then(|type_name| descriptors.enum_value(type_name, number)) {
+                Some(name) => format!("{} ({})", name, number),
+                None => format!("{}", number)
+            }
+        }
+        (Value::Fixed64(value), TYPE_DOUBLE) => format!("{}", f64::from_bits(*value)),
+        (Value::Fixed64(value), TYPE_FIXED64) => format!("{}", value),
+        (Value::Fixed64(value), TYPE_SFIXED64) => format!("{}", *value as i64),
+        (Value::Fixed32(value), TYPE_FLOAT) => format!("{}", f32::from_bits(*value)),
+        (Value::Fixed32(value), TYPE_FIXED32) => format!("{}", value),
+        (Value::Fixed32(value), TYPE_SFIXED32) => format!("{}", *value as i32),
+        (Value::Bytes(value), TYPE_STRING) => format!("{:?}", String::from_utf8_lossy(value)),
+        (Value::Bytes(value), TYPE_BYTES) => hex(value),
+        _ => return None
+    };
+    Some(text)
+}
+
+impl ProtobufMessage {
+    /// Renders an encoded message, with the fields of the named type when the descriptor set
+    /// knows it. None when the bytes are not a message.
+    pub fn new(data: &[u8], descriptors: &DescriptorSet, type_name: Option<&str>) -> Option<Self> {
+        let fields = decode(data)?;
+        let type_name = type_name.filter(|type_name| descriptors.message(type_name).is_some());
+        let mut message = Self {
+            type_name: type_name.map(String::from),
+            rows: Vec::new(),
+        };
+        message.add_fields(&fields, "", descriptors, type_name, 0);
+        Some(message)
+    }
+
+    #[inline]
+    pub fn type_name(&self) -> Option<&str> {
+        self.type_name.as_deref()
+    }
+
+    fn add_fields(&mut self, fields: &[Field], path: &str, descriptors: &DescriptorSet, type_name: Option<&str>, depth: usize) {
+        let message = type_name.and_then(|type_name| descriptors.message(type_name));
+        for field in fields {
+            match message.and_then(|message| message.field(field.number)) {
+                Some(descriptor) => self.add_named(field, path, descriptors, descriptor, depth),
+                None => self.add_schemaless(field, path, depth)
+            }
+        }
+    }
+
+    /// Adds a field by its number and wire type, guessing what a length delimited value holds.
+    fn add_schemaless(&mut self, field: &Field, path: &str, depth: usize) {
+        let path = join(path, &field.number.to_string());
+        let kind = wire_type_name(field.wire_type());
+        match &field.value {
+            // Negative int32 and int64 values take the whole 64 bits
+            Value::Varint(value) | Value::Fixed64(value) if *value > i64::MAX as u64 => {
+                self.rows.push((path, kind, format!("{} ({})", value, *value as i64)));
+            }
+            Value::Varint(value) | Value::Fixed64(value) => self.rows.push((path, kind, value.to_string())),
+            Value::Fixed32(value) => self.rows.push((path, kind, value.to_string())),
+            Value::Group(fields) => {
+                self.rows.push((path.clone(), kind, String::new()));
+                for field in fields {
+                    self.add_schemaless(field, &path, depth + 1);
+                }
+            }
+            // Text first, as short strings often happen to decode as messages too
+            Value::Bytes(value) => if let Some(text) = printable(value) {
+                self.rows.push((path, String::from("string"), format!("{:?}", text)));
+            } else if let Some(fields) = nested(value, depth).filter(|fields| !fields.is_empty()) {
+                self.rows.push((path.clone(), String::from("message"), format!("{} bytes", value.len())));
+                for field in fields.iter() {
+                    self.add_schemaless(field, &path, depth + 1);
+                }
+            } else {
+                self.rows.push((path, kind, hex(value)));
+            }
+        }
+    }
+
+    /// Adds a field by the name and type its descriptor gives it.
+    fn add_named(&mut self, field: &Field, parent: &str, descriptors: &DescriptorSet, descriptor: &FieldDescriptor, depth: usize) {
+        let path = join(parent, descriptor.name());
+        let type_name = descriptor.type_name();
+        let kind = match (descriptor.field_type(), type_name) {
+            (TYPE_MESSAGE | TYPE_GROUP | TYPE_ENUM, Some(type_name)) => String::from(type_name),
+            (field_type, _) => field_type_name(field_type)
+        };
+        match (&field.value, descriptor.field_type()) {
+            (Value::Bytes(value), TYPE_MESSAGE) => match nested(value, depth) {
+                Some(fields) => {
+                    self.rows.push((path.clone(), kind, format!("{} bytes", value.len())));
+                    self.add_fields(&fields, &path, descriptors, type_name, depth + 1);
+                }
+                None => self.rows.push((path, kind, hex(value)))
+            },
+            (Value::Group(fields), TYPE_GROUP) => {
+                self.rows.push((path.clone(), kind, String::new()));
+                self.add_fields(fields, &path, descriptors, type_name, depth + 1);
+            }
+            // Repeated scalars are packed into a single length delimited value
+            (Value::Bytes(value), field_type) if !matches!(field_type, TYPE_STRING | TYPE_BYTES) => {
+                let mut values = Vec::new();
+                let mut offset = 0;
+                while offset < value.len() {
+                    match packed_value(&value[offset..], field_type, descriptors, type_name) {
+                        Some((text, length)) => {
+                            values.push(text);
+                            offset += length;
+                        }
+                        None => {
+                            self.rows.push((path, kind, hex(value)));
+                            return;
+                        }
+                    }
+                }
+                self.rows.push((path, format!("repeated {}", kind), format!("[{}]", values.join(", "))));
+            }
+            (value, field_type) => match scalar(value, field_type, descriptors, type_name) {
+                Some(text) => self.rows.push((path, kind, text)),
+                // The wire type contradicts the schema, so show what is there
+                None => self.add_schemaless(field, parent, depth)
+            }
+        }
+    }
+}
+
+impl Display for ProtobufMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.set_titles(row!["field", "type", "value"]);
+        for (path, kind, value) in self.rows.iter() {
+            table.add_row(row![path, kind, value]);
+        }
+
+        table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Field 1 holding the message, length delimited.
+    fn wrap(message: Vec<u8>) -> Vec<u8> {
+        let mut length = message.len() as u64;
+        let mut data = vec![0x0a];
+        while length >= 0x80 {
+            data.push(length as u8 | 0x80);
+            length >>= 7;
+        }
+        data.push(length as u8);
+        data.extend(message);
+        data
+    }
+
+    #[test]
+    fn decodes_varint() {
+        assert_eq!(varint(&[0x96, 0x01]), Some((150, 2)));
+        assert_eq!(varint(&[0x96]), None);
+    }
+
+    #[test]
+    fn decodes_fields() {
+        // The examples of the encoding guide: 150 in field 1, then "testing" in field 2
+        let fields = decode(&[0x08, 0x96, 0x01, 0x12, 0x07, b't', b'e', b's', b't', b'i', b'n', b'g']).unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].number(), 1);
+        assert_eq!(fields[0].unsigned(), Some(150));
+        assert_eq!(fields[1].string().as_deref(), Some("testing"));
+        assert!(decode(&[0x08]).is_none());
+    }
+
+    #[test]
+    fn stops_expanding_deep_messages() {
+        let mut data = vec![0x08, 0x01];
+        for _ in 0..10000 {
+            data = wrap(data);
+        }
+        let message = ProtobufMessage::new(&data, &DescriptorSet::default(), None).unwrap();
+        assert_eq!(message.rows.len(), MAX_DEPTH + 1);
+        assert_eq!(message.rows[MAX_DEPTH].1, "length_delimited");
+    }
+}
@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use prettytable::{format, row, table};
+use crate::util::*;
+
+pub const EVENT_PORT: u16 = 319;
+pub const GENERAL_PORT: u16 = 320;
+
+pub const HEADER_LENGTH: usize = 34;
+
+pub type MessageType = u8;
+
+pub const SYNC: MessageType = 0x0;
+pub const DELAY_REQ: MessageType = 0x1;
+pub const PDELAY_REQ: MessageType = 0x2;
+pub const PDELAY_RESP: MessageType = 0x3;
+pub const FOLLOW_UP: MessageType = 0x8;
+pub const DELAY_RESP: MessageType = 0x9;
+pub const PDELAY_RESP_FOLLOW_UP: MessageType = 0xA;
+pub const ANNOUNCE: MessageType = 0xB;
+pub const SIGNALING: MessageType = 0xC;
+pub const MANAGEMENT: MessageType = 0xD;
+
+pub const FLAG_LEAP_61: u16 = 0x0001;
+pub const FLAG_LEAP_59: u16 = 0x0002;
+pub const FLAG_UTC_OFFSET_VALID: u16 = 0x0004;
+pub const FLAG_PTP_TIMESCALE: u16 = 0x0008;
+pub const FLAG_TIME_TRACEABLE: u16 = 0x0010;
+pub const FLAG_FREQUENCY_TRACEABLE: u16 = 0x0020;
+pub const FLAG_ALTERNATE_MASTER: u16 = 0x0100;
+pub const FLAG_TWO_STEP: u16 = 0x0200;
+pub const FLAG_UNICAST: u16 = 0x0400;
+
+/// A clock identity and the number of one of its ports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PortIdentity {
+    clock_identity: [u8; 8],
+    port_number: u16,
+}
+
+/// Seconds and nanoseconds since the PTP epoch, which is the Unix epoch in TAI.
+#[derive(Debug, Clone, Copy)]
+pub struct PtpTimestamp {
+    seconds: u64,
+    nanoseconds: u32,
+}
+
+/// The grandmaster a master announces, which the best master clock algorithm picks from.
+#[derive(Debug, Clone)]
+pub struct Announce {
+    current_utc_offset: i16,
+    grandmaster_priority1: u8,
+    clock_class: u8,
+    clock_accuracy: u8,
+    offset_scaled_log_variance: u16,
+    grandmaster_priority2: u8,
+    grandmaster_identity: [u8; 8],
+    steps_removed: u16,
+    time_source: u8,
+}
+
+/// An IEEE 1588 version 2 message, over ethernet or UDP.
+#[derive(Debug, Clone)]
+pub struct PtpMessage {
+    major_sdo_id: u8,
+    message_type: MessageType,
+    version: u8,
+    length: u16,
+    domain: u8,
+    flags: u16,
+    /// Nanoseconds multiplied by 2^16, what transparent clocks added on the way
+    correction: i64,
+    source_port: PortIdentity,
+    sequence_id: u16,
+    log_message_interval: i8,
+    /// The origin, precise origin or receive timestamp the body starts with
+    timestamp: Option<PtpTimestamp>,
+    /// The port a Delay_Resp or Pdelay_Resp answers
+    requesting_port: Option<PortIdentity>,
+    announce: Option<Announce>,
+}
+
+/// The offset of a slave clock from its master and the mean path delay between them, from a
+/// Sync and a Delay_Req exchange.
+#[derive(Debug, Clone)]
+pub struct PtpExchange {
+    domain: u8,
+    master: PortIdentity,
+    slave: PortIdentity,
+    offset: i128,
+    mean_path_delay: i128,
+}
+
+/// The end-to-end exchanges in progress, with times in nanoseconds since the Unix epoch in UTC.
+/// The capture clock stands in for the slave clock, which holds when capturing on the slave.
+#[derive(Debug, Default)]
+pub struct PtpExchanges {
+    /// Two-step Sync messages waiting for their Follow_Up, with their capture time and correction
+    syncs: HashMap<(u8, PortIdentity, u16), (i128, i128)>,
+    /// The master time a Sync left and the time it arrived, for the last Sync of every domain
+    last_syncs: HashMap<u8, (PortIdentity, i128, i128)>,
+    /// Delay_Req messages waiting for their Delay_Resp, with the time they left
+    delay_requests: HashMap<(u8, PortIdentity, u16), i128>,
+    /// The offset of TAI from UTC announced in every domain using the PTP timescale
+    utc_offsets: HashMap<u8, i16>,
+}
+
+pub fn message_type_name(message_type: MessageType) -> String {
+    match message_type {
+        SYNC => String::from("sync"),
+        DELAY_REQ => String::from("delay_req"),
+        PDELAY_REQ => String::from("pdelay_req"),
+        PDELAY_RESP => String::from("pdelay_resp"),
+        FOLLOW_UP => String::from("follow_up"),
+        DELAY_RESP => String::from("delay_resp"),
+        PDELAY_RESP_FOLLOW_UP => String::from("pdelay_resp_follow_up"),
+        ANNOUNCE => String::from("announce"),
+        SIGNALING => String::from("signaling"),
+        MANAGEMENT => String::from("management"),
+        _ => String::from("unidentified")
+    }
+}
+
+pub fn time_source_name(time_source: u8) -> String {
+    match time_source {
+        0x10 => String::from("atomic_clock"),
+        0x20 => String::from("gnss"),
+        0x30 => String::from("terrestrial_radio"),
+        0x39 => String::from("serial_time_code"),
+        0x40 => String::from("ptp"),
+        0x50 => String::from("ntp"),
+        0x60 => String::from("hand_set"),
+        0x90 => String::from("other"),
+        0xa0 => String::from("internal_oscillator"),
+        _ => format!("{:02x}", time_source)
+    }
+}
+
+fn flag_names(flags: u16) -> String {
+    let names = [
+        (FLAG_LEAP_61, "leap61"),
+        (FLAG_LEAP_59, "leap59"),
+        (FLAG_UTC_OFFSET_VALID, "utc_offset_valid"),
+        (FLAG_PTP_TIMESCALE, "ptp_timescale"),
+        (FLAG_TIME_TRACEABLE, "time_traceable"),
+        (FLAG_FREQUENCY_TRACEABLE, "frequency_traceable"),
+        (FLAG_ALTERNATE_MASTER, "alternate_master"),
+        (FLAG_TWO_STEP, "two_step"),
+        (FLAG_UNICAST, "unicast"),
+    ];
+    let names: Vec<&str> = names.iter().filter(|(flag, _)| flags & flag != 0).map(|(_, name)| *name).collect();
+    names.join(", ")
+}
+
+fn clock_identity(identity: &[u8; 8]) -> String {
+    let bytes: Vec<String> = identity.iter().map(|byte| format!("{:02x}", byte)).collect();
+    bytes.join(":")
+}
+
+fn nanoseconds(nanoseconds: i128) -> String {
+    format!("{} ns ({:.6} ms)", nanoseconds, nanoseconds as f64 / 1_000_000.0)
+}
+
+impl PortIdentity {
+    fn new(data: &[u8]) -> Self {
+        Self {
+            clock_identity: data[0..8].try_into().unwrap(),
+            port_number: tou16(&data[8..10]),
+        }
+    }
+}
+
+impl Display for PortIdentity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} port {}", clock_identity(&self.clock_identity), self.port_number)
+    }
+}
+
+impl PtpTimestamp {
+    fn new(data: &[u8]) -> Self {
+        Self {
+            seconds: tou64(&[0, 0, data[0], data[1], data[2], data[3], data[4], data[5]]),
+            nanoseconds: tou32(&data[6..10]),
+        }
+    }
+
+    #[inline]
+    pub fn as_nanoseconds(&self) -> i128 {
+        self.seconds as i128 * 1_000_000_000 + self.nanoseconds as i128
+    }
+}
+
+impl Display for PtpTimestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:09}", self.seconds, self.nanoseconds)
+    }
+}
+
+impl Announce {
+    fn new(data: &[u8]) -> Self {
+        Self {
+            current_utc_offset: tou16(&data[0..2]) as i16,
+            grandmaster_priority1: data[3],
+            clock_class: data[4],
+            clock_accuracy: data[5],
+            offset_scaled_log_variance: tou16(&data[6..8]),
+            grandmaster_priority2: data[8],
+            grandmaster_identity: data[9..17].try_into().unwrap(),
+            steps_removed: tou16(&data[17..19]),
+            time_source: data[19],
+        }
+    }
+}
+
+impl PtpMessage {
+    /// Parses a message, None when it is shorter than its header or not version 2.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < HEADER_LENGTH || data[1] & 0x0f != 2 {
+            return None;
+        }
+        let message_type = data[0] & 0x0f;
+        let body = &data[HEADER_LENGTH..];
+        let timestamp = match message_type {
+            SYNC | DELAY_REQ | PDELAY_REQ | PDELAY_RESP | FOLLOW_UP | DELAY_RESP | PDELAY_RESP_FOLLOW_UP | ANNOUNCE
+                if body.len() >= 10 => Some(PtpTimestamp::new(body)),
+            _ => None
+        };
+        let requesting_port = match message_type {
+            DELAY_RESP | PDELAY_RESP | PDELAY_RESP_FOLLOW_UP if body.len() >= 20 => Some(PortIdentity::new(&body[10..20])),
+            _ => None
+        };
+        let announce = match message_type {
+            ANNOUNCE if body.len() >= 30 => Some(Announce::new(&body[10..30])),
+            _ => None
+        };
+        Some(Self {
+            major_sdo_id: data[0] >> 4,
+            message_type,
+            version: data[1] & 0x0f,
+            length: tou16(&data[2..4]),
+            domain: data[4],
+            flags: tou16(&data[6..8]),
+            correction: tou64(&data[8..16]) as i64,
+            source_port: PortIdentity::new(&data[20..30]),
+            sequence_id: tou16(&data[30..32]),
+            log_message_interval: data[33] as i8,
+            timestamp,
+            requesting_port,
+            announce,
+        })
+    }
+
+    #[inline]
+    pub fn has_flag(&self, flag: u16) -> bool {
+        self.flags & flag != 0
+    }
+
+    /// The correction field in whole nanoseconds.
+    #[inline]
+    pub fn correction(&self) -> i128 {
+        (self.correction >> 16) as i128
+    }
+}
+
+impl Display for PtpMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["message_type", format!("{} ({})", message_type_name(self.message_type), self.message_type)],
+            ["major_sdo_id", self.major_sdo_id],
+            ["version", self.version],
+            ["length", self.length],
+            ["domain", self.domain],
+            ["flags", flag_names(self.flags)],
+            ["correction", format!("{} ns", self.correction as f64 / 65536.0)],
+            ["source_port", self.source_port],
+            ["sequence_id", self.sequence_id],
+            ["log_message_interval", self.log_message_interval]
+        );
+        if let Some(timestamp) = self.timestamp {
+            let name = match self.message_type {
+                FOLLOW_UP => "precise_origin_timestamp",
+                DELAY_RESP | PDELAY_REQ | PDELAY_RESP => "receive_timestamp",
+                PDELAY_RESP_FOLLOW_UP => "response_origin_timestamp",
+                _ => "origin_timestamp"
+            };
+            table.add_row(row![name, timestamp]);
+        }
+        if let Some(requesting_port) = self.requesting_port {
+            table.add_row(row!["requesting_port", requesting_port]);
+        }
+        if let Some(announce) = &self.announce {
+            table.add_row(row!["current_utc_offset", format!("{} s", announce.current_utc_offset)]);
+            table.add_row(row!["grandmaster_priority1", announce.grandmaster_priority1]);
+            table.add_row(row!["clock_class", announce.clock_class]);
+            table.add_row(row!["clock_accuracy", format!("0x{:02x}", announce.clock_accuracy)]);
+            table.add_row(row!["offset_scaled_log_variance", format!("0x{:04x}", announce.offset_scaled_log_variance)]);
+            table.add_row(row!["grandmaster_priority2", announce.grandmaster_priority2]);
+            table.add_row(row!["grandmaster_identity", clock_identity(&announce.grandmaster_identity)]);
+            table.add_row(row!["steps_removed", announce.steps_removed]);
+            table.add_row(row!["time_source", time_source_name(announce.time_source)]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl PtpExchanges {
+    /// Follows the Sync, Follow_Up, Delay_Req and Delay_Resp messages of a domain, returning the
+    /// offset and delay once a Delay_Resp completes an exchange after a Sync.
+    pub fn update(&mut self, message: &PtpMessage, timestamp: Duration) -> Option<PtpExchange> {
+        let captured = timestamp.as_nanos() as i128;
+        let domain = message.domain;
+        let key = (domain, message.source_port, message.sequence_id);
+        // The PTP timescale is TAI, ahead of UTC by the announced offset
+        let utc_offset = self.utc_offsets.get(&domain).map(|offset| *offset as i128 * 1_000_000_000).unwrap_or_default();
+        match message.message_type {
+            ANNOUNCE => {
+                match &message.announce {
+                    Some(announce) if message.has_flag(FLAG_PTP_TIMESCALE) && message.has_flag(FLAG_UTC_OFFSET_VALID) => {
+                        self.utc_offsets.insert(domain, announce.current_utc_offset);
+                    }
+                    _ => {
+                        self.utc_offsets.remove(&domain);
+                    }
+                }
+            }
+            SYNC if message.has_flag(FLAG_TWO_STEP) => {
+                self.syncs.insert(key, (captured, message.correction()));
+            }
+            SYNC => {
+                let origin = message.timestamp?.as_nanoseconds() + message.correction() - utc_offset;
+                self.last_syncs.insert(domain, (message.source_port, origin, captured));
+            }
+            FOLLOW_UP => {
+                let (received, correction) = self.syncs.remove(&key)?;
+                let origin = message.timestamp?.as_nanoseconds() + correction + message.correction() - utc_offset;
+                self.last_syncs.insert(domain, (message.source_port, origin, received));
+            }
+            DELAY_REQ => {
+                self.delay_requests.insert(key, captured);
+            }
+            DELAY_RESP => {
+                let slave = message.requesting_port?;
+                let t3 = self.delay_requests.remove(&(domain, slave, message.sequence_id))?;
+                let t4 = message.timestamp?.as_nanoseconds() - message.correction() - utc_offset;
+                let (master, t1, t2) = *self.last_syncs.get(&domain)?;
+                let mean_path_delay = ((t2 - t1) + (t4 - t3)) / 2;
+                return Some(PtpExchange {
+                    domain,
+                    master,
+                    slave,
+                    offset: (t2 - t1) - mean_path_delay,
+                    mean_path_delay,
+                });
+            }
+            _ => ()
+        }
+        None
+    }
+}
+
+impl Display for PtpExchange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["domain", self.domain],
+            ["master", self.master],
+            ["slave", self.slave],
+            ["offset", nanoseconds(self.offset)],
+            ["mean_path_delay", nanoseconds(self.mean_path_delay)]
+        );
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
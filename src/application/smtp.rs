@@ -0,0 +1,253 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use prettytable::{format, row, Table};
+use crate::application::line::{self, Command, Reply, ReplyReader};
+
+/// The ports of relaying and of message submission.
+pub const PORTS: [u16; 2] = [25, 587];
+
+pub const REPLY_READY: u16 = 220;
+
+#[derive(Debug, Clone)]
+pub enum SmtpMessage {
+    Command(Command),
+    /// A reply and the command it answers, none for the greeting
+    Reply(Reply, Option<Command>),
+    Mail(SmtpMail),
+}
+
+/// A mail sent over the connection: its envelope and the message after DATA or BDAT.
+#[derive(Debug, Clone)]
+pub struct SmtpMail {
+    helo: Option<String>,
+    mail_from: String,
+    recipients: Vec<String>,
+    content: Vec<u8>,
+}
+
+/// The commands of the client, pipelined or not, the replies of the server and the envelope of
+/// the mail being sent.
+#[derive(Debug, Default)]
+pub struct SmtpConversation {
+    buffers: [Vec<u8>; 2],
+    replies: ReplyReader,
+    /// Commands waiting for their reply, oldest first
+    pending: VecDeque<Command>,
+    helo: Option<String>,
+    mail_from: Option<String>,
+    recipients: Vec<String>,
+    /// Whether the lines of the client are the message, after DATA
+    data: bool,
+    /// The message being received, since DATA or the first BDAT
+    content: Option<Vec<u8>>,
+    /// Bytes left of the current BDAT chunk, and whether it is the last
+    chunk: Option<(usize, bool)>,
+    encrypted: bool,
+}
+
+/// The address of a MAIL FROM or RCPT TO argument, between the angle brackets after the colon.
+fn path(argument: &str) -> String {
+    let (_, path) = argument.split_once(':').unwrap_or(("", argument));
+    let path = path.trim_start();
+    match path.strip_prefix('<').and_then(|path| path.split_once('>')) {
+        Some((address, _)) => String::from(address),
+        None => String::from(path.split(' ').next().unwrap_or_default())
+    }
+}
+
+impl SmtpMail {
+    /// The value of a header of the message, its folded lines joined.
+    pub fn header(&self, name: &str) -> Option<String> {
+        let text = String::from_utf8_lossy(&self.content);
+        let mut lines = text.split("\r\n").take_while(|line| !line.is_empty()).peekable();
+        while let Some(line) = lines.next() {
+            let Some((header, value)) = line.split_once(':') else {
+                continue;
+            };
+            if header.eq_ignore_ascii_case(name) {
+                let mut value = String::from(value.trim());
+                while let Some(continuation) = lines.next_if(|line| line.starts_with([' ', '\t'])) {
+                    value.push(' ');
+                    value.push_str(continuation.trim());
+                }
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+impl Display for SmtpMail {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        if let Some(helo) = &self.helo {
+            table.add_row(row!["helo", helo]);
+        }
+        table.add_row(row!["mail_from", self.mail_from]);
+        for recipient in self.recipients.iter() {
+            table.add_row(row!["rcpt_to", recipient]);
+        }
+        for header in ["From", "To", "Subject", "Date", "Message-ID"] {
+            if let Some(value) = self.header(header) {
+                table.add_row(row![header.to_ascii_lowercase(), value]);
+            }
+        }
+        table.add_row(row!["length", format!("{} bytes", self.content.len())]);
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl SmtpConversation {
+    /// Adds the data one frame brought to a direction, 0 for the client, and returns the
+    /// commands, replies and mails it completed.
+    pub fn push(&mut self, direction: usize, data: &[u8]) -> Vec<SmtpMessage> {
+        let mut messages = Vec::new();
+        if self.encrypted {
+            return messages;
+        }
+        self.buffers[direction].extend_from_slice(data);
+        if direction == 1 {
+            while let Some(line) = line::take_line(&mut self.buffers[1]) {
+                let Some(reply) = self.replies.push(&line) else {
+                    continue;
+                };
+                // An intermediate reply, such as 354 to DATA, leaves the command waiting for its final one
+                let command = if reply.code() / 100 == 3 { self.pending.front().cloned() } else { self.pending.pop_front() };
+                // A final reply to DATA refuses it, unless a later DATA is the one being sent
+                let data = command.as_ref().is_some_and(|command| command.verb() == "DATA");
+                if data && reply.code() / 100 != 3 && !self.pending.iter().any(|pending| pending.verb() == "DATA") {
+                    self.data = false;
+                    self.content = None;
+                }
+                let starttls = command.as_ref().is_some_and(|command| command.verb() == "STARTTLS");
+                if starttls && reply.code() == REPLY_READY {
+                    self.encrypted = true;
+                }
+                messages.push(SmtpMessage::Reply(reply, command));
+                if self.encrypted {
+                    break;
+                }
+            }
+            return messages;
+        }
+
+        loop {
+            if let Some((left, last)) = self.chunk {
+                let length = left.min(self.buffers[0].len());
+                let chunk: Vec<u8> = self.buffers[0].drain(..length).collect();
+                self.content.get_or_insert_with(Vec::new).extend_from_slice(&chunk);
+                if length < left {
+                    self.chunk = Some((left - length, last));
+                    break;
+                }
+                self.chunk = None;
+                if last {
+                    messages.push(SmtpMessage::Mail(self.mail()));
+                }
+                continue;
+            }
+            let Some(line) = line::take_line(&mut self.buffers[0]) else {
+                break;
+            };
+            if self.data {
+                // The message ends with a line holding a single dot, other leading dots are doubled
+                if line == "." {
+                    self.data = false;
+                    messages.push(SmtpMessage::Mail(self.mail()));
+                } else {
+                    let content = self.content.get_or_insert_with(Vec::new);
+                    content.extend_from_slice(line.strip_prefix('.').unwrap_or(&line).as_bytes());
+                    content.extend_from_slice(b"\r\n");
+                }
+                continue;
+            }
+            let command = Command::new(&line);
+            match command.verb() {
+                "HELO" | "EHLO" => self.helo = Some(String::from(command.argument())),
+                "MAIL" => {
+                    self.mail_from = Some(path(command.argument()));
+                    self.recipients.clear();
+                }
+                "RCPT" => self.recipients.push(path(command.argument())),
+                "RSET" => {
+                    self.mail_from = None;
+                    self.recipients.clear();
+                }
+                // The client waits for the 354 reply, a refusal ends the message again
+                "DATA" => self.data = true,
+                "BDAT" => {
+                    let mut arguments = command.argument().split(' ');
+                    if let Some(length) = arguments.next().and_then(|length| length.parse().ok()) {
+                        let last = arguments.next().is_some_and(|last| last.eq_ignore_ascii_case("LAST"));
+                        self.chunk = Some((length, last));
+                    }
+                }
+                _ => ()
+            }
+            self.pending.push_back(command.clone());
+            messages.push(SmtpMessage::Command(command));
+        }
+        messages
+    }
+
+    /// The mail just received, starting the next one with the same greeting.
+    fn mail(&mut self) -> SmtpMail {
+        SmtpMail {
+            helo: self.helo.clone(),
+            mail_from: self.mail_from.take().unwrap_or_default(),
+            recipients: std::mem::take(&mut self.recipients),
+            content: self.content.take().unwrap_or_default(),
+        }
+    }
+
+    /// Whether the connection switched to TLS after STARTTLS.
+    #[inline]
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(messages: &[SmtpMessage]) -> Vec<String> {
+        messages.iter().filter_map(|message| match message {
+            SmtpMessage::Command(command) => Some(String::from(command.verb())),
+            _ => None
+        }).collect()
+    }
+
+    #[test]
+    fn refused_data() {
+        let mut conversation = SmtpConversation::default();
+        conversation.push(1, b"220 mail.example.com ESMTP\r\n");
+        conversation.push(0, b"MAIL FROM:<alice@example.com>\r\n");
+        conversation.push(1, b"250 OK\r\n");
+        conversation.push(0, b"DATA\r\n");
+        conversation.push(1, b"554 No valid recipients\r\n");
+        let messages = conversation.push(0, b"RSET\r\nQUIT\r\n");
+        assert_eq!(commands(&messages), ["RSET", "QUIT"]);
+    }
+
+    #[test]
+    fn accepted_data() {
+        let mut conversation = SmtpConversation::default();
+        conversation.push(0, b"MAIL FROM:<alice@example.com>\r\nRCPT TO:<bob@example.com>\r\nDATA\r\n");
+        conversation.push(1, b"250 OK\r\n250 OK\r\n354 Go ahead\r\n");
+        let messages = conversation.push(0, b"Subject: hello\r\n\r\n..dot\r\n.\r\n");
+        let Some(SmtpMessage::Mail(mail)) = messages.last() else {
+            panic!("no mail");
+        };
+        assert_eq!(mail.mail_from, "alice@example.com");
+        assert_eq!(mail.recipients, ["bob@example.com"]);
+        assert_eq!(mail.content, b"Subject: hello\r\n\r\n.dot\r\n");
+        conversation.push(1, b"250 Queued\r\n");
+        assert_eq!(commands(&conversation.push(0, b"QUIT\r\n")), ["QUIT"]);
+    }
+}
@@ -0,0 +1,437 @@
+use std::fmt::{Display, Formatter};
+use std::net::Ipv4Addr;
+use prettytable::{format, row, Table};
+use crate::application::ber::{self, Element};
+use crate::util::*;
+
+pub const PORT: u16 = 161;
+pub const TRAP_PORT: u16 = 162;
+
+pub const VERSION_1: i64 = 0;
+pub const VERSION_2C: i64 = 1;
+pub const VERSION_3: i64 = 3;
+
+pub type PduType = u32;
+
+pub const GET_REQUEST: PduType = 0;
+pub const GET_NEXT_REQUEST: PduType = 1;
+pub const RESPONSE: PduType = 2;
+pub const SET_REQUEST: PduType = 3;
+pub const TRAP_V1: PduType = 4;
+pub const GET_BULK_REQUEST: PduType = 5;
+pub const INFORM_REQUEST: PduType = 6;
+pub const TRAP_V2: PduType = 7;
+pub const REPORT: PduType = 8;
+
+/// The application types of variable binding values.
+pub const IP_ADDRESS: ber::Tag = 0;
+pub const COUNTER32: ber::Tag = 1;
+pub const GAUGE32: ber::Tag = 2;
+pub const TIME_TICKS: ber::Tag = 3;
+pub const OPAQUE: ber::Tag = 4;
+pub const COUNTER64: ber::Tag = 6;
+
+/// The context specific exceptions a response gives in place of a value.
+pub const NO_SUCH_OBJECT: ber::Tag = 0;
+pub const NO_SUCH_INSTANCE: ber::Tag = 1;
+pub const END_OF_MIB_VIEW: ber::Tag = 2;
+
+pub const FLAG_AUTH: u8 = 0x01;
+pub const FLAG_PRIV: u8 = 0x02;
+pub const FLAG_REPORTABLE: u8 = 0x04;
+
+pub const SECURITY_MODEL_USM: i64 = 3;
+
+/// The header of an SNMPv3 message and the user based security model parameters in it.
+#[derive(Debug, Clone)]
+pub struct SnmpV3Header {
+    message_id: i64,
+    max_size: i64,
+    flags: u8,
+    security_model: i64,
+    engine_id: Vec<u8>,
+    engine_boots: i64,
+    engine_time: i64,
+    user_name: String,
+    authentication_parameters: Vec<u8>,
+    privacy_parameters: Vec<u8>,
+    /// The engine and context of the scoped PDU, when it is not encrypted
+    context: Option<(Vec<u8>, String)>,
+}
+
+/// A protocol data unit with its variable bindings, each an object identifier and a value.
+#[derive(Debug, Clone)]
+pub struct SnmpPdu {
+    pdu_type: PduType,
+    request_id: i64,
+    /// The error status, or the non repeaters of a GetBulk
+    error_status: i64,
+    /// The error index, or the max repetitions of a GetBulk
+    error_index: i64,
+    /// Enterprise, agent address, generic and specific trap and time stamp of a version 1 trap
+    trap: Option<(String, String, i64, i64, u64)>,
+    bindings: Vec<(String, String)>,
+}
+
+/// An SNMP message: the version, the community or SNMPv3 header and the PDU.
+#[derive(Debug, Clone)]
+pub struct SnmpMessage {
+    version: i64,
+    community: Option<String>,
+    header: Option<SnmpV3Header>,
+    pdu: Option<SnmpPdu>,
+    /// The length of the encrypted scoped PDU
+    encrypted: Option<usize>,
+}
+
+pub fn version_name(version: i64) -> String {
+    match version {
+        VERSION_1 => String::from("v1"),
+        VERSION_2C => String::from("v2c"),
+        VERSION_3 => String::from("v3"),
+        _ => format!("{}", version)
+    }
+}
+
+pub fn pdu_name(pdu_type: PduType) -> String {
+    match pdu_type {
+        GET_REQUEST => String::from("get_request"),
+        GET_NEXT_REQUEST => String::from("get_next_request"),
+        RESPONSE => String::from("response"),
+        SET_REQUEST => String::from("set_request"),
+        TRAP_V1 => String::from("trap"),
+        GET_BULK_REQUEST => String::from("get_bulk_request"),
+        INFORM_REQUEST => String::from("inform_request"),
+        TRAP_V2 => String::from("snmpv2_trap"),
+        REPORT => String::from("report"),
+        _ => format!("{}", pdu_type)
+    }
+}
+
+pub fn error_status_name(status: i64) -> String {
+    match status {
+        0 => String::from("no_error"),
+        1 => String::from("too_big"),
+        2 => String::from("no_such_name"),
+        3 => String::from("bad_value"),
+        4 => String::from("read_only"),
+        5 => String::from("gen_err"),
+        6 => String::from("no_access"),
+        7 => String::from("wrong_type"),
+        8 => String::from("wrong_length"),
+        9 => String::from("wrong_encoding"),
+        10 => String::from("wrong_value"),
+        11 => String::from("no_creation"),
+        12 => String::from("inconsistent_value"),
+        13 => String::from("resource_unavailable"),
+        14 => String::from("commit_failed"),
+        15 => String::from("undo_failed"),
+        16 => String::from("authorization_error"),
+        17 => String::from("not_writable"),
+        18 => String::from("inconsistent_name"),
+        _ => format!("{}", status)
+    }
+}
+
+pub fn generic_trap_name(trap: i64) -> String {
+    match trap {
+        0 => String::from("cold_start"),
+        1 => String::from("warm_start"),
+        2 => String::from("link_down"),
+        3 => String::from("link_up"),
+        4 => String::from("authentication_failure"),
+        5 => String::from("egp_neighbor_loss"),
+        6 => String::from("enterprise_specific"),
+        _ => format!("{}", trap)
+    }
+}
+
+pub fn security_model_name(model: i64) -> String {
+    match model {
+        1 => String::from("snmpv1"),
+        2 => String::from("snmpv2c"),
+        SECURITY_MODEL_USM => String::from("usm"),
+        4 => String::from("tsm"),
+        _ => format!("{}", model)
+    }
+}
+
+/// Hundredths of a second as days, hours, minutes and seconds.
+fn time_ticks(ticks: u64) -> String {
+    let seconds = ticks / 100;
+    format!("{} ({}d {:02}:{:02}:{:02}.{:02})", ticks, seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60,
+            seconds % 60, ticks % 100)
+}
+
+/// The value of a variable binding and its type.
+fn binding_value(element: &Element) -> String {
+    let unsigned = || element.unsigned().map(|value| value.to_string()).unwrap_or_else(|| hex(element.value()));
+    match element.class() {
+        ber::CLASS_APPLICATION => match element.tag() {
+            IP_ADDRESS if element.value().len() == 4 => {
+                let address: [u8; 4] = element.value().try_into().unwrap();
+                format!("{} (ip_address)", Ipv4Addr::from(address))
+            }
+            COUNTER32 => format!("{} (counter32)", unsigned()),
+            GAUGE32 => format!("{} (gauge32)", unsigned()),
+            TIME_TICKS => match element.unsigned() {
+                Some(ticks) => format!("{} (time_ticks)", time_ticks(ticks)),
+                None => format!("{} (time_ticks)", hex(element.value()))
+            },
+            OPAQUE => format!("{} (opaque)", hex(element.value())),
+            COUNTER64 => format!("{} (counter64)", unsigned()),
+            tag => format!("{} ([application {}])", hex(element.value()), tag)
+        },
+        ber::CLASS_CONTEXT if !element.constructed() => match element.tag() {
+            NO_SUCH_OBJECT => String::from("no_such_object"),
+            NO_SUCH_INSTANCE => String::from("no_such_instance"),
+            END_OF_MIB_VIEW => String::from("end_of_mib_view"),
+            tag => format!("{} ([context {}])", hex(element.value()), tag)
+        },
+        _ if element.is_universal(ber::NULL) => String::from("null"),
+        _ => format!("{} ({})", element.describe(), element.tag_name())
+    }
+}
+
+impl SnmpV3Header {
+    /// Parses the global data and the security parameters of an SNMPv3 message.
+    fn new(global: &Element, security: &Element) -> Option<Self> {
+        let global = global.children();
+        let [message_id, max_size, flags, security_model] = global.as_slice() else {
+            return None;
+        };
+        let mut header = Self {
+            message_id: message_id.integer()?,
+            max_size: max_size.integer()?,
+            flags: *flags.value().first()?,
+            security_model: security_model.integer()?,
+            engine_id: Vec::new(),
+            engine_boots: 0,
+            engine_time: 0,
+            user_name: String::new(),
+            authentication_parameters: Vec::new(),
+            privacy_parameters: Vec::new(),
+            context: None,
+        };
+        // The security parameters are an octet string holding the encoded USM sequence
+        if header.security_model == SECURITY_MODEL_USM {
+            let (usm, _) = Element::parse(security.value())?;
+            let usm = usm.children();
+            let [engine_id, engine_boots, engine_time, user_name, authentication, privacy] = usm.as_slice() else {
+                return None;
+            };
+            header.engine_id = Vec::from(engine_id.value());
+            header.engine_boots = engine_boots.integer().unwrap_or(0);
+            header.engine_time = engine_time.integer().unwrap_or(0);
+            header.user_name = user_name.string();
+            header.authentication_parameters = Vec::from(authentication.value());
+            header.privacy_parameters = Vec::from(privacy.value());
+        }
+        Some(header)
+    }
+
+    fn add_rows(&self, table: &mut Table) {
+        let mut flags = Vec::new();
+        for (flag, name) in [(FLAG_AUTH, "auth"), (FLAG_PRIV, "priv"), (FLAG_REPORTABLE, "reportable")] {
+            if self.flags & flag != 0 {
+                flags.push(name);
+            }
+        }
+        table.add_row(row!["message_id", self.message_id]);
+        table.add_row(row!["max_size", self.max_size]);
+        table.add_row(row!["flags", format!("0x{:02x} ({})", self.flags, flags.join(", "))]);
+        table.add_row(row!["security_model", format!("{} ({})", security_model_name(self.security_model), self.security_model)]);
+        if self.security_model == SECURITY_MODEL_USM {
+            table.add_row(row!["engine_id", hex(&self.engine_id)]);
+            table.add_row(row!["engine_boots", self.engine_boots]);
+            table.add_row(row!["engine_time", self.engine_time]);
+            table.add_row(row!["user_name", self.user_name]);
+            table.add_row(row!["authentication_parameters", hex(&self.authentication_parameters)]);
+            table.add_row(row!["privacy_parameters", hex(&self.privacy_parameters)]);
+        }
+        if let Some((engine_id, name)) = &self.context {
+            table.add_row(row!["context_engine_id", hex(engine_id)]);
+            table.add_row(row!["context_name", name]);
+        }
+    }
+}
+
+impl SnmpPdu {
+    /// Parses a PDU, a context specific constructed element tagged with its type.
+    pub fn new(element: &Element) -> Option<Self> {
+        if element.class() != ber::CLASS_CONTEXT || !element.constructed() {
+            return None;
+        }
+        let children = element.children();
+        let mut pdu = Self {
+            pdu_type: element.tag(),
+            request_id: 0,
+            error_status: 0,
+            error_index: 0,
+            trap: None,
+            bindings: Vec::new(),
+        };
+        let bindings = if pdu.pdu_type == TRAP_V1 {
+            let [enterprise, agent_address, generic, specific, time_stamp, bindings] = children.as_slice() else {
+                return None;
+            };
+            let agent_address = match <[u8; 4]>::try_from(agent_address.value()) {
+                Ok(address) => Ipv4Addr::from(address).to_string(),
+                Err(_) => hex(agent_address.value())
+            };
+            pdu.trap = Some((
+                enterprise.oid()?,
+                agent_address,
+                generic.integer()?,
+                specific.integer()?,
+                time_stamp.unsigned()?,
+            ));
+            bindings
+        } else {
+            let [request_id, error_status, error_index, bindings] = children.as_slice() else {
+                return None;
+            };
+            pdu.request_id = request_id.integer()?;
+            pdu.error_status = error_status.integer()?;
+            pdu.error_index = error_index.integer()?;
+            bindings
+        };
+        for binding in bindings.children() {
+            let binding = binding.children();
+            let [name, value] = binding.as_slice() else {
+                return None;
+            };
+            pdu.bindings.push((name.oid()?, binding_value(value)));
+        }
+        Some(pdu)
+    }
+
+    fn add_rows(&self, table: &mut Table) {
+        table.add_row(row!["pdu_type", format!("{} ({})", pdu_name(self.pdu_type), self.pdu_type)]);
+        if let Some((enterprise, agent_address, generic, specific, time_stamp)) = &self.trap {
+            table.add_row(row!["enterprise", enterprise]);
+            table.add_row(row!["agent_address", agent_address]);
+            table.add_row(row!["generic_trap", format!("{} ({})", generic_trap_name(*generic), generic)]);
+            table.add_row(row!["specific_trap", specific]);
+            table.add_row(row!["time_stamp", time_ticks(*time_stamp)]);
+        } else {
+            table.add_row(row!["request_id", self.request_id]);
+            if self.pdu_type == GET_BULK_REQUEST {
+                table.add_row(row!["non_repeaters", self.error_status]);
+                table.add_row(row!["max_repetitions", self.error_index]);
+            } else {
+                table.add_row(row!["error_status", format!("{} ({})", error_status_name(self.error_status), self.error_status)]);
+                table.add_row(row!["error_index", self.error_index]);
+            }
+        }
+        for (name, value) in self.bindings.iter() {
+            table.add_row(row![name, value]);
+        }
+    }
+}
+
+impl SnmpMessage {
+    /// Parses a message, None when it is not a well formed one of a known version.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        let (message, _) = Element::parse(data)?;
+        if !message.is_universal(ber::SEQUENCE) {
+            return None;
+        }
+        let children = message.children();
+        let version = children.first()?.integer()?;
+        match (version, children.as_slice()) {
+            (VERSION_1 | VERSION_2C, [_, community, pdu]) => Some(Self {
+                version,
+                community: Some(community.string()),
+                header: None,
+                pdu: Some(SnmpPdu::new(pdu)?),
+                encrypted: None,
+            }),
+            (VERSION_3, [_, global, security, data]) => {
+                let mut header = SnmpV3Header::new(global, security)?;
+                let mut message = Self {
+                    version,
+                    community: None,
+                    header: None,
+                    pdu: None,
+                    encrypted: None,
+                };
+                // An encrypted scoped PDU is an octet string, a plain one a sequence
+                if data.is_universal(ber::OCTET_STRING) {
+                    message.encrypted = Some(data.value().len());
+                } else {
+                    let scoped = data.children();
+                    let [engine_id, name, pdu] = scoped.as_slice() else {
+                        return None;
+                    };
+                    header.context = Some((Vec::from(engine_id.value()), name.string()));
+                    message.pdu = Some(SnmpPdu::new(pdu)?);
+                }
+                message.header = Some(header);
+                Some(message)
+            }
+            _ => None
+        }
+    }
+}
+
+impl Display for SnmpMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.add_row(row!["version", format!("{} ({})", version_name(self.version), self.version)]);
+        if let Some(community) = &self.community {
+            table.add_row(row!["community", community]);
+        }
+        if let Some(header) = &self.header {
+            header.add_rows(&mut table);
+        }
+        if let Some(length) = self.encrypted {
+            table.add_row(row!["scoped_pdu", format!("encrypted, {} bytes", length)]);
+        }
+        if let Some(pdu) = &self.pdu {
+            pdu.add_rows(&mut table);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_v1_get_request() {
+        // GetRequest for sysDescr.0 with the community "public"
+        let data = unhex(concat!(
+            "302902010004067075626c6963a01c020400000001020100020100",
+            "300e300c06082b060102010101000500"
+        )).unwrap();
+        let message = SnmpMessage::new(&data).unwrap();
+        assert_eq!(message.version, VERSION_1);
+        assert_eq!(message.community.as_deref(), Some("public"));
+        let pdu = message.pdu.unwrap();
+        assert_eq!((pdu.pdu_type, pdu.request_id, pdu.error_status), (GET_REQUEST, 1, 0));
+        assert_eq!(pdu.bindings, [(String::from("1.3.6.1.2.1.1.1.0"), String::from("null"))]);
+    }
+
+    #[test]
+    fn parses_v2c_response() {
+        // GetResponse with sysUpTime.0 as time ticks
+        let data = unhex(concat!(
+            "302c02010104067075626c6963a21f020400000002020100020100",
+            "3011300f06082b06010201010300430300ea60"
+        )).unwrap();
+        let message = SnmpMessage::new(&data).unwrap();
+        assert_eq!(message.version, VERSION_2C);
+        let pdu = message.pdu.unwrap();
+        assert_eq!(pdu.pdu_type, RESPONSE);
+        assert_eq!(pdu.bindings[0].0, "1.3.6.1.2.1.1.3.0");
+        assert!(pdu.bindings[0].1.ends_with("(time_ticks)"));
+        assert!(SnmpMessage::new(&data[..data.len() - 1]).is_none());
+    }
+}
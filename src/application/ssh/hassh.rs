@@ -0,0 +1,132 @@
+use std::fmt::{Display, Formatter};
+use md5::{Digest, Md5};
+use prettytable::{format, row, table, Table};
+use crate::application::ssh::KexInit;
+use crate::util::*;
+
+/// The HASSH fingerprint of the algorithms a client offers in its KEXINIT, HASSHServer for a
+/// server.
+#[derive(Debug, Clone)]
+pub struct Hassh {
+    client: bool,
+    /// The string HASSH hashes: key exchange, encryption, MAC and compression algorithms
+    text: String,
+    hash: String,
+    /// The software version of the identification string
+    software: Option<String>,
+}
+
+/// How often every HASSH was seen in the capture, with the software sending it.
+#[derive(Debug, Default)]
+pub struct Hasshes {
+    seen: Vec<(Hassh, usize)>,
+}
+
+impl Hassh {
+    pub fn new(kexinit: &KexInit, client: bool, software: Option<&str>) -> Self {
+        // Each side lists its algorithms for the direction it sends in
+        let direction = if client { 0 } else { 1 };
+        let text = [
+            kexinit.kex_algorithms(),
+            kexinit.encryption_algorithms(direction),
+            kexinit.mac_algorithms(direction),
+            kexinit.compression_algorithms(direction),
+        ].map(|algorithms| algorithms.join(",")).join(";");
+        Self {
+            client,
+            hash: hex(&Md5::digest(text.as_bytes())).to_lowercase(),
+            text,
+            software: software.map(String::from),
+        }
+    }
+}
+
+impl Display for Hassh {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = if self.client { "hassh" } else { "hassh_server" };
+        let mut table = table!(
+            [name, self.hash],
+            [format!("{}_text", name), self.text]
+        );
+        if let Some(software) = &self.software {
+            table.add_row(row!["software", software]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl Hasshes {
+    pub fn update(&mut self, hassh: &Hassh) {
+        let seen = self.seen.iter_mut()
+            .find(|(known, _)| known.client == hassh.client && known.hash == hassh.hash && known.software == hassh.software);
+        match seen {
+            Some((_, count)) => *count += 1,
+            None => self.seen.push((hassh.clone(), 1))
+        }
+    }
+}
+
+impl Display for Hasshes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.set_titles(row!["side", "hassh", "software", "count"]);
+        for (hassh, count) in self.seen.iter() {
+            table.add_row(row![
+                if hassh.client { "client" } else { "server" },
+                hassh.hash,
+                hassh.software.as_deref().unwrap_or("unidentified"),
+                count
+            ]);
+        }
+
+        table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(names: &str) -> Vec<String> {
+        names.split(',').map(String::from).collect()
+    }
+
+    #[test]
+    fn matches_published_hassh() {
+        // The OpenSSH client example of the HASSH README, hashing to ec7378c1a92f5a8dde7e8b7a1ddf33d1
+        let kex = "curve25519-sha256,curve25519-sha256@libssh.org,ecdh-sha2-nistp256,ecdh-sha2-nistp384,\
+            ecdh-sha2-nistp521,diffie-hellman-group-exchange-sha256,diffie-hellman-group16-sha512,\
+            diffie-hellman-group18-sha512,diffie-hellman-group14-sha256,diffie-hellman-group14-sha1,ext-info-c";
+        let encryption = "chacha20-poly1305@openssh.com,aes128-ctr,aes192-ctr,aes256-ctr,\
+            aes128-gcm@openssh.com,aes256-gcm@openssh.com";
+        let mac = "umac-64-etm@openssh.com,umac-128-etm@openssh.com,hmac-sha2-256-etm@openssh.com,\
+            hmac-sha2-512-etm@openssh.com,hmac-sha1-etm@openssh.com,umac-64@openssh.com,umac-128@openssh.com,\
+            hmac-sha2-256,hmac-sha2-512,hmac-sha1";
+        let compression = "none,zlib@openssh.com,zlib";
+        let kexinit = KexInit {
+            cookie: vec![0; 16],
+            lists: vec![
+                list(kex), list("ssh-ed25519"),
+                list(encryption), list("aes256-ctr"),
+                list(mac), list("hmac-sha2-256"),
+                list(compression), list("none"),
+                Vec::new(), Vec::new(),
+            ],
+            first_kex_packet_follows: false,
+        };
+
+        let hassh = Hassh::new(&kexinit, true, None);
+        assert_eq!(hassh.text, [kex, encryption, mac, compression].join(";"));
+        assert_eq!(hassh.hash, "ec7378c1a92f5a8dde7e8b7a1ddf33d1");
+
+        let server = Hassh::new(&kexinit, false, None);
+        assert_eq!(server.text, format!("{};aes256-ctr;hmac-sha2-256;none", kex));
+    }
+}
@@ -0,0 +1,530 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, row, Table};
+use sha2::{Digest, Sha256};
+use crate::application::line;
+use crate::application::ssh::hassh::Hassh;
+use crate::util::*;
+
+pub mod hassh;
+
+pub const PORT: u16 = 22;
+
+/// Longest packet framed, longer lengths being taken for encrypted data.
+const MAXIMUM_PACKET: usize = 1 << 18;
+
+/// Longest line waited for before the identification string.
+const MAXIMUM_LINE: usize = 1 << 13;
+
+/// Longest public value printed in hexadecimal, as the point of an ECDH key exchange on P-521.
+const MAXIMUM_PRINTED_VALUE: usize = 133;
+
+pub type MessageType = u8;
+
+pub const DISCONNECT: MessageType = 1;
+pub const IGNORE: MessageType = 2;
+pub const UNIMPLEMENTED: MessageType = 3;
+pub const DEBUG: MessageType = 4;
+pub const SERVICE_REQUEST: MessageType = 5;
+pub const SERVICE_ACCEPT: MessageType = 6;
+pub const EXT_INFO: MessageType = 7;
+pub const KEXINIT: MessageType = 20;
+pub const NEWKEYS: MessageType = 21;
+/// Also KEX_ECDH_INIT, and KEX_DH_GEX_REQUEST_OLD in a group exchange
+pub const KEXDH_INIT: MessageType = 30;
+/// Also KEX_ECDH_REPLY, and KEX_DH_GEX_GROUP in a group exchange
+pub const KEXDH_REPLY: MessageType = 31;
+pub const KEX_DH_GEX_REQUEST_OLD: MessageType = 30;
+pub const KEX_DH_GEX_GROUP: MessageType = 31;
+pub const KEX_DH_GEX_INIT: MessageType = 32;
+pub const KEX_DH_GEX_REPLY: MessageType = 33;
+pub const KEX_DH_GEX_REQUEST: MessageType = 34;
+
+/// The names of the lists of a KEXINIT, in their order.
+const LIST_NAMES: [&str; 10] = [
+    "kex_algorithms",
+    "server_host_key_algorithms",
+    "encryption_client_to_server",
+    "encryption_server_to_client",
+    "mac_client_to_server",
+    "mac_server_to_client",
+    "compression_client_to_server",
+    "compression_server_to_client",
+    "languages_client_to_server",
+    "languages_server_to_client",
+];
+
+/// Whether a key exchange method negotiates its group with messages 30 to 34 rather than using
+/// a fixed group or curve.
+fn is_group_exchange(kex: &str) -> bool {
+    kex.starts_with("diffie-hellman-group-exchange-")
+}
+
+/// Whether a key exchange method exchanges elliptic curve points, or hybrids of them, rather
+/// than Diffie-Hellman values.
+fn is_ecdh(kex: &str) -> bool {
+    !kex.starts_with("diffie-hellman-")
+}
+
+/// The name of a message type, the key exchange messages named after the method negotiated.
+pub fn message_name(message_type: MessageType, kex: Option<&str>) -> String {
+    let group_exchange = kex.is_some_and(is_group_exchange);
+    let ecdh = kex.is_some_and(is_ecdh);
+    let name = match message_type {
+        DISCONNECT => "disconnect",
+        IGNORE => "ignore",
+        UNIMPLEMENTED => "unimplemented",
+        DEBUG => "debug",
+        SERVICE_REQUEST => "service_request",
+        SERVICE_ACCEPT => "service_accept",
+        EXT_INFO => "ext_info",
+        KEXINIT => "kexinit",
+        NEWKEYS => "newkeys",
+        KEX_DH_GEX_REQUEST_OLD if group_exchange => "kex_dh_gex_request_old",
+        KEX_DH_GEX_GROUP if group_exchange => "kex_dh_gex_group",
+        KEX_DH_GEX_INIT if group_exchange => "kex_dh_gex_init",
+        KEX_DH_GEX_REPLY if group_exchange => "kex_dh_gex_reply",
+        KEX_DH_GEX_REQUEST if group_exchange => "kex_dh_gex_request",
+        KEXDH_INIT if ecdh => "kex_ecdh_init",
+        KEXDH_REPLY if ecdh => "kex_ecdh_reply",
+        KEXDH_INIT => "kexdh_init",
+        KEXDH_REPLY => "kexdh_reply",
+        message_type => return format!("{}", message_type)
+    };
+    String::from(name)
+}
+
+/// Reads the fields of a message: bytes, uint32 and the strings every other field is made of.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        if self.data.len() < length {
+            return None;
+        }
+        let (bytes, rest) = self.data.split_at(length);
+        self.data = rest;
+        Some(bytes)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(tou32(self.bytes(4)?))
+    }
+
+    fn string(&mut self) -> Option<&'a [u8]> {
+        let length = self.u32()? as usize;
+        self.bytes(length)
+    }
+
+    fn text(&mut self) -> Option<String> {
+        Some(String::from_utf8_lossy(self.string()?).into_owned())
+    }
+
+    fn name_list(&mut self) -> Option<Vec<String>> {
+        let text = self.text()?;
+        if text.is_empty() {
+            return Some(Vec::new());
+        }
+        Some(text.split(',').map(String::from).collect())
+    }
+}
+
+/// The length in bits of an mpint, its sign left out.
+fn mpint_bits(value: &[u8]) -> usize {
+    match value.iter().position(|byte| *byte != 0) {
+        Some(first) => (value.len() - first) * 8 - value[first].leading_zeros() as usize,
+        None => 0
+    }
+}
+
+/// A public value in hexadecimal when short, as a point on a curve, or its length.
+fn describe_value(value: &[u8]) -> String {
+    if value.len() <= MAXIMUM_PRINTED_VALUE {
+        hex(value)
+    } else {
+        format!("{} bits", mpint_bits(value))
+    }
+}
+
+/// The line each side starts with, as "SSH-2.0-OpenSSH_9.6 Ubuntu-3".
+#[derive(Debug, Clone)]
+pub struct SshIdentification {
+    protocol_version: String,
+    software_version: String,
+    comments: Option<String>,
+}
+
+impl SshIdentification {
+    pub fn new(line: &str) -> Option<Self> {
+        let rest = line.strip_prefix("SSH-")?;
+        let (protocol_version, rest) = rest.split_once('-')?;
+        let (software_version, comments) = match rest.split_once(' ') {
+            Some((software_version, comments)) => (software_version, Some(String::from(comments))),
+            None => (rest, None)
+        };
+        Some(Self {
+            protocol_version: String::from(protocol_version),
+            software_version: String::from(software_version),
+            comments,
+        })
+    }
+
+    #[inline]
+    pub fn software_version(&self) -> &str {
+        &self.software_version
+    }
+}
+
+impl Display for SshIdentification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.add_row(row!["protocol_version", self.protocol_version]);
+        table.add_row(row!["software_version", self.software_version]);
+        if let Some(comments) = &self.comments {
+            table.add_row(row!["comments", comments]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+/// The algorithms a side supports, each list in its order of preference.
+#[derive(Debug, Clone)]
+pub struct KexInit {
+    cookie: Vec<u8>,
+    lists: Vec<Vec<String>>,
+    first_kex_packet_follows: bool,
+}
+
+impl KexInit {
+    fn new(reader: &mut Reader) -> Option<Self> {
+        let cookie = reader.bytes(16)?.to_vec();
+        let lists = (0..LIST_NAMES.len()).map(|_| reader.name_list()).collect::<Option<_>>()?;
+        Some(Self {
+            cookie,
+            lists,
+            first_kex_packet_follows: reader.byte()? != 0,
+        })
+    }
+
+    #[inline]
+    pub fn kex_algorithms(&self) -> &[String] {
+        &self.lists[0]
+    }
+
+    /// The ciphers for a direction, 0 from the client to the server.
+    #[inline]
+    pub fn encryption_algorithms(&self, direction: usize) -> &[String] {
+        &self.lists[2 + direction]
+    }
+
+    #[inline]
+    pub fn mac_algorithms(&self, direction: usize) -> &[String] {
+        &self.lists[4 + direction]
+    }
+
+    #[inline]
+    pub fn compression_algorithms(&self, direction: usize) -> &[String] {
+        &self.lists[6 + direction]
+    }
+
+    fn add_rows(&self, table: &mut Table) {
+        table.add_row(row!["cookie", hex(&self.cookie)]);
+        for (name, list) in LIST_NAMES.iter().zip(self.lists.iter()) {
+            table.add_row(row![name, list.join(",")]);
+        }
+        table.add_row(row!["first_kex_packet_follows", self.first_kex_packet_follows]);
+    }
+}
+
+/// The public host key of a server, as sent in its key exchange reply.
+#[derive(Debug, Clone)]
+pub struct HostKey {
+    blob: Vec<u8>,
+}
+
+impl HostKey {
+    pub fn key_type(&self) -> String {
+        Reader::new(&self.blob).text().unwrap_or_default()
+    }
+
+    /// The fingerprint OpenSSH prints, the unpadded base64 of the SHA-256 of the key.
+    pub fn fingerprint(&self) -> String {
+        let digest = base64(&Sha256::digest(&self.blob));
+        format!("SHA256:{}", digest.trim_end_matches('='))
+    }
+
+    /// The length of the modulus of an RSA key.
+    pub fn bits(&self) -> Option<usize> {
+        let mut reader = Reader::new(&self.blob);
+        if reader.string()? != b"ssh-rsa" {
+            return None;
+        }
+        reader.string()?;
+        Some(mpint_bits(reader.string()?))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SshMessage {
+    Disconnect {
+        reason: u32,
+        description: String,
+    },
+    KexInit(KexInit),
+    /// The public value of the client, in KEXDH_INIT, KEX_ECDH_INIT or KEX_DH_GEX_INIT
+    KexDhInit {
+        public_value: Vec<u8>,
+    },
+    /// The host key, public value and signature algorithm of the server, in KEXDH_REPLY,
+    /// KEX_ECDH_REPLY or KEX_DH_GEX_REPLY
+    KexDhReply {
+        host_key: HostKey,
+        public_value: Vec<u8>,
+        signature_algorithm: String,
+    },
+    /// The group sizes a client asks for, the old request only giving the preferred one
+    GexRequest {
+        minimum: Option<u32>,
+        preferred: u32,
+        maximum: Option<u32>,
+    },
+    GexGroup {
+        prime: Vec<u8>,
+        generator: Vec<u8>,
+    },
+    NewKeys,
+    Other,
+}
+
+impl SshMessage {
+    fn new(message_type: MessageType, reader: &mut Reader, kex: Option<&str>) -> Option<Self> {
+        let group_exchange = kex.is_some_and(is_group_exchange);
+        let message = match message_type {
+            DISCONNECT => SshMessage::Disconnect {
+                reason: reader.u32()?,
+                description: reader.text()?,
+            },
+            KEXINIT => SshMessage::KexInit(KexInit::new(reader)?),
+            NEWKEYS => SshMessage::NewKeys,
+            KEX_DH_GEX_REQUEST_OLD if group_exchange => SshMessage::GexRequest {
+                minimum: None,
+                preferred: reader.u32()?,
+                maximum: None,
+            },
+            KEX_DH_GEX_REQUEST if group_exchange => SshMessage::GexRequest {
+                minimum: Some(reader.u32()?),
+                preferred: reader.u32()?,
+                maximum: Some(reader.u32()?),
+            },
+            KEX_DH_GEX_GROUP if group_exchange => SshMessage::GexGroup {
+                prime: reader.string()?.to_vec(),
+                generator: reader.string()?.to_vec(),
+            },
+            KEX_DH_GEX_INIT if group_exchange => SshMessage::KexDhInit {
+                public_value: reader.string()?.to_vec(),
+            },
+            KEXDH_INIT if !group_exchange => SshMessage::KexDhInit {
+                public_value: reader.string()?.to_vec(),
+            },
+            KEX_DH_GEX_REPLY if group_exchange => Self::reply(reader)?,
+            KEXDH_REPLY if !group_exchange => Self::reply(reader)?,
+            _ => SshMessage::Other
+        };
+        Some(message)
+    }
+
+    fn reply(reader: &mut Reader) -> Option<Self> {
+        let host_key = HostKey { blob: reader.string()?.to_vec() };
+        let public_value = reader.string()?.to_vec();
+        let signature_algorithm = Reader::new(reader.string()?).text()?;
+        Some(SshMessage::KexDhReply { host_key, public_value, signature_algorithm })
+    }
+
+    fn add_rows(&self, table: &mut Table) {
+        match self {
+            SshMessage::Disconnect { reason, description } => {
+                table.add_row(row!["reason", reason]);
+                table.add_row(row!["description", description]);
+            }
+            SshMessage::KexInit(kexinit) => kexinit.add_rows(table),
+            SshMessage::KexDhInit { public_value } => {
+                table.add_row(row!["public_value", describe_value(public_value)]);
+            }
+            SshMessage::KexDhReply { host_key, public_value, signature_algorithm } => {
+                table.add_row(row!["host_key_type", host_key.key_type()]);
+                if let Some(bits) = host_key.bits() {
+                    table.add_row(row!["host_key_bits", bits]);
+                }
+                table.add_row(row!["host_key_fingerprint", host_key.fingerprint()]);
+                table.add_row(row!["public_value", describe_value(public_value)]);
+                table.add_row(row!["signature_algorithm", signature_algorithm]);
+            }
+            SshMessage::GexRequest { minimum, preferred, maximum } => {
+                if let Some(minimum) = minimum {
+                    table.add_row(row!["minimum_bits", minimum]);
+                }
+                table.add_row(row!["preferred_bits", preferred]);
+                if let Some(maximum) = maximum {
+                    table.add_row(row!["maximum_bits", maximum]);
+                }
+            }
+            SshMessage::GexGroup { prime, generator } => {
+                table.add_row(row!["prime", format!("{} bits", mpint_bits(prime))]);
+                table.add_row(row!["generator", hex(generator)]);
+            }
+            SshMessage::NewKeys | SshMessage::Other => ()
+        }
+    }
+}
+
+/// A binary packet sent before the keys are in use.
+#[derive(Debug, Clone)]
+pub struct SshPacket {
+    packet_length: u32,
+    padding_length: u8,
+    message_type: MessageType,
+    name: String,
+    message: SshMessage,
+}
+
+impl SshPacket {
+    /// Reads a packet from its length on, naming the key exchange messages after the method
+    /// negotiated.
+    pub fn new(data: &[u8], kex: Option<&str>) -> Option<Self> {
+        let mut reader = Reader::new(data);
+        let packet_length = reader.u32()?;
+        let padding_length = reader.byte()?;
+        let length = (packet_length as usize).checked_sub(padding_length as usize + 1)?;
+        let mut payload = Reader::new(reader.bytes(length)?);
+        let message_type = payload.byte()?;
+        Some(Self {
+            packet_length,
+            padding_length,
+            message_type,
+            name: message_name(message_type, kex),
+            message: SshMessage::new(message_type, &mut payload, kex).unwrap_or(SshMessage::Other),
+        })
+    }
+}
+
+impl Display for SshPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.add_row(row!["packet_length", self.packet_length]);
+        table.add_row(row!["padding_length", self.padding_length]);
+        table.add_row(row!["message_type", format!("{} ({})", self.name, self.message_type)]);
+        self.message.add_rows(&mut table);
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SshEvent {
+    Identification(SshIdentification),
+    Packet(SshPacket),
+    /// The HASSH of a KEXINIT, computed once its packet is complete
+    Hassh(Hassh),
+    /// The direction switched to encrypted packets after NEWKEYS, or sent data that no packet
+    /// frames
+    Encrypted,
+}
+
+/// Both sides of a connection, from their identification strings to the end of the key
+/// exchange.
+#[derive(Debug, Default)]
+pub struct SshConversation {
+    buffers: [Vec<u8>; 2],
+    identifications: [Option<SshIdentification>; 2],
+    kexinits: [Option<KexInit>; 2],
+    encrypted: [bool; 2],
+}
+
+impl SshConversation {
+    /// Adds the data one frame brought to a direction, 0 for the client, and returns what it
+    /// completed.
+    pub fn push(&mut self, direction: usize, data: &[u8]) -> Vec<SshEvent> {
+        let mut events = Vec::new();
+        if self.encrypted[direction] {
+            return events;
+        }
+        self.buffers[direction].extend_from_slice(data);
+        // The server may send other lines before its identification string
+        while self.identifications[direction].is_none() {
+            let Some(line) = line::take_line(&mut self.buffers[direction]) else {
+                if self.buffers[direction].len() > MAXIMUM_LINE {
+                    self.buffers[direction].clear();
+                }
+                return events;
+            };
+            if let Some(identification) = SshIdentification::new(&line) {
+                self.identifications[direction] = Some(identification.clone());
+                events.push(SshEvent::Identification(identification));
+            }
+        }
+
+        while self.buffers[direction].len() >= 5 {
+            let buffer = &self.buffers[direction];
+            let length = tou32(&buffer[..4]) as usize;
+            if length > MAXIMUM_PACKET || length <= buffer[4] as usize {
+                self.encrypt(direction, &mut events);
+                break;
+            }
+            if buffer.len() < 4 + length {
+                break;
+            }
+            let data: Vec<u8> = self.buffers[direction].drain(..4 + length).collect();
+            let kex = self.kex();
+            let Some(packet) = SshPacket::new(&data, kex.as_deref()) else {
+                continue;
+            };
+            let message = packet.message.clone();
+            events.push(SshEvent::Packet(packet));
+            match message {
+                SshMessage::KexInit(kexinit) => {
+                    let software = self.identifications[direction].as_ref().map(|identification| identification.software_version());
+                    events.push(SshEvent::Hassh(Hassh::new(&kexinit, direction == 0, software)));
+                    self.kexinits[direction] = Some(kexinit);
+                }
+                SshMessage::NewKeys => {
+                    self.encrypt(direction, &mut events);
+                    break;
+                }
+                _ => ()
+            }
+        }
+        events
+    }
+
+    fn encrypt(&mut self, direction: usize, events: &mut Vec<SshEvent>) {
+        self.encrypted[direction] = true;
+        self.buffers[direction].clear();
+        events.push(SshEvent::Encrypted);
+    }
+
+    /// The key exchange method negotiated: the first of the client the server supports too.
+    pub fn kex(&self) -> Option<String> {
+        let [Some(client), Some(server)] = &self.kexinits else {
+            return None;
+        };
+        client.kex_algorithms().iter().find(|kex| server.kex_algorithms().contains(kex)).cloned()
+    }
+}
@@ -0,0 +1,323 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, row, Table};
+
+pub const PORT: u16 = 514;
+
+/// Longest octet count accepted when framing messages over TCP.
+const MAXIMUM_LENGTH: usize = 1 << 20;
+
+/// A structured data element: its id and its parameters.
+pub type SdElement = (String, Vec<(String, String)>);
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// A syslog message in either format: RFC 5424, which has a version, or the BSD format of
+/// RFC 3164.
+#[derive(Debug, Clone)]
+pub struct SyslogMessage {
+    facility: u8,
+    severity: u8,
+    version: Option<u32>,
+    timestamp: Option<String>,
+    hostname: Option<String>,
+    app_name: Option<String>,
+    proc_id: Option<String>,
+    msg_id: Option<String>,
+    structured_data: Vec<SdElement>,
+    message: String,
+}
+
+/// Splits the messages sent over a TCP stream, which counts the octets of each or ends each
+/// with a line feed as RFC 6587 describes.
+#[derive(Debug, Default)]
+pub struct SyslogStream {
+    buffer: Vec<u8>,
+}
+
+pub fn facility_name(facility: u8) -> String {
+    match facility {
+        0 => String::from("kern"),
+        1 => String::from("user"),
+        2 => String::from("mail"),
+        3 => String::from("daemon"),
+        4 => String::from("auth"),
+        5 => String::from("syslog"),
+        6 => String::from("lpr"),
+        7 => String::from("news"),
+        8 => String::from("uucp"),
+        9 => String::from("cron"),
+        10 => String::from("authpriv"),
+        11 => String::from("ftp"),
+        12 => String::from("ntp"),
+        13 => String::from("security"),
+        14 => String::from("console"),
+        15 => String::from("solaris_cron"),
+        16..=23 => format!("local{}", facility - 16),
+        _ => format!("{}", facility)
+    }
+}
+
+pub fn severity_name(severity: u8) -> String {
+    match severity {
+        0 => String::from("emergency"),
+        1 => String::from("alert"),
+        2 => String::from("critical"),
+        3 => String::from("error"),
+        4 => String::from("warning"),
+        5 => String::from("notice"),
+        6 => String::from("informational"),
+        7 => String::from("debug"),
+        _ => format!("{}", severity)
+    }
+}
+
+/// The next field up to a space, None for the nil value.
+fn field(text: &str) -> (Option<String>, &str) {
+    let (field, rest) = text.split_once(' ').unwrap_or((text, ""));
+    ((field != "-").then(|| String::from(field)), rest)
+}
+
+/// The structured data elements at the start of the text and the text after them.
+fn structured_data(text: &str) -> Option<(Vec<SdElement>, &str)> {
+    if let Some(rest) = text.strip_prefix('-') {
+        return Some((Vec::new(), rest));
+    }
+    let mut elements = Vec::new();
+    let mut rest = text;
+    while let Some(element) = rest.strip_prefix('[') {
+        let end = element.find([' ', ']'])?;
+        let id = String::from(&element[..end]);
+        rest = &element[end..];
+        let mut parameters = Vec::new();
+        loop {
+            rest = rest.trim_start_matches(' ');
+            if let Some(after) = rest.strip_prefix(']') {
+                rest = after;
+                break;
+            }
+            let (name, after) = rest.split_once("=\"")?;
+            // Quotes, backslashes and closing brackets are escaped with a backslash
+            let mut value = String::new();
+            let mut chars = after.char_indices();
+            let end = loop {
+                match chars.next()? {
+                    (_, '\\') => {
+                        let (_, c) = chars.next()?;
+                        if !"\"\\]".contains(c) {
+                            value.push('\\');
+                        }
+                        value.push(c);
+                    }
+                    (index, '"') => break index,
+                    (_, c) => value.push(c)
+                }
+            };
+            parameters.push((String::from(name), value));
+            rest = &after[end + 1..];
+        }
+        elements.push((id, parameters));
+    }
+    (!elements.is_empty()).then_some((elements, rest))
+}
+
+/// Whether the text starts with a BSD timestamp such as "Oct  1 22:14:15".
+fn bsd_timestamp(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() >= 15
+        && text.get(..3).is_some_and(|month| MONTHS.contains(&month))
+        && bytes[3] == b' '
+        && (bytes[4] == b' ' || bytes[4].is_ascii_digit())
+        && bytes[5].is_ascii_digit()
+        && bytes[6] == b' '
+        && bytes[9] == b':'
+        && bytes[12] == b':'
+        && [7, 8, 10, 11, 13, 14].iter().all(|index| bytes[*index].is_ascii_digit())
+}
+
+impl SyslogMessage {
+    /// Parses a message, None when it does not start with a priority.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        let text = String::from_utf8_lossy(data);
+        let text = text.trim_end_matches(['\n', '\r', '\0']);
+        let (priority, rest) = text.strip_prefix('<')?.split_once('>')?;
+        if priority.is_empty() || priority.len() > 3 || !priority.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        let priority: u8 = priority.parse().ok().filter(|priority| *priority <= 191)?;
+        let mut message = Self {
+            facility: priority >> 3,
+            severity: priority & 0x07,
+            version: None,
+            timestamp: None,
+            hostname: None,
+            app_name: None,
+            proc_id: None,
+            msg_id: None,
+            structured_data: Vec::new(),
+            message: String::new(),
+        };
+
+        let version = rest.split_once(' ')
+            .filter(|(version, _)| !version.is_empty() && version.len() <= 2 && version.bytes().all(|byte| byte.is_ascii_digit()));
+        if let Some((version, rest)) = version {
+            message.version = version.parse().ok();
+            let (timestamp, rest) = field(rest);
+            let (hostname, rest) = field(rest);
+            let (app_name, rest) = field(rest);
+            let (proc_id, rest) = field(rest);
+            let (msg_id, rest) = field(rest);
+            let (elements, rest) = structured_data(rest)?;
+            message.timestamp = timestamp;
+            message.hostname = hostname;
+            message.app_name = app_name;
+            message.proc_id = proc_id;
+            message.msg_id = msg_id;
+            message.structured_data = elements;
+            let rest = rest.strip_prefix(' ').unwrap_or(rest);
+            message.message = String::from(rest.strip_prefix('\u{feff}').unwrap_or(rest));
+        } else if bsd_timestamp(rest) {
+            message.timestamp = Some(String::from(&rest[..15]));
+            let after = rest[15..].trim_start_matches(' ');
+            let (hostname, rest) = after.split_once(' ').unwrap_or((after, ""));
+            message.hostname = Some(String::from(hostname));
+            // The tag names the program, with the process id in brackets after it
+            let end = rest.find(|c: char| !c.is_ascii_alphanumeric() && !"_-./".contains(c)).unwrap_or(rest.len());
+            if end > 0 && rest[end..].starts_with([':', '[']) {
+                message.app_name = Some(String::from(&rest[..end]));
+                if let Some((proc_id, _)) = rest[end..].strip_prefix('[').and_then(|after| after.split_once(']')) {
+                    message.proc_id = Some(String::from(proc_id));
+                }
+            }
+            message.message = String::from(rest);
+        } else {
+            // Relays pass on messages without a timestamp or host as they are
+            message.message = String::from(rest);
+        }
+        Some(message)
+    }
+}
+
+impl Display for SyslogMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.add_row(row!["format", match self.version {
+            Some(version) => format!("rfc5424 (version {})", version),
+            None => String::from("rfc3164")
+        }]);
+        table.add_row(row!["facility", format!("{} ({})", facility_name(self.facility), self.facility)]);
+        table.add_row(row!["severity", format!("{} ({})", severity_name(self.severity), self.severity)]);
+        let fields = [
+            ("timestamp", &self.timestamp),
+            ("hostname", &self.hostname),
+            ("app_name", &self.app_name),
+            ("proc_id", &self.proc_id),
+            ("msg_id", &self.msg_id),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                table.add_row(row![name, value]);
+            }
+        }
+        for (id, parameters) in self.structured_data.iter() {
+            table.add_row(row!["structured_data", id]);
+            for (name, value) in parameters.iter() {
+                table.add_row(row![format!("  {}", name), value]);
+            }
+        }
+        table.add_row(row!["message", self.message]);
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl SyslogStream {
+    /// Adds the data one frame brought and returns the messages it completed.
+    pub fn push(&mut self, data: &[u8], finished: bool) -> Vec<Vec<u8>> {
+        self.buffer.extend_from_slice(data);
+        let mut messages = Vec::new();
+        loop {
+            let digits = self.buffer.iter().take_while(|byte| byte.is_ascii_digit()).count();
+            let length = (digits > 0 && self.buffer.get(digits) == Some(&b' '))
+                .then(|| std::str::from_utf8(&self.buffer[..digits]).ok()?.parse::<usize>().ok())
+                .flatten()
+                .filter(|length| *length <= MAXIMUM_LENGTH);
+            if let Some(length) = length {
+                if self.buffer.len() < digits + 1 + length {
+                    break;
+                }
+                messages.push(self.buffer[digits + 1..digits + 1 + length].to_vec());
+                self.buffer.drain(..digits + 1 + length);
+            } else if let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+                messages.push(self.buffer[..end].to_vec());
+                self.buffer.drain(..=end);
+            } else {
+                break;
+            }
+        }
+        if finished && !self.buffer.is_empty() {
+            messages.push(std::mem::take(&mut self.buffer));
+        }
+        messages.retain(|message| !message.is_empty());
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rfc5424_examples() {
+        let message = SyslogMessage::new(b"<34>1 2003-10-11T22:14:15.003Z mymachine.example.com su - ID47 - 'su root' failed for lonvick on /dev/pts/8").unwrap();
+        assert_eq!((message.facility, message.severity), (4, 2));
+        assert_eq!(message.version, Some(1));
+        assert_eq!(message.timestamp.as_deref(), Some("2003-10-11T22:14:15.003Z"));
+        assert_eq!(message.hostname.as_deref(), Some("mymachine.example.com"));
+        assert_eq!(message.app_name.as_deref(), Some("su"));
+        assert_eq!(message.proc_id, None);
+        assert_eq!(message.msg_id.as_deref(), Some("ID47"));
+        assert_eq!(message.message, "'su root' failed for lonvick on /dev/pts/8");
+
+        let message = SyslogMessage::new(b"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut=\"3\" eventSource=\"Application\" eventID=\"1011\"] An application event log entry...").unwrap();
+        assert_eq!((message.facility, message.severity), (20, 5));
+        assert_eq!(message.structured_data.len(), 1);
+        let (id, parameters) = &message.structured_data[0];
+        assert_eq!(id, "exampleSDID@32473");
+        assert_eq!(parameters[1], (String::from("eventSource"), String::from("Application")));
+        assert_eq!(message.message, "An application event log entry...");
+    }
+
+    #[test]
+    fn parses_rfc3164_example() {
+        let message = SyslogMessage::new(b"<34>Oct 11 22:14:15 mymachine su: 'su root' failed for lonvick on /dev/pts/8\n").unwrap();
+        assert_eq!(message.version, None);
+        assert_eq!(message.timestamp.as_deref(), Some("Oct 11 22:14:15"));
+        assert_eq!(message.hostname.as_deref(), Some("mymachine"));
+        assert_eq!(message.app_name.as_deref(), Some("su"));
+        assert_eq!(message.message, "su: 'su root' failed for lonvick on /dev/pts/8");
+    }
+
+    #[test]
+    fn keeps_multibyte_text_after_priority() {
+        let message = SyslogMessage::new("<13>a€ and some more text".as_bytes()).unwrap();
+        assert_eq!(message.message, "a€ and some more text");
+        let message = SyslogMessage::new(b"<13>\xff\xfe and some more text").unwrap();
+        assert_eq!(message.timestamp, None);
+        assert!(SyslogMessage::new(b"<192>too high").is_none());
+    }
+
+    #[test]
+    fn frames_octet_counted_and_newline_messages() {
+        let mut stream = SyslogStream::default();
+        assert_eq!(stream.push(b"13 <13>first m", false), Vec::<Vec<u8>>::new());
+        assert_eq!(stream.push(b"sg<13>second\n<13>third", true), vec![
+            b"<13>first msg".to_vec(),
+            b"<13>second".to_vec(),
+            b"<13>third".to_vec(),
+        ]);
+    }
+}
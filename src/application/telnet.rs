@@ -0,0 +1,263 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, row, Table};
+use crate::util::*;
+
+pub const PORT: u16 = 23;
+
+/// Longest subnegotiation kept waiting for its end before it is taken for user data.
+const MAXIMUM_SUBNEGOTIATION: usize = 1 << 16;
+
+pub type Command = u8;
+
+pub const SE: Command = 240;
+pub const NOP: Command = 241;
+pub const DM: Command = 242;
+pub const BRK: Command = 243;
+pub const IP: Command = 244;
+pub const AO: Command = 245;
+pub const AYT: Command = 246;
+pub const EC: Command = 247;
+pub const EL: Command = 248;
+pub const GA: Command = 249;
+pub const SB: Command = 250;
+pub const WILL: Command = 251;
+pub const WONT: Command = 252;
+pub const DO: Command = 253;
+pub const DONT: Command = 254;
+pub const IAC: Command = 255;
+
+pub type TelnetOption = u8;
+
+pub const BINARY: TelnetOption = 0;
+pub const ECHO: TelnetOption = 1;
+pub const SUPPRESS_GO_AHEAD: TelnetOption = 3;
+pub const STATUS: TelnetOption = 5;
+pub const TIMING_MARK: TelnetOption = 6;
+pub const TERMINAL_TYPE: TelnetOption = 24;
+pub const END_OF_RECORD: TelnetOption = 25;
+pub const NAWS: TelnetOption = 31;
+pub const TERMINAL_SPEED: TelnetOption = 32;
+pub const REMOTE_FLOW_CONTROL: TelnetOption = 33;
+pub const LINEMODE: TelnetOption = 34;
+pub const X_DISPLAY_LOCATION: TelnetOption = 35;
+pub const ENVIRON: TelnetOption = 36;
+pub const AUTHENTICATION: TelnetOption = 37;
+pub const ENCRYPT: TelnetOption = 38;
+pub const NEW_ENVIRON: TelnetOption = 39;
+pub const CHARSET: TelnetOption = 42;
+pub const COM_PORT: TelnetOption = 44;
+pub const START_TLS: TelnetOption = 46;
+
+/// An IAC sequence: the negotiation of an option, the parameters of one, or a bare command.
+#[derive(Debug, Clone)]
+pub enum TelnetCommand {
+    Negotiation(Command, TelnetOption),
+    /// The option and its parameters, the doubled IACs in them undone
+    Subnegotiation(TelnetOption, Vec<u8>),
+    Command(Command),
+}
+
+/// What one frame of a direction carried: the IAC sequences and the user data between them.
+#[derive(Debug, Clone)]
+pub struct TelnetSegment {
+    commands: Vec<TelnetCommand>,
+    data: Vec<u8>,
+}
+
+/// Both directions of a Telnet connection, keeping an IAC sequence split across frames until
+/// its end arrives.
+#[derive(Debug, Default)]
+pub struct TelnetStream {
+    pending: [Vec<u8>; 2],
+}
+
+pub fn command_name(command: Command) -> String {
+    match command {
+        SE => String::from("se"),
+        NOP => String::from("nop"),
+        DM => String::from("data_mark"),
+        BRK => String::from("break"),
+        IP => String::from("interrupt_process"),
+        AO => String::from("abort_output"),
+        AYT => String::from("are_you_there"),
+        EC => String::from("erase_character"),
+        EL => String::from("erase_line"),
+        GA => String::from("go_ahead"),
+        SB => String::from("sb"),
+        WILL => String::from("will"),
+        WONT => String::from("wont"),
+        DO => String::from("do"),
+        DONT => String::from("dont"),
+        IAC => String::from("iac"),
+        _ => format!("{}", command)
+    }
+}
+
+pub fn option_name(option: TelnetOption) -> String {
+    match option {
+        BINARY => String::from("binary"),
+        ECHO => String::from("echo"),
+        SUPPRESS_GO_AHEAD => String::from("suppress_go_ahead"),
+        STATUS => String::from("status"),
+        TIMING_MARK => String::from("timing_mark"),
+        TERMINAL_TYPE => String::from("terminal_type"),
+        END_OF_RECORD => String::from("end_of_record"),
+        NAWS => String::from("window_size"),
+        TERMINAL_SPEED => String::from("terminal_speed"),
+        REMOTE_FLOW_CONTROL => String::from("remote_flow_control"),
+        LINEMODE => String::from("linemode"),
+        X_DISPLAY_LOCATION => String::from("x_display_location"),
+        ENVIRON => String::from("environ"),
+        AUTHENTICATION => String::from("authentication"),
+        ENCRYPT => String::from("encrypt"),
+        NEW_ENVIRON => String::from("new_environ"),
+        CHARSET => String::from("charset"),
+        COM_PORT => String::from("com_port"),
+        START_TLS => String::from("start_tls"),
+        _ => format!("{}", option)
+    }
+}
+
+/// The parameters of a subnegotiation, for the options that carry text.
+fn subnegotiation_value(option: TelnetOption, parameters: &[u8]) -> String {
+    match (option, parameters) {
+        (NAWS, [_, _, _, _]) => format!("{}x{}", tou16(&parameters[..2]), tou16(&parameters[2..])),
+        (TERMINAL_TYPE | TERMINAL_SPEED | X_DISPLAY_LOCATION, [0, text @ ..]) => {
+            format!("is {}", String::from_utf8_lossy(text))
+        }
+        (TERMINAL_TYPE | TERMINAL_SPEED | X_DISPLAY_LOCATION, [1]) => String::from("send"),
+        (ENVIRON | NEW_ENVIRON, [kind @ 0..=2, variables @ ..]) => {
+            // Each variable starts with VAR or USERVAR and its value with VALUE, ESC quoting
+            let mut text = String::from(["is", "send", "info"][*kind as usize]);
+            let mut bytes = variables.iter();
+            while let Some(&byte) = bytes.next() {
+                match byte {
+                    0 | 3 => text.push(' '),
+                    1 => text.push('='),
+                    2 => if let Some(&byte) = bytes.next() {
+                        text.push(byte as char);
+                    },
+                    _ => text.push(byte as char)
+                }
+            }
+            text
+        }
+        _ => hex(parameters)
+    }
+}
+
+impl TelnetSegment {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty() && self.data.is_empty()
+    }
+}
+
+impl Display for TelnetSegment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        for command in self.commands.iter() {
+            match command {
+                TelnetCommand::Negotiation(command, option) => {
+                    table.add_row(row![command_name(*command), format!("{} ({})", option_name(*option), option)]);
+                }
+                TelnetCommand::Subnegotiation(option, parameters) => {
+                    table.add_row(row![
+                        command_name(SB),
+                        format!("{} ({}) {}", option_name(*option), option, subnegotiation_value(*option, parameters))
+                    ]);
+                }
+                TelnetCommand::Command(command) => {
+                    table.add_row(row![command_name(*command), ""]);
+                }
+            }
+        }
+        if !self.data.is_empty() {
+            table.add_row(row!["data", format!("{:?}", String::from_utf8_lossy(&self.data))]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl TelnetStream {
+    /// Adds the data one frame brought to a direction, separating the IAC sequences it completed
+    /// from the user data.
+    pub fn push(&mut self, direction: usize, data: &[u8]) -> TelnetSegment {
+        let mut buffer = std::mem::take(&mut self.pending[direction]);
+        buffer.extend_from_slice(data);
+        let mut segment = TelnetSegment {
+            commands: Vec::new(),
+            data: Vec::new(),
+        };
+
+        let mut index = 0;
+        while index < buffer.len() {
+            if buffer[index] != IAC {
+                segment.data.push(buffer[index]);
+                index += 1;
+                continue;
+            }
+            let Some(&command) = buffer.get(index + 1) else {
+                break;
+            };
+            match command {
+                IAC => {
+                    segment.data.push(IAC);
+                    index += 2;
+                }
+                WILL | WONT | DO | DONT => {
+                    let Some(&option) = buffer.get(index + 2) else {
+                        break;
+                    };
+                    segment.commands.push(TelnetCommand::Negotiation(command, option));
+                    index += 3;
+                }
+                SB => {
+                    let Some(&option) = buffer.get(index + 2) else {
+                        break;
+                    };
+                    let mut parameters = Vec::new();
+                    let mut end = None;
+                    let mut position = index + 3;
+                    while position < buffer.len() {
+                        match (buffer[position], buffer.get(position + 1)) {
+                            (IAC, Some(&SE)) => {
+                                end = Some(position + 2);
+                                break;
+                            }
+                            (IAC, Some(&IAC)) => {
+                                parameters.push(IAC);
+                                position += 2;
+                            }
+                            (IAC, None) => break,
+                            (byte, _) => {
+                                parameters.push(byte);
+                                position += 1;
+                            }
+                        }
+                    }
+                    let Some(end) = end else {
+                        break;
+                    };
+                    segment.commands.push(TelnetCommand::Subnegotiation(option, parameters));
+                    index = end;
+                }
+                command => {
+                    segment.commands.push(TelnetCommand::Command(command));
+                    index += 2;
+                }
+            }
+        }
+        if buffer.len() - index <= MAXIMUM_SUBNEGOTIATION {
+            self.pending[direction] = buffer.split_off(index);
+        } else {
+            segment.data.extend_from_slice(&buffer[index..]);
+        }
+        segment
+    }
+}
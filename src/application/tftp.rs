@@ -0,0 +1,393 @@
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::net::SocketAddr;
+use std::time::Duration;
+use prettytable::{format, row, Table};
+use crate::util::*;
+
+pub const PORT: u16 = 69;
+
+pub const DEFAULT_BLOCK_SIZE: usize = 512;
+
+pub type Opcode = u16;
+
+pub const READ_REQUEST: Opcode = 1;
+pub const WRITE_REQUEST: Opcode = 2;
+pub const DATA: Opcode = 3;
+pub const ACKNOWLEDGEMENT: Opcode = 4;
+pub const ERROR: Opcode = 5;
+pub const OPTION_ACKNOWLEDGEMENT: Opcode = 6;
+
+/// A TFTP packet, the options being those of RFC 2347.
+#[derive(Debug, Clone)]
+pub enum TftpPacket {
+    Request {
+        opcode: Opcode,
+        file_name: String,
+        mode: String,
+        options: Vec<(String, String)>,
+    },
+    Data {
+        block: u16,
+        data: Vec<u8>,
+    },
+    Acknowledgement {
+        block: u16,
+    },
+    Error {
+        code: u16,
+        message: String,
+    },
+    OptionAcknowledgement {
+        options: Vec<(String, String)>,
+    },
+}
+
+/// The transfer of one file, between the port the client sent the request from and the port
+/// the server picked to answer it from.
+#[derive(Debug, Clone)]
+pub struct TftpTransfer {
+    client: SocketAddr,
+    server: SocketAddr,
+    /// Whether the server picked its port yet, the request going to the well known one
+    handed_off: bool,
+    file_name: String,
+    mode: String,
+    write: bool,
+    block_size: usize,
+    /// The last block received in order
+    block: u16,
+    blocks: usize,
+    data: Vec<u8>,
+    started: Duration,
+    ended: Duration,
+    error: Option<String>,
+}
+
+/// The transfers in progress.
+#[derive(Debug, Default)]
+pub struct TftpTransfers {
+    transfers: Vec<TftpTransfer>,
+}
+
+pub fn opcode_name(opcode: Opcode) -> String {
+    match opcode {
+        READ_REQUEST => String::from("read_request"),
+        WRITE_REQUEST => String::from("write_request"),
+        DATA => String::from("data"),
+        ACKNOWLEDGEMENT => String::from("acknowledgement"),
+        ERROR => String::from("error"),
+        OPTION_ACKNOWLEDGEMENT => String::from("option_acknowledgement"),
+        _ => format!("{}", opcode)
+    }
+}
+
+pub fn error_name(code: u16) -> String {
+    match code {
+        0 => String::from("not_defined"),
+        1 => String::from("file_not_found"),
+        2 => String::from("access_violation"),
+        3 => String::from("disk_full"),
+        4 => String::from("illegal_operation"),
+        5 => String::from("unknown_transfer_id"),
+        6 => String::from("file_already_exists"),
+        7 => String::from("no_such_user"),
+        8 => String::from("option_refused"),
+        _ => format!("{}", code)
+    }
+}
+
+/// The zero terminated strings of the data, ignoring anything after the last terminator.
+fn strings(data: &[u8]) -> Vec<String> {
+    let mut strings: Vec<String> = data.split(|byte| *byte == 0)
+        .map(|string| String::from_utf8_lossy(string).into_owned())
+        .collect();
+    strings.pop();
+    strings
+}
+
+fn options(strings: &[String]) -> Vec<(String, String)> {
+    strings.chunks_exact(2).map(|option| (option[0].clone(), option[1].clone())).collect()
+}
+
+impl TftpPacket {
+    /// Parses a packet, None when the opcode is unknown or the packet too short for it.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < 4 && !(data.len() == 2 && tou16(data) == OPTION_ACKNOWLEDGEMENT) {
+            return None;
+        }
+        let rest = &data[2..];
+        match tou16(data) {
+            opcode @ (READ_REQUEST | WRITE_REQUEST) => {
+                let strings = strings(rest);
+                let [file_name, mode, ..] = strings.as_slice() else {
+                    return None;
+                };
+                Some(Self::Request {
+                    opcode,
+                    file_name: file_name.clone(),
+                    mode: mode.to_ascii_lowercase(),
+                    options: options(&strings[2..]),
+                })
+            }
+            DATA => Some(Self::Data {
+                block: tou16(rest),
+                data: Vec::from(&rest[2..]),
+            }),
+            ACKNOWLEDGEMENT => Some(Self::Acknowledgement {
+                block: tou16(rest),
+            }),
+            ERROR => Some(Self::Error {
+                code: tou16(rest),
+                message: strings(&rest[2..]).into_iter().next().unwrap_or_default(),
+            }),
+            OPTION_ACKNOWLEDGEMENT => Some(Self::OptionAcknowledgement {
+                options: options(&strings(rest)),
+            }),
+            _ => None
+        }
+    }
+
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            Self::Request { opcode, .. } => *opcode,
+            Self::Data { .. } => DATA,
+            Self::Acknowledgement { .. } => ACKNOWLEDGEMENT,
+            Self::Error { .. } => ERROR,
+            Self::OptionAcknowledgement { .. } => OPTION_ACKNOWLEDGEMENT,
+        }
+    }
+}
+
+impl Display for TftpPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.add_row(row!["opcode", format!("{} ({})", opcode_name(self.opcode()), self.opcode())]);
+        match self {
+            Self::Request { file_name, mode, options, .. } => {
+                table.add_row(row!["file_name", file_name]);
+                table.add_row(row!["mode", mode]);
+                for (name, value) in options.iter() {
+                    table.add_row(row![format!("option {}", name), value]);
+                }
+            }
+            Self::Data { block, data } => {
+                table.add_row(row!["block", block]);
+                table.add_row(row!["length", format!("{} bytes", data.len())]);
+            }
+            Self::Acknowledgement { block } => {
+                table.add_row(row!["block", block]);
+            }
+            Self::Error { code, message } => {
+                table.add_row(row!["error_code", format!("{} ({})", error_name(*code), code)]);
+                table.add_row(row!["message", message]);
+            }
+            Self::OptionAcknowledgement { options } => {
+                for (name, value) in options.iter() {
+                    table.add_row(row![format!("option {}", name), value]);
+                }
+            }
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl TftpTransfer {
+    /// Whether a packet between the two endpoints belongs to the transfer.
+    fn matches(&self, source: SocketAddr, destination: SocketAddr) -> bool {
+        let server = |endpoint: SocketAddr| {
+            endpoint.ip() == self.server.ip() && (!self.handed_off || endpoint.port() == self.server.port())
+        };
+        (source == self.client && server(destination)) || (destination == self.client && server(source))
+    }
+
+    #[inline]
+    pub fn client(&self) -> SocketAddr {
+        self.client
+    }
+
+    #[inline]
+    pub fn server(&self) -> SocketAddr {
+        self.server
+    }
+
+    #[inline]
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    #[inline]
+    pub fn mode(&self) -> &str {
+        &self.mode
+    }
+
+    /// Whether the client sent the file rather than fetched it.
+    #[inline]
+    pub fn is_write(&self) -> bool {
+        self.write
+    }
+
+    #[inline]
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// The content of the file, with the line ends of netascii turned back into line feeds.
+    pub fn contents(&self) -> Cow<'_, [u8]> {
+        if self.mode != "netascii" {
+            return Cow::Borrowed(&self.data);
+        }
+        let mut contents = Vec::with_capacity(self.data.len());
+        let mut bytes = self.data.iter().peekable();
+        while let Some(&byte) = bytes.next() {
+            match (byte, bytes.peek()) {
+                (b'\r', Some(b'\n')) => {
+                    contents.push(b'\n');
+                    bytes.next();
+                }
+                (b'\r', Some(0)) => {
+                    contents.push(b'\r');
+                    bytes.next();
+                }
+                _ => contents.push(byte)
+            }
+        }
+        Cow::Owned(contents)
+    }
+}
+
+impl Display for TftpTransfer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.add_row(row!["client", self.client]);
+        table.add_row(row!["server", self.server]);
+        table.add_row(row!["file_name", self.file_name]);
+        table.add_row(row!["direction", if self.write { "write" } else { "read" }]);
+        table.add_row(row!["mode", self.mode]);
+        table.add_row(row!["block_size", self.block_size]);
+        table.add_row(row!["blocks", self.blocks]);
+        table.add_row(row!["size", format!("{} bytes", self.contents().len())]);
+        table.add_row(row!["duration", format!("{:.3} ms", self.ended.saturating_sub(self.started).as_secs_f64() * 1000.0)]);
+        table.add_row(row!["state", match &self.error {
+            Some(error) => format!("failed: {}", error),
+            None => String::from("complete")
+        }]);
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl TftpTransfers {
+    /// Whether a packet between the two endpoints belongs to a transfer in progress, the server
+    /// answering from another port than the one the request went to.
+    pub fn contains(&self, source: SocketAddr, destination: SocketAddr) -> bool {
+        self.transfers.iter().any(|transfer| transfer.matches(source, destination))
+    }
+
+    /// Records a packet, returning the transfer it completed or failed.
+    pub fn update(&mut self, packet: &TftpPacket, source: SocketAddr, destination: SocketAddr, timestamp: Duration) -> Option<TftpTransfer> {
+        if let TftpPacket::Request { opcode, file_name, mode, .. } = packet {
+            // A client reusing its port starts over
+            self.transfers.retain(|transfer| transfer.client != source);
+            self.transfers.push(TftpTransfer {
+                client: source,
+                server: destination,
+                handed_off: false,
+                file_name: file_name.clone(),
+                mode: mode.clone(),
+                write: *opcode == WRITE_REQUEST,
+                block_size: DEFAULT_BLOCK_SIZE,
+                block: 0,
+                blocks: 0,
+                data: Vec::new(),
+                started: timestamp,
+                ended: timestamp,
+                error: None,
+            });
+            return None;
+        }
+
+        let position = self.transfers.iter().position(|transfer| transfer.matches(source, destination))?;
+        let transfer = &mut self.transfers[position];
+        if !transfer.handed_off && source.ip() == transfer.server.ip() {
+            transfer.server = source;
+            transfer.handed_off = true;
+        }
+        transfer.ended = timestamp;
+        match packet {
+            TftpPacket::OptionAcknowledgement { options } => {
+                let block_size = options.iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case("blksize"))
+                    .and_then(|(_, value)| value.parse().ok());
+                if let Some(block_size) = block_size {
+                    transfer.block_size = block_size;
+                }
+                None
+            }
+            // Retransmitted blocks are dropped, the block number wrapping around on large files
+            TftpPacket::Data { block, data } if *block == transfer.block.wrapping_add(1) => {
+                transfer.block = *block;
+                transfer.blocks += 1;
+                transfer.data.extend_from_slice(data);
+                (data.len() < transfer.block_size).then(|| self.transfers.remove(position))
+            }
+            TftpPacket::Error { code, message } => {
+                transfer.error = Some(format!("{} ({})", message, error_name(*code)));
+                Some(self.transfers.remove(position))
+            }
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(values: &[&str]) -> Vec<(String, String)> {
+        values.chunks_exact(2).map(|pair| (String::from(pair[0]), String::from(pair[1]))).collect()
+    }
+
+    #[test]
+    fn parses_rfc2347_negotiation() {
+        // The option negotiation example of RFC 2347, a read request answered by an OACK
+        let Some(TftpPacket::Request { opcode, file_name, mode, options }) =
+            TftpPacket::new(b"\x00\x01foobar\x00OCTET\x00blksize\x001432\x00tsize\x000\x00") else {
+            panic!("not a request");
+        };
+        assert_eq!((opcode, file_name.as_str(), mode.as_str()), (READ_REQUEST, "foobar", "octet"));
+        assert_eq!(options, pairs(&["blksize", "1432", "tsize", "0"]));
+
+        let Some(TftpPacket::OptionAcknowledgement { options }) = TftpPacket::new(b"\x00\x06blksize\x001432\x00") else {
+            panic!("not an option acknowledgement");
+        };
+        assert_eq!(options, pairs(&["blksize", "1432"]));
+        assert!(matches!(TftpPacket::new(b"\x00\x06"), Some(TftpPacket::OptionAcknowledgement { options }) if options.is_empty()));
+    }
+
+    #[test]
+    fn parses_rfc1350_packets() {
+        assert!(matches!(
+            TftpPacket::new(b"\x00\x03\x00\x01hello"),
+            Some(TftpPacket::Data { block: 1, data }) if data == b"hello"
+        ));
+        assert!(matches!(TftpPacket::new(b"\x00\x04\x01\x00"), Some(TftpPacket::Acknowledgement { block: 256 })));
+        assert!(matches!(
+            TftpPacket::new(b"\x00\x05\x00\x01File not found\x00"),
+            Some(TftpPacket::Error { code: 1, message }) if message == "File not found"
+        ));
+        // A request needs both its file name and mode
+        assert!(TftpPacket::new(b"\x00\x02foobar\x00").is_none());
+        assert!(TftpPacket::new(b"\x00\x03\x00").is_none());
+        assert!(TftpPacket::new(b"\x00\x09\x00\x00").is_none());
+    }
+}
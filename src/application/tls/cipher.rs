@@ -0,0 +1,290 @@
+use aes::{Aes128, Aes256};
+use aes::cipher::{BlockDecryptMut, KeyIvInit};
+use aes::cipher::block_padding::NoPadding;
+use aes_gcm::{Aes128Gcm, Aes256Gcm, KeyInit, Nonce};
+use aes_gcm::aead::{Aead, Payload};
+use chacha20poly1305::ChaCha20Poly1305;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha384};
+use crate::application::tls::ContentType;
+
+/// The hash of the PRF, HKDF or record MAC of a cipher suite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkCipher {
+    Aes128Gcm,
+    Aes256Gcm,
+    ChaCha20Poly1305,
+    Aes128Cbc,
+    Aes256Cbc,
+}
+
+/// What decrypting records needs to know about a cipher suite.
+#[derive(Debug, Clone, Copy)]
+pub struct CipherSuite {
+    cipher: BulkCipher,
+    /// The hash of the PRF in TLS 1.2 and of the HKDF in TLS 1.3
+    hash: HashAlgorithm,
+    /// The record MAC of the CBC suites
+    mac: Option<HashAlgorithm>,
+}
+
+impl HashAlgorithm {
+    pub fn length(&self) -> usize {
+        match self {
+            HashAlgorithm::Sha1 => 20,
+            HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Sha384 => 48
+        }
+    }
+
+    fn hmac(&self, key: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+        fn compute<M: Mac + KeyInit>(key: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+            let mut mac = <M as Mac>::new_from_slice(key).unwrap();
+            parts.iter().for_each(|part| mac.update(part));
+            mac.finalize().into_bytes().to_vec()
+        }
+        match self {
+            HashAlgorithm::Sha1 => compute::<Hmac<Sha1>>(key, parts),
+            HashAlgorithm::Sha256 => compute::<Hmac<Sha256>>(key, parts),
+            HashAlgorithm::Sha384 => compute::<Hmac<Sha384>>(key, parts)
+        }
+    }
+
+    /// The TLS 1.2 PRF, P_hash over the label and seed.
+    pub fn prf(&self, secret: &[u8], label: &str, seed: &[u8], length: usize) -> Vec<u8> {
+        let mut output = Vec::with_capacity(length);
+        let mut a = self.hmac(secret, &[label.as_bytes(), seed]);
+        while output.len() < length {
+            output.extend(self.hmac(secret, &[&a, label.as_bytes(), seed]));
+            a = self.hmac(secret, &[&a]);
+        }
+        output.truncate(length);
+        output
+    }
+
+    /// The TLS 1.3 HKDF-Expand-Label, with an empty context, which QUIC derives its keys with too.
+    pub fn expand_label(&self, secret: &[u8], label: &str, length: usize) -> Option<Vec<u8>> {
+        let label = format!("tls13 {}", label);
+        let mut info = Vec::with_capacity(4 + label.len());
+        info.extend_from_slice(&(length as u16).to_be_bytes());
+        info.push(label.len() as u8);
+        info.extend_from_slice(label.as_bytes());
+        info.push(0);
+        let mut output = vec![0; length];
+        match self {
+            HashAlgorithm::Sha256 => Hkdf::<Sha256>::from_prk(secret).ok()?.expand(&info, &mut output).ok()?,
+            HashAlgorithm::Sha384 => Hkdf::<Sha384>::from_prk(secret).ok()?.expand(&info, &mut output).ok()?,
+            HashAlgorithm::Sha1 => return None
+        }
+        Some(output)
+    }
+}
+
+impl CipherSuite {
+    /// The suites that can be decrypted, None for the others.
+    pub fn new(suite: u16) -> Option<Self> {
+        use BulkCipher::*;
+        use HashAlgorithm::*;
+        let (cipher, hash, mac) = match suite {
+            0x1301 | 0x009C | 0xC02B | 0xC02F => (Aes128Gcm, Sha256, None),
+            0x1302 | 0x009D | 0xC02C | 0xC030 => (Aes256Gcm, Sha384, None),
+            0x1303 | 0xCCA8 | 0xCCA9 => (ChaCha20Poly1305, Sha256, None),
+            0x002F | 0xC009 | 0xC013 => (Aes128Cbc, Sha256, Some(Sha1)),
+            0x0035 | 0xC00A | 0xC014 => (Aes256Cbc, Sha256, Some(Sha1)),
+            0x003C | 0xC023 | 0xC027 => (Aes128Cbc, Sha256, Some(Sha256)),
+            0x003D => (Aes256Cbc, Sha256, Some(Sha256)),
+            0xC024 | 0xC028 => (Aes256Cbc, Sha384, Some(Sha384)),
+            _ => return None
+        };
+        Some(Self { cipher, hash, mac })
+    }
+
+    fn key_length(&self) -> usize {
+        match self.cipher {
+            BulkCipher::Aes128Gcm | BulkCipher::Aes128Cbc => 16,
+            _ => 32
+        }
+    }
+
+    /// The length of the implicit part of the nonce in TLS 1.2.
+    fn fixed_iv_length(&self) -> usize {
+        match self.cipher {
+            BulkCipher::Aes128Gcm | BulkCipher::Aes256Gcm => 4,
+            BulkCipher::ChaCha20Poly1305 => 12,
+            _ => 0
+        }
+    }
+}
+
+/// The keys protecting the records one side sends, with the sequence number of the next record.
+#[derive(Debug, Clone)]
+pub struct RecordKeys {
+    suite: CipherSuite,
+    /// The traffic secret the keys came from in TLS 1.3, None in TLS 1.2
+    secret: Option<Vec<u8>>,
+    key: Vec<u8>,
+    iv: Vec<u8>,
+    mac_key: Vec<u8>,
+    /// Whether the CBC suites compute the MAC over the ciphertext, as negotiated by RFC 7366
+    encrypt_then_mac: bool,
+    sequence: u64,
+}
+
+impl RecordKeys {
+    /// The keys of one side in TLS 1.2, from the key block the master secret expands to.
+    pub fn tls12(
+        suite: CipherSuite,
+        master_secret: &[u8],
+        client_random: &[u8],
+        server_random: &[u8],
+        server: bool,
+        encrypt_then_mac: bool,
+    ) -> Self {
+        let mac_length = suite.mac.map_or(0, |mac| mac.length());
+        let (key_length, iv_length) = (suite.key_length(), suite.fixed_iv_length());
+        let seed = [server_random, client_random].concat();
+        let block = suite.hash.prf(master_secret, "key expansion", &seed, 2 * (mac_length + key_length + iv_length));
+        // The block holds both MAC keys, then both keys, then both IVs, client first
+        let part = |offset: usize, length: usize| {
+            let offset = offset + if server { length } else { 0 };
+            Vec::from(&block[offset..offset + length])
+        };
+        Self {
+            suite,
+            secret: None,
+            mac_key: part(0, mac_length),
+            key: part(2 * mac_length, key_length),
+            iv: part(2 * (mac_length + key_length), iv_length),
+            encrypt_then_mac,
+            sequence: 0,
+        }
+    }
+
+    /// The keys of one side in TLS 1.3, from one of its traffic secrets.
+    pub fn tls13(suite: CipherSuite, secret: &[u8]) -> Option<Self> {
+        Some(Self {
+            suite,
+            secret: Some(Vec::from(secret)),
+            key: suite.hash.expand_label(secret, "key", suite.key_length())?,
+            iv: suite.hash.expand_label(secret, "iv", 12)?,
+            mac_key: Vec::new(),
+            encrypt_then_mac: false,
+            sequence: 0,
+        })
+    }
+
+    /// The keys after a TLS 1.3 key update.
+    pub fn update(&self) -> Option<Self> {
+        let secret = self.secret.as_ref()?;
+        let next = self.suite.hash.expand_label(secret, "traffic upd", self.suite.hash.length())?;
+        Self::tls13(self.suite, &next)
+    }
+
+    fn aead(&self, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        let nonce = Nonce::from_slice(nonce);
+        let payload = Payload { msg: ciphertext, aad };
+        match self.suite.cipher {
+            BulkCipher::Aes128Gcm => Aes128Gcm::new_from_slice(&self.key).ok()?.decrypt(nonce, payload),
+            BulkCipher::Aes256Gcm => Aes256Gcm::new_from_slice(&self.key).ok()?.decrypt(nonce, payload),
+            BulkCipher::ChaCha20Poly1305 => ChaCha20Poly1305::new_from_slice(&self.key).ok()?.decrypt(nonce, payload),
+            _ => return None
+        }.ok()
+    }
+
+    /// Decrypts a record, returning its real content type and its plaintext, None when it does
+    /// not authenticate. The sequence number moves on either way.
+    pub fn decrypt(&mut self, content_type: ContentType, version: u16, fragment: &[u8]) -> Option<(ContentType, Vec<u8>)> {
+        let sequence = self.sequence;
+        self.sequence += 1;
+        let header = |length: usize| {
+            let mut header = Vec::with_capacity(13);
+            header.extend_from_slice(&sequence.to_be_bytes());
+            header.push(content_type);
+            header.extend_from_slice(&version.to_be_bytes());
+            header.extend_from_slice(&(length as u16).to_be_bytes());
+            header
+        };
+
+        if self.secret.is_some() {
+            // The additional data is the record header, the content type hides after the padding
+            let mut aad = vec![content_type];
+            aad.extend_from_slice(&version.to_be_bytes());
+            aad.extend_from_slice(&(fragment.len() as u16).to_be_bytes());
+            let mut plaintext = self.aead(&self.nonce(sequence), fragment, &aad)?;
+            let end = plaintext.iter().rposition(|byte| *byte != 0)?;
+            let inner_type = plaintext[end];
+            plaintext.truncate(end);
+            return Some((inner_type, plaintext));
+        }
+
+        match self.suite.cipher {
+            BulkCipher::Aes128Gcm | BulkCipher::Aes256Gcm => {
+                // The explicit part of the nonce leads the fragment
+                let length = fragment.len().checked_sub(8 + 16)?;
+                let nonce = [self.iv.as_slice(), &fragment[..8]].concat();
+                let plaintext = self.aead(&nonce, &fragment[8..], &header(length))?;
+                Some((content_type, plaintext))
+            }
+            BulkCipher::ChaCha20Poly1305 => {
+                let length = fragment.len().checked_sub(16)?;
+                let plaintext = self.aead(&self.nonce(sequence), fragment, &header(length))?;
+                Some((content_type, plaintext))
+            }
+            BulkCipher::Aes128Cbc | BulkCipher::Aes256Cbc => {
+                let mac = self.suite.mac?;
+                // With encrypt-then-MAC the MAC covers the IV and the ciphertext and comes last,
+                // otherwise it covers the content and is encrypted with it
+                let fragment = if self.encrypt_then_mac {
+                    let (authenticated, expected) = fragment.split_at(fragment.len().checked_sub(mac.length())?);
+                    if mac.hmac(&self.mac_key, &[&header(authenticated.len()), authenticated]) != expected {
+                        return None;
+                    }
+                    authenticated
+                } else {
+                    fragment
+                };
+                if fragment.len() < 32 || !fragment.len().is_multiple_of(16) {
+                    return None;
+                }
+                let (iv, ciphertext) = fragment.split_at(16);
+                let mut buffer = Vec::from(ciphertext);
+                match self.suite.cipher {
+                    BulkCipher::Aes128Cbc => cbc::Decryptor::<Aes128>::new_from_slices(&self.key, iv).ok()?
+                        .decrypt_padded_mut::<NoPadding>(&mut buffer).ok()?,
+                    _ => cbc::Decryptor::<Aes256>::new_from_slices(&self.key, iv).ok()?
+                        .decrypt_padded_mut::<NoPadding>(&mut buffer).ok()?
+                };
+                let padding = *buffer.last()? as usize + 1;
+                let mut content = buffer.len().checked_sub(padding)?;
+                if !self.encrypt_then_mac {
+                    content = content.checked_sub(mac.length())?;
+                    let expected = &buffer[content..content + mac.length()];
+                    if mac.hmac(&self.mac_key, &[&header(content), &buffer[..content]]) != expected {
+                        return None;
+                    }
+                }
+                buffer.truncate(content);
+                Some((content_type, buffer))
+            }
+        }
+    }
+
+    /// The per record nonce, the IV with the sequence number xored into its end.
+    fn nonce(&self, sequence: u64) -> Vec<u8> {
+        let mut nonce = self.iv.clone();
+        let offset = nonce.len() - 8;
+        for (index, byte) in sequence.to_be_bytes().iter().enumerate() {
+            nonce[offset + index] ^= byte;
+        }
+        nonce
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use md5::{Digest, Md5};
+use prettytable::{format, row, table, Table};
+use sha2::Sha256;
+use crate::application::tls::{self, Hello};
+use crate::util::*;
+
+/// The JA3 and JA4 fingerprints of a hello, JA3S and JA4S for a server hello.
+#[derive(Debug, Clone)]
+pub struct Fingerprint {
+    client: bool,
+    /// The string JA3 hashes, kept for those who want to see what went in
+    ja3_text: String,
+    ja3: String,
+    ja4: String,
+    /// The name of the client or server from the database
+    name: Option<String>,
+}
+
+/// Names of known clients and servers, read from a file with one `<fingerprint> <name>` per line,
+/// blank lines and `#` comments ignored. The fingerprint is a JA3 or JA3S hash, or a JA4 or JA4S.
+#[derive(Debug, Default)]
+pub struct FingerprintDatabase {
+    names: HashMap<String, String>,
+}
+
+/// How often every fingerprint was seen in the capture, with the names of the known ones.
+#[derive(Debug, Default)]
+pub struct Fingerprints {
+    database: FingerprintDatabase,
+    seen: Vec<(Fingerprint, usize)>,
+}
+
+fn decimal(values: &[u16]) -> String {
+    let values: Vec<String> = values.iter()
+        .filter(|value| !tls::is_grease(**value))
+        .map(|value| value.to_string())
+        .collect();
+    values.join("-")
+}
+
+/// The JA4 truncated SHA-256 of a list of values, all zeros for an empty list.
+fn truncated_hash(text: &str) -> String {
+    if text.is_empty() {
+        return String::from("000000000000");
+    }
+    hex(&Sha256::digest(text.as_bytes())[..6]).to_lowercase()
+}
+
+fn hex_list(values: &[u16]) -> String {
+    let values: Vec<String> = values.iter().map(|value| format!("{:04x}", value)).collect();
+    values.join(",")
+}
+
+fn ja4_version(version: u16) -> &'static str {
+    match version {
+        0x0304 => "13",
+        0x0303 => "12",
+        0x0302 => "11",
+        0x0301 => "10",
+        0x0300 => "s3",
+        0x0002 => "s2",
+        0xfeff => "d1",
+        0xfefd => "d2",
+        0xfefc => "d3",
+        _ => "00"
+    }
+}
+
+/// The first and last characters of the first application protocol, or of its hex when they
+/// are not alphanumeric.
+fn ja4_alpn(hello: &Hello) -> String {
+    let alpn = hello.alpn();
+    let Some(protocol) = alpn.first().filter(|protocol| !protocol.is_empty()) else {
+        return String::from("00");
+    };
+    let (first, last) = (protocol.bytes().next().unwrap(), protocol.bytes().last().unwrap());
+    if first.is_ascii_alphanumeric() && last.is_ascii_alphanumeric() {
+        format!("{}{}", first as char, last as char)
+    } else {
+        let hex = hex(protocol.as_bytes()).to_lowercase();
+        format!("{}{}", &hex[..1], &hex[hex.len() - 1..])
+    }
+}
+
+impl Fingerprint {
+    /// Fingerprints a hello, carried over QUIC rather than TCP when `quic` is set.
+    pub fn new(hello: &Hello, quic: bool) -> Self {
+        let without_grease = |values: &[u16]| -> Vec<u16> {
+            values.iter().copied().filter(|value| !tls::is_grease(*value)).collect()
+        };
+        let extensions: Vec<u16> = without_grease(&hello.extensions().iter().map(|(extension, _)| *extension).collect::<Vec<_>>());
+        let cipher_suites = without_grease(hello.cipher_suites());
+        let version = *without_grease(&hello.versions()).iter().max().unwrap_or(&hello.legacy_version());
+        let prefix = format!("{}{}", if quic { "q" } else { "t" }, ja4_version(version));
+
+        let (ja3_text, ja4) = if hello.is_client() {
+            let point_formats: Vec<u16> = hello.ec_point_formats().iter().map(|format| *format as u16).collect();
+            let ja3_text = format!(
+                "{},{},{},{},{}",
+                hello.legacy_version(),
+                decimal(&cipher_suites),
+                decimal(&extensions),
+                decimal(&hello.supported_groups()),
+                decimal(&point_formats)
+            );
+
+            let mut sorted_suites = cipher_suites.clone();
+            sorted_suites.sort();
+            // The server name and the application protocols already show in the first part
+            let mut sorted_extensions: Vec<u16> = extensions.iter().copied()
+                .filter(|extension| *extension != tls::EXTENSION_SERVER_NAME && *extension != tls::EXTENSION_ALPN)
+                .collect();
+            sorted_extensions.sort();
+            let mut extension_text = hex_list(&sorted_extensions);
+            let signature_algorithms = without_grease(&hello.signature_algorithms());
+            if !signature_algorithms.is_empty() {
+                extension_text = format!("{}_{}", extension_text, hex_list(&signature_algorithms));
+            }
+            let ja4 = format!(
+                "{}{}{:02}{:02}{}_{}_{}",
+                prefix,
+                if hello.server_name().is_some() { "d" } else { "i" },
+                cipher_suites.len().min(99),
+                extensions.len().min(99),
+                ja4_alpn(hello),
+                truncated_hash(&hex_list(&sorted_suites)),
+                truncated_hash(&extension_text)
+            );
+            (ja3_text, ja4)
+        } else {
+            let ja3_text = format!("{},{},{}", hello.legacy_version(), decimal(&cipher_suites), decimal(&extensions));
+            let ja4 = format!(
+                "{}{:02}{}_{}_{}",
+                prefix,
+                extensions.len().min(99),
+                ja4_alpn(hello),
+                hex_list(&cipher_suites),
+                truncated_hash(&hex_list(&extensions))
+            );
+            (ja3_text, ja4)
+        };
+
+        Self {
+            client: hello.is_client(),
+            ja3: hex(&Md5::digest(ja3_text.as_bytes())).to_lowercase(),
+            ja3_text,
+            ja4,
+            name: None,
+        }
+    }
+}
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let suffix = if self.client { "" } else { "s" };
+        let mut table = table!(
+            [format!("ja3{}", suffix), self.ja3],
+            [format!("ja3{}_text", suffix), self.ja3_text],
+            [format!("ja4{}", suffix), self.ja4]
+        );
+        if let Some(name) = &self.name {
+            table.add_row(row!["name", name]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+impl FingerprintDatabase {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut names = HashMap::new();
+        for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((fingerprint, name)) = line.split_once(char::is_whitespace) else {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{}:{}: expected <fingerprint> <name>", path.display(), number + 1)
+                ));
+            };
+            names.insert(fingerprint.to_lowercase(), String::from(name.trim()));
+        }
+        Ok(Self { names })
+    }
+
+    /// The name known for either fingerprint, JA4 first as it is the more specific.
+    pub fn name(&self, fingerprint: &Fingerprint) -> Option<&str> {
+        self.names.get(&fingerprint.ja4.to_lowercase())
+            .or_else(|| self.names.get(&fingerprint.ja3))
+            .map(String::as_str)
+    }
+}
+
+impl Fingerprints {
+    pub fn new(database: FingerprintDatabase) -> Self {
+        Self {
+            database,
+            seen: Vec::new(),
+        }
+    }
+
+    /// Counts the fingerprint, returning it with its name when the database knows it.
+    pub fn update(&mut self, mut fingerprint: Fingerprint) -> Fingerprint {
+        fingerprint.name = self.database.name(&fingerprint).map(String::from);
+        let seen = self.seen.iter_mut()
+            .find(|(known, _)| known.client == fingerprint.client && known.ja3 == fingerprint.ja3 && known.ja4 == fingerprint.ja4);
+        match seen {
+            Some((_, count)) => *count += 1,
+            None => self.seen.push((fingerprint.clone(), 1))
+        }
+        fingerprint
+    }
+
+}
+
+impl Display for Fingerprints {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.set_titles(row!["side", "ja3", "ja4", "name", "count"]);
+        for (fingerprint, count) in self.seen.iter() {
+            table.add_row(row![
+                if fingerprint.client { "client" } else { "server" },
+                fingerprint.ja3,
+                fingerprint.ja4,
+                fingerprint.name.as_deref().unwrap_or("unidentified"),
+                count
+            ]);
+        }
+
+        table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::tls::{CLIENT_HELLO, ExtensionType};
+
+    fn u16s(values: &[u16]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_be_bytes()).collect()
+    }
+
+    fn with_length(length_size: usize, data: &[u8]) -> Vec<u8> {
+        [&data.len().to_be_bytes()[8 - length_size..], data].concat()
+    }
+
+    fn client_hello(legacy_version: u16, cipher_suites: &[u16], extensions: Vec<(ExtensionType, Vec<u8>)>) -> Hello {
+        Hello {
+            msg_type: CLIENT_HELLO,
+            legacy_version,
+            random: [0; 32],
+            session_id: Vec::new(),
+            cipher_suites: Vec::from(cipher_suites),
+            compression_methods: vec![0],
+            extensions,
+        }
+    }
+
+    fn server_name(name: &str) -> Vec<u8> {
+        with_length(2, &[&[0][..], &with_length(2, name.as_bytes())].concat())
+    }
+
+    #[test]
+    fn matches_published_ja3() {
+        // The example of the JA3 README, with GREASE values that JA3 leaves out
+        let hello = client_hello(0x0301, &[0x0a0a, 47, 53, 5, 10, 49161, 49162, 49171, 49172, 50, 56, 19, 4], vec![
+            (0x1a1a, Vec::new()),
+            (0, server_name("example.com")),
+            (10, with_length(2, &u16s(&[0x2a2a, 23, 24, 25]))),
+            (11, with_length(1, &[0])),
+        ]);
+        let fingerprint = Fingerprint::new(&hello, false);
+        assert_eq!(fingerprint.ja3_text, "769,47-53-5-10-49161-49162-49171-49172-50-56-19-4,0-10-11,23-24-25,0");
+        assert_eq!(fingerprint.ja3, "ada70206e40642a3e4461f35503241d5");
+    }
+
+    #[test]
+    fn matches_published_ja4() {
+        // The Chrome example of the JA4 technical details, t13d1516h2_8daaf6152771_e5627efa2ab1
+        let cipher_suites = [
+            0x3a3a, 0x1301, 0x1302, 0x1303, 0xc02b, 0xc02f, 0xc02c, 0xc030,
+            0xcca9, 0xcca8, 0xc013, 0xc014, 0x009c, 0x009d, 0x002f, 0x0035,
+        ];
+        let alpn: Vec<u8> = ["h2", "http/1.1"].iter().flat_map(|protocol| with_length(1, protocol.as_bytes())).collect();
+        let signature_algorithms = [0x0403, 0x0804, 0x0401, 0x0503, 0x0805, 0x0501, 0x0806, 0x0601];
+        let hello = client_hello(0x0303, &cipher_suites, vec![
+            (0x4a4a, Vec::new()),
+            (0x0000, server_name("example.com")),
+            (0x0017, Vec::new()),
+            (0xff01, vec![0]),
+            (0x000a, with_length(2, &u16s(&[0x4a4a, 0x001d, 0x0017, 0x0018]))),
+            (0x000b, with_length(1, &[0])),
+            (0x0023, Vec::new()),
+            (0x0010, with_length(2, &alpn)),
+            (0x0005, vec![1, 0, 0, 0, 0]),
+            (0x000d, with_length(2, &u16s(&signature_algorithms))),
+            (0x0012, Vec::new()),
+            (0x0033, Vec::new()),
+            (0x002d, vec![1, 1]),
+            (0x002b, with_length(1, &u16s(&[0x7a7a, 0x0304, 0x0303]))),
+            (0x001b, vec![2, 0, 2]),
+            (0x4469, Vec::new()),
+            (0x0015, Vec::new()),
+            (0x5a5a, Vec::new()),
+        ]);
+        let fingerprint = Fingerprint::new(&hello, false);
+        assert_eq!(fingerprint.ja4, "t13d1516h2_8daaf6152771_e5627efa2ab1");
+        assert!(Fingerprint::new(&hello, true).ja4.starts_with("q13d1516h2_"));
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use crate::util::*;
+
+pub const CLIENT_RANDOM: &str = "CLIENT_RANDOM";
+pub const CLIENT_HANDSHAKE_TRAFFIC_SECRET: &str = "CLIENT_HANDSHAKE_TRAFFIC_SECRET";
+pub const SERVER_HANDSHAKE_TRAFFIC_SECRET: &str = "SERVER_HANDSHAKE_TRAFFIC_SECRET";
+pub const CLIENT_TRAFFIC_SECRET_0: &str = "CLIENT_TRAFFIC_SECRET_0";
+pub const SERVER_TRAFFIC_SECRET_0: &str = "SERVER_TRAFFIC_SECRET_0";
+
+/// The secrets of an NSS key log file, as written by clients honouring SSLKEYLOGFILE: one
+/// `<label> <client random> <secret>` per line, in hex, blank lines and `#` comments ignored.
+///
+/// `CLIENT_RANDOM` lines hold TLS 1.2 master secrets, the `*_TRAFFIC_SECRET*` lines the TLS 1.3
+/// traffic secrets. Other labels are kept but not used.
+#[derive(Debug, Default)]
+pub struct KeyLog {
+    secrets: HashMap<(String, [u8; 32]), Vec<u8>>,
+}
+
+impl KeyLog {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut secrets = HashMap::new();
+        for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = |message: &str| io::Error::new(
+                ErrorKind::InvalidData, format!("{}:{}: {}", path.display(), number + 1, message)
+            );
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [label, client_random, secret] = fields[..] else {
+                return Err(invalid("expected <label> <client random> <secret>"));
+            };
+            let client_random: [u8; 32] = unhex(client_random)
+                .and_then(|client_random| client_random.try_into().ok())
+                .ok_or_else(|| invalid("invalid client random"))?;
+            let secret = unhex(secret).ok_or_else(|| invalid("invalid secret"))?;
+            secrets.insert((String::from(label), client_random), secret);
+        }
+        Ok(Self { secrets })
+    }
+
+    /// The secret logged with the label for the connection that started with the client random.
+    pub fn get(&self, label: &str, client_random: &[u8; 32]) -> Option<&[u8]> {
+        self.secrets.get(&(String::from(label), *client_random)).map(Vec::as_slice)
+    }
+}
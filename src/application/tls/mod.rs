@@ -0,0 +1,699 @@
+pub mod cipher;
+pub mod keylog;
+pub mod fingerprint;
+
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table, row};
+use crate::application::tls::cipher::{CipherSuite, RecordKeys};
+use crate::application::tls::keylog::KeyLog;
+use crate::application::x509::Certificate;
+use crate::util::*;
+
+/// Ports of protocols that run over TLS from the first byte.
+pub const PORTS: [u16; 8] = [443, 465, 636, 853, 993, 995, 5061, 8443];
+/// Ports of HTTP over TLS.
+pub const HTTPS_PORTS: [u16; 2] = [443, 8443];
+
+pub type ContentType = u8;
+
+pub const CHANGE_CIPHER_SPEC: ContentType = 20;
+pub const ALERT: ContentType = 21;
+pub const HANDSHAKE: ContentType = 22;
+pub const APPLICATION_DATA: ContentType = 23;
+pub const HEARTBEAT: ContentType = 24;
+
+/// The largest fragment a record may carry, with the room allowed for encryption overhead.
+const MAX_FRAGMENT_LENGTH: usize = (1 << 14) + 2048;
+
+pub type HandshakeType = u8;
+
+pub const CLIENT_HELLO: HandshakeType = 1;
+pub const SERVER_HELLO: HandshakeType = 2;
+pub const NEW_SESSION_TICKET: HandshakeType = 4;
+pub const ENCRYPTED_EXTENSIONS: HandshakeType = 8;
+pub const CERTIFICATE: HandshakeType = 11;
+pub const SERVER_KEY_EXCHANGE: HandshakeType = 12;
+pub const CERTIFICATE_REQUEST: HandshakeType = 13;
+pub const SERVER_HELLO_DONE: HandshakeType = 14;
+pub const CERTIFICATE_VERIFY: HandshakeType = 15;
+pub const CLIENT_KEY_EXCHANGE: HandshakeType = 16;
+pub const FINISHED: HandshakeType = 20;
+pub const KEY_UPDATE: HandshakeType = 24;
+
+pub type ExtensionType = u16;
+
+pub const EXTENSION_SERVER_NAME: ExtensionType = 0;
+pub const EXTENSION_SUPPORTED_GROUPS: ExtensionType = 10;
+pub const EXTENSION_EC_POINT_FORMATS: ExtensionType = 11;
+pub const EXTENSION_SIGNATURE_ALGORITHMS: ExtensionType = 13;
+pub const EXTENSION_ALPN: ExtensionType = 16;
+pub const EXTENSION_ENCRYPT_THEN_MAC: ExtensionType = 22;
+pub const EXTENSION_SUPPORTED_VERSIONS: ExtensionType = 43;
+pub const EXTENSION_KEY_SHARE: ExtensionType = 51;
+pub const EXTENSION_QUIC_TRANSPORT_PARAMETERS: ExtensionType = 57;
+
+/// The random of a server hello that is really a hello retry request.
+const HELLO_RETRY_REQUEST: [u8; 32] = [
+    0xcf, 0x21, 0xad, 0x74, 0xe5, 0x9a, 0x61, 0x11, 0xbe, 0x1d, 0x8c, 0x02, 0x1e, 0x65, 0xb8, 0x91,
+    0xc2, 0xa2, 0x11, 0x16, 0x7a, 0xbb, 0x8c, 0x5e, 0x07, 0x9e, 0x09, 0xe2, 0xc8, 0xa8, 0x33, 0x9c
+];
+
+/// A record of the TLS record layer.
+#[derive(Debug, Clone)]
+pub struct TlsRecord {
+    content_type: ContentType,
+    version: u16,
+    fragment: Vec<u8>,
+    /// Whether the fragment was protected by the keys negotiated so far
+    encrypted: bool,
+    /// The real content type and the plaintext of an encrypted fragment, when the keys are known
+    plaintext: Option<(ContentType, Vec<u8>)>,
+}
+
+impl TlsRecord {
+    /// Whether the data starts like a record, to recognise TLS on unexpected ports.
+    pub fn looks_like(data: &[u8]) -> bool {
+        matches!(data, [CHANGE_CIPHER_SPEC..=HEARTBEAT, 3, 0..=4, ..])
+    }
+
+    /// The content type and the content, after decryption. None when encrypted with unknown keys.
+    pub fn content(&self) -> Option<(ContentType, &[u8])> {
+        match &self.plaintext {
+            Some((content_type, plaintext)) => Some((*content_type, plaintext)),
+            None if !self.encrypted => Some((self.content_type, &self.fragment)),
+            None => None
+        }
+    }
+}
+
+pub fn content_type_name(content_type: ContentType) -> &'static str {
+    match content_type {
+        CHANGE_CIPHER_SPEC => "change cipher spec",
+        ALERT => "alert",
+        HANDSHAKE => "handshake",
+        APPLICATION_DATA => "application data",
+        HEARTBEAT => "heartbeat",
+        _ => "unidentified"
+    }
+}
+
+impl DataContainer for TlsRecord {
+    fn data(&self) -> &[u8] {
+        &self.fragment
+    }
+}
+
+fn alert_description(description: u8) -> &'static str {
+    match description {
+        0 => "close notify",
+        10 => "unexpected message",
+        20 => "bad record mac",
+        22 => "record overflow",
+        40 => "handshake failure",
+        42 => "bad certificate",
+        43 => "unsupported certificate",
+        44 => "certificate revoked",
+        45 => "certificate expired",
+        46 => "certificate unknown",
+        47 => "illegal parameter",
+        48 => "unknown ca",
+        50 => "decode error",
+        51 => "decrypt error",
+        70 => "protocol version",
+        71 => "insufficient security",
+        80 => "internal error",
+        86 => "inappropriate fallback",
+        90 => "user canceled",
+        109 => "missing extension",
+        110 => "unsupported extension",
+        112 => "unrecognized name",
+        116 => "certificate required",
+        120 => "no application protocol",
+        _ => "unidentified"
+    }
+}
+
+impl Display for TlsRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["content_type", content_type_name(self.content_type)],
+            ["version", version_name(self.version)],
+            ["length", self.fragment.len()],
+            ["encrypted", self.encrypted]
+        );
+        if let Some((content_type, plaintext)) = &self.plaintext {
+            table.add_row(row!["decrypted", format!("{}, {} bytes", content_type_name(*content_type), plaintext.len())]);
+        }
+        if let Some((ALERT, &[level, description])) = self.content() {
+            let level = match level {
+                1 => "warning",
+                2 => "fatal",
+                _ => "unidentified"
+            };
+            table.add_row(row!["alert", format!("{} {}", level, alert_description(description))]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct TlsDirection {
+    /// Bytes of a record still missing some of its fragment
+    records: Vec<u8>,
+    /// Bytes of a handshake message split over several records
+    handshake: Vec<u8>,
+    /// Set once the sender switched to the negotiated keys
+    encrypted: bool,
+    /// The keys the sender encrypts with, when the key log has them
+    keys: Option<RecordKeys>,
+    /// Set when the bytes did not look like records, most likely because the capture started
+    /// in the middle of one
+    abandoned: bool,
+}
+
+/// Both directions of a TLS connection over a TCP stream, direction 0 being the client.
+#[derive(Debug, Default)]
+pub struct TlsConversation {
+    directions: [TlsDirection; 2],
+    client_random: Option<[u8; 32]>,
+    server_random: Option<[u8; 32]>,
+    cipher_suite: Option<u16>,
+    /// Whether the server agreed to encrypt-then-MAC
+    encrypt_then_mac: bool,
+    tls13: bool,
+    /// The application protocol the server chose
+    protocol: Option<String>,
+}
+
+impl TlsConversation {
+    /// Adds the data one segment brought to a direction and returns the records it completed,
+    /// each with the plaintext handshake messages it completed. Records are decrypted when the
+    /// key log holds the secrets of the connection.
+    pub fn push(&mut self, direction: usize, data: &[u8], keylog: Option<&KeyLog>) -> Vec<(TlsRecord, Vec<HandshakeMessage>)> {
+        let mut records = Vec::new();
+        if self.directions[direction].abandoned {
+            return records;
+        }
+        self.directions[direction].records.extend_from_slice(data);
+
+        while let Some(mut record) = self.next_record(direction) {
+            let state = &mut self.directions[direction];
+            if record.encrypted {
+                record.plaintext = state.keys.as_mut()
+                    .and_then(|keys| keys.decrypt(record.content_type, record.version, &record.fragment));
+            }
+            let mut messages = Vec::new();
+            let mut change_cipher_spec = false;
+            match record.content() {
+                Some((HANDSHAKE, content)) => {
+                    state.handshake.extend_from_slice(content);
+                    let (complete, consumed) = HandshakeMessage::parse_all(&state.handshake);
+                    state.handshake.drain(..consumed);
+                    messages = complete;
+                }
+                // TLS 1.3 only keeps them for the sake of middleboxes
+                Some((CHANGE_CIPHER_SPEC, _)) => change_cipher_spec = !self.tls13,
+                _ => ()
+            }
+            if change_cipher_spec {
+                let keys = self.tls12_keys(direction, keylog);
+                let state = &mut self.directions[direction];
+                state.encrypted = true;
+                state.keys = keys;
+            }
+            for message in messages.iter() {
+                self.handshake(direction, message, keylog);
+            }
+            records.push((record, messages));
+        }
+        records
+    }
+
+    /// The application protocol the server chose with ALPN.
+    #[inline]
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// Takes the next complete record off the buffer of a direction.
+    fn next_record(&mut self, direction: usize) -> Option<TlsRecord> {
+        let state = &mut self.directions[direction];
+        let header = state.records.get(..5)?;
+        let length = tou16(&header[3..5]) as usize;
+        if !TlsRecord::looks_like(header) || length > MAX_FRAGMENT_LENGTH {
+            state.abandoned = true;
+            state.records.clear();
+            return None;
+        }
+        if state.records.len() - 5 < length {
+            return None;
+        }
+        let record = TlsRecord {
+            content_type: header[0],
+            version: tou16(&header[1..3]),
+            fragment: Vec::from(&state.records[5..5 + length]),
+            encrypted: state.encrypted && header[0] != CHANGE_CIPHER_SPEC,
+            plaintext: None,
+        };
+        state.records.drain(..5 + length);
+        Some(record)
+    }
+
+    /// Follows the handshake to know the randoms, the cipher suite and when keys change.
+    fn handshake(&mut self, direction: usize, message: &HandshakeMessage, keylog: Option<&KeyLog>) {
+        match message.msg_type() {
+            CLIENT_HELLO => self.client_random = Hello::new(message).map(|hello| *hello.random()),
+            SERVER_HELLO => {
+                let Some(hello) = Hello::new(message) else {
+                    return;
+                };
+                if *hello.random() == HELLO_RETRY_REQUEST {
+                    return;
+                }
+                self.server_random = Some(*hello.random());
+                self.cipher_suite = hello.cipher_suites().first().copied();
+                self.encrypt_then_mac = hello.extension(EXTENSION_ENCRYPT_THEN_MAC).is_some();
+                self.protocol = hello.alpn().into_iter().next();
+                // A server choosing TLS 1.3 encrypts everything after its hello, on both sides
+                if hello.versions() == [0x0304] {
+                    self.tls13 = true;
+                    let labels = [keylog::CLIENT_HANDSHAKE_TRAFFIC_SECRET, keylog::SERVER_HANDSHAKE_TRAFFIC_SECRET];
+                    for (direction, label) in labels.iter().enumerate() {
+                        let keys = self.tls13_keys(label, keylog);
+                        self.directions[direction].encrypted = true;
+                        self.directions[direction].keys = keys;
+                    }
+                }
+            }
+            FINISHED if self.tls13 => {
+                let label = if direction == 0 { keylog::CLIENT_TRAFFIC_SECRET_0 } else { keylog::SERVER_TRAFFIC_SECRET_0 };
+                self.directions[direction].keys = self.tls13_keys(label, keylog);
+            }
+            KEY_UPDATE => {
+                let keys = self.directions[direction].keys.as_ref().and_then(RecordKeys::update);
+                self.directions[direction].keys = keys;
+            }
+            _ => ()
+        }
+    }
+
+    fn tls12_keys(&self, direction: usize, keylog: Option<&KeyLog>) -> Option<RecordKeys> {
+        let suite = CipherSuite::new(self.cipher_suite?)?;
+        let client_random = self.client_random?;
+        let master_secret = keylog?.get(keylog::CLIENT_RANDOM, &client_random)?;
+        let server_random = self.server_random?;
+        Some(RecordKeys::tls12(suite, master_secret, &client_random, &server_random, direction == 1, self.encrypt_then_mac))
+    }
+
+    fn tls13_keys(&self, label: &str, keylog: Option<&KeyLog>) -> Option<RecordKeys> {
+        let suite = CipherSuite::new(self.cipher_suite?)?;
+        let secret = keylog?.get(label, &self.client_random?)?;
+        RecordKeys::tls13(suite, secret)
+    }
+}
+
+/// A handshake message, as carried in TLS records or QUIC CRYPTO frames.
+#[derive(Debug, Clone)]
+pub struct HandshakeMessage {
+    msg_type: HandshakeType,
+    body: Vec<u8>,
+}
+
+impl HandshakeMessage {
+    /// Splits the complete handshake messages at the start of the data, returning them and the
+    /// number of bytes they took.
+    pub fn parse_all(data: &[u8]) -> (Vec<Self>, usize) {
+        let mut messages = Vec::new();
+        let mut offset = 0;
+        while data.len() - offset >= 4 {
+            let length = tou32(&[0, data[offset + 1], data[offset + 2], data[offset + 3]]) as usize;
+            if data.len() - offset - 4 < length {
+                break;
+            }
+            messages.push(Self {
+                msg_type: data[offset],
+                body: Vec::from(&data[offset + 4..offset + 4 + length]),
+            });
+            offset += 4 + length;
+        }
+        (messages, offset)
+    }
+
+    #[inline]
+    pub fn msg_type(&self) -> HandshakeType {
+        self.msg_type
+    }
+
+    #[inline]
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.msg_type {
+            CLIENT_HELLO => "client hello",
+            SERVER_HELLO => "server hello",
+            NEW_SESSION_TICKET => "new session ticket",
+            ENCRYPTED_EXTENSIONS => "encrypted extensions",
+            CERTIFICATE => "certificate",
+            SERVER_KEY_EXCHANGE => "server key exchange",
+            CERTIFICATE_REQUEST => "certificate request",
+            SERVER_HELLO_DONE => "server hello done",
+            CERTIFICATE_VERIFY => "certificate verify",
+            CLIENT_KEY_EXCHANGE => "client key exchange",
+            FINISHED => "finished",
+            _ => "unidentified"
+        }
+    }
+}
+
+/// The certificates of a certificate message, sender first. Those that cannot be decoded are
+/// left out.
+pub fn certificates(message: &HandshakeMessage) -> Vec<Certificate> {
+    let mut certificates = Vec::new();
+    let data = message.body();
+    let u24 = |data: &[u8]| tou32(&[0, data[0], data[1], data[2]]) as usize;
+    // TLS 1.3 puts a request context first and extensions after every certificate
+    let (list, tls13) = match data {
+        [a, b, c, rest @ ..] if u24(&[*a, *b, *c]) == rest.len() => (rest, false),
+        [context, rest @ ..] => match rest.get(*context as usize..) {
+            Some([a, b, c, list @ ..]) if u24(&[*a, *b, *c]) == list.len() => (list, true),
+            _ => return certificates
+        },
+        _ => return certificates
+    };
+    let mut rest = list;
+    while rest.len() >= 3 {
+        let length = u24(rest).min(rest.len() - 3);
+        certificates.extend(Certificate::new(&rest[3..3 + length]));
+        rest = &rest[3 + length..];
+        if tls13 && rest.len() >= 2 {
+            let length = (tou16(rest) as usize).min(rest.len() - 2);
+            rest = &rest[2 + length..];
+        }
+    }
+    certificates
+}
+
+/// The hello of either side, which share their layout up to the cipher suites.
+#[derive(Debug, Clone)]
+pub struct Hello {
+    msg_type: HandshakeType,
+    legacy_version: u16,
+    random: [u8; 32],
+    session_id: Vec<u8>,
+    /// All the suites offered by a client, the one chosen by a server
+    cipher_suites: Vec<u16>,
+    compression_methods: Vec<u8>,
+    extensions: Vec<(ExtensionType, Vec<u8>)>,
+}
+
+impl Hello {
+    /// Decodes a client or server hello, None for any other message or a truncated one.
+    pub fn new(message: &HandshakeMessage) -> Option<Self> {
+        let data = message.body();
+        let client = match message.msg_type() {
+            CLIENT_HELLO => true,
+            SERVER_HELLO => false,
+            _ => return None
+        };
+        let mut offset = 34;
+        let session_id_length = *data.get(offset)? as usize;
+        let session_id = Vec::from(data.get(offset + 1..offset + 1 + session_id_length)?);
+        offset += 1 + session_id_length;
+
+        let cipher_suites = if client {
+            let length = tou16(data.get(offset..offset + 2)?) as usize;
+            let suites = data.get(offset + 2..offset + 2 + length)?.chunks_exact(2).map(tou16).collect();
+            offset += 2 + length;
+            suites
+        } else {
+            offset += 2;
+            vec![tou16(data.get(offset - 2..offset)?)]
+        };
+
+        let compression_methods = if client {
+            let length = *data.get(offset)? as usize;
+            offset += 1 + length;
+            Vec::from(data.get(offset - length..offset)?)
+        } else {
+            offset += 1;
+            vec![*data.get(offset - 1)?]
+        };
+
+        let mut extensions = Vec::new();
+        if let Some(length) = data.get(offset..offset + 2).map(tou16) {
+            let mut rest = data.get(offset + 2..offset + 2 + length as usize).unwrap_or_default();
+            while rest.len() >= 4 {
+                let length = (tou16(&rest[2..4]) as usize).min(rest.len() - 4);
+                extensions.push((tou16(&rest[0..2]), Vec::from(&rest[4..4 + length])));
+                rest = &rest[4 + length..];
+            }
+        }
+
+        Some(Self {
+            msg_type: message.msg_type(),
+            legacy_version: tou16(data.get(0..2)?),
+            random: data.get(2..34)?.try_into().ok()?,
+            session_id,
+            cipher_suites,
+            compression_methods,
+            extensions,
+        })
+    }
+
+    #[inline]
+    pub fn is_client(&self) -> bool {
+        self.msg_type == CLIENT_HELLO
+    }
+
+    #[inline]
+    pub fn legacy_version(&self) -> u16 {
+        self.legacy_version
+    }
+
+    #[inline]
+    pub fn random(&self) -> &[u8; 32] {
+        &self.random
+    }
+
+    #[inline]
+    pub fn cipher_suites(&self) -> &[u16] {
+        &self.cipher_suites
+    }
+
+    #[inline]
+    pub fn extensions(&self) -> &[(ExtensionType, Vec<u8>)] {
+        &self.extensions
+    }
+
+    pub fn extension(&self, extension_type: ExtensionType) -> Option<&[u8]> {
+        self.extensions.iter()
+            .find(|(known, _)| *known == extension_type)
+            .map(|(_, data)| data.as_slice())
+    }
+
+    /// The host name the client asked for in the server name indication.
+    pub fn server_name(&self) -> Option<String> {
+        let data = self.extension(EXTENSION_SERVER_NAME)?;
+        // A list of names, of which only host names (type 0) are defined
+        let length = tou16(data.get(3..5)?) as usize;
+        data.get(5..5 + length).map(|name| String::from_utf8_lossy(name).into_owned())
+    }
+
+    /// The application protocols offered by a client or chosen by a server.
+    pub fn alpn(&self) -> Vec<String> {
+        let mut protocols = Vec::new();
+        let mut rest = self.extension(EXTENSION_ALPN).and_then(|data| data.get(2..)).unwrap_or_default();
+        while let Some((&length, tail)) = rest.split_first() {
+            let length = (length as usize).min(tail.len());
+            protocols.push(String::from_utf8_lossy(&tail[..length]).into_owned());
+            rest = &tail[length..];
+        }
+        protocols
+    }
+
+    /// The groups of the supported groups extension.
+    pub fn supported_groups(&self) -> Vec<u16> {
+        self.extension(EXTENSION_SUPPORTED_GROUPS)
+            .and_then(|data| data.get(2..))
+            .unwrap_or_default()
+            .chunks_exact(2)
+            .map(tou16)
+            .collect()
+    }
+
+    /// The point formats of the EC point formats extension.
+    pub fn ec_point_formats(&self) -> Vec<u8> {
+        Vec::from(self.extension(EXTENSION_EC_POINT_FORMATS).and_then(|data| data.get(1..)).unwrap_or_default())
+    }
+
+    /// The schemes of the signature algorithms extension, in the order of preference.
+    pub fn signature_algorithms(&self) -> Vec<u16> {
+        self.extension(EXTENSION_SIGNATURE_ALGORITHMS)
+            .and_then(|data| data.get(2..))
+            .unwrap_or_default()
+            .chunks_exact(2)
+            .map(tou16)
+            .collect()
+    }
+
+    /// The groups of the key shares offered by a client, chosen by a server or asked for in a
+    /// hello retry request.
+    pub fn key_share_groups(&self) -> Vec<u16> {
+        let Some(data) = self.extension(EXTENSION_KEY_SHARE) else {
+            return Vec::new();
+        };
+        if !self.is_client() {
+            return data.get(0..2).map(tou16).into_iter().collect();
+        }
+        let mut groups = Vec::new();
+        let mut rest = data.get(2..).unwrap_or_default();
+        while rest.len() >= 4 {
+            let length = (tou16(&rest[2..4]) as usize).min(rest.len() - 4);
+            groups.push(tou16(&rest[0..2]));
+            rest = &rest[4 + length..];
+        }
+        groups
+    }
+
+    /// The protocol versions from the supported versions extension, or the legacy version alone.
+    pub fn versions(&self) -> Vec<u16> {
+        match self.extension(EXTENSION_SUPPORTED_VERSIONS) {
+            Some(data) if self.is_client() => data.get(1..).unwrap_or_default().chunks_exact(2).map(tou16).collect(),
+            Some(data) if data.len() == 2 => vec![tou16(data)],
+            _ => vec![self.legacy_version]
+        }
+    }
+}
+
+pub fn version_name(version: u16) -> String {
+    match version {
+        0x0300 => String::from("ssl 3.0"),
+        0x0301 => String::from("tls 1.0"),
+        0x0302 => String::from("tls 1.1"),
+        0x0303 => String::from("tls 1.2"),
+        0x0304 => String::from("tls 1.3"),
+        version if is_grease(version) => String::from("grease"),
+        version => format!("{:04X}", version)
+    }
+}
+
+/// Whether the value is one of the reserved values clients sprinkle in to keep servers tolerant.
+pub fn is_grease(value: u16) -> bool {
+    value & 0x0f0f == 0x0a0a && value >> 8 == value & 0xff
+}
+
+pub fn cipher_suite_name(suite: u16) -> String {
+    let name = match suite {
+        0x000A => "TLS_RSA_WITH_3DES_EDE_CBC_SHA",
+        0x002F => "TLS_RSA_WITH_AES_128_CBC_SHA",
+        0x0035 => "TLS_RSA_WITH_AES_256_CBC_SHA",
+        0x009C => "TLS_RSA_WITH_AES_128_GCM_SHA256",
+        0x009D => "TLS_RSA_WITH_AES_256_GCM_SHA384",
+        0x00FF => "TLS_EMPTY_RENEGOTIATION_INFO_SCSV",
+        0x1301 => "TLS_AES_128_GCM_SHA256",
+        0x1302 => "TLS_AES_256_GCM_SHA384",
+        0x1303 => "TLS_CHACHA20_POLY1305_SHA256",
+        0x5600 => "TLS_FALLBACK_SCSV",
+        0xC009 => "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA",
+        0xC00A => "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA",
+        0xC013 => "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA",
+        0xC014 => "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA",
+        0xC02B => "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256",
+        0xC02C => "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384",
+        0xC02F => "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+        0xC030 => "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384",
+        0xCCA8 => "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256",
+        0xCCA9 => "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256",
+        suite if is_grease(suite) => "grease",
+        suite => return format!("{:04X}", suite)
+    };
+    String::from(name)
+}
+
+pub fn group_name(group: u16) -> String {
+    let name = match group {
+        0x0017 => "secp256r1",
+        0x0018 => "secp384r1",
+        0x0019 => "secp521r1",
+        0x001D => "x25519",
+        0x001E => "x448",
+        0x0100 => "ffdhe2048",
+        0x0101 => "ffdhe3072",
+        0x11EC => "x25519mlkem768",
+        0x6399 => "x25519kyber768draft00",
+        group if is_grease(group) => "grease",
+        group => return format!("{:04X}", group)
+    };
+    String::from(name)
+}
+
+pub fn extension_name(extension_type: ExtensionType) -> String {
+    match extension_type {
+        EXTENSION_SERVER_NAME => String::from("server_name"),
+        5 => String::from("status_request"),
+        EXTENSION_SUPPORTED_GROUPS => String::from("supported_groups"),
+        EXTENSION_EC_POINT_FORMATS => String::from("ec_point_formats"),
+        EXTENSION_SIGNATURE_ALGORITHMS => String::from("signature_algorithms"),
+        EXTENSION_ALPN => String::from("alpn"),
+        18 => String::from("signed_certificate_timestamp"),
+        21 => String::from("padding"),
+        EXTENSION_ENCRYPT_THEN_MAC => String::from("encrypt_then_mac"),
+        23 => String::from("extended_master_secret"),
+        27 => String::from("compress_certificate"),
+        35 => String::from("session_ticket"),
+        41 => String::from("pre_shared_key"),
+        EXTENSION_SUPPORTED_VERSIONS => String::from("supported_versions"),
+        45 => String::from("psk_key_exchange_modes"),
+        EXTENSION_KEY_SHARE => String::from("key_share"),
+        EXTENSION_QUIC_TRANSPORT_PARAMETERS => String::from("quic_transport_parameters"),
+        0xff01 => String::from("renegotiation_info"),
+        extension_type if is_grease(extension_type) => String::from("grease"),
+        extension_type => format!("{:04X}", extension_type)
+    }
+}
+
+impl Display for Hello {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let versions: Vec<String> = self.versions().into_iter().map(version_name).collect();
+        let cipher_suites: Vec<String> = self.cipher_suites.iter().map(|suite| cipher_suite_name(*suite)).collect();
+        let extensions: Vec<String> = self.extensions.iter().map(|(extension, _)| extension_name(*extension)).collect();
+
+        let mut table = table!(
+            ["legacy_version", version_name(self.legacy_version)],
+            ["versions", versions.join(", ")],
+            ["random", hex(&self.random)],
+            ["session_id", hex(&self.session_id)],
+            ["cipher_suites", cipher_suites.join("\n")],
+            ["compression_methods", format!("{:?}", self.compression_methods)],
+            ["extensions", extensions.join(", ")]
+        );
+        if let Some(server_name) = self.server_name() {
+            table.add_row(row!["server_name", server_name]);
+        }
+        let supported_groups: Vec<String> = self.supported_groups().into_iter().map(group_name).collect();
+        if !supported_groups.is_empty() {
+            table.add_row(row!["supported_groups", supported_groups.join(", ")]);
+        }
+        let key_share_groups: Vec<String> = self.key_share_groups().into_iter().map(group_name).collect();
+        if !key_share_groups.is_empty() {
+            table.add_row(row!["key_share", key_share_groups.join(", ")]);
+        }
+        let alpn = self.alpn();
+        if !alpn.is_empty() {
+            table.add_row(row!["alpn", alpn.join(", ")]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
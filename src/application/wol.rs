@@ -0,0 +1,57 @@
+use std::fmt::{Display, Formatter};
+use pnet::util::MacAddr;
+use prettytable::{format, table, row};
+
+pub const PORT_ECHO: u16 = 7;
+pub const PORT_DISCARD: u16 = 9;
+
+/// A Wake-on-LAN magic packet: six bytes of 0xFF, the target MAC address sixteen times, and an
+/// optional SecureOn password.
+#[derive(Debug)]
+pub struct MagicPacket {
+    target: MacAddr,
+    password: Vec<u8>,
+}
+
+impl MagicPacket {
+    /// Finds the magic packet in the payload, `None` if it isn't one.
+    pub fn find(data: &[u8]) -> Option<Self> {
+        let start = data.windows(6).position(|window| window == [0xff; 6])?;
+        let body = &data[start + 6..];
+        let target = body.get(0..6)?;
+        if body.len() < 96 || !body[..96].chunks(6).all(|chunk| chunk == target) {
+            return None;
+        }
+        // The password is either 4 or 6 bytes, anything else is padding
+        let password = match body.len() - 96 {
+            length @ (4 | 6) => Vec::from(&body[96..96 + length]),
+            _ => Vec::new()
+        };
+
+        Some(Self {
+            target: MacAddr::new(target[0], target[1], target[2], target[3], target[4], target[5]),
+            password,
+        })
+    }
+}
+
+impl Display for MagicPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["target", self.target]
+        );
+        if !self.password.is_empty() {
+            let password = self.password.iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<Vec<_>>()
+                .join(":");
+            table.add_row(row!["secureon_password", password]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
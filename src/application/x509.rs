@@ -0,0 +1,217 @@
+use std::fmt::{Display, Formatter};
+use std::net::Ipv6Addr;
+use prettytable::{format, table, row};
+use crate::application::ber::{self, Element};
+use crate::util::*;
+
+const SUBJECT_ALT_NAME: &str = "2.5.29.17";
+const BASIC_CONSTRAINTS: &str = "2.5.29.19";
+
+/// The fields of an X.509 certificate worth showing, decoded from its DER encoding.
+#[derive(Debug, Clone)]
+pub struct Certificate {
+    version: u64,
+    serial_number: Vec<u8>,
+    signature_algorithm: String,
+    issuer: String,
+    subject: String,
+    not_before: String,
+    not_after: String,
+    public_key: String,
+    subject_alt_names: Vec<String>,
+    ca: Option<bool>,
+}
+
+fn algorithm_name(oid: &str) -> String {
+    match oid {
+        "1.2.840.113549.1.1.1" => String::from("rsa"),
+        "1.2.840.113549.1.1.5" => String::from("sha1 with rsa"),
+        "1.2.840.113549.1.1.10" => String::from("rsassa-pss"),
+        "1.2.840.113549.1.1.11" => String::from("sha256 with rsa"),
+        "1.2.840.113549.1.1.12" => String::from("sha384 with rsa"),
+        "1.2.840.113549.1.1.13" => String::from("sha512 with rsa"),
+        "1.2.840.10045.2.1" => String::from("ec"),
+        "1.2.840.10045.4.3.2" => String::from("ecdsa with sha256"),
+        "1.2.840.10045.4.3.3" => String::from("ecdsa with sha384"),
+        "1.2.840.10045.4.3.4" => String::from("ecdsa with sha512"),
+        "1.2.840.10045.3.1.7" => String::from("p-256"),
+        "1.3.132.0.34" => String::from("p-384"),
+        "1.3.132.0.35" => String::from("p-521"),
+        "1.3.101.112" => String::from("ed25519"),
+        "1.3.101.113" => String::from("ed448"),
+        oid => String::from(oid)
+    }
+}
+
+fn attribute_name(oid: &str) -> String {
+    match oid {
+        "2.5.4.3" => String::from("CN"),
+        "2.5.4.5" => String::from("serialNumber"),
+        "2.5.4.6" => String::from("C"),
+        "2.5.4.7" => String::from("L"),
+        "2.5.4.8" => String::from("ST"),
+        "2.5.4.9" => String::from("street"),
+        "2.5.4.10" => String::from("O"),
+        "2.5.4.11" => String::from("OU"),
+        "0.9.2342.19200300.100.1.25" => String::from("DC"),
+        "1.2.840.113549.1.9.1" => String::from("emailAddress"),
+        oid => String::from(oid)
+    }
+}
+
+/// A distinguished name as comma separated attributes, in the order they were encoded.
+fn name(element: &Element) -> String {
+    let mut attributes = Vec::new();
+    for set in element.children() {
+        for attribute in set.children() {
+            let children = attribute.children();
+            if let (Some(oid), Some(value)) = (children.first().and_then(Element::oid), children.get(1)) {
+                attributes.push(format!("{}={}", attribute_name(&oid), value.string()));
+            }
+        }
+    }
+    attributes.join(", ")
+}
+
+/// A UTCTime or GeneralizedTime as an ISO 8601 date and time.
+fn time(element: &Element) -> Option<String> {
+    let text = element.string();
+    let text = text.strip_suffix('Z')?;
+    let text = match element.tag() {
+        // Two digit years below 50 are in the 21st century
+        ber::UTC_TIME if text.len() >= 12 => {
+            let century = if text.as_bytes()[0] < b'5' { "20" } else { "19" };
+            format!("{}{}", century, text)
+        }
+        ber::GENERALIZED_TIME if text.len() >= 14 => String::from(text),
+        _ => return None
+    };
+    if !text.is_char_boundary(14) || !text[..14].bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("{}-{}-{} {}:{}:{} UTC", &text[0..4], &text[4..6], &text[6..8], &text[8..10], &text[10..12], &text[12..14]))
+}
+
+/// The algorithm of a subject public key info with its size or curve.
+fn public_key(element: &Element) -> Option<String> {
+    let children = element.children();
+    let algorithm = children.first()?.children();
+    let oid = algorithm.first()?.oid()?;
+    let description = match (oid.as_str(), algorithm.get(1)) {
+        // The key is a bit string, whose first byte counts the unused bits
+        ("1.2.840.113549.1.1.1", _) => {
+            let key = children.get(1)?.value().get(1..)?;
+            let (modulus, _) = Element::parse(Element::parse(key)?.0.value())?;
+            let modulus = match modulus.value() {
+                [0, rest @ ..] => rest,
+                value => value
+            };
+            format!("rsa {} bits", modulus.len() * 8)
+        }
+        ("1.2.840.10045.2.1", Some(curve)) => format!("ec {}", algorithm_name(&curve.oid()?)),
+        (oid, _) => algorithm_name(oid)
+    };
+    Some(description)
+}
+
+impl Certificate {
+    /// Decodes a DER encoded certificate, None when it is malformed.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        let (certificate, _) = Element::parse(data)?;
+        let certificate = certificate.children();
+        let mut fields = certificate.first()?.children().into_iter().peekable();
+
+        // The version is explicitly tagged and left out for version 1
+        let version = match fields.peek() {
+            Some(field) if field.is_context(0) => {
+                let version = Element::parse(field.value())?.0.unsigned()?;
+                fields.next();
+                version + 1
+            }
+            _ => 1
+        };
+        let serial_number = Vec::from(fields.next()?.value());
+        let signature_algorithm = fields.next()?.children().first()?.oid()?;
+        let issuer = name(&fields.next()?);
+        let validity = fields.next()?.children();
+        let not_before = time(validity.first()?)?;
+        let not_after = time(validity.get(1)?)?;
+        let subject = name(&fields.next()?);
+        let public_key = public_key(&fields.next()?).unwrap_or_else(|| String::from("unidentified"));
+
+        let mut subject_alt_names = Vec::new();
+        let mut ca = None;
+        let extensions = fields.find(|field| field.is_context(3))
+            .and_then(|field| Element::parse(field.value()))
+            .map(|(extensions, _)| extensions.children())
+            .unwrap_or_default();
+        for extension in extensions {
+            let children = extension.children();
+            // The criticality is optional and the value always comes last
+            let (Some(oid), Some(value)) = (children.first().and_then(Element::oid), children.last()) else {
+                continue;
+            };
+            let Some((value, _)) = Element::parse(value.value()) else {
+                continue;
+            };
+            match oid.as_str() {
+                SUBJECT_ALT_NAME => for general_name in value.children() {
+                    match (general_name.class(), general_name.tag(), general_name.value()) {
+                        (ber::CLASS_CONTEXT, 1 | 2 | 6, value) => subject_alt_names.push(String::from_utf8_lossy(value).into_owned()),
+                        (ber::CLASS_CONTEXT, 7, &[a, b, c, d]) => subject_alt_names.push(format!("{}.{}.{}.{}", a, b, c, d)),
+                        (ber::CLASS_CONTEXT, 7, value) => if let Ok(address) = <[u8; 16]>::try_from(value) {
+                            subject_alt_names.push(Ipv6Addr::from(address).to_string());
+                        },
+                        _ => ()
+                    }
+                },
+                BASIC_CONSTRAINTS => {
+                    // The CA flag is an optional boolean defaulting to false
+                    let flag = value.children().into_iter().next().filter(|flag| flag.tag() == 1);
+                    ca = Some(flag.is_some_and(|flag| flag.value().first().is_some_and(|byte| *byte != 0)));
+                }
+                _ => ()
+            }
+        }
+
+        Some(Self {
+            version,
+            serial_number,
+            signature_algorithm: algorithm_name(&signature_algorithm),
+            issuer,
+            subject,
+            not_before,
+            not_after,
+            public_key,
+            subject_alt_names,
+            ca,
+        })
+    }
+}
+
+impl Display for Certificate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["version", self.version],
+            ["serial_number", hex(&self.serial_number)],
+            ["signature_algorithm", self.signature_algorithm],
+            ["issuer", self.issuer],
+            ["subject", self.subject],
+            ["not_before", self.not_before],
+            ["not_after", self.not_after],
+            ["public_key", self.public_key]
+        );
+        if !self.subject_alt_names.is_empty() {
+            table.add_row(row!["subject_alt_names", self.subject_alt_names.join(", ")]);
+        }
+        if let Some(ca) = self.ca {
+            table.add_row(row!["ca", ca]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+pub mod pcap;
+pub mod pcapng;
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use pnet::datalink::{Channel, DataLinkReceiver, NetworkInterface};
+use crate::capture::pcap::PcapReader;
+use crate::capture::pcapng::PcapngReader;
+use crate::datalink::LinkType;
+
+/// A captured packet together with the link layer it was captured on.
+#[derive(Debug)]
+pub struct Packet {
+    link_type: LinkType,
+    timestamp: Duration,
+    /// Name of the interface the packet was captured on, when known
+    interface: Option<String>,
+    data: Vec<u8>
+}
+
+impl Packet {
+    pub fn new(link_type: LinkType, timestamp: Duration, data: Vec<u8>) -> Self {
+        Self {
+            link_type,
+            timestamp,
+            interface: None,
+            data
+        }
+    }
+
+    pub fn with_interface(mut self, interface: &str) -> Self {
+        self.interface = Some(String::from(interface));
+        self
+    }
+
+    #[inline]
+    pub fn link_type(&self) -> LinkType {
+        self.link_type
+    }
+
+    /// The time the packet was captured, since the unix epoch.
+    #[inline]
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    #[inline]
+    pub fn interface(&self) -> Option<&str> {
+        self.interface.as_deref()
+    }
+
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        self.data.as_slice()
+    }
+}
+
+/// Where packets come from, either a live interface or a capture file.
+pub enum Capture {
+    Live(Box<dyn DataLinkReceiver>, LinkType, String),
+    Pcap(PcapReader<BufReader<File>>),
+    Pcapng(PcapngReader<BufReader<File>>),
+}
+
+impl Capture {
+    /// Starts capturing on the interface. pnet hands out frames without telling what link
+    /// layer they're on, so that has to be known up front.
+    pub fn live(interface: &NetworkInterface, link_type: LinkType) -> io::Result<Self> {
+        match pnet::datalink::channel(interface, Default::default())? {
+            Channel::Ethernet(_, rx) => Ok(Capture::Live(rx, link_type, interface.name.clone())),
+            _ => Err(io::Error::other("unhandled channel type"))
+        }
+    }
+
+    /// Opens a pcap or pcapng file, telling them apart by their magic number.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic == pcapng::SECTION_HEADER.to_be_bytes() {
+            Ok(Capture::Pcapng(PcapngReader::new(reader)?))
+        } else {
+            Ok(Capture::Pcap(PcapReader::new(reader, magic)?))
+        }
+    }
+}
+
+impl Iterator for Capture {
+    type Item = io::Result<Packet>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Capture::Live(rx, link_type, interface) => {
+                let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                Some(rx.next().map(|data| Packet::new(*link_type, timestamp, Vec::from(data)).with_interface(interface)))
+            }
+            Capture::Pcap(reader) => reader.next(),
+            Capture::Pcapng(reader) => reader.next()
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::io::{self, ErrorKind, Read};
+use std::time::Duration;
+use crate::capture::Packet;
+use crate::datalink::LinkType;
+
+pub const MAGIC_MICROSECONDS: u32 = 0xA1B2C3D4;
+pub const MAGIC_NANOSECONDS: u32 = 0xA1B23C4D;
+
+/// Largest captured length read, that of libpcap, longer ones coming from a corrupt file.
+const MAXIMUM_SNAPLEN: usize = 262144;
+
+/// Reads packets from a classic libpcap capture file.
+pub struct PcapReader<R: Read> {
+    reader: R,
+    big_endian: bool,
+    nanoseconds: bool,
+    link_type: LinkType,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Reads the rest of the global header, after the magic number.
+    pub fn new(mut reader: R, magic: [u8; 4]) -> io::Result<Self> {
+        let (big_endian, nanoseconds) = match (u32::from_be_bytes(magic), u32::from_le_bytes(magic)) {
+            (MAGIC_MICROSECONDS, _) => (true, false),
+            (MAGIC_NANOSECONDS, _) => (true, true),
+            (_, MAGIC_MICROSECONDS) => (false, false),
+            (_, MAGIC_NANOSECONDS) => (false, true),
+            _ => return Err(io::Error::new(ErrorKind::InvalidData, "not a pcap or pcapng file"))
+        };
+        let mut header = [0; 20];
+        reader.read_exact(&mut header)?;
+
+        let mut pcap = Self {
+            reader,
+            big_endian,
+            nanoseconds,
+            link_type: LinkType::Ethernet,
+        };
+        // The upper bits of the link type field hold FCS information
+        pcap.link_type = LinkType::from(pcap.u32(&header[16..20]) & 0x0fffffff);
+        Ok(pcap)
+    }
+
+    fn u32(&self, data: &[u8]) -> u32 {
+        let bytes = [data[0], data[1], data[2], data[3]];
+        if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+    }
+
+    fn read_packet(&mut self) -> io::Result<Option<Packet>> {
+        let mut header = [0; 16];
+        match self.reader.read_exact(&mut header) {
+            Ok(()) => (),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e)
+        }
+        let seconds = self.u32(&header[0..4]) as u64;
+        let fraction = self.u32(&header[4..8]);
+        let timestamp = if self.nanoseconds {
+            Duration::new(seconds, fraction)
+        } else {
+            Duration::new(seconds, 0) + Duration::from_micros(fraction as u64)
+        };
+
+        let length = self.u32(&header[8..12]) as usize;
+        if length > MAXIMUM_SNAPLEN {
+            return Err(io::Error::new(ErrorKind::InvalidData, "packet longer than the maximum snapshot length"));
+        }
+        let mut data = vec![0; length];
+        self.reader.read_exact(&mut data)?;
+        Ok(Some(Packet::new(self.link_type, timestamp, data)))
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = io::Result<Packet>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_packet().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A little endian microsecond capture of ethernet holding one packet record.
+    fn capture(captured_length: u32, data: &[u8]) -> Vec<u8> {
+        let mut file = vec![2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0, 0, 1, 0, 0, 0];
+        file.extend_from_slice(&[1, 0, 0, 0, 0x20, 0xa1, 0x07, 0]);
+        file.extend_from_slice(&captured_length.to_le_bytes());
+        file.extend_from_slice(&captured_length.to_le_bytes());
+        file.extend_from_slice(data);
+        file
+    }
+
+    #[test]
+    fn reads_packet() {
+        let magic = MAGIC_MICROSECONDS.to_le_bytes();
+        let mut reader = PcapReader::new(Cursor::new(capture(3, b"abc")), magic).unwrap();
+        let packet = reader.next().unwrap().unwrap();
+        assert_eq!(packet.data(), b"abc");
+        assert_eq!(packet.timestamp(), Duration::new(1, 500_000_000));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn rejects_oversized_packet() {
+        let magic = MAGIC_MICROSECONDS.to_le_bytes();
+        let mut reader = PcapReader::new(Cursor::new(capture(u32::MAX, b"abc")), magic).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
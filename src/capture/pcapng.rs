@@ -0,0 +1,163 @@
+use std::io::{self, ErrorKind, Read};
+use std::time::Duration;
+use crate::capture::Packet;
+use crate::datalink::LinkType;
+
+pub const SECTION_HEADER: u32 = 0x0A0D0D0A;
+pub const INTERFACE_DESCRIPTION: u32 = 0x00000001;
+pub const SIMPLE_PACKET: u32 = 0x00000003;
+pub const ENHANCED_PACKET: u32 = 0x00000006;
+
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B3C4D;
+const OPTION_END: u16 = 0;
+const OPTION_NAME: u16 = 2;
+const OPTION_TSRESOL: u16 = 9;
+
+/// Largest block read, longer ones coming from a corrupt file.
+const MAXIMUM_BLOCK: usize = 1 << 24;
+
+fn check_block_length(length: usize) -> io::Result<usize> {
+    if length > MAXIMUM_BLOCK {
+        return Err(io::Error::new(ErrorKind::InvalidData, "block longer than the maximum block length"));
+    }
+    Ok(length)
+}
+
+/// Reads packets from a pcapng capture file. Every interface in a section can have its own
+/// link type and timestamp resolution.
+pub struct PcapngReader<R: Read> {
+    reader: R,
+    big_endian: bool,
+    interfaces: Vec<Interface>,
+}
+
+#[derive(Debug, Clone)]
+struct Interface {
+    link_type: LinkType,
+    name: Option<String>,
+    /// Timestamp units per second
+    resolution: u64,
+}
+
+impl<R: Read> PcapngReader<R> {
+    /// Reads the rest of the first section header block, after its block type.
+    pub fn new(reader: R) -> io::Result<Self> {
+        let mut pcapng = Self {
+            reader,
+            big_endian: false,
+            interfaces: Vec::new(),
+        };
+        pcapng.read_section_header()?;
+        Ok(pcapng)
+    }
+
+    fn u16(&self, data: &[u8]) -> u16 {
+        let bytes = [data[0], data[1]];
+        if self.big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) }
+    }
+
+    fn u32(&self, data: &[u8]) -> u32 {
+        let bytes = [data[0], data[1], data[2], data[3]];
+        if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+    }
+
+    fn read_section_header(&mut self) -> io::Result<()> {
+        let mut header = [0; 8];
+        self.reader.read_exact(&mut header)?;
+        self.big_endian = match u32::from_be_bytes([header[4], header[5], header[6], header[7]]) {
+            BYTE_ORDER_MAGIC => true,
+            _ if u32::from_le_bytes([header[4], header[5], header[6], header[7]]) == BYTE_ORDER_MAGIC => false,
+            _ => return Err(io::Error::new(ErrorKind::InvalidData, "invalid pcapng byte order magic"))
+        };
+        self.interfaces.clear();
+        let length = check_block_length(self.u32(&header[0..4]) as usize)?;
+        let mut rest = vec![0; length.saturating_sub(12)];
+        self.reader.read_exact(&mut rest)
+    }
+
+    fn read_interface_description(&mut self, body: &[u8]) {
+        let mut interface = Interface {
+            link_type: LinkType::from(self.u16(&body[0..2]) as u32),
+            name: None,
+            resolution: 1_000_000,
+        };
+        let mut options = &body[8.min(body.len())..];
+        while options.len() >= 4 {
+            let code = self.u16(&options[0..2]);
+            let length = (self.u16(&options[2..4]) as usize).min(options.len() - 4);
+            if code == OPTION_END {
+                break;
+            }
+            if code == OPTION_NAME {
+                let name = String::from_utf8_lossy(&options[4..4 + length]);
+                interface.name = Some(String::from(name.trim_end_matches('\0')));
+            }
+            if code == OPTION_TSRESOL && length >= 1 {
+                let exponent = (options[4] & 0x7f) as u32;
+                let base: u64 = if options[4] & 0x80 == 0 { 10 } else { 2 };
+                interface.resolution = base.checked_pow(exponent).unwrap_or(1_000_000);
+            }
+            options = &options[(4 + length.div_ceil(4) * 4).min(options.len())..];
+        }
+        self.interfaces.push(interface);
+    }
+
+    fn packet(&self, interface_id: usize, timestamp: u64, data: &[u8]) -> io::Result<Packet> {
+        let interface = self.interfaces.get(interface_id)
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "packet from undescribed interface"))?;
+        let timestamp = Duration::new(
+            timestamp / interface.resolution,
+            ((timestamp % interface.resolution) * 1_000_000_000 / interface.resolution) as u32
+        );
+        let name = interface.name.clone().unwrap_or_else(|| format!("interface {}", interface_id));
+        Ok(Packet::new(interface.link_type, timestamp, Vec::from(data)).with_interface(&name))
+    }
+
+    fn read_packet(&mut self) -> io::Result<Option<Packet>> {
+        loop {
+            let mut header = [0; 4];
+            match self.reader.read_exact(&mut header) {
+                Ok(()) => (),
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e)
+            }
+            // The section header type reads the same in both byte orders
+            if u32::from_be_bytes(header) == SECTION_HEADER {
+                self.read_section_header()?;
+                continue;
+            }
+
+            let block_type = self.u32(&header);
+            let mut length = [0; 4];
+            self.reader.read_exact(&mut length)?;
+            let length = check_block_length(self.u32(&length) as usize)?;
+            let mut body = vec![0; length.saturating_sub(8)];
+            self.reader.read_exact(&mut body)?;
+            // Leave out the trailing copy of the block length
+            let body = &body[..body.len().saturating_sub(4)];
+
+            match block_type {
+                INTERFACE_DESCRIPTION if body.len() >= 8 => self.read_interface_description(body),
+                ENHANCED_PACKET if body.len() >= 20 => {
+                    let timestamp = (self.u32(&body[4..8]) as u64) << 32 | self.u32(&body[8..12]) as u64;
+                    let captured_length = (self.u32(&body[12..16]) as usize).min(body.len() - 20);
+                    let interface_id = self.u32(&body[0..4]) as usize;
+                    return self.packet(interface_id, timestamp, &body[20..20 + captured_length]).map(Some);
+                }
+                SIMPLE_PACKET if body.len() >= 4 => {
+                    let captured_length = (self.u32(&body[0..4]) as usize).min(body.len() - 4);
+                    return self.packet(0, 0, &body[4..4 + captured_length]).map(Some);
+                }
+                _ => ()
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for PcapngReader<R> {
+    type Item = io::Result<Packet>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_packet().transpose()
+    }
+}
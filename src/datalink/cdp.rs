@@ -0,0 +1,178 @@
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use prettytable::{format, table, row};
+use crate::util::*;
+
+pub const TLV_DEVICE_ID: u16 = 0x0001;
+pub const TLV_ADDRESSES: u16 = 0x0002;
+pub const TLV_PORT_ID: u16 = 0x0003;
+pub const TLV_CAPABILITIES: u16 = 0x0004;
+pub const TLV_SOFTWARE_VERSION: u16 = 0x0005;
+pub const TLV_PLATFORM: u16 = 0x0006;
+pub const TLV_VTP_DOMAIN: u16 = 0x0009;
+pub const TLV_NATIVE_VLAN: u16 = 0x000A;
+pub const TLV_DUPLEX: u16 = 0x000B;
+pub const TLV_MANAGEMENT_ADDRESSES: u16 = 0x0016;
+
+/// A Cisco Discovery Protocol announcement.
+#[derive(Debug)]
+pub struct CdpPacket {
+    version: u8,
+    ttl: u8,
+    checksum: u16,
+    tlvs: Vec<CdpTlv>,
+}
+
+#[derive(Debug)]
+pub struct CdpTlv {
+    tlv_type: u16,
+    value: Vec<u8>,
+}
+
+impl CdpPacket {
+    /// Parses an announcement, or returns `None` if it ends inside the fixed header.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < 4 {
+            return None;
+        }
+        let mut tlvs = Vec::new();
+        let mut rest = &data[4..];
+        while rest.len() >= 4 {
+            // The length includes the type and length fields themselves
+            let length = (tou16(&rest[2..4]) as usize).clamp(4, rest.len());
+            tlvs.push(CdpTlv {
+                tlv_type: tou16(&rest[0..2]),
+                value: Vec::from(&rest[4..length]),
+            });
+            rest = &rest[length..];
+        }
+
+        Some(Self {
+            version: data[0],
+            ttl: data[1],
+            checksum: tou16(&data[2..4]),
+            tlvs,
+        })
+    }
+
+    fn string(&self, tlv_type: u16) -> Option<String> {
+        self.tlvs.iter()
+            .find(|tlv| tlv.tlv_type == tlv_type)
+            .map(|tlv| String::from_utf8_lossy(&tlv.value).into_owned())
+    }
+
+    #[inline]
+    pub fn device_id(&self) -> Option<String> {
+        self.string(TLV_DEVICE_ID)
+    }
+
+    #[inline]
+    pub fn port_id(&self) -> Option<String> {
+        self.string(TLV_PORT_ID)
+    }
+
+    #[inline]
+    pub fn platform(&self) -> Option<String> {
+        self.string(TLV_PLATFORM)
+    }
+
+    /// The management addresses, or the interface addresses if there are none.
+    pub fn management_address(&self) -> Option<IpAddr> {
+        [TLV_MANAGEMENT_ADDRESSES, TLV_ADDRESSES].iter()
+            .filter_map(|tlv_type| self.tlvs.iter().find(|tlv| tlv.tlv_type == *tlv_type))
+            .flat_map(|tlv| addresses(&tlv.value))
+            .next()
+    }
+}
+
+/// Decodes an address list TLV: a count, then entries of protocol type, protocol, and address.
+fn addresses(data: &[u8]) -> Vec<IpAddr> {
+    let mut addresses = Vec::new();
+    if data.len() < 4 {
+        return addresses;
+    }
+    let mut rest = &data[4..];
+    for _ in 0..tou32(&data[0..4]) {
+        if rest.len() < 2 || rest.len() < 2 + rest[1] as usize + 2 {
+            break;
+        }
+        let protocol = &rest[2..2 + rest[1] as usize];
+        let offset = 2 + protocol.len();
+        let length = tou16(&rest[offset..offset + 2]) as usize;
+        let Some(address) = rest.get(offset + 2..offset + 2 + length) else {
+            break;
+        };
+        // NLPID 0xCC is IPv4, IPv6 is identified by its 802.2 SNAP header
+        match (protocol, length) {
+            ([0xcc], 4) => addresses.push(IpAddr::V4(Ipv4Addr::from(tou32(address)))),
+            ([0xaa, 0xaa, 0x03, 0x00, 0x00, 0x00, 0x86, 0xdd], 16) => {
+                addresses.push(IpAddr::V6(Ipv6Addr::from(tou128(address))))
+            }
+            _ => ()
+        }
+        rest = &rest[offset + 2 + length..];
+    }
+    addresses
+}
+
+fn capabilities(capabilities: u32) -> String {
+    [
+        (0x01, "router"), (0x02, "transparent_bridge"), (0x04, "source_route_bridge"),
+        (0x08, "switch"), (0x10, "host"), (0x20, "igmp"), (0x40, "repeater"), (0x80, "phone")
+    ].iter()
+        .filter(|(bit, _)| capabilities & bit != 0)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Display for CdpTlv {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let value = self.value.as_slice();
+        match self.tlv_type {
+            TLV_DEVICE_ID | TLV_PORT_ID | TLV_SOFTWARE_VERSION | TLV_PLATFORM | TLV_VTP_DOMAIN => {
+                write!(f, "{}", String::from_utf8_lossy(value).trim_end())
+            }
+            TLV_ADDRESSES | TLV_MANAGEMENT_ADDRESSES => {
+                let addresses = addresses(value).iter().map(|a| a.to_string()).collect::<Vec<_>>();
+                write!(f, "{}", addresses.join(", "))
+            }
+            TLV_CAPABILITIES if value.len() >= 4 => write!(f, "{}", capabilities(tou32(value))),
+            TLV_NATIVE_VLAN if value.len() >= 2 => write!(f, "{}", tou16(value)),
+            TLV_DUPLEX if !value.is_empty() => write!(f, "{}", if value[0] == 1 { "full" } else { "half" }),
+            _ => write!(f, "{} bytes", value.len())
+        }
+    }
+}
+
+impl Display for CdpPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["version", self.version],
+            ["ttl", format!("{} s", self.ttl)],
+            ["checksum", format!("{:X}", self.checksum)]
+        );
+        for tlv in self.tlvs.iter() {
+            let name = match tlv.tlv_type {
+                TLV_DEVICE_ID => String::from("device_id"),
+                TLV_ADDRESSES => String::from("addresses"),
+                TLV_PORT_ID => String::from("port_id"),
+                TLV_CAPABILITIES => String::from("capabilities"),
+                TLV_SOFTWARE_VERSION => String::from("software_version"),
+                TLV_PLATFORM => String::from("platform"),
+                TLV_VTP_DOMAIN => String::from("vtp_domain"),
+                TLV_NATIVE_VLAN => String::from("native_vlan"),
+                TLV_DUPLEX => String::from("duplex"),
+                TLV_MANAGEMENT_ADDRESSES => String::from("management_addresses"),
+                tlv_type => format!("tlv {:04X}", tlv_type)
+            };
+            table.add_row(row![name, tlv]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -1,18 +1,102 @@
-use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use pnet::util::MacAddr;
-use prettytable::{format, table, row, cell};
-use crate::util::DataContainer;
+use prettytable::{format, table, row};
+use crate::datalink;
+use crate::datalink::llc::LlcHeader;
+use crate::datalink::vlan::VlanTag;
+use crate::network::{self, EthernetType};
+use crate::util::*;
+
+/// The largest value of the type field that is an 802.3 length rather than an EtherType.
+pub const MAX_LENGTH: u16 = 1500;
+
+/// The minimum size of a frame including its frame check sequence.
+const MIN_FRAME_SIZE: usize = 64;
 
 #[derive(Debug)]
 pub struct Frame {
     destination: MacAddr,
     source: MacAddr,
-    frame_type: u16,
-    frame_data: Vec<u8>
+    vlan_tags: Vec<VlanTag>,
+    length: Option<u16>,
+    llc: Option<LlcHeader>,
+    frame_type: Option<EthernetType>,
+    frame_data: Vec<u8>,
+    /// Zero bytes that pad a short frame to the minimum size
+    padding: usize,
+    trailer: Vec<u8>,
+    fcs: Option<u32>
 }
 
 impl Frame {
+    /// Parses a frame, or returns `None` if it ends before the EtherType, inside a VLAN tag or
+    /// inside the LLC header.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        let mut data = data;
+        let mut fcs = None;
+        if data.len() >= MIN_FRAME_SIZE {
+            let (frame, check) = data.split_at(data.len() - 4);
+            let check = u32::from_le_bytes([check[0], check[1], check[2], check[3]]);
+            if crc32(frame) == check {
+                fcs = Some(check);
+                data = frame;
+            }
+        }
+
+        let mut offset = 12;
+        let mut vlan_tags = Vec::new();
+        while data.len() >= offset + 2 && datalink::is_vlan_tpid(tou16(&data[offset..offset + 2])) {
+            vlan_tags.push(VlanTag::from(data.get(offset..offset + 4)?));
+            offset += 4;
+        }
+
+        let type_or_length = tou16(data.get(offset..offset + 2)?);
+        let mut payload = &data[offset + 2..];
+        let mut length = None;
+        let mut llc = None;
+        let frame_type;
+        let payload_length;
+        if type_or_length <= MAX_LENGTH {
+            length = Some(type_or_length);
+            payload_length = Some(type_or_length as usize);
+            if payload.len() >= 2 && tou16(payload) == 0xffff {
+                // Novell "raw" 802.3, IPX directly after the length with its checksum unused
+                frame_type = Some(network::IPX);
+            } else {
+                let header = LlcHeader::new(payload)?;
+                frame_type = header.ethernet_type();
+                llc = Some(header);
+            }
+        } else {
+            frame_type = Some(type_or_length);
+            payload_length = network::packet_length(type_or_length, payload);
+        }
+
+        let mut trailer: &[u8] = &[];
+        if let Some(payload_length) = payload_length.filter(|length| *length <= payload.len()) {
+            (payload, trailer) = payload.split_at(payload_length);
+        }
+        if let Some(header) = llc.as_ref() {
+            payload = &payload[header.length().min(payload.len())..];
+        }
+        // Padding fills the frame up to the minimum size, anything after it is a real trailer
+        let padding = trailer.len().min((MIN_FRAME_SIZE - 4).saturating_sub(data.len() - trailer.len()));
+        let padding = if trailer[..padding].iter().all(|byte| *byte == 0) { padding } else { 0 };
+
+        Some(Self {
+            destination: MacAddr::new(data[0], data[1], data[2], data[3], data[4], data[5]),
+            source: MacAddr::new(data[6], data[7], data[8], data[9], data[10], data[11]),
+            vlan_tags,
+            length,
+            llc,
+            frame_type,
+            frame_data: Vec::from(payload),
+            padding,
+            trailer: Vec::from(&trailer[padding..]),
+            fcs
+        })
+    }
+
     #[inline]
     pub fn destination(&self) -> &MacAddr {
         &self.destination
@@ -23,20 +107,22 @@ impl Frame {
         &self.source
     }
 
+    /// The VLAN tags of the frame, outermost first.
     #[inline]
-    pub fn frame_type(&self) -> u16 {
-        self.frame_type
+    pub fn vlan_tags(&self) -> &[VlanTag] {
+        self.vlan_tags.as_slice()
     }
-}
 
-impl From<&[u8]> for Frame {
-    fn from(data: &[u8]) -> Self {
-        Self {
-            destination: MacAddr::new(data[0], data[1], data[2], data[3], data[4], data[5]),
-            source: MacAddr::new(data[6], data[7], data[8], data[9], data[10], data[11]),
-            frame_type: (data[12] as u16) << 8 | data[13] as u16,
-            frame_data: Vec::from(&data[14..])
-        }
+    #[inline]
+    pub fn llc(&self) -> Option<&LlcHeader> {
+        self.llc.as_ref()
+    }
+
+    /// The EtherType of the payload, after any VLAN tags. For 802.3 frames this is taken from
+    /// the SNAP header, and `None` if the payload isn't identified by an EtherType.
+    #[inline]
+    pub fn frame_type(&self) -> Option<EthernetType> {
+        self.frame_type
     }
 }
 
@@ -49,17 +135,41 @@ impl DataContainer for Frame {
 impl Display for Frame {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let frame_type = match self.frame_type {
-            IPV4 => "ipv4",
-            ARP => "ARP",
-            IPV6 => "ipv6",
-            _ => "undefined"
+            Some(network::IPV4) => "ipv4",
+            Some(network::ARP) => "ARP",
+            Some(network::IPV6) => "ipv6",
+            Some(network::IPX) => "ipx",
+            Some(network::LLDP) => "lldp",
+            Some(network::WOL) => "wake-on-lan",
+            Some(network::GOOSE) => "goose",
+            Some(network::PTP) => "ptp",
+            Some(network::MPLS) | Some(network::MPLS_MULTICAST) => "mpls",
+            Some(network::PPPOE_DISCOVERY) => "pppoe discovery",
+            Some(network::PPPOE_SESSION) => "pppoe session",
+            Some(_) => "undefined",
+            None => "llc"
         };
 
         let mut table = table!(
             ["destination", self.destination],
-            ["source", self.source],
-            ["frame_type", frame_type]
+            ["source", self.source]
         );
+        for tag in self.vlan_tags.iter() {
+            table.add_row(row!["vlan", tag]);
+        }
+        if let Some(length) = self.length {
+            table.add_row(row!["length", length]);
+        }
+        table.add_row(row!["frame_type", frame_type]);
+        if self.padding > 0 {
+            table.add_row(row!["padding", format!("{} bytes", self.padding)]);
+        }
+        if !self.trailer.is_empty() {
+            table.add_row(row!["trailer", format!("{:02X?}", self.trailer)]);
+        }
+        if let Some(fcs) = self.fcs {
+            table.add_row(row!["fcs", format!("{:08X} (valid)", fcs)]);
+        }
 
         table.set_format(*format::consts::FORMAT_CLEAN);
         table.get_format().padding(5, 5);
@@ -68,3 +178,34 @@ impl Display for Frame {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_truncated_headers() {
+        let header = unhex("94b86dc4cd31001122334455").unwrap();
+        assert!(Frame::new(&header[..10]).is_none());
+        assert!(Frame::new(&header).is_none());
+        assert!(Frame::new(&[header.as_slice(), &[0x81, 0x00]].concat()).is_none());
+        assert!(Frame::new(&[header.as_slice(), &[0x81, 0x00, 0x00, 0x05]].concat()).is_none());
+
+        let frame = Frame::new(&[header.as_slice(), &[0x81, 0x00, 0x00, 0x05, 0x08, 0x00]].concat()).unwrap();
+        assert_eq!(frame.vlan_tags()[0].vid(), 5);
+        assert_eq!(frame.frame_type(), Some(network::IPV4));
+    }
+
+    #[test]
+    fn separates_padding_from_trailer() {
+        // An ARP request padded to 60 bytes, followed by a 4 byte trailer added by a tap
+        let arp = unhex("ffffffffffff00112233445508060001080006040001001122334455c0a80001000000000000c0a80002").unwrap();
+        let frame = Frame::new(&[arp.as_slice(), &[0; 18], &[1, 2, 3, 4]].concat()).unwrap();
+        assert_eq!(frame.padding, 18);
+        assert_eq!(frame.trailer, [1, 2, 3, 4]);
+
+        let frame = Frame::new(&[arp.as_slice(), &[0xaa; 18]].concat()).unwrap();
+        assert_eq!(frame.padding, 0);
+        assert_eq!(frame.trailer.len(), 18);
+    }
+}
@@ -0,0 +1,541 @@
+use std::fmt::{Display, Formatter};
+use pnet::util::MacAddr;
+use prettytable::{format, table, row};
+use crate::util::*;
+
+pub const TYPE_MANAGEMENT: u8 = 0;
+pub const TYPE_CONTROL: u8 = 1;
+pub const TYPE_DATA: u8 = 2;
+
+pub const SUBTYPE_ASSOCIATION_REQUEST: u8 = 0;
+pub const SUBTYPE_ASSOCIATION_RESPONSE: u8 = 1;
+pub const SUBTYPE_REASSOCIATION_REQUEST: u8 = 2;
+pub const SUBTYPE_REASSOCIATION_RESPONSE: u8 = 3;
+pub const SUBTYPE_PROBE_REQUEST: u8 = 4;
+pub const SUBTYPE_PROBE_RESPONSE: u8 = 5;
+pub const SUBTYPE_BEACON: u8 = 8;
+pub const SUBTYPE_DISASSOCIATION: u8 = 10;
+pub const SUBTYPE_AUTHENTICATION: u8 = 11;
+pub const SUBTYPE_DEAUTHENTICATION: u8 = 12;
+pub const SUBTYPE_ACTION: u8 = 13;
+
+pub const FLAG_TO_DS: u8 = 0x01;
+pub const FLAG_FROM_DS: u8 = 0x02;
+pub const FLAG_MORE_FRAGMENTS: u8 = 0x04;
+pub const FLAG_RETRY: u8 = 0x08;
+pub const FLAG_POWER_MANAGEMENT: u8 = 0x10;
+pub const FLAG_MORE_DATA: u8 = 0x20;
+pub const FLAG_PROTECTED: u8 = 0x40;
+pub const FLAG_ORDER: u8 = 0x80;
+
+pub const ELEMENT_SSID: u8 = 0;
+pub const ELEMENT_SUPPORTED_RATES: u8 = 1;
+pub const ELEMENT_DS_PARAMETER: u8 = 3;
+pub const ELEMENT_TIM: u8 = 5;
+pub const ELEMENT_COUNTRY: u8 = 7;
+pub const ELEMENT_HT_CAPABILITIES: u8 = 45;
+pub const ELEMENT_RSN: u8 = 48;
+pub const ELEMENT_EXTENDED_RATES: u8 = 50;
+pub const ELEMENT_HT_OPERATION: u8 = 61;
+pub const ELEMENT_VHT_CAPABILITIES: u8 = 191;
+pub const ELEMENT_VHT_OPERATION: u8 = 192;
+pub const ELEMENT_VENDOR: u8 = 221;
+pub const ELEMENT_EXTENSION: u8 = 255;
+
+pub const EXTENSION_HE_CAPABILITIES: u8 = 35;
+pub const EXTENSION_HE_OPERATION: u8 = 36;
+
+#[inline]
+fn le16(data: &[u8]) -> u16 {
+    u16::from_le_bytes([data[0], data[1]])
+}
+
+#[inline]
+fn le32(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
+
+#[inline]
+fn mac(data: &[u8]) -> MacAddr {
+    MacAddr::new(data[0], data[1], data[2], data[3], data[4], data[5])
+}
+
+/// An IEEE 802.11 MAC frame. All multi-byte fields are little endian, unlike on the wire
+/// elsewhere.
+#[derive(Debug)]
+pub struct WlanFrame {
+    frame_type: u8,
+    subtype: u8,
+    flags: u8,
+    duration: u16,
+    addresses: Vec<MacAddr>,
+    sequence_number: Option<u16>,
+    fragment_number: Option<u8>,
+    qos_control: Option<u16>,
+    management: Option<ManagementFrame>,
+    /// The frame check sequence and whether it matches the frame
+    fcs: Option<(u32, bool)>,
+    frame_data: Vec<u8>
+}
+
+impl WlanFrame {
+    #[inline]
+    pub fn addresses(&self) -> &[MacAddr] {
+        self.addresses.as_slice()
+    }
+
+    #[inline]
+    pub fn management(&self) -> Option<&ManagementFrame> {
+        self.management.as_ref()
+    }
+
+    #[inline]
+    pub fn is_protected(&self) -> bool {
+        self.flags & FLAG_PROTECTED != 0
+    }
+
+    /// Whether the body holds an unencrypted LLC header and payload.
+    pub fn has_llc(&self) -> bool {
+        // Subtypes with bit 2 set are null functions without a body
+        self.frame_type == TYPE_DATA && self.subtype & 0b0100 == 0 && !self.is_protected()
+            && !self.frame_data.is_empty()
+    }
+
+    fn type_name(&self) -> &'static str {
+        match (self.frame_type, self.subtype) {
+            (TYPE_MANAGEMENT, SUBTYPE_ASSOCIATION_REQUEST) => "association request",
+            (TYPE_MANAGEMENT, SUBTYPE_ASSOCIATION_RESPONSE) => "association response",
+            (TYPE_MANAGEMENT, SUBTYPE_REASSOCIATION_REQUEST) => "reassociation request",
+            (TYPE_MANAGEMENT, SUBTYPE_REASSOCIATION_RESPONSE) => "reassociation response",
+            (TYPE_MANAGEMENT, SUBTYPE_PROBE_REQUEST) => "probe request",
+            (TYPE_MANAGEMENT, SUBTYPE_PROBE_RESPONSE) => "probe response",
+            (TYPE_MANAGEMENT, SUBTYPE_BEACON) => "beacon",
+            (TYPE_MANAGEMENT, SUBTYPE_DISASSOCIATION) => "disassociation",
+            (TYPE_MANAGEMENT, SUBTYPE_AUTHENTICATION) => "authentication",
+            (TYPE_MANAGEMENT, SUBTYPE_DEAUTHENTICATION) => "deauthentication",
+            (TYPE_MANAGEMENT, SUBTYPE_ACTION) => "action",
+            (TYPE_CONTROL, 8) => "block ack request",
+            (TYPE_CONTROL, 9) => "block ack",
+            (TYPE_CONTROL, 10) => "ps-poll",
+            (TYPE_CONTROL, 11) => "rts",
+            (TYPE_CONTROL, 12) => "cts",
+            (TYPE_CONTROL, 13) => "ack",
+            (TYPE_CONTROL, 14) => "cf-end",
+            (TYPE_DATA, 0) => "data",
+            (TYPE_DATA, 4) => "null",
+            (TYPE_DATA, 8) => "qos data",
+            (TYPE_DATA, 12) => "qos null",
+            _ => "unidentified"
+        }
+    }
+
+    /// Names of the addresses, depending on the frame type and the DS bits.
+    fn address_names(&self) -> [&'static str; 4] {
+        if self.frame_type == TYPE_CONTROL {
+            return ["receiver", "transmitter", "", ""];
+        }
+        match self.flags & (FLAG_TO_DS | FLAG_FROM_DS) {
+            0 => ["destination", "source", "bssid", ""],
+            FLAG_FROM_DS => ["destination", "bssid", "source", ""],
+            FLAG_TO_DS => ["bssid", "source", "destination", ""],
+            _ => ["receiver", "transmitter", "destination", "source"]
+        }
+    }
+}
+
+impl From<&[u8]> for WlanFrame {
+    /// Parses a frame captured without radiotap to tell whether it ends with its frame check
+    /// sequence, which is then taken to be there when it matches.
+    fn from(data: &[u8]) -> Self {
+        let fcs = data.len() > 14 && {
+            let (frame, check) = data.split_at(data.len() - 4);
+            crc32(frame) == le32(check)
+        };
+        Self::new(data, fcs)
+    }
+}
+
+impl WlanFrame {
+    /// Parses a frame, which ends with its frame check sequence when `fcs` is set.
+    pub fn new(data: &[u8], fcs: bool) -> Self {
+        let mut data = data;
+        let mut check = None;
+        if fcs && data.len() >= 4 {
+            let (frame, sequence) = data.split_at(data.len() - 4);
+            check = Some((le32(sequence), crc32(frame) == le32(sequence)));
+            data = frame;
+        }
+
+        let frame_type = (data[0] >> 2) & 0b11;
+        let subtype = data[0] >> 4;
+        let flags = data[1];
+        let address_count = match (frame_type, subtype) {
+            (TYPE_CONTROL, 12) | (TYPE_CONTROL, 13) => 1,
+            (TYPE_CONTROL, _) => 2,
+            _ if flags & FLAG_TO_DS != 0 && flags & FLAG_FROM_DS != 0 => 4,
+            _ => 3
+        };
+
+        let mut frame = Self {
+            frame_type,
+            subtype,
+            flags,
+            duration: le16(&data[2..4]),
+            addresses: Vec::new(),
+            sequence_number: None,
+            fragment_number: None,
+            qos_control: None,
+            management: None,
+            fcs: check,
+            frame_data: Vec::new()
+        };
+
+        let mut offset = 4;
+        for index in 0..address_count {
+            // The sequence control field sits between the third and fourth address
+            if index == 3 {
+                offset += 2;
+            }
+            frame.addresses.push(mac(&data[offset..offset + 6]));
+            offset += 6;
+        }
+        if frame_type != TYPE_CONTROL {
+            let sequence_control = le16(&data[22..24]);
+            frame.sequence_number = Some(sequence_control >> 4);
+            frame.fragment_number = Some((sequence_control & 0xf) as u8);
+            offset = offset.max(24);
+        }
+        if frame_type == TYPE_DATA && subtype & 0b1000 != 0 {
+            frame.qos_control = Some(le16(&data[offset..offset + 2]));
+            offset += 2;
+        }
+        if flags & FLAG_ORDER != 0 && (frame_type == TYPE_MANAGEMENT || frame.qos_control.is_some()) {
+            // HT control field
+            offset += 4;
+        }
+
+        let body = &data[offset.min(data.len())..];
+        if frame_type == TYPE_MANAGEMENT && flags & FLAG_PROTECTED == 0 {
+            frame.management = Some(ManagementFrame::new(subtype, body));
+        }
+        frame.frame_data = Vec::from(body);
+        frame
+    }
+}
+
+impl DataContainer for WlanFrame {
+    fn data(&self) -> &[u8] {
+        self.frame_data.as_slice()
+    }
+}
+
+impl Display for WlanFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let flag_names = [
+            (FLAG_TO_DS, "to_ds"), (FLAG_FROM_DS, "from_ds"), (FLAG_MORE_FRAGMENTS, "more_fragments"),
+            (FLAG_RETRY, "retry"), (FLAG_POWER_MANAGEMENT, "power_management"),
+            (FLAG_MORE_DATA, "more_data"), (FLAG_PROTECTED, "protected"), (FLAG_ORDER, "order")
+        ];
+        let flags = flag_names.iter()
+            .filter(|(flag, _)| self.flags & flag != 0)
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut table = table!(
+            ["frame_type", format!("{} ({}/{})", self.type_name(), self.frame_type, self.subtype)],
+            ["flags", format!("{:08b} {}", self.flags, flags).trim_end()],
+            ["duration", self.duration]
+        );
+        for (address, name) in self.addresses.iter().zip(self.address_names()) {
+            table.add_row(row![name, address]);
+        }
+        if let (Some(sequence_number), Some(fragment_number)) = (self.sequence_number, self.fragment_number) {
+            table.add_row(row!["sequence", format!("{} fragment {}", sequence_number, fragment_number)]);
+        }
+        if let Some(qos_control) = self.qos_control {
+            table.add_row(row!["qos_tid", qos_control & 0xf]);
+        }
+        if let Some((fcs, valid)) = self.fcs {
+            table.add_row(row!["fcs", format!("{:08X} ({})", fcs, if valid { "valid" } else { "invalid" })]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+/// The fixed fields and tagged parameters of a management frame body.
+#[derive(Debug, Default)]
+pub struct ManagementFrame {
+    timestamp: Option<u64>,
+    beacon_interval: Option<u16>,
+    capabilities: Option<u16>,
+    listen_interval: Option<u16>,
+    current_ap: Option<MacAddr>,
+    status: Option<u16>,
+    association_id: Option<u16>,
+    reason: Option<u16>,
+    authentication_algorithm: Option<u16>,
+    authentication_sequence: Option<u16>,
+    elements: Vec<Element>,
+}
+
+impl ManagementFrame {
+    fn new(subtype: u8, data: &[u8]) -> Self {
+        let mut frame = Self::default();
+        let fixed_length = match subtype {
+            SUBTYPE_ASSOCIATION_REQUEST => 4,
+            SUBTYPE_ASSOCIATION_RESPONSE | SUBTYPE_REASSOCIATION_RESPONSE => 6,
+            SUBTYPE_REASSOCIATION_REQUEST => 10,
+            SUBTYPE_PROBE_RESPONSE | SUBTYPE_BEACON => 12,
+            SUBTYPE_DISASSOCIATION | SUBTYPE_DEAUTHENTICATION => 2,
+            SUBTYPE_AUTHENTICATION => 6,
+            SUBTYPE_PROBE_REQUEST => 0,
+            _ => return frame
+        };
+        if data.len() < fixed_length {
+            return frame;
+        }
+
+        match subtype {
+            SUBTYPE_ASSOCIATION_REQUEST | SUBTYPE_REASSOCIATION_REQUEST => {
+                frame.capabilities = Some(le16(&data[0..2]));
+                frame.listen_interval = Some(le16(&data[2..4]));
+                if subtype == SUBTYPE_REASSOCIATION_REQUEST {
+                    frame.current_ap = Some(mac(&data[4..10]));
+                }
+            }
+            SUBTYPE_ASSOCIATION_RESPONSE | SUBTYPE_REASSOCIATION_RESPONSE => {
+                frame.capabilities = Some(le16(&data[0..2]));
+                frame.status = Some(le16(&data[2..4]));
+                // The two most significant bits of the AID are always set
+                frame.association_id = Some(le16(&data[4..6]) & 0x3fff);
+            }
+            SUBTYPE_PROBE_RESPONSE | SUBTYPE_BEACON => {
+                frame.timestamp = Some(u64::from_le_bytes(data[0..8].try_into().unwrap()));
+                frame.beacon_interval = Some(le16(&data[8..10]));
+                frame.capabilities = Some(le16(&data[10..12]));
+            }
+            SUBTYPE_DISASSOCIATION | SUBTYPE_DEAUTHENTICATION => {
+                frame.reason = Some(le16(&data[0..2]));
+            }
+            SUBTYPE_AUTHENTICATION => {
+                frame.authentication_algorithm = Some(le16(&data[0..2]));
+                frame.authentication_sequence = Some(le16(&data[2..4]));
+                frame.status = Some(le16(&data[4..6]));
+            }
+            _ => ()
+        }
+
+        let mut rest = &data[fixed_length..];
+        while rest.len() >= 2 {
+            let length = (rest[1] as usize).min(rest.len() - 2);
+            frame.elements.push(Element {
+                id: rest[0],
+                value: Vec::from(&rest[2..2 + length]),
+            });
+            rest = &rest[2 + length..];
+        }
+        frame
+    }
+}
+
+impl Display for ManagementFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!();
+        if let Some(timestamp) = self.timestamp {
+            table.add_row(row!["timestamp", format!("{} us", timestamp)]);
+        }
+        if let Some(beacon_interval) = self.beacon_interval {
+            table.add_row(row!["beacon_interval", format!("{} TU", beacon_interval)]);
+        }
+        if let Some(capabilities) = self.capabilities {
+            table.add_row(row!["capabilities", format!("{:04X}", capabilities)]);
+        }
+        if let Some(listen_interval) = self.listen_interval {
+            table.add_row(row!["listen_interval", listen_interval]);
+        }
+        if let Some(current_ap) = self.current_ap {
+            table.add_row(row!["current_ap", current_ap]);
+        }
+        if let Some(authentication_algorithm) = self.authentication_algorithm {
+            let algorithm = match authentication_algorithm {
+                0 => "open system",
+                1 => "shared key",
+                2 => "fast bss transition",
+                3 => "sae",
+                _ => "unidentified"
+            };
+            table.add_row(row!["authentication_algorithm", algorithm]);
+        }
+        if let Some(authentication_sequence) = self.authentication_sequence {
+            table.add_row(row!["authentication_sequence", authentication_sequence]);
+        }
+        if let Some(status) = self.status {
+            table.add_row(row!["status", if status == 0 { String::from("success") } else { status.to_string() }]);
+        }
+        if let Some(association_id) = self.association_id {
+            table.add_row(row!["association_id", association_id]);
+        }
+        if let Some(reason) = self.reason {
+            table.add_row(row!["reason", reason]);
+        }
+        for element in self.elements.iter() {
+            table.add_row(row![element.name(), element]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+/// A tagged parameter of a management frame.
+#[derive(Debug)]
+pub struct Element {
+    id: u8,
+    value: Vec<u8>,
+}
+
+impl Element {
+    fn name(&self) -> String {
+        match (self.id, self.value.first()) {
+            (ELEMENT_SSID, _) => String::from("ssid"),
+            (ELEMENT_SUPPORTED_RATES, _) => String::from("rates"),
+            (ELEMENT_DS_PARAMETER, _) => String::from("channel"),
+            (ELEMENT_TIM, _) => String::from("tim"),
+            (ELEMENT_COUNTRY, _) => String::from("country"),
+            (ELEMENT_HT_CAPABILITIES, _) => String::from("ht_capabilities"),
+            (ELEMENT_RSN, _) => String::from("rsn"),
+            (ELEMENT_EXTENDED_RATES, _) => String::from("extended_rates"),
+            (ELEMENT_HT_OPERATION, _) => String::from("ht_operation"),
+            (ELEMENT_VHT_CAPABILITIES, _) => String::from("vht_capabilities"),
+            (ELEMENT_VHT_OPERATION, _) => String::from("vht_operation"),
+            (ELEMENT_VENDOR, _) => String::from("vendor"),
+            (ELEMENT_EXTENSION, Some(&EXTENSION_HE_CAPABILITIES)) => String::from("he_capabilities"),
+            (ELEMENT_EXTENSION, Some(&EXTENSION_HE_OPERATION)) => String::from("he_operation"),
+            (ELEMENT_EXTENSION, Some(extension)) => format!("extension {}", extension),
+            (id, _) => format!("element {}", id)
+        }
+    }
+}
+
+/// Names a cipher or AKM suite selector of the RSN element.
+fn suite(data: &[u8], akm: bool) -> String {
+    let oui = tou32(&[0, data[0], data[1], data[2]]);
+    if oui != 0x000FAC {
+        return format!("{:06X}:{}", oui, data[3]);
+    }
+    let name = match (akm, data[3]) {
+        (false, 1) => "wep-40",
+        (false, 2) => "tkip",
+        (false, 4) => "ccmp-128",
+        (false, 5) => "wep-104",
+        (false, 6) => "bip-cmac-128",
+        (false, 8) => "gcmp-128",
+        (false, 9) => "gcmp-256",
+        (false, 10) => "ccmp-256",
+        (true, 1) => "802.1x",
+        (true, 2) => "psk",
+        (true, 3) => "ft-802.1x",
+        (true, 4) => "ft-psk",
+        (true, 5) => "802.1x-sha256",
+        (true, 6) => "psk-sha256",
+        (true, 8) => "sae",
+        (true, 9) => "ft-sae",
+        (true, 12) => "802.1x-suite-b-192",
+        (true, 18) => "owe",
+        (true, 24) => "sae-ext-key",
+        _ => return format!("{}", data[3])
+    };
+    String::from(name)
+}
+
+fn rates(data: &[u8]) -> String {
+    data.iter()
+        .map(|rate| {
+            // The most significant bit marks rates in the basic rate set
+            let basic = if rate & 0x80 != 0 { "*" } else { "" };
+            format!("{}{}", (rate & 0x7f) as f32 / 2.0, basic)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn rsn(data: &[u8]) -> Option<String> {
+    let mut description = format!("version {}", le16(data.get(0..2)?));
+    description += &format!(", group {}", suite(data.get(2..6)?, false));
+    let mut offset = 6;
+    for (name, akm) in [("pairwise", false), ("akm", true)] {
+        let count = le16(data.get(offset..offset + 2)?) as usize;
+        offset += 2;
+        let suites = (0..count)
+            .map(|index| data.get(offset + index * 4..offset + index * 4 + 4).map(|s| suite(s, akm)))
+            .collect::<Option<Vec<_>>>()?;
+        description += &format!(", {} {}", name, suites.join(" "));
+        offset += count * 4;
+    }
+    if let Some(capabilities) = data.get(offset..offset + 2) {
+        description += &format!(", capabilities {:04X}", le16(capabilities));
+    }
+    Some(description)
+}
+
+/// Number of spatial streams in an HT MCS set or VHT/HE MCS map.
+fn ht_streams(mcs_set: &[u8]) -> usize {
+    mcs_set.iter().take(4).filter(|byte| **byte != 0).count()
+}
+
+fn mcs_map_streams(map: u16) -> usize {
+    (0..8).filter(|stream| (map >> (stream * 2)) & 0b11 != 0b11).count()
+}
+
+impl Display for Element {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let value = self.value.as_slice();
+        let description = match self.id {
+            ELEMENT_SSID if value.is_empty() => Some(String::from("<wildcard>")),
+            ELEMENT_SSID => Some(String::from_utf8_lossy(value).into_owned()),
+            ELEMENT_SUPPORTED_RATES | ELEMENT_EXTENDED_RATES => Some(format!("{} Mb/s", rates(value))),
+            ELEMENT_DS_PARAMETER if !value.is_empty() => Some(value[0].to_string()),
+            ELEMENT_TIM if value.len() >= 3 => Some(format!("dtim {} of {}", value[0], value[1])),
+            ELEMENT_COUNTRY if value.len() >= 2 => Some(String::from_utf8_lossy(&value[0..2]).into_owned()),
+            ELEMENT_RSN => rsn(value),
+            ELEMENT_HT_CAPABILITIES if value.len() >= 26 => Some(format!(
+                "info {:04X}, 40 MHz {}, {} streams",
+                le16(&value[0..2]), le16(&value[0..2]) & 0x0002 != 0, ht_streams(&value[3..19])
+            )),
+            ELEMENT_HT_OPERATION if !value.is_empty() => Some(format!("primary channel {}", value[0])),
+            ELEMENT_VHT_CAPABILITIES if value.len() >= 12 => Some(format!(
+                "info {:08X}, {} rx streams",
+                le32(&value[0..4]), mcs_map_streams(le16(&value[4..6]))
+            )),
+            ELEMENT_VHT_OPERATION if value.len() >= 3 => Some(format!(
+                "width {}, center {} {}", value[0], value[1], value[2]
+            )),
+            ELEMENT_VENDOR if value.len() >= 4 => Some(format!(
+                "{:06X} type {}", tou32(&[0, value[0], value[1], value[2]]), value[3]
+            )),
+            ELEMENT_EXTENSION if value.first() == Some(&EXTENSION_HE_CAPABILITIES) && value.len() >= 21 => {
+                Some(format!(
+                    "mac {:02X?}, phy {:02X?}, {} rx streams",
+                    &value[1..7], &value[7..18], mcs_map_streams(le16(&value[18..20]))
+                ))
+            }
+            ELEMENT_EXTENSION if value.first() == Some(&EXTENSION_HE_OPERATION) && value.len() >= 7 => {
+                Some(format!("bss color {}", value[4] & 0x3f))
+            }
+            _ => None
+        };
+        match description {
+            Some(description) => write!(f, "{}", description),
+            None => write!(f, "{} bytes", value.len())
+        }
+    }
+}
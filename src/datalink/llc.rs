@@ -0,0 +1,158 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table, row};
+use crate::network::{self, EthernetType};
+use crate::util::*;
+
+pub type Sap = u8;
+
+pub const SAP_STP: Sap = 0x42;
+pub const SAP_SNAP: Sap = 0xAA;
+pub const SAP_IPX: Sap = 0xE0;
+pub const SAP_NETBIOS: Sap = 0xF0;
+
+pub type Oui = u32;
+
+pub const OUI_RFC1042: Oui = 0x000000;
+pub const OUI_CISCO: Oui = 0x00000C;
+
+pub const PID_CDP: u16 = 0x2000;
+pub const PID_PVST: u16 = 0x010B;
+
+/// An IEEE 802.2 LLC header, optionally followed by a SNAP header.
+#[derive(Debug)]
+pub struct LlcHeader {
+    dsap: Sap,
+    ssap: Sap,
+    control: u16,
+    snap: Option<SnapHeader>,
+    header_length: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SnapHeader {
+    oui: Oui,
+    pid: u16,
+}
+
+impl LlcHeader {
+    /// Parses the header, or returns `None` if the data ends inside the control field or the
+    /// SNAP header.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < 3 {
+            return None;
+        }
+        let control_length = Self::control_length(data);
+        let control = if control_length == 1 { data[2] as u16 } else { tou16(data.get(2..4)?) };
+        let snap = if data[0] == SAP_SNAP && data[1] == SAP_SNAP {
+            let offset = 2 + control_length;
+            let snap = data.get(offset..offset + 5)?;
+            Some(SnapHeader {
+                oui: tou32(&[0, snap[0], snap[1], snap[2]]),
+                pid: tou16(&snap[3..5]),
+            })
+        } else {
+            None
+        };
+
+        Some(Self {
+            dsap: data[0],
+            ssap: data[1],
+            control,
+            header_length: 2 + control_length + if snap.is_some() { 5 } else { 0 },
+            snap,
+        })
+    }
+
+    #[inline]
+    pub fn dsap(&self) -> Sap {
+        self.dsap
+    }
+
+    #[inline]
+    pub fn snap(&self) -> Option<SnapHeader> {
+        self.snap
+    }
+
+    /// Unnumbered frames have a one byte control field, information and supervisory frames
+    /// have two.
+    #[inline]
+    fn control_length(data: &[u8]) -> usize {
+        if data[2] & 0b11 == 0b11 { 1 } else { 2 }
+    }
+
+    /// The length of the LLC header, including the SNAP header if there is one.
+    #[inline]
+    pub fn length(&self) -> usize {
+        self.header_length
+    }
+
+    /// The EtherType equivalent of the payload, for RFC 1042 encapsulated and IPX payloads.
+    pub fn ethernet_type(&self) -> Option<EthernetType> {
+        match self.snap {
+            Some(snap) if snap.oui == OUI_RFC1042 => Some(snap.pid),
+            None if self.dsap == SAP_IPX => Some(network::IPX),
+            _ => None
+        }
+    }
+}
+
+impl SnapHeader {
+    #[inline]
+    pub fn oui(&self) -> Oui {
+        self.oui
+    }
+
+    #[inline]
+    pub fn pid(&self) -> u16 {
+        self.pid
+    }
+}
+
+impl Display for LlcHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let sap = |sap: Sap| match sap & 0xfe {
+            SAP_STP => "stp",
+            SAP_SNAP => "snap",
+            SAP_IPX => "ipx",
+            SAP_NETBIOS => "netbios",
+            _ => "unidentified"
+        };
+
+        let mut table = table!(
+            ["dsap", format!("{:02X} ({})", self.dsap, sap(self.dsap))],
+            ["ssap", format!("{:02X} ({})", self.ssap, sap(self.ssap))],
+            ["control", format!("{:X}", self.control)]
+        );
+        if let Some(snap) = self.snap {
+            let oui = match snap.oui {
+                OUI_RFC1042 => "rfc1042",
+                OUI_CISCO => "cisco",
+                _ => "unidentified"
+            };
+            table.add_row(row!["oui", format!("{:06X} ({})", snap.oui, oui)]);
+            table.add_row(row!["pid", format!("{:04X}", snap.pid)]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_truncated_headers() {
+        assert!(LlcHeader::new(&[SAP_STP, SAP_STP]).is_none());
+        assert!(LlcHeader::new(&[SAP_STP, SAP_STP, 0x00]).is_none());
+        assert!(LlcHeader::new(&[SAP_SNAP, SAP_SNAP, 0x03, 0x00, 0x00, 0x0c, 0x20]).is_none());
+
+        let header = LlcHeader::new(&[SAP_SNAP, SAP_SNAP, 0x03, 0x00, 0x00, 0x0c, 0x20, 0x00]).unwrap();
+        assert_eq!(header.length(), 8);
+        assert_eq!(header.snap().map(|snap| (snap.oui(), snap.pid())), Some((OUI_CISCO, PID_CDP)));
+    }
+}
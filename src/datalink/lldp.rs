@@ -0,0 +1,209 @@
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use pnet::util::MacAddr;
+use prettytable::{format, table, row};
+use crate::util::*;
+
+pub const TLV_END: u8 = 0;
+pub const TLV_CHASSIS_ID: u8 = 1;
+pub const TLV_PORT_ID: u8 = 2;
+pub const TLV_TTL: u8 = 3;
+pub const TLV_PORT_DESCRIPTION: u8 = 4;
+pub const TLV_SYSTEM_NAME: u8 = 5;
+pub const TLV_SYSTEM_DESCRIPTION: u8 = 6;
+pub const TLV_SYSTEM_CAPABILITIES: u8 = 7;
+pub const TLV_MANAGEMENT_ADDRESS: u8 = 8;
+pub const TLV_ORGANIZATION: u8 = 127;
+
+pub const OUI_IEEE_8021: u32 = 0x0080C2;
+pub const OUI_IEEE_8023: u32 = 0x00120F;
+
+/// A Link Layer Discovery Protocol data unit, a list of TLVs ending with an end TLV.
+#[derive(Debug)]
+pub struct LldpPacket {
+    tlvs: Vec<LldpTlv>,
+}
+
+#[derive(Debug)]
+pub struct LldpTlv {
+    tlv_type: u8,
+    value: Vec<u8>,
+}
+
+impl LldpPacket {
+    fn tlv(&self, tlv_type: u8) -> Option<&LldpTlv> {
+        self.tlvs.iter().find(|tlv| tlv.tlv_type == tlv_type)
+    }
+
+    pub fn chassis_id(&self) -> Option<String> {
+        self.tlv(TLV_CHASSIS_ID).map(|tlv| tlv.to_string())
+    }
+
+    pub fn port_id(&self) -> Option<String> {
+        self.tlv(TLV_PORT_ID).map(|tlv| tlv.to_string())
+    }
+
+    pub fn port_description(&self) -> Option<String> {
+        self.tlv(TLV_PORT_DESCRIPTION).map(|tlv| tlv.to_string())
+    }
+
+    pub fn system_name(&self) -> Option<String> {
+        self.tlv(TLV_SYSTEM_NAME).map(|tlv| tlv.to_string())
+    }
+
+    pub fn system_description(&self) -> Option<String> {
+        self.tlv(TLV_SYSTEM_DESCRIPTION).map(|tlv| tlv.to_string())
+    }
+
+    pub fn management_address(&self) -> Option<IpAddr> {
+        self.tlvs.iter()
+            .filter(|tlv| tlv.tlv_type == TLV_MANAGEMENT_ADDRESS && tlv.value.len() >= 2)
+            .find_map(|tlv| address(tlv.value[1], &tlv.value[2..(1 + tlv.value[0] as usize).min(tlv.value.len())]))
+    }
+}
+
+impl LldpTlv {
+    fn name(&self) -> String {
+        match self.tlv_type {
+            TLV_CHASSIS_ID => String::from("chassis_id"),
+            TLV_PORT_ID => String::from("port_id"),
+            TLV_TTL => String::from("ttl"),
+            TLV_PORT_DESCRIPTION => String::from("port_description"),
+            TLV_SYSTEM_NAME => String::from("system_name"),
+            TLV_SYSTEM_DESCRIPTION => String::from("system_description"),
+            TLV_SYSTEM_CAPABILITIES => String::from("system_capabilities"),
+            TLV_MANAGEMENT_ADDRESS => String::from("management_address"),
+            TLV_ORGANIZATION if self.value.len() >= 4 => {
+                match (tou32(&[0, self.value[0], self.value[1], self.value[2]]), self.value[3]) {
+                    (OUI_IEEE_8021, 1) => String::from("port_vlan_id"),
+                    (OUI_IEEE_8021, 2) => String::from("protocol_vlan_id"),
+                    (OUI_IEEE_8021, 3) => String::from("vlan_name"),
+                    (OUI_IEEE_8021, 7) => String::from("link_aggregation"),
+                    (OUI_IEEE_8023, 1) => String::from("mac_phy_configuration"),
+                    (OUI_IEEE_8023, 2) => String::from("power_via_mdi"),
+                    (OUI_IEEE_8023, 3) => String::from("link_aggregation"),
+                    (OUI_IEEE_8023, 4) => String::from("maximum_frame_size"),
+                    (oui, subtype) => format!("organization {:06X}/{}", oui, subtype)
+                }
+            }
+            tlv_type => format!("tlv {}", tlv_type)
+        }
+    }
+}
+
+/// Decodes an address by its IANA address family number.
+fn address(family: u8, data: &[u8]) -> Option<IpAddr> {
+    match (family, data.len()) {
+        (1, 4) => Some(IpAddr::V4(Ipv4Addr::from(tou32(data)))),
+        (2, 16) => Some(IpAddr::V6(Ipv6Addr::from(tou128(data)))),
+        _ => None
+    }
+}
+
+/// Decodes the subtyped id of the chassis and port id TLVs.
+fn id(data: &[u8], mac_subtype: u8, address_subtype: u8) -> String {
+    let Some((subtype, id)) = data.split_first() else {
+        return String::new();
+    };
+    if *subtype == mac_subtype && id.len() == 6 {
+        MacAddr::new(id[0], id[1], id[2], id[3], id[4], id[5]).to_string()
+    } else if *subtype == address_subtype && !id.is_empty() {
+        address(id[0], &id[1..]).map(|address| address.to_string()).unwrap_or_default()
+    } else {
+        String::from_utf8_lossy(id).into_owned()
+    }
+}
+
+fn capabilities(capabilities: u16) -> String {
+    [
+        (0x01, "other"), (0x02, "repeater"), (0x04, "bridge"), (0x08, "wlan_access_point"),
+        (0x10, "router"), (0x20, "telephone"), (0x40, "docsis"), (0x80, "station"),
+        (0x100, "c-vlan"), (0x200, "s-vlan"), (0x400, "tpmr")
+    ].iter()
+        .filter(|(bit, _)| capabilities & bit != 0)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl From<&[u8]> for LldpPacket {
+    fn from(data: &[u8]) -> Self {
+        let mut tlvs = Vec::new();
+        let mut rest = data;
+        while rest.len() >= 2 {
+            // 7 bits of type followed by 9 bits of length
+            let header = tou16(rest);
+            let tlv_type = (header >> 9) as u8;
+            let length = ((header & 0x01ff) as usize).min(rest.len() - 2);
+            if tlv_type == TLV_END {
+                break;
+            }
+            tlvs.push(LldpTlv {
+                tlv_type,
+                value: Vec::from(&rest[2..2 + length]),
+            });
+            rest = &rest[2 + length..];
+        }
+
+        Self {
+            tlvs,
+        }
+    }
+}
+
+impl Display for LldpTlv {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let value = self.value.as_slice();
+        match self.tlv_type {
+            TLV_CHASSIS_ID => write!(f, "{}", id(value, 4, 5)),
+            TLV_PORT_ID => write!(f, "{}", id(value, 3, 4)),
+            TLV_TTL if value.len() >= 2 => write!(f, "{} s", tou16(value)),
+            TLV_PORT_DESCRIPTION | TLV_SYSTEM_NAME | TLV_SYSTEM_DESCRIPTION => {
+                write!(f, "{}", String::from_utf8_lossy(value).trim_end())
+            }
+            TLV_SYSTEM_CAPABILITIES if value.len() >= 4 => write!(
+                f, "{} (enabled {})", capabilities(tou16(value)), capabilities(tou16(&value[2..4]))
+            ),
+            TLV_MANAGEMENT_ADDRESS if value.len() >= 2 => {
+                let end = (1 + value[0] as usize).min(value.len());
+                match address(value[1], &value[2..end]) {
+                    Some(address) => write!(f, "{}", address),
+                    None => write!(f, "{:02X?}", &value[2..end])
+                }
+            }
+            TLV_ORGANIZATION if value.len() >= 4 => {
+                let oui = tou32(&[0, value[0], value[1], value[2]]);
+                let info = &value[4..];
+                match (oui, value[3]) {
+                    (OUI_IEEE_8021, 1) if info.len() >= 2 => write!(f, "{}", tou16(info)),
+                    (OUI_IEEE_8021, 2) if info.len() >= 3 => write!(f, "{}", tou16(&info[1..3])),
+                    (OUI_IEEE_8021, 3) if info.len() >= 3 => write!(
+                        f, "{} {}", tou16(info), String::from_utf8_lossy(&info[3..])
+                    ),
+                    (OUI_IEEE_8023, 1) if info.len() >= 5 => write!(
+                        f, "autonegotiation {}, mau type {}",
+                        if info[0] & 0x02 != 0 { "enabled" } else { "disabled" }, tou16(&info[3..5])
+                    ),
+                    (OUI_IEEE_8023, 4) if info.len() >= 2 => write!(f, "{}", tou16(info)),
+                    _ => write!(f, "{} bytes", info.len())
+                }
+            }
+            _ => write!(f, "{} bytes", value.len())
+        }
+    }
+}
+
+impl Display for LldpPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!();
+        for tlv in self.tlvs.iter() {
+            table.add_row(row![tlv.name(), tlv]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
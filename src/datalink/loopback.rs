@@ -0,0 +1,72 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table};
+use crate::network::{self, EthernetType};
+use crate::util::*;
+
+pub const AF_INET: u32 = 2;
+pub const AF_INET6_LINUX: u32 = 10;
+pub const AF_INET6_BSD: u32 = 24;
+pub const AF_INET6_FREEBSD: u32 = 28;
+pub const AF_INET6_DARWIN: u32 = 30;
+
+/// The 4 byte address family header of BSD loopback captures (DLT_NULL and DLT_LOOP).
+#[derive(Debug)]
+pub struct LoopbackHeader {
+    family: u32,
+    packet_data: Vec<u8>
+}
+
+impl LoopbackHeader {
+    /// DLT_NULL stores the family in the byte order of the capturing host. Families are small,
+    /// so a value that doesn't fit in the low half was written in the other order.
+    pub fn null(data: &[u8]) -> Self {
+        let family = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        Self {
+            family: if family > 0xffff { family.swap_bytes() } else { family },
+            packet_data: Vec::from(&data[4..])
+        }
+    }
+
+    /// DLT_LOOP always stores the family in network byte order.
+    pub fn r#loop(data: &[u8]) -> Self {
+        Self {
+            family: tou32(&data[0..4]),
+            packet_data: Vec::from(&data[4..])
+        }
+    }
+
+    /// The EtherType matching the address family.
+    pub fn ethernet_type(&self) -> Option<EthernetType> {
+        match self.family {
+            AF_INET => Some(network::IPV4),
+            AF_INET6_LINUX | AF_INET6_BSD | AF_INET6_FREEBSD | AF_INET6_DARWIN => Some(network::IPV6),
+            _ => None
+        }
+    }
+}
+
+impl DataContainer for LoopbackHeader {
+    fn data(&self) -> &[u8] {
+        self.packet_data.as_slice()
+    }
+}
+
+impl Display for LoopbackHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let family = match self.ethernet_type() {
+            Some(network::IPV4) => "ipv4",
+            Some(network::IPV6) => "ipv6",
+            _ => "unidentified"
+        };
+
+        let mut table = table!(
+            ["family", format!("{} ({})", self.family, family)]
+        );
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -1 +1,98 @@
-pub mod ethernet;
\ No newline at end of file
+pub mod ethernet;
+pub mod vlan;
+pub mod llc;
+pub mod stp;
+pub mod cdp;
+pub mod lldp;
+pub mod neighbors;
+pub mod sll;
+pub mod loopback;
+pub mod radiotap;
+pub mod ieee80211;
+pub mod ppp;
+pub mod pppoe;
+
+
+use std::str::FromStr;
+use crate::network::EthernetType;
+
+pub const VLAN: EthernetType = 0x8100;
+pub const QINQ: EthernetType = 0x88A8;
+pub const QINQ_LEGACY: EthernetType = 0x9100;
+
+/// Whether the EtherType field actually holds the TPID of a VLAN tag.
+#[inline]
+pub fn is_vlan_tpid(ethernet_type: EthernetType) -> bool {
+    matches!(ethernet_type, VLAN | QINQ | QINQ_LEGACY)
+}
+
+/// The link layer header type of captured packets, numbered after the pcap LINKTYPE_ values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkType {
+    Null,
+    Ethernet,
+    Ppp,
+    Raw,
+    Ieee80211,
+    Loop,
+    LinuxSll,
+    Ieee80211Radiotap,
+    Ipv4,
+    Ipv6,
+    LinuxSll2,
+    Unknown(u32),
+}
+
+impl LinkType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LinkType::Null => "null",
+            LinkType::Ethernet => "ethernet",
+            LinkType::Ppp => "ppp",
+            LinkType::Raw => "raw",
+            LinkType::Ieee80211 => "ieee802_11",
+            LinkType::Loop => "loop",
+            LinkType::LinuxSll => "linux_sll",
+            LinkType::Ieee80211Radiotap => "ieee802_11_radiotap",
+            LinkType::Ipv4 => "ipv4",
+            LinkType::Ipv6 => "ipv6",
+            LinkType::LinuxSll2 => "linux_sll2",
+            LinkType::Unknown(_) => "unidentified"
+        }
+    }
+}
+
+impl From<u32> for LinkType {
+    fn from(link_type: u32) -> Self {
+        match link_type {
+            0 => LinkType::Null,
+            1 => LinkType::Ethernet,
+            // With and without HDLC framing, the address and control bytes are optional anyway
+            9 | 50 => LinkType::Ppp,
+            // DLT_RAW has a different value depending on the platform that wrote the file
+            12 | 14 | 101 => LinkType::Raw,
+            105 => LinkType::Ieee80211,
+            108 => LinkType::Loop,
+            113 => LinkType::LinuxSll,
+            127 => LinkType::Ieee80211Radiotap,
+            228 => LinkType::Ipv4,
+            229 => LinkType::Ipv6,
+            276 => LinkType::LinuxSll2,
+            _ => LinkType::Unknown(link_type)
+        }
+    }
+}
+
+impl FromStr for LinkType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            LinkType::Null, LinkType::Ethernet, LinkType::Ppp, LinkType::Raw, LinkType::Ieee80211, LinkType::Loop,
+            LinkType::LinuxSll, LinkType::Ieee80211Radiotap, LinkType::Ipv4, LinkType::Ipv6,
+            LinkType::LinuxSll2
+        ].into_iter()
+            .find(|link_type| link_type.name() == s)
+            .ok_or_else(|| format!("unknown link type {}", s))
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use std::time::Duration;
+use pnet::util::MacAddr;
+use prettytable::{format, Table, row};
+use crate::datalink::cdp::CdpPacket;
+use crate::datalink::lldp::LldpPacket;
+use crate::datalink::stp::Bpdu;
+
+/// A switch or other device that announced itself through a discovery protocol.
+#[derive(Debug)]
+pub struct Neighbor {
+    protocol: &'static str,
+    source: MacAddr,
+    device: String,
+    port: String,
+    description: String,
+    management_address: Option<IpAddr>,
+    last_seen: Duration,
+}
+
+impl Neighbor {
+    pub fn lldp(source: MacAddr, packet: &LldpPacket, timestamp: Duration) -> Self {
+        Self {
+            protocol: "lldp",
+            source,
+            device: packet.system_name().or_else(|| packet.chassis_id()).unwrap_or_default(),
+            port: packet.port_id().unwrap_or_default(),
+            description: packet.port_description().or_else(|| packet.system_description()).unwrap_or_default(),
+            management_address: packet.management_address(),
+            last_seen: timestamp,
+        }
+    }
+
+    pub fn cdp(source: MacAddr, packet: &CdpPacket, timestamp: Duration) -> Self {
+        Self {
+            protocol: "cdp",
+            source,
+            device: packet.device_id().unwrap_or_default(),
+            port: packet.port_id().unwrap_or_default(),
+            description: packet.platform().unwrap_or_default(),
+            management_address: packet.management_address(),
+            last_seen: timestamp,
+        }
+    }
+
+    /// Spanning tree only tells which bridge and port sent the BPDU, and which bridge is root.
+    pub fn stp(source: MacAddr, bpdu: &Bpdu, timestamp: Duration) -> Option<Self> {
+        let bridge_id = bpdu.bridge_id()?;
+        let root_id = bpdu.root_id()?;
+        let mut description = format!("root {} cost {}", root_id, bpdu.root_path_cost());
+        if let Some(role) = bpdu.port_role() {
+            description += &format!(", {}", role);
+        }
+        Some(Self {
+            protocol: "stp",
+            source,
+            device: bridge_id.to_string(),
+            port: format!("{:04X}", bpdu.port_id()),
+            description,
+            management_address: None,
+            last_seen: timestamp,
+        })
+    }
+}
+
+/// The neighbors discovered during a capture, one entry per protocol, device and port.
+#[derive(Debug, Default)]
+pub struct Neighbors {
+    neighbors: Vec<Neighbor>,
+}
+
+impl Neighbors {
+    pub fn update(&mut self, neighbor: Neighbor) {
+        let existing = self.neighbors.iter_mut().find(|known| {
+            known.protocol == neighbor.protocol && known.source == neighbor.source && known.port == neighbor.port
+        });
+        match existing {
+            Some(known) => *known = neighbor,
+            None => self.neighbors.push(neighbor)
+        }
+    }
+}
+
+impl Display for Neighbors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.set_titles(row!["protocol", "source", "device", "port", "description", "management_address", "last_seen"]);
+        for neighbor in self.neighbors.iter() {
+            table.add_row(row![
+                neighbor.protocol,
+                neighbor.source,
+                neighbor.device,
+                neighbor.port,
+                neighbor.description,
+                neighbor.management_address.map(|address| address.to_string()).unwrap_or_default(),
+                format!("{:.3}", neighbor.last_seen.as_secs_f64())
+            ]);
+        }
+
+        table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt::{Display, Formatter};
+use std::net::Ipv4Addr;
+use prettytable::{format, table, row};
+use crate::util::*;
+
+pub type PppProtocol = u16;
+
+pub const IPV4: PppProtocol = 0x0021;
+pub const IPV6: PppProtocol = 0x0057;
+pub const IPCP: PppProtocol = 0x8021;
+pub const IPV6CP: PppProtocol = 0x8057;
+pub const LCP: PppProtocol = 0xC021;
+pub const PAP: PppProtocol = 0xC023;
+pub const CHAP: PppProtocol = 0xC223;
+
+pub const CONFIGURE_REQUEST: u8 = 1;
+pub const CONFIGURE_ACK: u8 = 2;
+pub const CONFIGURE_NAK: u8 = 3;
+pub const CONFIGURE_REJECT: u8 = 4;
+pub const TERMINATE_REQUEST: u8 = 5;
+pub const TERMINATE_ACK: u8 = 6;
+pub const CODE_REJECT: u8 = 7;
+pub const PROTOCOL_REJECT: u8 = 8;
+pub const ECHO_REQUEST: u8 = 9;
+pub const ECHO_REPLY: u8 = 10;
+pub const DISCARD_REQUEST: u8 = 11;
+
+fn protocol_name(protocol: PppProtocol) -> &'static str {
+    match protocol {
+        IPV4 => "ipv4",
+        IPV6 => "ipv6",
+        IPCP => "ipcp",
+        IPV6CP => "ipv6cp",
+        LCP => "lcp",
+        PAP => "pap",
+        CHAP => "chap",
+        _ => "unidentified"
+    }
+}
+
+/// A PPP frame, with the HDLC address and control bytes left out if present.
+#[derive(Debug)]
+pub struct PppFrame {
+    protocol: PppProtocol,
+    frame_data: Vec<u8>
+}
+
+impl PppFrame {
+    /// Parses a frame, or returns `None` if it ends before the protocol field does.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        let data = data.strip_prefix(&[0xff, 0x03]).unwrap_or(data);
+        // With protocol field compression the protocol is a single, odd, byte
+        if *data.first()? & 1 == 1 {
+            Some(Self {
+                protocol: data[0] as PppProtocol,
+                frame_data: Vec::from(&data[1..])
+            })
+        } else {
+            Some(Self {
+                protocol: tou16(data.get(0..2)?),
+                frame_data: Vec::from(&data[2..])
+            })
+        }
+    }
+
+    #[inline]
+    pub fn protocol(&self) -> PppProtocol {
+        self.protocol
+    }
+}
+
+impl DataContainer for PppFrame {
+    fn data(&self) -> &[u8] {
+        self.frame_data.as_slice()
+    }
+}
+
+impl Display for PppFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["protocol", format!("{:04X} ({})", self.protocol, protocol_name(self.protocol))]
+        );
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+/// A configuration option of LCP, IPCP or IPV6CP.
+#[derive(Debug)]
+pub struct PppOption {
+    option_type: u8,
+    value: Vec<u8>,
+}
+
+/// A packet of one of the PPP control protocols, which all share the LCP packet format.
+#[derive(Debug)]
+pub struct ControlPacket {
+    protocol: PppProtocol,
+    code: u8,
+    identifier: u8,
+    length: u16,
+    options: Vec<PppOption>,
+    packet_data: Vec<u8>
+}
+
+impl ControlPacket {
+    pub fn new(protocol: PppProtocol, data: &[u8]) -> Self {
+        let length = tou16(&data[2..4]);
+        let body = &data[4..(length as usize).clamp(4, data.len())];
+        let mut options = Vec::new();
+        if (CONFIGURE_REQUEST..=CONFIGURE_REJECT).contains(&data[0]) {
+            let mut rest = body;
+            while rest.len() >= 2 {
+                // The option length includes its type and length bytes
+                let length = (rest[1] as usize).clamp(2, rest.len());
+                options.push(PppOption {
+                    option_type: rest[0],
+                    value: Vec::from(&rest[2..length]),
+                });
+                rest = &rest[length..];
+            }
+        }
+
+        Self {
+            protocol,
+            code: data[0],
+            identifier: data[1],
+            length,
+            options,
+            packet_data: Vec::from(body)
+        }
+    }
+
+    fn option_name(&self, option: &PppOption) -> String {
+        let name = match (self.protocol, option.option_type) {
+            (LCP, 1) => "mru",
+            (LCP, 2) => "async_control_character_map",
+            (LCP, 3) => "authentication_protocol",
+            (LCP, 4) => "quality_protocol",
+            (LCP, 5) => "magic_number",
+            (LCP, 7) => "protocol_field_compression",
+            (LCP, 8) => "address_control_field_compression",
+            (IPCP, 2) => "ip_compression_protocol",
+            (IPCP, 3) => "ip_address",
+            (IPCP, 129) => "primary_dns",
+            (IPCP, 130) => "primary_nbns",
+            (IPCP, 131) => "secondary_dns",
+            (IPCP, 132) => "secondary_nbns",
+            (IPV6CP, 1) => "interface_identifier",
+            _ => return format!("option {}", option.option_type)
+        };
+        String::from(name)
+    }
+
+    fn option_value(&self, option: &PppOption) -> String {
+        let value = option.value.as_slice();
+        match (self.protocol, option.option_type, value.len()) {
+            (LCP, 1, 2) => tou16(value).to_string(),
+            (LCP, 3, 2..) => {
+                let protocol = tou16(value);
+                match (protocol, value.get(2)) {
+                    (CHAP, Some(5)) => String::from("chap md5"),
+                    (CHAP, Some(0x80)) => String::from("ms-chap"),
+                    (CHAP, Some(0x81)) => String::from("ms-chap-v2"),
+                    _ => String::from(protocol_name(protocol))
+                }
+            }
+            (LCP, 5, 4) => format!("{:08X}", tou32(value)),
+            (LCP, 7 | 8, 0) => String::from("yes"),
+            (IPCP, 3 | 129..=132, 4) => Ipv4Addr::from(tou32(value)).to_string(),
+            (IPV6CP, 1, 8) => value.chunks(2)
+                .map(|chunk| format!("{:x}", tou16(chunk)))
+                .collect::<Vec<_>>()
+                .join(":"),
+            _ => format!("{:02X?}", value)
+        }
+    }
+}
+
+impl DataContainer for ControlPacket {
+    fn data(&self) -> &[u8] {
+        self.packet_data.as_slice()
+    }
+}
+
+impl Display for ControlPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let code = match self.code {
+            CONFIGURE_REQUEST => "configure request",
+            CONFIGURE_ACK => "configure ack",
+            CONFIGURE_NAK => "configure nak",
+            CONFIGURE_REJECT => "configure reject",
+            TERMINATE_REQUEST => "terminate request",
+            TERMINATE_ACK => "terminate ack",
+            CODE_REJECT => "code reject",
+            PROTOCOL_REJECT => "protocol reject",
+            ECHO_REQUEST => "echo request",
+            ECHO_REPLY => "echo reply",
+            DISCARD_REQUEST => "discard request",
+            _ => "unidentified"
+        };
+
+        let mut table = table!(
+            ["code", code],
+            ["identifier", self.identifier],
+            ["length", self.length]
+        );
+        for option in self.options.iter() {
+            table.add_row(row![self.option_name(option), self.option_value(option)]);
+        }
+        if matches!(self.code, ECHO_REQUEST | ECHO_REPLY | DISCARD_REQUEST) && self.packet_data.len() >= 4 {
+            table.add_row(row!["magic_number", format!("{:08X}", tou32(&self.packet_data))]);
+        }
+        if self.code == PROTOCOL_REJECT && self.packet_data.len() >= 2 {
+            table.add_row(row!["rejected_protocol", format!("{:04X}", tou16(&self.packet_data))]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
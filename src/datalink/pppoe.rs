@@ -0,0 +1,122 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table, row};
+use crate::util::*;
+
+pub const CODE_SESSION: u8 = 0x00;
+pub const CODE_PADO: u8 = 0x07;
+pub const CODE_PADI: u8 = 0x09;
+pub const CODE_PADR: u8 = 0x19;
+pub const CODE_PADS: u8 = 0x65;
+pub const CODE_PADT: u8 = 0xA7;
+
+pub const TAG_END_OF_LIST: u16 = 0x0000;
+pub const TAG_SERVICE_NAME: u16 = 0x0101;
+pub const TAG_AC_NAME: u16 = 0x0102;
+pub const TAG_HOST_UNIQ: u16 = 0x0103;
+pub const TAG_AC_COOKIE: u16 = 0x0104;
+pub const TAG_VENDOR_SPECIFIC: u16 = 0x0105;
+pub const TAG_RELAY_SESSION_ID: u16 = 0x0110;
+pub const TAG_SERVICE_NAME_ERROR: u16 = 0x0201;
+pub const TAG_AC_SYSTEM_ERROR: u16 = 0x0202;
+pub const TAG_GENERIC_ERROR: u16 = 0x0203;
+
+/// A PPPoE packet. Discovery packets carry tags, session packets a PPP frame.
+#[derive(Debug)]
+pub struct PppoePacket {
+    version: u8,
+    pppoe_type: u8,
+    code: u8,
+    session_id: u16,
+    length: u16,
+    tags: Vec<(u16, Vec<u8>)>,
+    packet_data: Vec<u8>
+}
+
+impl PppoePacket {
+    /// Parses a packet, or returns `None` if it ends inside the fixed header.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < 6 {
+            return None;
+        }
+        let length = tou16(&data[4..6]);
+        let payload = &data[6..(6 + length as usize).min(data.len())];
+        let mut tags = Vec::new();
+        if data[1] != CODE_SESSION {
+            let mut rest = payload;
+            while rest.len() >= 4 {
+                let tag_type = tou16(&rest[0..2]);
+                let tag_length = (tou16(&rest[2..4]) as usize).min(rest.len() - 4);
+                if tag_type == TAG_END_OF_LIST {
+                    break;
+                }
+                tags.push((tag_type, Vec::from(&rest[4..4 + tag_length])));
+                rest = &rest[4 + tag_length..];
+            }
+        }
+
+        Some(Self {
+            version: data[0] >> 4,
+            pppoe_type: data[0] & 0x0f,
+            code: data[1],
+            session_id: tou16(&data[2..4]),
+            length,
+            tags,
+            packet_data: Vec::from(payload)
+        })
+    }
+
+    #[inline]
+    pub fn code(&self) -> u8 {
+        self.code
+    }
+}
+
+impl DataContainer for PppoePacket {
+    fn data(&self) -> &[u8] {
+        self.packet_data.as_slice()
+    }
+}
+
+impl Display for PppoePacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let code = match self.code {
+            CODE_SESSION => "session data",
+            CODE_PADO => "pado offer",
+            CODE_PADI => "padi initiation",
+            CODE_PADR => "padr request",
+            CODE_PADS => "pads session confirmation",
+            CODE_PADT => "padt terminate",
+            _ => "unidentified"
+        };
+
+        let mut table = table!(
+            ["version", self.version],
+            ["type", self.pppoe_type],
+            ["code", code],
+            ["session_id", format!("{:04X}", self.session_id)],
+            ["length", self.length]
+        );
+        for (tag_type, value) in self.tags.iter() {
+            let text = || String::from_utf8_lossy(value).into_owned();
+            let (name, value) = match *tag_type {
+                TAG_SERVICE_NAME => ("service_name", text()),
+                TAG_AC_NAME => ("ac_name", text()),
+                TAG_HOST_UNIQ => ("host_uniq", format!("{:02X?}", value)),
+                TAG_AC_COOKIE => ("ac_cookie", format!("{:02X?}", value)),
+                TAG_VENDOR_SPECIFIC => ("vendor_specific", format!("{} bytes", value.len())),
+                TAG_RELAY_SESSION_ID => ("relay_session_id", format!("{:02X?}", value)),
+                TAG_SERVICE_NAME_ERROR => ("service_name_error", text()),
+                TAG_AC_SYSTEM_ERROR => ("ac_system_error", text()),
+                TAG_GENERIC_ERROR => ("generic_error", text()),
+                _ => ("tag", format!("{:04X} {:02X?}", tag_type, value))
+            };
+            table.add_row(row![name, value]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table, row};
+use crate::util::*;
+
+pub const FLAG_FCS: u8 = 0x10;
+
+/// A radiotap header, carrying the radio information of a captured 802.11 frame. Only the
+/// fields of the first present bitmap in the default namespace are decoded.
+#[derive(Debug, Default)]
+pub struct RadiotapHeader {
+    version: u8,
+    length: u16,
+    present: u32,
+    tsft: Option<u64>,
+    flags: Option<u8>,
+    rate: Option<u8>,
+    channel_frequency: Option<u16>,
+    channel_flags: Option<u16>,
+    antenna_signal: Option<i8>,
+    antenna_noise: Option<i8>,
+    antenna: Option<u8>,
+    mcs_index: Option<u8>,
+    frame_data: Vec<u8>
+}
+
+/// Alignment and size of the fields numbered by their bit in the present bitmap.
+const FIELDS: [(usize, usize); 22] = [
+    (8, 8), (1, 1), (1, 1), (2, 4), (1, 2), (1, 1), (1, 1), (2, 2), (2, 2), (2, 2), (1, 1),
+    (1, 1), (1, 1), (1, 1), (2, 2), (2, 2), (1, 1), (1, 1), (4, 8), (1, 3), (4, 8), (2, 12),
+];
+
+impl RadiotapHeader {
+    /// The data rate in Mb/s.
+    pub fn rate(&self) -> Option<f32> {
+        self.rate.map(|rate| rate as f32 / 2.0)
+    }
+
+    /// The IEEE channel number belonging to the channel frequency.
+    pub fn channel(&self) -> Option<u16> {
+        self.channel_frequency.map(|frequency| match frequency {
+            2484 => 14,
+            2412..=2472 => (frequency - 2407) / 5,
+            5955..=7115 => (frequency - 5950) / 5,
+            _ => frequency.saturating_sub(5000) / 5
+        })
+    }
+
+    /// Whether the 802.11 frame that follows ends with its frame check sequence.
+    pub fn has_fcs(&self) -> bool {
+        self.flags.is_some_and(|flags| flags & FLAG_FCS != 0)
+    }
+}
+
+impl From<&[u8]> for RadiotapHeader {
+    fn from(data: &[u8]) -> Self {
+        let length = u16::from_le_bytes([data[2], data[3]]) as usize;
+        let mut header = Self {
+            version: data[0],
+            length: length as u16,
+            present: u32::from_le_bytes([data[4], data[5], data[6], data[7]]),
+            frame_data: Vec::from(&data[length.min(data.len())..]),
+            ..Default::default()
+        };
+        let data = &data[..length.min(data.len())];
+
+        // Skip any extended bitmaps, the fields start after the last one
+        let mut offset = 4;
+        while offset + 4 <= data.len() && data[offset + 3] & 0x80 != 0 {
+            offset += 4;
+        }
+        offset += 4;
+
+        for (bit, (align, size)) in FIELDS.iter().enumerate() {
+            if header.present & (1 << bit) == 0 {
+                continue;
+            }
+            offset = offset.div_ceil(*align) * align;
+            if offset + size > data.len() {
+                break;
+            }
+            let field = &data[offset..offset + size];
+            match bit {
+                0 => header.tsft = Some(u64::from_le_bytes(field.try_into().unwrap())),
+                1 => header.flags = Some(field[0]),
+                2 => header.rate = Some(field[0]),
+                3 => {
+                    header.channel_frequency = Some(u16::from_le_bytes([field[0], field[1]]));
+                    header.channel_flags = Some(u16::from_le_bytes([field[2], field[3]]));
+                }
+                5 => header.antenna_signal = Some(field[0] as i8),
+                6 => header.antenna_noise = Some(field[0] as i8),
+                11 => header.antenna = Some(field[0]),
+                19 => header.mcs_index = Some(field[2]),
+                _ => ()
+            }
+            offset += size;
+        }
+        header
+    }
+}
+
+impl DataContainer for RadiotapHeader {
+    fn data(&self) -> &[u8] {
+        self.frame_data.as_slice()
+    }
+}
+
+impl Display for RadiotapHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["version", self.version],
+            ["length", self.length],
+            ["present", format!("{:08X}", self.present)]
+        );
+        if let Some(tsft) = self.tsft {
+            table.add_row(row!["tsft", format!("{} us", tsft)]);
+        }
+        if let Some(flags) = self.flags {
+            table.add_row(row!["flags", format!("{:08b}", flags)]);
+        }
+        if let Some(rate) = self.rate() {
+            table.add_row(row!["rate", format!("{} Mb/s", rate)]);
+        }
+        if let (Some(frequency), Some(channel)) = (self.channel_frequency, self.channel()) {
+            table.add_row(row!["channel", format!("{} ({} MHz)", channel, frequency)]);
+        }
+        if let Some(channel_flags) = self.channel_flags {
+            table.add_row(row!["channel_flags", format!("{:04X}", channel_flags)]);
+        }
+        if let Some(signal) = self.antenna_signal {
+            table.add_row(row!["signal", format!("{} dBm", signal)]);
+        }
+        if let Some(noise) = self.antenna_noise {
+            table.add_row(row!["noise", format!("{} dBm", noise)]);
+        }
+        if let Some(antenna) = self.antenna {
+            table.add_row(row!["antenna", antenna]);
+        }
+        if let Some(mcs_index) = self.mcs_index {
+            table.add_row(row!["mcs_index", mcs_index]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligns_fields_after_fhss_rts_and_xchannel() {
+        let mut data = vec![0, 0, 27, 0];
+        // Flags, FHSS, antenna signal, RTS retries, XChannel and MCS
+        let present: u32 = 1 << 1 | 1 << 4 | 1 << 5 | 1 << 16 | 1 << 18 | 1 << 19;
+        data.extend_from_slice(&present.to_le_bytes());
+        data.extend_from_slice(&[FLAG_FCS, 1, 2, 0xd8, 3]);
+        // XChannel, aligned to 4 bytes
+        data.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0x6c, 0x09, 1, 20]);
+        data.extend_from_slice(&[0x02, 0, 7]);
+        data.extend_from_slice(b"frame");
+
+        let header = RadiotapHeader::from(data.as_slice());
+        assert!(header.has_fcs());
+        assert_eq!(header.antenna_signal, Some(-40));
+        assert_eq!(header.mcs_index, Some(7));
+        assert_eq!(header.data(), b"frame");
+    }
+}
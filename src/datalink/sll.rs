@@ -0,0 +1,102 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table, row};
+use crate::network::EthernetType;
+use crate::util::*;
+
+/// Protocol value of Novell raw 802.3 payloads.
+pub const PROTOCOL_8023: EthernetType = 0x0001;
+/// Protocol value of payloads starting with an 802.2 LLC header.
+pub const PROTOCOL_8022: EthernetType = 0x0004;
+
+/// A Linux "cooked" capture header, as produced when capturing on the `any` pseudo-interface.
+/// Both the original (SLL) and the version 2 (SLL2) layout decode into this.
+#[derive(Debug)]
+pub struct SllHeader {
+    version: u8,
+    packet_type: u8,
+    arphrd_type: u16,
+    interface_index: Option<u32>,
+    address: Vec<u8>,
+    protocol: EthernetType,
+    packet_data: Vec<u8>
+}
+
+impl SllHeader {
+    /// Decodes the 16 byte header of DLT_LINUX_SLL.
+    pub fn v1(data: &[u8]) -> Self {
+        let address_length = (tou16(&data[4..6]) as usize).min(8);
+        Self {
+            version: 1,
+            packet_type: tou16(&data[0..2]) as u8,
+            arphrd_type: tou16(&data[2..4]),
+            interface_index: None,
+            address: Vec::from(&data[6..6 + address_length]),
+            protocol: tou16(&data[14..16]),
+            packet_data: Vec::from(&data[16..])
+        }
+    }
+
+    /// Decodes the 20 byte header of DLT_LINUX_SLL2.
+    pub fn v2(data: &[u8]) -> Self {
+        let address_length = (data[11] as usize).min(8);
+        Self {
+            version: 2,
+            packet_type: data[10],
+            arphrd_type: tou16(&data[8..10]),
+            interface_index: Some(tou32(&data[4..8])),
+            address: Vec::from(&data[12..12 + address_length]),
+            protocol: tou16(&data[0..2]),
+            packet_data: Vec::from(&data[20..])
+        }
+    }
+
+    #[inline]
+    pub fn address(&self) -> &[u8] {
+        self.address.as_slice()
+    }
+
+    #[inline]
+    pub fn protocol(&self) -> EthernetType {
+        self.protocol
+    }
+}
+
+impl DataContainer for SllHeader {
+    fn data(&self) -> &[u8] {
+        self.packet_data.as_slice()
+    }
+}
+
+impl Display for SllHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let packet_type = match self.packet_type {
+            0 => "to us",
+            1 => "broadcast",
+            2 => "multicast",
+            3 => "to other host",
+            4 => "sent by us",
+            _ => "unidentified"
+        };
+        let address = self.address.iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join(":");
+
+        let mut table = table!(
+            ["version", self.version],
+            ["packet_type", packet_type],
+            ["arphrd_type", self.arphrd_type]
+        );
+        if let Some(interface_index) = self.interface_index {
+            table.add_row(row!["interface_index", interface_index]);
+        }
+        table.add_row(row!["address", address]);
+        table.add_row(row!["protocol", format!("{:04X}", self.protocol)]);
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
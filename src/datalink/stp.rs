@@ -0,0 +1,291 @@
+use std::fmt::{Display, Formatter};
+use pnet::util::MacAddr;
+use prettytable::{format, table, row};
+use crate::util::*;
+
+pub const BPDU_CONFIG: u8 = 0x00;
+pub const BPDU_RST: u8 = 0x02;
+pub const BPDU_TCN: u8 = 0x80;
+
+pub const VERSION_STP: u8 = 0;
+pub const VERSION_RSTP: u8 = 2;
+pub const VERSION_MSTP: u8 = 3;
+
+pub const FLAG_TOPOLOGY_CHANGE: u8 = 0x01;
+pub const FLAG_PROPOSAL: u8 = 0x02;
+pub const FLAG_LEARNING: u8 = 0x10;
+pub const FLAG_FORWARDING: u8 = 0x20;
+pub const FLAG_AGREEMENT: u8 = 0x40;
+pub const FLAG_TOPOLOGY_CHANGE_ACK: u8 = 0x80;
+
+/// A bridge identifier, the priority followed by the MAC address of the bridge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BridgeId {
+    priority: u16,
+    address: MacAddr,
+}
+
+impl BridgeId {
+    /// The priority, without the system id extension in its lower 12 bits.
+    #[inline]
+    pub fn priority(&self) -> u16 {
+        self.priority & 0xf000
+    }
+
+    /// The system id extension, the VLAN or MST instance the identifier is for.
+    #[inline]
+    pub fn system_id(&self) -> u16 {
+        self.priority & 0x0fff
+    }
+}
+
+impl From<&[u8]> for BridgeId {
+    fn from(data: &[u8]) -> Self {
+        Self {
+            priority: tou16(&data[0..2]),
+            address: MacAddr::new(data[2], data[3], data[4], data[5], data[6], data[7]),
+        }
+    }
+}
+
+impl Display for BridgeId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}/{}", self.priority(), self.system_id(), self.address)
+    }
+}
+
+/// The port role encoded in bits 2 and 3 of the RST and MST flags.
+fn port_role(flags: u8) -> &'static str {
+    match (flags >> 2) & 0b11 {
+        1 => "alternate/backup",
+        2 => "root",
+        3 => "designated",
+        _ => "unknown"
+    }
+}
+
+fn flag_names(flags: u8, rapid: bool) -> String {
+    let mut names = Vec::new();
+    for (flag, name) in [
+        (FLAG_TOPOLOGY_CHANGE, "topology_change"), (FLAG_PROPOSAL, "proposal"),
+        (FLAG_LEARNING, "learning"), (FLAG_FORWARDING, "forwarding"), (FLAG_AGREEMENT, "agreement"),
+        (FLAG_TOPOLOGY_CHANGE_ACK, "topology_change_ack")
+    ] {
+        // Only the topology change flags exist in classic configuration BPDUs
+        if flags & flag != 0 && (rapid || flag & (FLAG_TOPOLOGY_CHANGE | FLAG_TOPOLOGY_CHANGE_ACK) != 0) {
+            names.push(name);
+        }
+    }
+    if rapid {
+        names.push(port_role(flags));
+    }
+    format!("{:08b} {}", flags, names.join(", ")).trim_end().to_string()
+}
+
+/// The configuration of a multiple spanning tree instance, as carried after the CIST part of an
+/// MST BPDU.
+#[derive(Debug)]
+pub struct MstiConfiguration {
+    flags: u8,
+    regional_root: BridgeId,
+    internal_root_path_cost: u32,
+    bridge_priority: u8,
+    port_priority: u8,
+    remaining_hops: u8,
+}
+
+impl From<&[u8]> for MstiConfiguration {
+    fn from(data: &[u8]) -> Self {
+        Self {
+            flags: data[0],
+            regional_root: BridgeId::from(&data[1..9]),
+            internal_root_path_cost: tou32(&data[9..13]),
+            bridge_priority: data[13],
+            port_priority: data[14],
+            remaining_hops: data[15],
+        }
+    }
+}
+
+/// The MST part of a BPDU, following the RST fields.
+#[derive(Debug)]
+pub struct MstExtension {
+    configuration_name: String,
+    revision_level: u16,
+    configuration_digest: [u8; 16],
+    cist_internal_root_path_cost: u32,
+    cist_bridge_id: BridgeId,
+    cist_remaining_hops: u8,
+    mstis: Vec<MstiConfiguration>,
+}
+
+impl MstExtension {
+    /// Decodes the MST fields, which start after the version 1 length at offset 36.
+    fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < 102 {
+            return None;
+        }
+        let version_3_length = tou16(&data[36..38]) as usize;
+        let end = (38 + version_3_length).min(data.len());
+        let mstis = data[102.min(end)..end]
+            .chunks_exact(16)
+            .map(MstiConfiguration::from)
+            .collect();
+
+        Some(Self {
+            configuration_name: String::from_utf8_lossy(&data[39..71]).trim_end_matches('\0').to_string(),
+            revision_level: tou16(&data[71..73]),
+            configuration_digest: data[73..89].try_into().unwrap(),
+            cist_internal_root_path_cost: tou32(&data[89..93]),
+            cist_bridge_id: BridgeId::from(&data[93..101]),
+            cist_remaining_hops: data[101],
+            mstis,
+        })
+    }
+}
+
+/// A spanning tree bridge protocol data unit, classic, rapid or multiple.
+#[derive(Debug)]
+pub struct Bpdu {
+    protocol_id: u16,
+    version: u8,
+    bpdu_type: u8,
+    flags: u8,
+    root_id: Option<BridgeId>,
+    root_path_cost: u32,
+    bridge_id: Option<BridgeId>,
+    port_id: u16,
+    message_age: u16,
+    max_age: u16,
+    hello_time: u16,
+    forward_delay: u16,
+    mst: Option<MstExtension>,
+}
+
+impl Bpdu {
+    /// Parses a BPDU, or returns `None` if it is shorter than the fields of its type.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < 4 || (data[3] != BPDU_TCN && data.len() < 35) {
+            return None;
+        }
+        let mut bpdu = Self {
+            protocol_id: tou16(&data[0..2]),
+            version: data[2],
+            bpdu_type: data[3],
+            flags: 0,
+            root_id: None,
+            root_path_cost: 0,
+            bridge_id: None,
+            port_id: 0,
+            message_age: 0,
+            max_age: 0,
+            hello_time: 0,
+            forward_delay: 0,
+            mst: None,
+        };
+        if bpdu.bpdu_type != BPDU_TCN {
+            bpdu.flags = data[4];
+            bpdu.root_id = Some(BridgeId::from(&data[5..13]));
+            bpdu.root_path_cost = tou32(&data[13..17]);
+            bpdu.bridge_id = Some(BridgeId::from(&data[17..25]));
+            bpdu.port_id = tou16(&data[25..27]);
+            bpdu.message_age = tou16(&data[27..29]);
+            bpdu.max_age = tou16(&data[29..31]);
+            bpdu.hello_time = tou16(&data[31..33]);
+            bpdu.forward_delay = tou16(&data[33..35]);
+        }
+        if bpdu.bpdu_type == BPDU_RST && bpdu.version >= VERSION_MSTP {
+            bpdu.mst = MstExtension::new(data);
+        }
+        Some(bpdu)
+    }
+
+    #[inline]
+    pub fn root_id(&self) -> Option<BridgeId> {
+        self.root_id
+    }
+
+    #[inline]
+    pub fn root_path_cost(&self) -> u32 {
+        self.root_path_cost
+    }
+
+    #[inline]
+    pub fn bridge_id(&self) -> Option<BridgeId> {
+        self.bridge_id
+    }
+
+    #[inline]
+    pub fn port_id(&self) -> u16 {
+        self.port_id
+    }
+
+    /// The port role, for rapid and multiple spanning tree BPDUs.
+    pub fn port_role(&self) -> Option<&'static str> {
+        if self.bpdu_type == BPDU_RST {
+            Some(port_role(self.flags))
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for Bpdu {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let version = match self.version {
+            VERSION_STP => "stp",
+            VERSION_RSTP => "rstp",
+            VERSION_MSTP => "mstp",
+            _ => "unidentified"
+        };
+        let bpdu_type = match self.bpdu_type {
+            BPDU_CONFIG => "configuration",
+            BPDU_RST => "rapid/multiple spanning tree",
+            BPDU_TCN => "topology change notification",
+            _ => "unidentified"
+        };
+        // Timers are carried in units of 1/256 seconds
+        let seconds = |time: u16| format!("{} s", time as f32 / 256.0);
+
+        let mut table = table!(
+            ["protocol_id", self.protocol_id],
+            ["version", version],
+            ["bpdu_type", bpdu_type]
+        );
+        if let (Some(root_id), Some(bridge_id)) = (self.root_id, self.bridge_id) {
+            table.add_row(row!["flags", flag_names(self.flags, self.bpdu_type == BPDU_RST)]);
+            table.add_row(row!["root_id", root_id]);
+            table.add_row(row!["root_path_cost", self.root_path_cost]);
+            table.add_row(row!["bridge_id", bridge_id]);
+            table.add_row(row!["port_id", format!("{:04X}", self.port_id)]);
+            table.add_row(row!["message_age", seconds(self.message_age)]);
+            table.add_row(row!["max_age", seconds(self.max_age)]);
+            table.add_row(row!["hello_time", seconds(self.hello_time)]);
+            table.add_row(row!["forward_delay", seconds(self.forward_delay)]);
+        }
+        if let Some(mst) = self.mst.as_ref() {
+            table.add_row(row!["configuration_name", mst.configuration_name]);
+            table.add_row(row!["revision_level", mst.revision_level]);
+            table.add_row(row!["configuration_digest", mst.configuration_digest.iter().map(|b| format!("{:02x}", b)).collect::<String>()]);
+            table.add_row(row!["cist_internal_root_path_cost", mst.cist_internal_root_path_cost]);
+            table.add_row(row!["cist_bridge_id", mst.cist_bridge_id]);
+            table.add_row(row!["cist_remaining_hops", mst.cist_remaining_hops]);
+            for msti in mst.mstis.iter() {
+                table.add_row(row![
+                    format!("msti {}", msti.regional_root.system_id()),
+                    format!(
+                        "regional root {}, cost {}, priority {}/{}, hops {}, {}",
+                        msti.regional_root, msti.internal_root_path_cost, msti.bridge_priority,
+                        msti.port_priority, msti.remaining_hops, flag_names(msti.flags, true)
+                    )
+                ]);
+            }
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt::{Display, Formatter};
+use crate::datalink;
+use crate::network::EthernetType;
+use crate::util::*;
+
+/// A single IEEE 802.1Q tag, as found (possibly stacked) between the source address and the
+/// EtherType of a frame.
+#[derive(Debug, Clone, Copy)]
+pub struct VlanTag {
+    tpid: EthernetType,
+    pcp: u8,
+    dei: bool,
+    vid: u16,
+}
+
+impl VlanTag {
+    #[inline]
+    pub fn vid(&self) -> u16 {
+        self.vid
+    }
+}
+
+impl From<&[u8]> for VlanTag {
+    fn from(data: &[u8]) -> Self {
+        Self {
+            tpid: tou16(&data[0..2]),
+            pcp: data[2] >> 5,
+            dei: data[2] & 0b00010000 != 0,
+            vid: tou16(&data[2..4]) & 0x0fff,
+        }
+    }
+}
+
+impl Display for VlanTag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let tpid = match self.tpid {
+            datalink::VLAN => "802.1Q",
+            datalink::QINQ => "802.1ad",
+            datalink::QINQ_LEGACY => "QinQ",
+            _ => "unidentified"
+        };
+        write!(f, "{} ({}, pcp {}, dei {})", self.vid, tpid, self.pcp, self.dei as u8)
+    }
+}
@@ -0,0 +1,1225 @@
+use std::collections::HashMap;
+use std::io::{self, stdout, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use pnet::util::MacAddr;
+use crate::application::dhcp::{self, DhcpMessage};
+use crate::application::dhcp::lease::Leases;
+use crate::application::dhcp::v6::{self as dhcpv6, Dhcpv6Message};
+use crate::application::diameter::DiameterMessage;
+use crate::application::dns::DNSQuery;
+use crate::application::export::ObjectExporter;
+use crate::application::ftp::{self, FtpConversation, FtpDataTransfer, FtpMessage};
+use crate::application::imap::{self, ImapConversation, ImapMessage};
+use crate::application::pop3::{self, Pop3Conversation, Pop3Message};
+use crate::application::smtp::{self, SmtpConversation, SmtpMessage};
+use crate::application::goose::GooseMessage;
+use crate::application::http::{self, HttpConversation};
+use crate::application::grpc::{self, GrpcStatus};
+use crate::application::ntp::{self, NtpExchanges, NtpPacket};
+use crate::application::ptp::{self, PtpExchanges, PtpMessage};
+use crate::application::snmp::{self, SnmpMessage};
+use crate::application::ssh::{self, SshConversation, SshEvent};
+use crate::application::ssh::hassh::Hasshes;
+use crate::application::syslog::{self, SyslogMessage, SyslogStream};
+use crate::application::telnet::{self, TelnetStream};
+use crate::application::tftp::{self, TftpPacket, TftpTransfers};
+use crate::application::ber::Element;
+use crate::application::http2::{self, Http2Connection, Http2Event, Http2Message};
+use crate::application::protobuf::descriptor::DescriptorSet;
+use crate::application::tls::{self, Hello, HandshakeMessage, TlsConversation, TlsRecord};
+use crate::application::tls::fingerprint::{Fingerprint, FingerprintDatabase, Fingerprints};
+use crate::application::tls::keylog::KeyLog;
+use crate::application::wol::{self, MagicPacket};
+use crate::capture::Packet;
+use crate::datalink::{ppp, pppoe, sll, LinkType};
+use crate::datalink::cdp::CdpPacket;
+use crate::datalink::ethernet::Frame;
+use crate::datalink::ieee80211::WlanFrame;
+use crate::datalink::llc::{self, LlcHeader};
+use crate::datalink::lldp::LldpPacket;
+use crate::datalink::loopback::LoopbackHeader;
+use crate::datalink::neighbors::{Neighbor, Neighbors};
+use crate::datalink::ppp::{ControlPacket, PppFrame};
+use crate::datalink::pppoe::PppoePacket;
+use crate::datalink::radiotap::RadiotapHeader;
+use crate::datalink::sll::SllHeader;
+use crate::datalink::stp::Bpdu;
+use crate::ipsec::Sequences;
+use crate::ipsec::ah::AhHeader;
+use crate::ipsec::esp::EspPacket;
+use crate::ipsec::sa::SecurityAssociations;
+use crate::network;
+use crate::network::EthernetType;
+use crate::network::arp::ARPPacket;
+use crate::network::icmp::ICMPSegment;
+use crate::network::igmp::IgmpMessage;
+use crate::network::mld::MldMessage;
+use crate::network::ipv4::Ipv4Packet;
+use crate::network::ipv6::Ipv6Packet;
+use crate::network::ipx::IpxPacket;
+use crate::network::mpls::MplsPacket;
+use crate::network::multicast::Memberships;
+use crate::options::Options;
+use crate::transport::{self, Protocol};
+use crate::transport::association::Associations;
+use crate::transport::quic::{self, QuicPacket};
+use crate::transport::quic::connection::Connections;
+use crate::transport::sctp::{self, Chunk, SctpPacket};
+use crate::transport::stream::{StreamData, TcpStreams};
+use crate::transport::tcp::TcpSegment;
+use crate::transport::udp::UDPSegment;
+use crate::tunnel::{self, geneve, vxlan};
+use crate::tunnel::geneve::GeneveHeader;
+use crate::tunnel::gre::{ErspanHeader, GrePacket};
+use crate::tunnel::vxlan::VxlanHeader;
+use crate::util::DataContainer;
+
+/// Walks captured packets down the layers, printing every header it understands, and keeps the
+/// state that outlives a single packet.
+pub struct Dissector<'a> {
+    options: &'a Options,
+    /// Number of the packet being dissected, counting from 1
+    frame: usize,
+    /// Capture time of the packet being dissected
+    timestamp: Duration,
+    /// Interface the packet being dissected was captured on
+    interface: Option<String>,
+    /// Link layer source address of the packet being dissected
+    source: Option<MacAddr>,
+    /// Network layer source and destination addresses of the innermost packet dissected so far
+    addresses: Option<(IpAddr, IpAddr)>,
+    neighbors: Neighbors,
+    memberships: Memberships,
+    sctp_associations: Associations,
+    leases: Leases,
+    ntp_exchanges: NtpExchanges,
+    ptp_exchanges: PtpExchanges,
+    tftp_transfers: TftpTransfers,
+    quic_connections: Connections,
+    tcp_streams: TcpStreams,
+    /// HTTP state of the TCP streams carrying it, by stream id
+    http_conversations: HashMap<usize, HttpConversation>,
+    http2_connections: HashMap<usize, Http2Connection>,
+    tls_conversations: HashMap<usize, TlsConversation>,
+    syslog_streams: HashMap<usize, SyslogStream>,
+    telnet_streams: HashMap<usize, TelnetStream>,
+    ftp_conversations: HashMap<usize, FtpConversation>,
+    /// The control streams of the data connections announced but not opened yet, by the
+    /// endpoint they go to
+    ftp_data_endpoints: HashMap<SocketAddr, usize>,
+    ftp_data_transfers: HashMap<usize, FtpDataTransfer>,
+    smtp_conversations: HashMap<usize, SmtpConversation>,
+    pop3_conversations: HashMap<usize, Pop3Conversation>,
+    imap_conversations: HashMap<usize, ImapConversation>,
+    ssh_conversations: HashMap<usize, SshConversation>,
+    keylog: Option<KeyLog>,
+    fingerprints: Fingerprints,
+    hasshes: Hasshes,
+    http_exporter: Option<ObjectExporter>,
+    tftp_exporter: Option<ObjectExporter>,
+    /// Protobuf types to decode gRPC messages with
+    descriptors: DescriptorSet,
+    /// Keys to decrypt ESP with
+    security_associations: SecurityAssociations,
+    sequences: Sequences,
+    /// Number of tunnels around the headers being dissected
+    depth: usize,
+}
+
+impl<'a> Dissector<'a> {
+    /// Creates a dissector, loading the key files named in the options.
+    pub fn new(options: &'a Options) -> io::Result<Self> {
+        let security_associations = match options.esp_sa() {
+            Some(path) => SecurityAssociations::load(path)?,
+            None => SecurityAssociations::default()
+        };
+        let keylog = match options.tls_keylog() {
+            Some(path) => Some(KeyLog::load(path)?),
+            None => None
+        };
+        let fingerprint_database = match options.fingerprint_db() {
+            Some(path) => FingerprintDatabase::load(path)?,
+            None => FingerprintDatabase::default()
+        };
+        let http_exporter = match options.export_http() {
+            Some(directory) => Some(ObjectExporter::http(directory)?),
+            None => None
+        };
+        let tftp_exporter = match options.export_tftp() {
+            Some(directory) => Some(ObjectExporter::tftp(directory)?),
+            None => None
+        };
+        let descriptors = DescriptorSet::load(options.proto_descriptors())?;
+        Ok(Self {
+            options,
+            frame: 0,
+            timestamp: Duration::ZERO,
+            interface: None,
+            source: None,
+            addresses: None,
+            neighbors: Neighbors::default(),
+            memberships: Memberships::default(),
+            sctp_associations: Associations::default(),
+            leases: Leases::default(),
+            ntp_exchanges: NtpExchanges::default(),
+            ptp_exchanges: PtpExchanges::default(),
+            tftp_transfers: TftpTransfers::default(),
+            quic_connections: Connections::default(),
+            tcp_streams: TcpStreams::default(),
+            http_conversations: HashMap::new(),
+            http2_connections: HashMap::new(),
+            tls_conversations: HashMap::new(),
+            syslog_streams: HashMap::new(),
+            telnet_streams: HashMap::new(),
+            ftp_conversations: HashMap::new(),
+            ftp_data_endpoints: HashMap::new(),
+            ftp_data_transfers: HashMap::new(),
+            smtp_conversations: HashMap::new(),
+            pop3_conversations: HashMap::new(),
+            imap_conversations: HashMap::new(),
+            ssh_conversations: HashMap::new(),
+            keylog,
+            fingerprints: Fingerprints::new(fingerprint_database),
+            hasshes: Hasshes::default(),
+            http_exporter,
+            tftp_exporter,
+            descriptors,
+            security_associations,
+            sequences: Sequences::default(),
+            depth: 0,
+        })
+    }
+
+    pub fn dissect(&mut self, packet: &Packet) {
+        self.frame += 1;
+        self.timestamp = packet.timestamp();
+        self.interface = packet.interface().map(String::from);
+        self.source = None;
+        self.addresses = None;
+        self.dissect_link(packet.link_type(), packet.data());
+    }
+
+    /// Prints the summaries asked for on the command line, once the capture is over.
+    pub fn finish(&self) {
+        if self.options.neighbors() {
+            println!("================================================================");
+            println!("NEIGHBORS");
+            println!("{}", self.neighbors);
+        }
+        if self.options.multicast() {
+            println!("================================================================");
+            println!("MULTICAST");
+            println!("{}", self.memberships);
+        }
+        if self.options.associations() {
+            println!("================================================================");
+            println!("SCTP ASSOCIATIONS");
+            println!("{}", self.sctp_associations);
+        }
+        if self.options.leases() {
+            println!("================================================================");
+            println!("DHCP LEASES");
+            println!("{}", self.leases);
+        }
+        if self.options.quic_connections() {
+            println!("================================================================");
+            println!("QUIC CONNECTIONS");
+            println!("{}", self.quic_connections);
+        }
+        if self.options.fingerprints() {
+            println!("================================================================");
+            println!("TLS FINGERPRINTS");
+            println!("{}", self.fingerprints);
+            println!("================================================================");
+            println!("SSH FINGERPRINTS");
+            println!("{}", self.hasshes);
+        }
+    }
+
+    /// Prints the title of a header, marked with the tunnel depth when it was encapsulated.
+    fn heading(&self, title: &str) {
+        if self.depth > 0 {
+            println!("[TUNNEL DEPTH {}] {}", self.depth, title);
+        } else {
+            println!("{}", title);
+        }
+    }
+
+    fn dissect_link(&mut self, link_type: LinkType, data: &[u8]) {
+        if link_type == LinkType::Ethernet {
+            match Frame::new(data) {
+                Some(frame) if self.options.matches(&frame) => {
+                    println!("----------------------------------------------------------------");
+                    self.dissect_frame(&frame);
+                }
+                None if !self.options.filters_frames() => {
+                    println!("----------------------------------------------------------------");
+                    self.truncated("LAYER 2: DATALINK - FRAME", data);
+                }
+                _ => ()
+            }
+            return;
+        }
+        if self.options.filters_frames() {
+            return;
+        }
+
+        println!("----------------------------------------------------------------");
+        self.heading(&format!("LAYER 2: DATALINK - {}", link_type.name().to_uppercase()));
+        match link_type {
+            LinkType::LinuxSll | LinkType::LinuxSll2 => {
+                let header = if link_type == LinkType::LinuxSll { SllHeader::v1(data) } else { SllHeader::v2(data) };
+                println!("{}", header);
+                if let [a, b, c, d, e, f] = header.address() {
+                    self.source = Some(MacAddr::new(*a, *b, *c, *d, *e, *f));
+                }
+                match header.protocol() {
+                    sll::PROTOCOL_8023 => self.dissect_network(network::IPX, header.data()),
+                    sll::PROTOCOL_8022 => self.dissect_llc_header(header.data()),
+                    protocol => self.dissect_network(protocol, header.data())
+                }
+            }
+            LinkType::Null | LinkType::Loop => {
+                let header = if link_type == LinkType::Null { LoopbackHeader::null(data) } else { LoopbackHeader::r#loop(data) };
+                println!("{}", header);
+                match header.ethernet_type() {
+                    Some(frame_type) => self.dissect_network(frame_type, header.data()),
+                    None => println!("unidentified")
+                }
+            }
+            LinkType::Raw => match data.first().map(|byte| byte >> 4) {
+                Some(4) => self.dissect_network(network::IPV4, data),
+                Some(6) => self.dissect_network(network::IPV6, data),
+                _ => println!("unidentified")
+            },
+            LinkType::Ipv4 => self.dissect_network(network::IPV4, data),
+            LinkType::Ipv6 => self.dissect_network(network::IPV6, data),
+            LinkType::Ieee80211Radiotap => {
+                let header = RadiotapHeader::from(data);
+                println!("{}", header);
+                self.dissect_wlan(&WlanFrame::new(header.data(), header.has_fcs()));
+            }
+            LinkType::Ieee80211 => self.dissect_wlan(&WlanFrame::from(data)),
+            LinkType::Ppp => self.dissect_ppp(data),
+            _ => println!("unidentified")
+        }
+    }
+
+    /// Reports a header that ends before its fixed fields do.
+    fn truncated(&self, title: &str, data: &[u8]) {
+        self.heading(title);
+        println!("{} bytes of a truncated header\n", data.len());
+    }
+
+    fn dissect_ethernet(&mut self, data: &[u8]) {
+        match Frame::new(data) {
+            Some(frame) => self.dissect_frame(&frame),
+            None => self.truncated("LAYER 2: DATALINK - FRAME", data)
+        }
+    }
+
+    fn dissect_frame(&mut self, frame: &Frame) {
+        self.source = Some(*frame.source());
+        self.heading("LAYER 2: DATALINK - FRAME");
+        println!("{}", frame);
+        if let Some(llc) = frame.llc() {
+            println!("{}", llc);
+        }
+        match (frame.frame_type(), frame.llc()) {
+            (Some(frame_type), _) => self.dissect_network(frame_type, frame.data()),
+            (None, Some(llc)) => self.dissect_llc(llc, frame.data()),
+            (None, None) => println!("unidentified")
+        }
+    }
+
+    fn dissect_wlan(&mut self, frame: &WlanFrame) {
+        self.heading("IEEE 802.11 - FRAME");
+        println!("{}", frame);
+        self.source = frame.addresses().get(1).copied();
+        if let Some(management) = frame.management() {
+            println!("{}", management);
+        } else if frame.has_llc() {
+            self.dissect_llc_header(frame.data());
+        }
+    }
+
+    /// Dissects a payload that starts with an LLC header, as used outside of ethernet.
+    fn dissect_llc_header(&mut self, data: &[u8]) {
+        let Some(llc) = LlcHeader::new(data) else {
+            self.truncated("LAYER 2: DATALINK - LLC", data);
+            return;
+        };
+        println!("{}", llc);
+        let payload = &data[llc.length()..];
+        match llc.ethernet_type() {
+            Some(frame_type) => self.dissect_network(frame_type, payload),
+            None => self.dissect_llc(&llc, payload)
+        }
+    }
+
+    fn dissect_llc(&mut self, llc: &LlcHeader, data: &[u8]) {
+        match (llc.dsap(), llc.snap()) {
+            (llc::SAP_STP, _) => self.dissect_stp(data),
+            (_, Some(snap)) if snap.oui() == llc::OUI_CISCO && snap.pid() == llc::PID_PVST => {
+                self.dissect_stp(data)
+            }
+            (_, Some(snap)) if snap.oui() == llc::OUI_CISCO && snap.pid() == llc::PID_CDP => {
+                let Some(packet) = CdpPacket::new(data) else {
+                    self.truncated("CISCO DISCOVERY PROTOCOL", data);
+                    return;
+                };
+                self.heading("CISCO DISCOVERY PROTOCOL");
+                println!("{}", packet);
+                if let Some(source) = self.source {
+                    self.neighbors.update(Neighbor::cdp(source, &packet, self.timestamp));
+                }
+            }
+            _ => println!("unidentified")
+        }
+    }
+
+    fn dissect_stp(&mut self, data: &[u8]) {
+        let Some(bpdu) = Bpdu::new(data) else {
+            self.truncated("SPANNING TREE - BPDU", data);
+            return;
+        };
+        self.heading("SPANNING TREE - BPDU");
+        println!("{}", bpdu);
+        if let Some(neighbor) = self.source.and_then(|source| Neighbor::stp(source, &bpdu, self.timestamp)) {
+            self.neighbors.update(neighbor);
+        }
+    }
+
+    fn dissect_ppp(&mut self, data: &[u8]) {
+        let Some(frame) = PppFrame::new(data) else {
+            self.truncated("PPP - FRAME", data);
+            return;
+        };
+        self.heading("PPP - FRAME");
+        println!("{}", frame);
+        match frame.protocol() {
+            ppp::IPV4 => self.dissect_network(network::IPV4, frame.data()),
+            ppp::IPV6 => self.dissect_network(network::IPV6, frame.data()),
+            ppp::LCP | ppp::IPCP | ppp::IPV6CP => {
+                println!("{}", ControlPacket::new(frame.protocol(), frame.data()));
+            }
+            _ => println!("unidentified")
+        }
+    }
+
+    fn dissect_network(&mut self, frame_type: EthernetType, data: &[u8]) {
+        if frame_type == network::LLDP {
+            let packet = LldpPacket::from(data);
+            self.heading("LINK LAYER DISCOVERY PROTOCOL");
+            println!("{}", packet);
+            if let Some(source) = self.source {
+                self.neighbors.update(Neighbor::lldp(source, &packet, self.timestamp));
+            }
+            return;
+        }
+
+        match frame_type {
+            network::WOL => {
+                self.heading("WAKE-ON-LAN - MAGIC PACKET");
+                match MagicPacket::find(data) {
+                    Some(packet) => println!("{}", packet),
+                    None => println!("unidentified")
+                }
+                return;
+            }
+            network::PTP => {
+                self.dissect_ptp(data);
+                return;
+            }
+            network::GOOSE => {
+                self.heading("IEC 61850 - GOOSE");
+                match GooseMessage::new(data) {
+                    Some(message) => println!("{}", message),
+                    None => println!("unidentified")
+                }
+                return;
+            }
+            network::MPLS | network::MPLS_MULTICAST => {
+                let packet = MplsPacket::from(data);
+                self.heading("MPLS - LABEL STACK");
+                println!("{}", packet);
+                match packet.payload_type() {
+                    Some(payload_type) => self.dissect_network(payload_type, packet.data()),
+                    None => println!("unidentified")
+                }
+                return;
+            }
+            network::PPPOE_DISCOVERY | network::PPPOE_SESSION => {
+                let heading = if frame_type == network::PPPOE_SESSION { "PPPOE - SESSION" } else { "PPPOE - DISCOVERY" };
+                let Some(packet) = PppoePacket::new(data) else {
+                    self.truncated(heading, data);
+                    return;
+                };
+                self.heading(heading);
+                println!("{}", packet);
+                if packet.code() == pppoe::CODE_SESSION {
+                    self.dissect_ppp(packet.data());
+                }
+                return;
+            }
+            _ => ()
+        }
+
+        self.heading("LAYER 3: NETWORK - PACKET");
+        match frame_type {
+            network::ARP => {
+                println!("{}", ARPPacket::from(data));
+            }
+            network::IPV4 => {
+                let packet = Ipv4Packet::from(data);
+                println!("{}", packet);
+                self.addresses = Some((IpAddr::V4(packet.source()), IpAddr::V4(packet.destination())));
+                self.dissect_transport(packet.protocol(), packet.data());
+            }
+            network::IPV6 => {
+                let packet = Ipv6Packet::from(data);
+                println!("{}", packet);
+                self.addresses = Some((IpAddr::V6(packet.source()), IpAddr::V6(packet.destination())));
+                self.dissect_transport(packet.next_header(), packet.data());
+            }
+            network::IPX => {
+                println!("{}", IpxPacket::from(data));
+            }
+            _ => println!("unidentified")
+        };
+    }
+
+    fn dissect_transport(&mut self, protocol: Protocol, data: &[u8]) {
+        self.heading("LAYER 4: SESSION - SEGMENT");
+        match protocol {
+            transport::TCP => {
+                let Some(segment) = TcpSegment::new(data) else {
+                    println!("unidentified");
+                    return;
+                };
+                println!("{}", segment);
+                if let Some((source, destination)) = self.addresses {
+                    let source = SocketAddr::new(source, segment.source());
+                    let destination = SocketAddr::new(destination, segment.destination());
+                    let update = self.tcp_streams.update(source, destination, &segment);
+                    if update.gap > 0 {
+                        println!("stream {} gap: {} bytes missing", update.stream, update.gap);
+                    }
+                    self.dissect_tcp_stream(&update);
+                }
+                if segment.source() == 53 {
+                    self.heading("LAYER 5,6,7 - APPLICATION - DATA");
+                    stdout().write_all(segment.data().get(16..).unwrap_or_default()).unwrap();
+                }
+            }
+            transport::UDP => {
+                let Some(segment) = UDPSegment::new(data) else {
+                    println!("unidentified");
+                    return;
+                };
+                println!("{}", segment);
+                self.dissect_udp(&segment);
+            }
+            transport::IPIP => self.dissect_tunnel(|this| this.dissect_encapsulated(network::IPV4, data)),
+            transport::IPV6 => self.dissect_tunnel(|this| this.dissect_encapsulated(network::IPV6, data)),
+            transport::GRE => {
+                let Some(packet) = GrePacket::new(data) else {
+                    self.truncated("GRE - TUNNEL", data);
+                    return;
+                };
+                self.heading("GRE - TUNNEL");
+                println!("{}", packet);
+                self.dissect_tunnel(|this| this.dissect_gre(&packet));
+            }
+            transport::AH => {
+                let Some(header) = AhHeader::new(data) else {
+                    self.truncated("IPSEC - AUTHENTICATION HEADER", data);
+                    return;
+                };
+                self.heading("IPSEC - AUTHENTICATION HEADER");
+                println!("{}", header);
+                if let Some((_, destination)) = self.addresses {
+                    if let Some(gap) = self.sequences.update(destination, protocol, header.spi(), header.sequence_number()) {
+                        println!("{}", gap);
+                    }
+                }
+                self.dissect_transport(header.next_header(), header.data());
+            }
+            transport::ESP => {
+                let Some(packet) = EspPacket::new(data) else {
+                    self.truncated("IPSEC - ENCAPSULATING SECURITY PAYLOAD", data);
+                    return;
+                };
+                self.heading("IPSEC - ENCAPSULATING SECURITY PAYLOAD");
+                println!("{}", packet);
+                if let Some((_, destination)) = self.addresses {
+                    if let Some(gap) = self.sequences.update(destination, protocol, packet.spi(), packet.sequence_number()) {
+                        println!("{}", gap);
+                    }
+                }
+                if let Some(association) = self.security_associations.get(packet.spi()) {
+                    match association.decrypt(&packet) {
+                        Some(payload) => {
+                            self.heading("IPSEC - DECRYPTED PAYLOAD");
+                            println!("{}", payload);
+                            self.dissect_transport(payload.next_header(), payload.data());
+                        }
+                        None => println!("decryption failed")
+                    }
+                }
+            }
+            transport::SCTP => {
+                let Some(packet) = SctpPacket::new(data) else {
+                    println!("unidentified");
+                    return;
+                };
+                println!("{}", packet);
+                let Some((source, destination)) = self.addresses else {
+                    return;
+                };
+                let association = self.sctp_associations.update(source, destination, &packet, self.timestamp);
+                println!("association {}: {}", association.id(), association.state());
+                let association = association.id();
+                for chunk in packet.chunks() {
+                    self.dissect_sctp_data(association, SocketAddr::new(source, packet.source()), chunk);
+                }
+            }
+            transport::IGMP => match IgmpMessage::new(data) {
+                Some(message) => {
+                    self.heading("IGMP - MESSAGE");
+                    println!("{}", message);
+                    if let Some((IpAddr::V4(host), _)) = self.addresses {
+                        let interface = self.interface.as_deref().unwrap_or("-");
+                        self.memberships.update_igmp(interface, host, &message, self.timestamp);
+                    }
+                }
+                None => println!("unidentified")
+            },
+            transport::HOP_BY_HOP if data.len() >= 2 => {
+                // Skip the options, MLD uses them only for the router alert
+                let length = (data[1] as usize + 1) * 8;
+                self.heading("IPV6 - HOP-BY-HOP OPTIONS");
+                println!("next_header {}, {} bytes", data[0], length);
+                self.dissect_transport(data[0], data.get(length..).unwrap_or_default());
+            }
+            transport::IPV6_ICMP => match MldMessage::new(data) {
+                Some(message) => {
+                    self.heading("MLD - MESSAGE");
+                    println!("{}", message);
+                    if let Some((IpAddr::V6(host), _)) = self.addresses {
+                        let interface = self.interface.as_deref().unwrap_or("-");
+                        self.memberships.update_mld(interface, host, &message, self.timestamp);
+                    }
+                }
+                None if data.len() >= 8 => println!("{}", ICMPSegment::from(data)),
+                None => println!("unidentified")
+            },
+            transport::ICMP => {
+                let segment = ICMPSegment::from(data);
+                println!("{}", segment);
+                self.heading("LAYER 5,6,7 - APPLICATION - DATA");
+                stdout().write_all(data.get(40..).unwrap_or_default()).unwrap();
+                println!();
+            }
+            _ => println!("unidentified")
+        }
+    }
+
+    fn dissect_udp(&mut self, segment: &UDPSegment) {
+        self.heading("LAYER 5,6,7 - APPLICATION - DATA");
+        let ports = [segment.source(), segment.destination()];
+        if segment.destination() == vxlan::PORT {
+            let Some(header) = VxlanHeader::new(segment.data()) else {
+                self.truncated("VXLAN - TUNNEL", segment.data());
+                return;
+            };
+            self.heading("VXLAN - TUNNEL");
+            println!("{}", header);
+            self.dissect_tunnel(|this| this.dissect_ethernet(header.data()));
+        } else if segment.destination() == geneve::PORT {
+            let Some(header) = GeneveHeader::new(segment.data()) else {
+                self.truncated("GENEVE - TUNNEL", segment.data());
+                return;
+            };
+            self.heading("GENEVE - TUNNEL");
+            println!("{}", header);
+            self.dissect_tunnel(|this| this.dissect_encapsulated(header.protocol_type(), header.data()));
+        } else if ports.contains(&quic::PORT) {
+            self.dissect_quic(segment);
+        } else if ports.contains(&tftp::PORT) || self.is_tftp_transfer(segment) {
+            self.dissect_tftp(segment);
+        } else if ports.contains(&dhcp::SERVER_PORT) || ports.contains(&dhcp::CLIENT_PORT) {
+            if let Some(message) = DhcpMessage::new(segment.data()) {
+                self.heading("DHCP - MESSAGE");
+                println!("{}", message);
+                let source = self.addresses.map(|(source, _)| source);
+                self.leases.update_v4(&message, source, self.timestamp);
+            }
+        } else if ports.contains(&dhcpv6::SERVER_PORT) {
+            self.dissect_dhcpv6(segment.data());
+        } else if ports.contains(&ntp::PORT) {
+            self.dissect_ntp(segment.data());
+        } else if ports.contains(&ptp::EVENT_PORT) || ports.contains(&ptp::GENERAL_PORT) {
+            self.dissect_ptp(segment.data());
+        } else if segment.destination() == syslog::PORT {
+            self.dissect_syslog(segment.data());
+        } else if ports.contains(&snmp::PORT) || ports.contains(&snmp::TRAP_PORT) {
+            self.dissect_snmp(segment.data());
+        } else if segment.source() == 53 {
+            println!("{}", DNSQuery::from(segment.data()));
+        } else if ports.contains(&wol::PORT_ECHO) || ports.contains(&wol::PORT_DISCARD) {
+            if let Some(packet) = MagicPacket::find(segment.data()) {
+                self.heading("WAKE-ON-LAN - MAGIC PACKET");
+                println!("{}", packet);
+            }
+        }
+    }
+
+    /// Prints an NTP packet, and the offset and delay of the exchange a response completes.
+    fn dissect_ntp(&mut self, data: &[u8]) {
+        let Some(packet) = NtpPacket::new(data) else {
+            println!("unidentified");
+            return;
+        };
+        self.heading("NTP - PACKET");
+        println!("{}", packet);
+        let Some((source, destination)) = self.addresses else {
+            return;
+        };
+        if let Some(exchange) = self.ntp_exchanges.update(&packet, source, destination, self.timestamp) {
+            self.heading("NTP - EXCHANGE");
+            println!("{}", exchange);
+        }
+    }
+
+    /// Prints a PTP message, and the offset and delay of the exchange a Delay_Resp completes.
+    fn dissect_ptp(&mut self, data: &[u8]) {
+        let Some(message) = PtpMessage::new(data) else {
+            println!("unidentified");
+            return;
+        };
+        self.heading("PTP - MESSAGE");
+        println!("{}", message);
+        if let Some(exchange) = self.ptp_exchanges.update(&message, self.timestamp) {
+            self.heading("PTP - EXCHANGE");
+            println!("{}", exchange);
+        }
+    }
+
+    /// The source and destination endpoints of a UDP segment, None when the network layer
+    /// carried no IP addresses.
+    fn endpoints(&self, segment: &UDPSegment) -> Option<(SocketAddr, SocketAddr)> {
+        let (source, destination) = self.addresses?;
+        Some((SocketAddr::new(source, segment.source()), SocketAddr::new(destination, segment.destination())))
+    }
+
+    /// Whether a segment belongs to a TFTP transfer the server answers from its own port.
+    fn is_tftp_transfer(&self, segment: &UDPSegment) -> bool {
+        self.endpoints(segment)
+            .is_some_and(|(source, destination)| self.tftp_transfers.contains(source, destination))
+    }
+
+    /// Prints a TFTP packet, and the transfer it completes, exporting its file.
+    fn dissect_tftp(&mut self, segment: &UDPSegment) {
+        let Some(packet) = TftpPacket::new(segment.data()) else {
+            println!("unidentified");
+            return;
+        };
+        self.heading("TFTP - PACKET");
+        println!("{}", packet);
+        let Some((source, destination)) = self.endpoints(segment) else {
+            return;
+        };
+        let Some(transfer) = self.tftp_transfers.update(&packet, source, destination, self.timestamp) else {
+            return;
+        };
+        self.heading("TFTP - TRANSFER");
+        println!("{}", transfer);
+        if let Some(exporter) = self.tftp_exporter.as_mut() {
+            match exporter.export_tftp(&transfer) {
+                Ok(Some(file_name)) => println!("exported to {}", file_name),
+                Ok(None) => (),
+                Err(e) => eprintln!("An error occurred when exporting an object: {}", e)
+            }
+        }
+    }
+
+    fn dissect_syslog(&mut self, data: &[u8]) {
+        match SyslogMessage::new(data) {
+            Some(message) => {
+                self.heading("SYSLOG - MESSAGE");
+                println!("{}", message);
+            }
+            None => println!("unidentified")
+        }
+    }
+
+    /// Prints an SNMP message, or the BER elements of one that is not well formed.
+    fn dissect_snmp(&mut self, data: &[u8]) {
+        if let Some(message) = SnmpMessage::new(data) {
+            self.heading("SNMP - MESSAGE");
+            println!("{}", message);
+        } else if let Some((element, _)) = Element::parse(data) {
+            self.heading("SNMP - BER ELEMENTS");
+            println!("{}", element);
+        } else {
+            println!("unidentified");
+        }
+    }
+
+    /// Prints a DHCPv6 message and the messages the relay agents wrapped it around.
+    fn dissect_dhcpv6(&mut self, data: &[u8]) {
+        let mut message = Dhcpv6Message::new(data);
+        while let Some(current) = message {
+            self.heading(if current.is_relay() { "DHCPV6 - RELAY MESSAGE" } else { "DHCPV6 - MESSAGE" });
+            println!("{}", current);
+            if !current.is_relay() {
+                self.leases.update_v6(&current, self.timestamp);
+            }
+            message = current.relay_message();
+        }
+    }
+
+    /// Dissects the packet carried by a tunnel one level deeper than its outer headers.
+    fn dissect_tunnel<F: FnOnce(&mut Self)>(&mut self, dissect: F) {
+        self.depth += 1;
+        dissect(self);
+        self.depth -= 1;
+    }
+
+    fn dissect_gre(&mut self, packet: &GrePacket) {
+        match packet.protocol_type() {
+            tunnel::ERSPAN_TYPE_II | tunnel::ERSPAN_TYPE_III => {
+                let Some(header) = ErspanHeader::new(packet.protocol_type(), packet.data()) else {
+                    self.truncated("ERSPAN - MIRRORED FRAME", packet.data());
+                    return;
+                };
+                self.heading("ERSPAN - MIRRORED FRAME");
+                println!("{}", header);
+                self.dissect_ethernet(header.data());
+            }
+            tunnel::PPP => self.dissect_ppp(packet.data()),
+            protocol_type => self.dissect_encapsulated(protocol_type, packet.data())
+        }
+    }
+
+    /// Dissects a tunnel payload announced by an ethernet type, which may be a whole frame.
+    /// Inner IP packets too short for their header are reported as truncated.
+    fn dissect_encapsulated(&mut self, protocol_type: EthernetType, data: &[u8]) {
+        if protocol_type == tunnel::TRANSPARENT_ETHERNET_BRIDGING {
+            self.dissect_ethernet(data);
+        } else if network::has_ip_header(protocol_type, data) {
+            self.dissect_network(protocol_type, data);
+        } else {
+            self.truncated("LAYER 3: NETWORK - PACKET", data);
+        }
+    }
+
+    /// Hands the user data of a DATA chunk to the dissector of its payload protocol, once all
+    /// the fragments of its message are there.
+    fn dissect_sctp_data(&mut self, association: usize, source: SocketAddr, chunk: &Chunk) {
+        let Some(length) = chunk.user_data().map(|data| data.len()) else {
+            return;
+        };
+        self.heading("LAYER 5,6,7 - APPLICATION - DATA");
+        let Some((ppid, data)) = self.sctp_associations.reassemble(association, source, chunk) else {
+            println!("fragment of {} bytes", length);
+            return;
+        };
+        if !chunk.unfragmented() {
+            println!("reassembled message of {} bytes", data.len());
+        }
+        match ppid {
+            sctp::PPID_DIAMETER => match DiameterMessage::new(&data) {
+                Some(message) => {
+                    self.heading("DIAMETER - MESSAGE");
+                    println!("{}", message);
+                }
+                None => println!("unidentified")
+            },
+            _ => println!("unidentified")
+        }
+    }
+
+    /// Dissects the packets coalesced in a QUIC datagram, with the TLS hellos of the Initials.
+    fn dissect_quic(&mut self, segment: &UDPSegment) {
+        let Some((source, destination)) = self.addresses else {
+            return;
+        };
+        let source = SocketAddr::new(source, segment.source());
+        let destination = SocketAddr::new(destination, segment.destination());
+        let mut data = segment.data();
+        while !data.is_empty() {
+            let short_dcid_length = self.quic_connections.short_dcid_length(source, destination, data);
+            let Some((mut packet, length)) = QuicPacket::parse(data, short_dcid_length) else {
+                println!("unidentified");
+                return;
+            };
+            data = &data[length..];
+            let update = self.quic_connections.update(source, destination, &mut packet, self.timestamp);
+            let (connection, hellos) = match update {
+                Some((connection, hellos)) => (Some(connection.id()), hellos),
+                None => (None, Vec::new())
+            };
+            self.heading("QUIC - PACKET");
+            println!("{}", packet);
+            if let Some(connection) = connection {
+                println!("connection {}", connection);
+            }
+            for hello in hellos.iter() {
+                self.dissect_hello(hello, true);
+            }
+        }
+    }
+
+    /// Hands the bytes a segment added to its stream to the dissector of the server port.
+    fn dissect_tcp_stream(&mut self, update: &StreamData) {
+        let Some(stream) = self.tcp_streams.get(update.stream) else {
+            return;
+        };
+        let (client_port, server_port) = (stream.client().port(), stream.server().port());
+        if let Some(control) = self.ftp_data_endpoints.remove(&stream.server()) {
+            self.ftp_data_transfers.insert(update.stream, FtpDataTransfer::new(control));
+        }
+        // After STARTTLS and the like, the TLS records take over the stream
+        let encrypted = self.tls_conversations.contains_key(&update.stream);
+        // Cleartext HTTP/2 with prior knowledge, on whatever port, starts with the preface
+        if self.ftp_data_transfers.contains_key(&update.stream) {
+            self.dissect_ftp_data(update);
+        } else if server_port == ftp::PORT && !encrypted {
+            self.dissect_ftp(update.stream, update.direction, &update.data);
+        } else if smtp::PORTS.contains(&server_port) && !encrypted {
+            self.dissect_smtp(update.stream, update.direction, &update.data);
+        } else if server_port == pop3::PORT && !encrypted {
+            self.dissect_pop3(update.stream, update.direction, &update.data);
+        } else if server_port == imap::PORT && !encrypted {
+            self.dissect_imap(update.stream, update.direction, &update.data);
+        } else if server_port == ssh::PORT {
+            self.dissect_ssh(update.stream, update.direction, &update.data);
+        } else if self.http2_connections.contains_key(&update.stream)
+            || (update.direction == 0 && update.data.starts_with(http2::PREFACE)) {
+            self.dissect_http2(update.stream, update.direction, &update.data);
+        } else if server_port == syslog::PORT {
+            // Only the client sends messages
+            if update.direction == 0 {
+                let messages = self.syslog_streams.entry(update.stream).or_default().push(&update.data, update.finished);
+                for message in messages {
+                    self.dissect_syslog(&message);
+                }
+            }
+        } else if server_port == telnet::PORT {
+            let segment = self.telnet_streams.entry(update.stream).or_default().push(update.direction, &update.data);
+            if !segment.is_empty() {
+                self.heading("TELNET - DATA");
+                println!("stream {} {}", update.stream, if update.direction == 0 { "client" } else { "server" });
+                println!("{}", segment);
+            }
+        } else if http::PORTS.contains(&server_port) || http::PORTS.contains(&client_port) {
+            // Requests go to the HTTP port, whichever side the stream took for the client
+            let direction = if http::PORTS.contains(&server_port) { update.direction } else { 1 - update.direction };
+            self.dissect_http(update.stream, direction, &update.data, update.finished);
+        } else if tls::PORTS.contains(&server_port)
+            || self.tls_conversations.contains_key(&update.stream)
+            || TlsRecord::looks_like(&update.data) {
+            let conversation = self.tls_conversations.entry(update.stream).or_default();
+            let records = conversation.push(update.direction, &update.data, self.keylog.as_ref());
+            // Without ALPN, only the usual HTTPS ports are taken to carry HTTP/1.x
+            let (http, http2) = match conversation.protocol() {
+                Some(protocol) => (protocol == "http/1.1" || protocol == "http/1.0", protocol == "h2"),
+                None => (tls::HTTPS_PORTS.contains(&server_port), false)
+            };
+            for (record, messages) in records {
+                self.heading("TLS - RECORD");
+                println!("stream {}", update.stream);
+                println!("{}", record);
+                for message in messages.iter() {
+                    self.dissect_handshake(message);
+                }
+                if let Some((tls::APPLICATION_DATA, plaintext)) = record.content() {
+                    if http {
+                        self.dissect_http(update.stream, update.direction, plaintext, update.finished);
+                    } else if http2 {
+                        self.dissect_http2(update.stream, update.direction, plaintext);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Prints the commands and replies of an FTP control connection, noting the data connections
+    /// they announce.
+    fn dissect_ftp(&mut self, stream: usize, direction: usize, data: &[u8]) {
+        let Some(server) = self.tcp_streams.get(stream).map(|stream| stream.server()) else {
+            return;
+        };
+        let conversation = self.ftp_conversations.entry(stream).or_default();
+        let messages = conversation.push(direction, data);
+        let encrypted = conversation.is_encrypted();
+        for message in messages {
+            let endpoint = match message {
+                FtpMessage::Command(command) => {
+                    self.heading("FTP - COMMAND");
+                    println!("stream {}", stream);
+                    println!("{}", command);
+                    match command.verb() {
+                        "PORT" => ftp::port_endpoint(command.argument()),
+                        "EPRT" => ftp::extended_port_endpoint(command.argument()),
+                        _ => None
+                    }
+                }
+                FtpMessage::Reply(reply) => {
+                    self.heading("FTP - REPLY");
+                    println!("stream {}", stream);
+                    println!("{}", reply);
+                    ftp::passive_endpoint(&reply, server.ip())
+                }
+            };
+            if let Some(endpoint) = endpoint {
+                println!("data connection expected to {}\n", endpoint);
+                self.ftp_data_endpoints.insert(endpoint, stream);
+            }
+        }
+        if encrypted {
+            self.start_tls(stream, "FTP - AUTH TLS");
+        }
+    }
+
+    /// Counts the bytes of an FTP data connection, printing the transfer when it ends, named
+    /// after the last transfer command of its control connection.
+    fn dissect_ftp_data(&mut self, update: &StreamData) {
+        let Some(transfer) = self.ftp_data_transfers.get_mut(&update.stream) else {
+            return;
+        };
+        transfer.push(&update.data);
+        if !update.finished {
+            return;
+        }
+        let mut transfer = self.ftp_data_transfers.remove(&update.stream).unwrap();
+        let command = self.ftp_conversations.get(&transfer.control()).and_then(FtpConversation::transfer);
+        transfer.finish(command);
+        self.heading("FTP-DATA - TRANSFER");
+        println!("stream {}", update.stream);
+        println!("{}", transfer);
+    }
+
+    /// Prints the commands and replies of an SMTP connection and the mails it carries.
+    fn dissect_smtp(&mut self, stream: usize, direction: usize, data: &[u8]) {
+        let conversation = self.smtp_conversations.entry(stream).or_default();
+        let messages = conversation.push(direction, data);
+        let encrypted = conversation.is_encrypted();
+        for message in messages {
+            match message {
+                SmtpMessage::Command(command) => {
+                    self.heading("SMTP - COMMAND");
+                    println!("stream {}", stream);
+                    println!("{}", command);
+                }
+                SmtpMessage::Reply(reply, command) => {
+                    self.heading("SMTP - REPLY");
+                    match command {
+                        Some(command) => println!("stream {} answering {}", stream, command.verb()),
+                        None => println!("stream {}", stream)
+                    }
+                    println!("{}", reply);
+                }
+                SmtpMessage::Mail(mail) => {
+                    self.heading("SMTP - MAIL");
+                    println!("stream {}", stream);
+                    println!("{}", mail);
+                }
+            }
+        }
+        if encrypted {
+            self.start_tls(stream, "SMTP - STARTTLS");
+        }
+    }
+
+    fn dissect_pop3(&mut self, stream: usize, direction: usize, data: &[u8]) {
+        let conversation = self.pop3_conversations.entry(stream).or_default();
+        let messages = conversation.push(direction, data);
+        let encrypted = conversation.is_encrypted();
+        for message in messages {
+            match message {
+                Pop3Message::Command(command) => {
+                    self.heading("POP3 - COMMAND");
+                    println!("stream {}", stream);
+                    println!("{}", command);
+                }
+                Pop3Message::Response(response) => {
+                    self.heading("POP3 - RESPONSE");
+                    println!("stream {}", stream);
+                    println!("{}", response);
+                }
+            }
+        }
+        if encrypted {
+            self.start_tls(stream, "POP3 - STLS");
+        }
+    }
+
+    fn dissect_imap(&mut self, stream: usize, direction: usize, data: &[u8]) {
+        let conversation = self.imap_conversations.entry(stream).or_default();
+        let messages = conversation.push(direction, data);
+        let encrypted = conversation.is_encrypted();
+        for message in messages {
+            match message {
+                ImapMessage::Command(command) => {
+                    self.heading("IMAP - COMMAND");
+                    println!("stream {}", stream);
+                    println!("{}", command);
+                }
+                ImapMessage::Response(response) => {
+                    self.heading("IMAP - RESPONSE");
+                    println!("stream {}", stream);
+                    println!("{}", response);
+                }
+                ImapMessage::Continuation(line) => {
+                    self.heading("IMAP - AUTHENTICATION DATA");
+                    println!("stream {}", stream);
+                    println!("{}\n", line);
+                }
+            }
+        }
+        if encrypted {
+            self.start_tls(stream, "IMAP - STARTTLS");
+        }
+    }
+
+    fn dissect_ssh(&mut self, stream: usize, direction: usize, data: &[u8]) {
+        let side = if direction == 0 { "client" } else { "server" };
+        let events = self.ssh_conversations.entry(stream).or_default().push(direction, data);
+        for event in events {
+            match event {
+                SshEvent::Identification(identification) => {
+                    self.heading("SSH - IDENTIFICATION");
+                    println!("stream {} {}", stream, side);
+                    println!("{}", identification);
+                }
+                SshEvent::Packet(packet) => {
+                    self.heading("SSH - PACKET");
+                    println!("stream {} {}", stream, side);
+                    println!("{}", packet);
+                }
+                SshEvent::Hassh(hassh) => {
+                    self.hasshes.update(&hassh);
+                    self.heading("SSH - FINGERPRINT");
+                    println!("stream {} {}", stream, side);
+                    println!("{}", hassh);
+                }
+                SshEvent::Encrypted => {
+                    self.heading("SSH - ENCRYPTED");
+                    println!("stream {} {} packets are encrypted from here on\n", stream, side);
+                }
+            }
+        }
+    }
+
+    /// Hands a stream that switched to TLS over to the TLS dissection.
+    fn start_tls(&mut self, stream: usize, heading: &str) {
+        if self.tls_conversations.contains_key(&stream) {
+            return;
+        }
+        self.tls_conversations.insert(stream, TlsConversation::default());
+        self.heading(heading);
+        println!("stream {} continues over TLS\n", stream);
+    }
+
+    /// Adds the bytes of a stream to its HTTP conversation, printing and exporting the messages
+    /// they complete. Direction 0 carries the requests.
+    fn dissect_http(&mut self, stream: usize, direction: usize, data: &[u8], finished: bool) {
+        let conversation = self.http_conversations.entry(stream).or_default();
+        let messages = conversation.push(direction, data, finished, self.timestamp, self.frame);
+        for message in messages {
+            self.heading(if message.is_request() { "HTTP - REQUEST" } else { "HTTP - RESPONSE" });
+            println!("stream {}", stream);
+            println!("{}", message);
+            if let (Some(exporter), false) = (self.http_exporter.as_mut(), message.is_request()) {
+                match exporter.export(&message) {
+                    Ok(Some(file_name)) => println!("exported to {}", file_name),
+                    Ok(None) => (),
+                    Err(e) => eprintln!("An error occurred when exporting an object: {}", e)
+                }
+            }
+        }
+    }
+
+    /// Adds the bytes of a stream to its HTTP/2 connection, printing the frames, the decoded
+    /// header blocks and the messages they complete. Direction 0 is the client.
+    fn dissect_http2(&mut self, stream: usize, direction: usize, data: &[u8]) {
+        let connection = self.http2_connections.entry(stream).or_default();
+        for event in connection.push(direction, data, self.timestamp, self.frame) {
+            match event {
+                Http2Event::Preface => {
+                    self.heading("HTTP/2 - CONNECTION PREFACE");
+                    println!("stream {}\n", stream);
+                }
+                Http2Event::Frame(frame) => {
+                    self.heading("HTTP/2 - FRAME");
+                    println!("{}", frame);
+                }
+                Http2Event::Headers(headers) => {
+                    self.heading("HTTP/2 - HEADER BLOCK");
+                    println!("{}", headers);
+                }
+                Http2Event::Message(message) => {
+                    self.heading(if message.is_request() { "HTTP/2 - REQUEST" } else { "HTTP/2 - RESPONSE" });
+                    println!("stream {}", stream);
+                    println!("{}", message);
+                    if grpc::is_grpc(message.header("content-type").unwrap_or_default()) {
+                        self.dissect_grpc(&message);
+                    }
+                }
+                Http2Event::Error(e) => {
+                    self.heading("HTTP/2 - ERROR");
+                    println!("{}\n", e);
+                }
+            }
+        }
+    }
+
+    /// Prints the messages of a gRPC request or response, typed by the method its path names when
+    /// a descriptor set has it, and the status the response ends with.
+    fn dissect_grpc(&mut self, message: &Http2Message) {
+        let method = message.path().and_then(|path| self.descriptors.method(path));
+        let type_name = method.map(|(input, output)| if message.is_request() { input } else { output });
+        let (messages, rest) = grpc::messages(message.body(), message.header("grpc-encoding"), &self.descriptors, type_name);
+        for grpc_message in messages {
+            self.heading("GRPC - MESSAGE");
+            println!("stream_id {}", message.stream_id());
+            println!("{}", grpc_message);
+        }
+        if !rest.is_empty() {
+            println!("{} bytes of a truncated message\n", rest.len());
+        }
+        if let (false, Some(status)) = (message.is_request(), GrpcStatus::new(message)) {
+            self.heading("GRPC - STATUS");
+            println!("stream_id {}", message.stream_id());
+            println!("{}", status);
+        }
+    }
+
+    /// Prints a TLS handshake message, decoding the hellos and the certificates.
+    fn dissect_handshake(&mut self, message: &HandshakeMessage) {
+        if let Some(hello) = Hello::new(message) {
+            self.dissect_hello(&hello, false);
+        } else if message.msg_type() == tls::CERTIFICATE {
+            for certificate in tls::certificates(message) {
+                self.heading("TLS - CERTIFICATE");
+                println!("{}", certificate);
+            }
+        } else {
+            self.heading("TLS - HANDSHAKE");
+            println!("{} ({} bytes)", message.name(), message.body().len());
+        }
+    }
+
+    /// Prints a TLS hello with its fingerprints, carried over QUIC when `quic` is set.
+    fn dissect_hello(&mut self, hello: &Hello, quic: bool) {
+        self.heading(if hello.is_client() { "TLS - CLIENT HELLO" } else { "TLS - SERVER HELLO" });
+        println!("{}", hello);
+        let fingerprint = self.fingerprints.update(Fingerprint::new(hello, quic));
+        self.heading("TLS - FINGERPRINT");
+        println!("{}", fingerprint);
+    }
+}
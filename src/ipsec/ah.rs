@@ -0,0 +1,78 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table};
+use crate::transport::Protocol;
+use crate::util::*;
+
+/// An authentication header, followed by the protected transport or network packet.
+#[derive(Debug)]
+pub struct AhHeader {
+    next_header: Protocol,
+    payload_length: u8,
+    spi: u32,
+    sequence_number: u32,
+    icv: Vec<u8>,
+    packet_data: Vec<u8>
+}
+
+impl AhHeader {
+    /// Parses an authentication header, or returns `None` if the fixed fields or the ICV are cut off.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        let length = (*data.get(1)? as usize + 2) * 4;
+        if length < 12 || data.len() < length {
+            return None;
+        }
+        Some(Self {
+            next_header: data[0],
+            payload_length: data[1],
+            spi: tou32(&data[4..8]),
+            sequence_number: tou32(&data[8..12]),
+            icv: Vec::from(&data[12..length]),
+            packet_data: Vec::from(&data[length..])
+        })
+    }
+
+    #[inline]
+    pub fn next_header(&self) -> Protocol {
+        self.next_header
+    }
+
+    #[inline]
+    pub fn spi(&self) -> u32 {
+        self.spi
+    }
+
+    #[inline]
+    pub fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+
+    /// The length of the whole header, which counts 4 byte words minus two.
+    #[inline]
+    pub fn length(&self) -> usize {
+        (self.payload_length as usize + 2) * 4
+    }
+}
+
+impl DataContainer for AhHeader {
+    fn data(&self) -> &[u8] {
+        self.packet_data.as_slice()
+    }
+}
+
+impl Display for AhHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["next_header", self.next_header],
+            ["length", format!("{} bytes", self.length())],
+            ["spi", format!("{:08X}", self.spi)],
+            ["sequence_number", self.sequence_number],
+            ["icv", hex(&self.icv)]
+        );
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
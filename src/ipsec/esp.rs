@@ -0,0 +1,141 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table, row};
+use crate::transport::Protocol;
+use crate::util::*;
+
+/// An encapsulating security payload packet, its payload still encrypted.
+#[derive(Debug)]
+pub struct EspPacket {
+    spi: u32,
+    sequence_number: u32,
+    packet_data: Vec<u8>
+}
+
+impl EspPacket {
+    /// Parses an ESP packet, or returns `None` if the SPI or sequence number is cut off.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        Some(Self {
+            spi: tou32(data.get(0..4)?),
+            sequence_number: tou32(data.get(4..8)?),
+            packet_data: Vec::from(&data[8..])
+        })
+    }
+
+    #[inline]
+    pub fn spi(&self) -> u32 {
+        self.spi
+    }
+
+    #[inline]
+    pub fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+}
+
+impl DataContainer for EspPacket {
+    fn data(&self) -> &[u8] {
+        self.packet_data.as_slice()
+    }
+}
+
+impl Display for EspPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["spi", format!("{:08X}", self.spi)],
+            ["sequence_number", self.sequence_number],
+            ["payload_length", format!("{} bytes", self.packet_data.len())]
+        );
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+/// The decrypted payload of an ESP packet, stripped of its padding and trailer.
+#[derive(Debug)]
+pub struct EspPayload {
+    next_header: Protocol,
+    pad_length: u8,
+    icv: Vec<u8>,
+    /// Whether the integrity check value matched, unknown without authentication
+    icv_valid: Option<bool>,
+    packet_data: Vec<u8>
+}
+
+impl EspPayload {
+    /// Strips the padding and trailer from the plaintext, None when they do not fit in it.
+    pub fn new(plaintext: &[u8], icv: &[u8], icv_valid: Option<bool>) -> Option<Self> {
+        // The trailer ends with the pad length and the next header
+        let [.., pad_length, next_header] = *plaintext else {
+            return None;
+        };
+        let end = plaintext.len().checked_sub(2 + pad_length as usize)?;
+        Some(Self {
+            next_header,
+            pad_length,
+            icv: Vec::from(icv),
+            icv_valid,
+            packet_data: Vec::from(&plaintext[..end])
+        })
+    }
+
+    #[inline]
+    pub fn next_header(&self) -> Protocol {
+        self.next_header
+    }
+}
+
+impl DataContainer for EspPayload {
+    fn data(&self) -> &[u8] {
+        self.packet_data.as_slice()
+    }
+}
+
+impl Display for EspPayload {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["next_header", self.next_header],
+            ["pad_length", self.pad_length],
+            ["icv", hex(&self.icv)]
+        );
+        match self.icv_valid {
+            Some(valid) => table.add_row(row!["icv_status", if valid { "valid" } else { "invalid" }]),
+            None => table.add_row(row!["icv_status", "unchecked"])
+        };
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_trailer() {
+        let payload = EspPayload::new(&[0xaa, 0xbb, 1, 2, 2, 17], &[0xcc], Some(false)).unwrap();
+        assert_eq!(payload.data(), [0xaa, 0xbb]);
+        assert_eq!(payload.pad_length, 2);
+        assert_eq!(payload.next_header(), 17);
+        assert_eq!(payload.icv, [0xcc]);
+        assert_eq!(payload.icv_valid, Some(false));
+        assert!(EspPayload::new(&[1, 2, 5, 17], &[], None).is_none());
+        assert!(EspPayload::new(&[17], &[], None).is_none());
+    }
+
+    #[test]
+    fn rejects_truncated_packets() {
+        let packet = EspPacket::new(&[0, 0, 1, 0, 0, 0, 0, 7, 0xaa]).unwrap();
+        assert_eq!(packet.spi(), 0x100);
+        assert_eq!(packet.sequence_number(), 7);
+        assert_eq!(packet.data(), [0xaa]);
+        assert!(EspPacket::new(&[0, 0, 1, 0, 0, 0, 0]).is_none());
+    }
+}
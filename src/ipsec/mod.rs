@@ -0,0 +1,63 @@
+pub mod ah;
+pub mod esp;
+pub mod sa;
+
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use crate::transport::Protocol;
+
+/// Keeps the last sequence number seen on every security association to spot lost or replayed packets.
+/// The receiver picks the SPI, so an association is told apart by its destination as well.
+#[derive(Debug, Default)]
+pub struct Sequences {
+    last: HashMap<(IpAddr, Protocol, u32), u32>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SequenceGap {
+    /// Packets with these many sequence numbers never showed up
+    Missing(u32),
+    /// The sequence number was not above the last one seen
+    Replayed(u32),
+}
+
+impl Sequences {
+    pub fn update(&mut self, destination: IpAddr, protocol: Protocol, spi: u32, sequence: u32) -> Option<SequenceGap> {
+        let key = (destination, protocol, spi);
+        let gap = match self.last.get(&key) {
+            Some(&last) if sequence <= last => return Some(SequenceGap::Replayed(last)),
+            Some(&last) if sequence > last + 1 => Some(SequenceGap::Missing(sequence - last - 1)),
+            _ => None
+        };
+        self.last.insert(key, sequence);
+        gap
+    }
+}
+
+impl Display for SequenceGap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SequenceGap::Missing(count) => write!(f, "sequence gap: {} packets missing", count),
+            SequenceGap::Replayed(last) => write!(f, "sequence gap: replayed or reordered, last was {}", last)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport;
+
+    #[test]
+    fn sequences_per_destination() {
+        let mut sequences = Sequences::default();
+        let first = IpAddr::from([192, 0, 2, 1]);
+        let second = IpAddr::from([192, 0, 2, 2]);
+        assert_eq!(sequences.update(first, transport::ESP, 0x1000, 1), None);
+        assert_eq!(sequences.update(second, transport::ESP, 0x1000, 1), None);
+        assert_eq!(sequences.update(first, transport::ESP, 0x1000, 4), Some(SequenceGap::Missing(2)));
+        assert_eq!(sequences.update(second, transport::ESP, 0x1000, 1), Some(SequenceGap::Replayed(1)));
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use aes::{Aes128, Aes192, Aes256};
+use aes::cipher::{BlockDecryptMut, KeyIvInit};
+use aes::cipher::block_padding::NoPadding;
+use aes_gcm::{Aes128Gcm, Aes256Gcm, KeyInit, Nonce};
+use aes_gcm::aead::{Aead, Payload};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha384, Sha512};
+use crate::ipsec::esp::{EspPacket, EspPayload};
+use crate::util::*;
+
+/// The cipher protecting the payload of an ESP security association.
+#[derive(Debug, Clone)]
+pub enum Encryption {
+    Null,
+    AesCbc(Vec<u8>),
+    /// AES-GCM with a 16 byte ICV, the key followed by the 4 byte salt as in RFC 4106
+    AesGcm(Vec<u8>, [u8; 4]),
+}
+
+/// The integrity algorithm of an ESP security association, with its truncated ICV length.
+#[derive(Debug, Clone)]
+pub enum Authentication {
+    None,
+    HmacSha1(Vec<u8>),
+    HmacSha256(Vec<u8>),
+    HmacSha384(Vec<u8>),
+    HmacSha512(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+pub struct SecurityAssociation {
+    encryption: Encryption,
+    authentication: Authentication,
+}
+
+/// The keys of the security associations to decrypt, read from a file with one association per
+/// line: `<spi> <encryption> <key> <authentication> [<key>]`, blank lines and `#` comments ignored.
+///
+/// Encryption is one of `null`, `aes-cbc` or `aes-gcm-16`, authentication one of `null`,
+/// `hmac-sha1-96`, `hmac-sha256-128`, `hmac-sha384-192` or `hmac-sha512-256`.
+#[derive(Debug, Default)]
+pub struct SecurityAssociations {
+    associations: HashMap<u32, SecurityAssociation>,
+}
+
+impl SecurityAssociations {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut associations = HashMap::new();
+        for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = |message: &str| io::Error::new(
+                ErrorKind::InvalidData, format!("{}:{}: {}", path.display(), number + 1, message)
+            );
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 3 {
+                return Err(invalid("expected <spi> <encryption> <key> <authentication> [<key>]"));
+            }
+            let spi = u32::from_str_radix(fields[0].strip_prefix("0x").unwrap_or(fields[0]), 16)
+                .map_err(|_| invalid("invalid spi"))?;
+
+            let key = |index: usize| fields.get(index).and_then(|key| unhex(key)).ok_or_else(|| invalid("invalid key"));
+            let (encryption, next) = match fields[1] {
+                "null" => (Encryption::Null, 2),
+                "aes-cbc" => match key(2)? {
+                    key if [16, 24, 32].contains(&key.len()) => (Encryption::AesCbc(key), 3),
+                    _ => return Err(invalid("aes-cbc keys are 128, 192 or 256 bits"))
+                },
+                "aes-gcm-16" => match key(2)? {
+                    key if [20, 36].contains(&key.len()) => {
+                        let (key, salt) = key.split_at(key.len() - 4);
+                        (Encryption::AesGcm(Vec::from(key), salt.try_into().unwrap()), 3)
+                    }
+                    _ => return Err(invalid("aes-gcm-16 keys are 128 or 256 bits followed by a 32 bit salt"))
+                },
+                other => return Err(invalid(&format!("unsupported encryption {}", other)))
+            };
+            let authentication = match fields.get(next).copied().unwrap_or("null") {
+                "null" => Authentication::None,
+                "hmac-sha1-96" => Authentication::HmacSha1(key(next + 1)?),
+                "hmac-sha256-128" => Authentication::HmacSha256(key(next + 1)?),
+                "hmac-sha384-192" => Authentication::HmacSha384(key(next + 1)?),
+                "hmac-sha512-256" => Authentication::HmacSha512(key(next + 1)?),
+                other => return Err(invalid(&format!("unsupported authentication {}", other)))
+            };
+            associations.insert(spi, SecurityAssociation { encryption, authentication });
+        }
+        Ok(Self { associations })
+    }
+
+    #[inline]
+    pub fn get(&self, spi: u32) -> Option<&SecurityAssociation> {
+        self.associations.get(&spi)
+    }
+}
+
+impl Authentication {
+    /// The length of the truncated ICV at the end of the packet.
+    pub fn icv_length(&self) -> usize {
+        match self {
+            Authentication::None => 0,
+            Authentication::HmacSha1(_) => 12,
+            Authentication::HmacSha256(_) => 16,
+            Authentication::HmacSha384(_) => 24,
+            Authentication::HmacSha512(_) => 32
+        }
+    }
+
+    /// Checks the ICV against the packet from the SPI up to the end of the ciphertext.
+    fn verify(&self, authenticated: &[u8], icv: &[u8]) -> Option<bool> {
+        fn check<M: Mac + KeyInit>(key: &[u8], authenticated: &[u8], icv: &[u8]) -> bool {
+            let mut mac = <M as Mac>::new_from_slice(key).unwrap();
+            mac.update(authenticated);
+            mac.verify_truncated_left(icv).is_ok()
+        }
+        Some(match self {
+            Authentication::None => return None,
+            Authentication::HmacSha1(key) => check::<Hmac<Sha1>>(key, authenticated, icv),
+            Authentication::HmacSha256(key) => check::<Hmac<Sha256>>(key, authenticated, icv),
+            Authentication::HmacSha384(key) => check::<Hmac<Sha384>>(key, authenticated, icv),
+            Authentication::HmacSha512(key) => check::<Hmac<Sha512>>(key, authenticated, icv)
+        })
+    }
+}
+
+impl SecurityAssociation {
+    /// Decrypts the payload of the packet, or None when it is malformed or fails AES-GCM
+    /// authentication. The ICV of the other ciphers is checked into the payload's icv_valid, so
+    /// a packet failing it is still shown.
+    pub fn decrypt(&self, packet: &EspPacket) -> Option<EspPayload> {
+        let data = packet.data();
+        let (plaintext, icv, icv_valid) = match &self.encryption {
+            Encryption::AesGcm(key, salt) => {
+                if data.len() < 8 + 16 {
+                    return None;
+                }
+                let mut nonce = [0u8; 12];
+                nonce[..4].copy_from_slice(salt);
+                nonce[4..].copy_from_slice(&data[..8]);
+                let mut aad = [0u8; 8];
+                aad[..4].copy_from_slice(&packet.spi().to_be_bytes());
+                aad[4..].copy_from_slice(&packet.sequence_number().to_be_bytes());
+                let payload = Payload { msg: &data[8..], aad: &aad };
+                let nonce = Nonce::from_slice(&nonce);
+                let plaintext = match key.len() {
+                    16 => Aes128Gcm::new_from_slice(key).unwrap().decrypt(nonce, payload),
+                    _ => Aes256Gcm::new_from_slice(key).unwrap().decrypt(nonce, payload)
+                }.ok()?;
+                (plaintext, &data[data.len() - 16..], Some(true))
+            }
+            encryption => {
+                let icv_length = self.authentication.icv_length();
+                if data.len() < icv_length {
+                    return None;
+                }
+                let (ciphertext, icv) = data.split_at(data.len() - icv_length);
+                let mut authenticated = Vec::with_capacity(8 + ciphertext.len());
+                authenticated.extend_from_slice(&packet.spi().to_be_bytes());
+                authenticated.extend_from_slice(&packet.sequence_number().to_be_bytes());
+                authenticated.extend_from_slice(ciphertext);
+                let icv_valid = self.authentication.verify(&authenticated, icv);
+
+                let plaintext = match encryption {
+                    Encryption::AesCbc(key) => {
+                        if ciphertext.len() < 16 || !ciphertext.len().is_multiple_of(16) {
+                            return None;
+                        }
+                        let (iv, ciphertext) = ciphertext.split_at(16);
+                        let mut buffer = Vec::from(ciphertext);
+                        match key.len() {
+                            16 => cbc::Decryptor::<Aes128>::new_from_slices(key, iv).unwrap()
+                                .decrypt_padded_mut::<NoPadding>(&mut buffer).ok()?,
+                            24 => cbc::Decryptor::<Aes192>::new_from_slices(key, iv).unwrap()
+                                .decrypt_padded_mut::<NoPadding>(&mut buffer).ok()?,
+                            _ => cbc::Decryptor::<Aes256>::new_from_slices(key, iv).unwrap()
+                                .decrypt_padded_mut::<NoPadding>(&mut buffer).ok()?
+                        };
+                        buffer
+                    }
+                    _ => Vec::from(ciphertext)
+                };
+                (plaintext, icv, icv_valid)
+            }
+        };
+
+        EspPayload::new(&plaintext, icv, icv_valid)
+    }
+}
@@ -1,7 +1,10 @@
 use std::fmt::{Display, Formatter};
-use prettytable::{format, table, row, cell};
+use prettytable::{format, table};
 use crate::util::*;
 
+pub const ICMP_ECHO_REPLY: u8 = 0;
+pub const ICMP_ECHO: u8 = 8;
+
 pub struct ICMPSegment {
     icmp_type: u8,
     code: u8,
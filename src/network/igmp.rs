@@ -0,0 +1,235 @@
+use std::fmt::{Display, Formatter};
+use std::net::Ipv4Addr;
+use prettytable::{format, table, row};
+use crate::util::*;
+
+pub type IgmpType = u8;
+
+pub const MEMBERSHIP_QUERY: IgmpType = 0x11;
+pub const V1_MEMBERSHIP_REPORT: IgmpType = 0x12;
+pub const V2_MEMBERSHIP_REPORT: IgmpType = 0x16;
+pub const LEAVE_GROUP: IgmpType = 0x17;
+pub const V3_MEMBERSHIP_REPORT: IgmpType = 0x22;
+
+pub type RecordType = u8;
+
+pub const MODE_IS_INCLUDE: RecordType = 1;
+pub const MODE_IS_EXCLUDE: RecordType = 2;
+pub const CHANGE_TO_INCLUDE_MODE: RecordType = 3;
+pub const CHANGE_TO_EXCLUDE_MODE: RecordType = 4;
+pub const ALLOW_NEW_SOURCES: RecordType = 5;
+pub const BLOCK_OLD_SOURCES: RecordType = 6;
+
+/// An IGMP message of any version. Version 3 queries carry a source list, version 3 reports
+/// carry group records instead of a single group.
+#[derive(Debug)]
+pub struct IgmpMessage {
+    igmp_type: IgmpType,
+    version: u8,
+    max_response_code: u8,
+    checksum: u16,
+    group: Ipv4Addr,
+    query: Option<QueryV3>,
+    records: Vec<GroupRecord>,
+}
+
+#[derive(Debug)]
+pub struct QueryV3 {
+    suppress_router_processing: bool,
+    robustness_variable: u8,
+    query_interval_code: u8,
+    sources: Vec<Ipv4Addr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GroupRecord {
+    record_type: RecordType,
+    group: Ipv4Addr,
+    sources: Vec<Ipv4Addr>,
+}
+
+/// Decodes the 8 bit floating point codes of IGMPv3 for values of 128 and above. MLDv2 uses
+/// the same encoding for its query interval.
+pub(crate) fn decode_code(code: u8) -> u32 {
+    if code < 128 {
+        code as u32
+    } else {
+        ((code & 0x0f) as u32 | 0x10) << ((code >> 4 & 0x07) + 3)
+    }
+}
+
+fn addresses(data: &[u8], count: usize) -> Vec<Ipv4Addr> {
+    data.chunks_exact(4)
+        .take(count)
+        .map(|address| Ipv4Addr::from([address[0], address[1], address[2], address[3]]))
+        .collect()
+}
+
+impl IgmpMessage {
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+        let mut message = Self {
+            igmp_type: data[0],
+            version: 2,
+            max_response_code: data[1],
+            checksum: tou16(&data[2..4]),
+            group: Ipv4Addr::from([data[4], data[5], data[6], data[7]]),
+            query: None,
+            records: Vec::new(),
+        };
+
+        match message.igmp_type {
+            // Queries tell their version apart by length, and version 1 by a zero response time
+            MEMBERSHIP_QUERY if data.len() >= 12 => {
+                message.version = 3;
+                let count = tou16(&data[10..12]) as usize;
+                message.query = Some(QueryV3 {
+                    suppress_router_processing: data[8] & 0x08 != 0,
+                    robustness_variable: data[8] & 0x07,
+                    query_interval_code: data[9],
+                    sources: addresses(&data[12..], count),
+                });
+            }
+            MEMBERSHIP_QUERY if message.max_response_code == 0 => message.version = 1,
+            V1_MEMBERSHIP_REPORT => message.version = 1,
+            V3_MEMBERSHIP_REPORT => {
+                message.version = 3;
+                message.group = Ipv4Addr::UNSPECIFIED;
+                let count = tou16(&data[6..8]) as usize;
+                let mut rest = &data[8..];
+                while message.records.len() < count && rest.len() >= 8 {
+                    let sources = tou16(&rest[2..4]) as usize;
+                    // Auxiliary data is counted in 4 byte words
+                    let length = (8 + sources * 4 + rest[1] as usize * 4).min(rest.len());
+                    message.records.push(GroupRecord {
+                        record_type: rest[0],
+                        group: Ipv4Addr::from([rest[4], rest[5], rest[6], rest[7]]),
+                        sources: addresses(&rest[8..length], sources),
+                    });
+                    rest = &rest[length..];
+                }
+            }
+            _ => ()
+        }
+        Some(message)
+    }
+
+    #[inline]
+    pub fn igmp_type(&self) -> IgmpType {
+        self.igmp_type
+    }
+
+    #[inline]
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    #[inline]
+    pub fn group(&self) -> Ipv4Addr {
+        self.group
+    }
+
+    #[inline]
+    pub fn records(&self) -> &[GroupRecord] {
+        &self.records
+    }
+
+    /// The time allowed to answer a query, in tenths of a second.
+    pub fn max_response_time(&self) -> u32 {
+        match self.version {
+            3 => decode_code(self.max_response_code),
+            _ => self.max_response_code as u32
+        }
+    }
+}
+
+impl GroupRecord {
+    #[inline]
+    pub fn record_type(&self) -> RecordType {
+        self.record_type
+    }
+
+    #[inline]
+    pub fn group(&self) -> Ipv4Addr {
+        self.group
+    }
+
+    #[inline]
+    pub fn sources(&self) -> &[Ipv4Addr] {
+        &self.sources
+    }
+
+    pub fn record_type_name(&self) -> &'static str {
+        match self.record_type {
+            MODE_IS_INCLUDE => "mode is include",
+            MODE_IS_EXCLUDE => "mode is exclude",
+            CHANGE_TO_INCLUDE_MODE => "change to include",
+            CHANGE_TO_EXCLUDE_MODE => "change to exclude",
+            ALLOW_NEW_SOURCES => "allow new sources",
+            BLOCK_OLD_SOURCES => "block old sources",
+            _ => "unidentified"
+        }
+    }
+}
+
+impl Display for IgmpMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let igmp_type = match self.igmp_type {
+            MEMBERSHIP_QUERY if self.group.is_unspecified() => "general query",
+            MEMBERSHIP_QUERY => "group specific query",
+            V1_MEMBERSHIP_REPORT | V2_MEMBERSHIP_REPORT | V3_MEMBERSHIP_REPORT => "membership report",
+            LEAVE_GROUP => "leave group",
+            _ => "unidentified"
+        };
+
+        let mut table = table!(
+            ["igmp_type", format!("{:02X} ({})", self.igmp_type, igmp_type)],
+            ["version", self.version],
+            ["checksum", format!("{:X}", self.checksum)]
+        );
+        if self.igmp_type == MEMBERSHIP_QUERY && self.version > 1 {
+            table.add_row(row!["max_response_time", format!("{:.1}s", self.max_response_time() as f64 / 10.0)]);
+        }
+        if self.igmp_type != V3_MEMBERSHIP_REPORT {
+            table.add_row(row!["group", self.group]);
+        }
+        if let Some(query) = &self.query {
+            table.add_row(row!["suppress_router_processing", query.suppress_router_processing]);
+            table.add_row(row!["robustness_variable", query.robustness_variable]);
+            table.add_row(row!["query_interval", format!("{}s", decode_code(query.query_interval_code))]);
+            for source in query.sources.iter() {
+                table.add_row(row!["source", source]);
+            }
+        }
+        for record in self.records.iter() {
+            let sources: Vec<String> = record.sources.iter().map(|source| source.to_string()).collect();
+            table.add_row(row![
+                format!("record {}", record.group),
+                format!("{} [{}]", record.record_type_name(), sources.join(", "))
+            ]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v2_report() {
+        let data = unhex("160009fbe00000fb").unwrap();
+        let message = IgmpMessage::new(&data).unwrap();
+        assert_eq!(message.igmp_type(), V2_MEMBERSHIP_REPORT);
+        assert_eq!(message.version(), 2);
+        assert_eq!(message.group(), Ipv4Addr::new(224, 0, 0, 251));
+        assert!(IgmpMessage::new(&data[..7]).is_none());
+    }
+}
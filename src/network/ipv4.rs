@@ -1,7 +1,6 @@
-use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::net::Ipv4Addr;
-use prettytable::{format, table, row, cell};
+use prettytable::{format, table};
 use crate::transport;
 use crate::transport::Protocol;
 use crate::util::DataContainer;
@@ -26,7 +25,6 @@ pub struct Ipv4Packet {
 impl From<&[u8]> for Ipv4Packet {
     fn from(data: &[u8]) -> Self {
         let header_length = ((data[0] & 0b00001111) * 4) as usize;
-        println!("{}", data[9]);
 
         Self {
             version: data[0] >> 4,
@@ -47,41 +45,49 @@ impl From<&[u8]> for Ipv4Packet {
 }
 
 impl Ipv4Packet {
+    #[allow(dead_code)]
     #[inline]
     pub fn version(&self) -> u8 {
         self.version
     }
 
+    #[allow(dead_code)]
     #[inline]
     pub fn ihl(&self) -> u8 {
         self.ihl
     }
 
+    #[allow(dead_code)]
     #[inline]
     pub fn tos(&self) -> u8 {
         self.tos
     }
 
+    #[allow(dead_code)]
     #[inline]
     pub fn total_length(&self) -> u16 {
         self.total_length
     }
 
+    #[allow(dead_code)]
     #[inline]
     pub fn id(&self) -> u16 {
         self.id
     }
 
+    #[allow(dead_code)]
     #[inline]
     pub fn flags(&self) -> u8 {
         self.flags
     }
 
+    #[allow(dead_code)]
     #[inline]
     pub fn offset(&self) -> u16 {
         self.offset
     }
 
+    #[allow(dead_code)]
     #[inline]
     pub fn ttl(&self) -> u8 {
         self.ttl
@@ -92,6 +98,7 @@ impl Ipv4Packet {
         self.protocol
     }
 
+    #[allow(dead_code)]
     #[inline]
     pub fn checksum(&self) -> u16 {
         self.checksum
@@ -128,6 +135,13 @@ impl Display for Ipv4Packet {
             transport::TCP => "tcp",
             transport::UDP => "udp",
             transport::ICMP => "icmp",
+            transport::IGMP => "igmp",
+            transport::IPIP => "ipv4",
+            transport::IPV6 => "ipv6",
+            transport::GRE => "gre",
+            transport::ESP => "esp",
+            transport::AH => "ah",
+            transport::SCTP => "sctp",
             _ => "unidentified"
         };
 
@@ -1,6 +1,6 @@
 use std::fmt::{Display, Formatter};
 use std::net::Ipv6Addr;
-use prettytable::{format, table, row, cell};
+use prettytable::{format, table};
 use crate::util::*;
 
 #[derive(Debug)]
@@ -13,6 +13,7 @@ pub struct Ipv6Packet {
     hop_limit: u8,
     source_address: Ipv6Addr,
     destination_address: Ipv6Addr,
+    packet_data: Vec<u8>
 }
 
 impl From<&[u8]> for Ipv6Packet {
@@ -25,11 +26,35 @@ impl From<&[u8]> for Ipv6Packet {
             next_header: data[6],
             hop_limit: data[7],
             source_address: Ipv6Addr::from(tou128(&data[8..24])),
-            destination_address: Ipv6Addr::from(tou128(&data[24..40]))
+            destination_address: Ipv6Addr::from(tou128(&data[24..40])),
+            packet_data: Vec::from(&data[40..])
         }
     }
 }
 
+impl Ipv6Packet {
+    #[inline]
+    pub fn next_header(&self) -> u8 {
+        self.next_header
+    }
+
+    #[inline]
+    pub fn source(&self) -> Ipv6Addr {
+        self.source_address
+    }
+
+    #[inline]
+    pub fn destination(&self) -> Ipv6Addr {
+        self.destination_address
+    }
+}
+
+impl DataContainer for Ipv6Packet {
+    fn data(&self) -> &[u8] {
+        self.packet_data.as_slice()
+    }
+}
+
 impl Display for Ipv6Packet {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let mut table = table!(
@@ -0,0 +1,74 @@
+use std::fmt::{Display, Formatter};
+use pnet::util::MacAddr;
+use prettytable::{format, table};
+use crate::util::*;
+
+/// A Novell IPX packet, either in Ethernet II, raw 802.3, 802.2 LLC or SNAP framing.
+#[derive(Debug)]
+pub struct IpxPacket {
+    checksum: u16,
+    length: u16,
+    transport_control: u8,
+    packet_type: u8,
+    destination_network: u32,
+    destination_node: MacAddr,
+    destination_socket: u16,
+    source_network: u32,
+    source_node: MacAddr,
+    source_socket: u16,
+    packet_data: Vec<u8>
+}
+
+impl From<&[u8]> for IpxPacket {
+    fn from(data: &[u8]) -> Self {
+        let node = |d: &[u8]| MacAddr::new(d[0], d[1], d[2], d[3], d[4], d[5]);
+        Self {
+            checksum: tou16(&data[0..2]),
+            length: tou16(&data[2..4]),
+            transport_control: data[4],
+            packet_type: data[5],
+            destination_network: tou32(&data[6..10]),
+            destination_node: node(&data[10..16]),
+            destination_socket: tou16(&data[16..18]),
+            source_network: tou32(&data[18..22]),
+            source_node: node(&data[22..28]),
+            source_socket: tou16(&data[28..30]),
+            packet_data: Vec::from(&data[30..])
+        }
+    }
+}
+
+impl DataContainer for IpxPacket {
+    fn data(&self) -> &[u8] {
+        self.packet_data.as_slice()
+    }
+}
+
+impl Display for IpxPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let packet_type = match self.packet_type {
+            0 => "unknown",
+            1 => "rip",
+            4 => "pep",
+            5 => "spx",
+            17 => "ncp",
+            20 => "netbios",
+            _ => "unidentified"
+        };
+
+        let mut table = table!(
+            ["checksum", format!("{:X}", self.checksum)],
+            ["length", self.length],
+            ["transport_control", self.transport_control],
+            ["packet_type", packet_type],
+            ["destination", format!("{:08X}.{}.{:04X}", self.destination_network, self.destination_node, self.destination_socket)],
+            ["source", format!("{:08X}.{}.{:04X}", self.source_network, self.source_node, self.source_socket)]
+        );
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+use std::fmt::{Display, Formatter};
+use std::net::Ipv6Addr;
+use prettytable::{format, table, row};
+use crate::network::igmp::{self, RecordType};
+use crate::util::*;
+
+pub type MldType = u8;
+
+pub const MULTICAST_LISTENER_QUERY: MldType = 130;
+pub const V1_MULTICAST_LISTENER_REPORT: MldType = 131;
+pub const MULTICAST_LISTENER_DONE: MldType = 132;
+pub const V2_MULTICAST_LISTENER_REPORT: MldType = 143;
+
+/// A Multicast Listener Discovery message, the ICMPv6 counterpart of IGMP. Version 2 queries
+/// carry a source list, version 2 reports carry multicast address records instead of a single
+/// address.
+#[derive(Debug)]
+pub struct MldMessage {
+    mld_type: MldType,
+    version: u8,
+    checksum: u16,
+    max_response_code: u16,
+    address: Ipv6Addr,
+    query: Option<QueryV2>,
+    records: Vec<AddressRecord>,
+}
+
+#[derive(Debug)]
+pub struct QueryV2 {
+    suppress_router_processing: bool,
+    robustness_variable: u8,
+    query_interval_code: u8,
+    sources: Vec<Ipv6Addr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AddressRecord {
+    record_type: RecordType,
+    address: Ipv6Addr,
+    sources: Vec<Ipv6Addr>,
+}
+
+/// Decodes the 16 bit floating point maximum response code of MLDv2 for values of 32768 and
+/// above.
+fn decode_response_code(code: u16) -> u32 {
+    if code < 32768 {
+        code as u32
+    } else {
+        ((code & 0x0fff) as u32 | 0x1000) << ((code >> 12 & 0x07) + 3)
+    }
+}
+
+fn addresses(data: &[u8], count: usize) -> Vec<Ipv6Addr> {
+    data.chunks_exact(16)
+        .take(count)
+        .map(|address| Ipv6Addr::from(tou128(address)))
+        .collect()
+}
+
+impl MldMessage {
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+        let mut message = Self {
+            mld_type: data[0],
+            version: 1,
+            checksum: tou16(&data[2..4]),
+            max_response_code: tou16(&data[4..6]),
+            address: Ipv6Addr::UNSPECIFIED,
+            query: None,
+            records: Vec::new(),
+        };
+
+        match message.mld_type {
+            MULTICAST_LISTENER_QUERY | V1_MULTICAST_LISTENER_REPORT | MULTICAST_LISTENER_DONE => {
+                message.address = Ipv6Addr::from(tou128(data.get(8..24)?));
+                // Version 2 queries are the only ones longer than 24 bytes
+                if message.mld_type == MULTICAST_LISTENER_QUERY && data.len() >= 28 {
+                    message.version = 2;
+                    let count = tou16(&data[26..28]) as usize;
+                    message.query = Some(QueryV2 {
+                        suppress_router_processing: data[24] & 0x08 != 0,
+                        robustness_variable: data[24] & 0x07,
+                        query_interval_code: data[25],
+                        sources: addresses(&data[28..], count),
+                    });
+                }
+            }
+            V2_MULTICAST_LISTENER_REPORT => {
+                message.version = 2;
+                let count = tou16(&data[6..8]) as usize;
+                let mut rest = &data[8..];
+                while message.records.len() < count && rest.len() >= 20 {
+                    let sources = tou16(&rest[2..4]) as usize;
+                    // Auxiliary data is counted in 4 byte words
+                    let length = (20 + sources * 16 + rest[1] as usize * 4).min(rest.len());
+                    message.records.push(AddressRecord {
+                        record_type: rest[0],
+                        address: Ipv6Addr::from(tou128(&rest[4..20])),
+                        sources: addresses(&rest[20..length], sources),
+                    });
+                    rest = &rest[length..];
+                }
+            }
+            _ => return None
+        }
+        Some(message)
+    }
+
+    #[inline]
+    pub fn mld_type(&self) -> MldType {
+        self.mld_type
+    }
+
+    #[inline]
+    pub fn address(&self) -> Ipv6Addr {
+        self.address
+    }
+
+    #[inline]
+    pub fn records(&self) -> &[AddressRecord] {
+        &self.records
+    }
+
+    /// The time allowed to answer a query, in milliseconds.
+    pub fn max_response_delay(&self) -> u32 {
+        match self.version {
+            2 => decode_response_code(self.max_response_code),
+            _ => self.max_response_code as u32
+        }
+    }
+}
+
+impl AddressRecord {
+    #[inline]
+    pub fn record_type(&self) -> RecordType {
+        self.record_type
+    }
+
+    #[inline]
+    pub fn address(&self) -> Ipv6Addr {
+        self.address
+    }
+
+    #[inline]
+    pub fn sources(&self) -> &[Ipv6Addr] {
+        &self.sources
+    }
+
+    pub fn record_type_name(&self) -> &'static str {
+        match self.record_type {
+            igmp::MODE_IS_INCLUDE => "mode is include",
+            igmp::MODE_IS_EXCLUDE => "mode is exclude",
+            igmp::CHANGE_TO_INCLUDE_MODE => "change to include",
+            igmp::CHANGE_TO_EXCLUDE_MODE => "change to exclude",
+            igmp::ALLOW_NEW_SOURCES => "allow new sources",
+            igmp::BLOCK_OLD_SOURCES => "block old sources",
+            _ => "unidentified"
+        }
+    }
+}
+
+impl Display for MldMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mld_type = match self.mld_type {
+            MULTICAST_LISTENER_QUERY if self.address.is_unspecified() => "general query",
+            MULTICAST_LISTENER_QUERY => "address specific query",
+            V1_MULTICAST_LISTENER_REPORT | V2_MULTICAST_LISTENER_REPORT => "listener report",
+            MULTICAST_LISTENER_DONE => "listener done",
+            _ => "unidentified"
+        };
+
+        let mut table = table!(
+            ["mld_type", format!("{} ({})", self.mld_type, mld_type)],
+            ["version", self.version],
+            ["checksum", format!("{:X}", self.checksum)]
+        );
+        if self.mld_type == MULTICAST_LISTENER_QUERY {
+            table.add_row(row!["max_response_delay", format!("{}ms", self.max_response_delay())]);
+        }
+        if self.mld_type != V2_MULTICAST_LISTENER_REPORT {
+            table.add_row(row!["multicast_address", self.address]);
+        }
+        if let Some(query) = &self.query {
+            table.add_row(row!["suppress_router_processing", query.suppress_router_processing]);
+            table.add_row(row!["robustness_variable", query.robustness_variable]);
+            table.add_row(row!["query_interval", format!("{}s", igmp::decode_code(query.query_interval_code))]);
+            for source in query.sources.iter() {
+                table.add_row(row!["source", source]);
+            }
+        }
+        for record in self.records.iter() {
+            let sources: Vec<String> = record.sources.iter().map(|source| source.to_string()).collect();
+            table.add_row(row![
+                format!("record {}", record.address),
+                format!("{} [{}]", record.record_type_name(), sources.join(", "))
+            ]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v1_report() {
+        let data = unhex("8300a1b200000000ff0200000000000000000000000000fb").unwrap();
+        let message = MldMessage::new(&data).unwrap();
+        assert_eq!(message.mld_type(), V1_MULTICAST_LISTENER_REPORT);
+        assert_eq!(message.version, 1);
+        assert_eq!(message.address(), "ff02::fb".parse::<Ipv6Addr>().unwrap());
+    }
+
+    #[test]
+    fn v2_report() {
+        // One CHANGE_TO_EXCLUDE record for ff02::fb without sources, and one ALLOW_NEW_SOURCES
+        // record for ff3e::1234 with a single source
+        let data = unhex(concat!(
+            "8f00000000000002",
+            "04000000", "ff0200000000000000000000000000fb",
+            "05000001", "ff3e0000000000000000000000001234", "20010db8000000000000000000000001"
+        )).unwrap();
+        let message = MldMessage::new(&data).unwrap();
+        assert_eq!(message.version, 2);
+        assert_eq!(message.records().len(), 2);
+        assert_eq!(message.records()[0].record_type(), igmp::CHANGE_TO_EXCLUDE_MODE);
+        assert_eq!(message.records()[0].address(), "ff02::fb".parse::<Ipv6Addr>().unwrap());
+        assert!(message.records()[0].sources().is_empty());
+        assert_eq!(message.records()[1].sources(), ["2001:db8::1".parse::<Ipv6Addr>().unwrap()]);
+    }
+
+    #[test]
+    fn v2_query() {
+        let data = unhex(concat!(
+            "8200000027100000", "00000000000000000000000000000000", "027d0000"
+        )).unwrap();
+        let message = MldMessage::new(&data).unwrap();
+        assert_eq!(message.version, 2);
+        assert!(message.address().is_unspecified());
+        assert_eq!(message.max_response_delay(), 10000);
+        assert!(MldMessage::new(&data[..20]).is_none());
+    }
+}
@@ -2,6 +2,13 @@ pub mod ipv6;
 pub mod ipv4;
 pub mod icmp;
 pub mod arp;
+pub mod ipx;
+pub mod mpls;
+pub mod igmp;
+pub mod mld;
+pub mod multicast;
+
+use crate::util::*;
 
 
 pub type EthernetType = u16;
@@ -10,5 +17,34 @@ pub const IPV4: EthernetType = 0x0800;
 pub const WOL: EthernetType = 0x0842;
 pub const RARP: EthernetType = 0x8035;
 pub const ARP: EthernetType = 0x0806;
+pub const IPX: EthernetType = 0x8137;
 pub const IPV6: EthernetType = 0x86DD;
-pub const GOOSE: EthernetType = 0x88B8;      // you mess with the the honk, you get the bonk
\ No newline at end of file
+pub const MPLS: EthernetType = 0x8847;
+pub const MPLS_MULTICAST: EthernetType = 0x8848;
+pub const PPPOE_DISCOVERY: EthernetType = 0x8863;
+pub const PPPOE_SESSION: EthernetType = 0x8864;
+pub const LLDP: EthernetType = 0x88CC;
+pub const PTP: EthernetType = 0x88F7;
+pub const GOOSE: EthernetType = 0x88B8;      // you mess with the the honk, you get the bonk
+
+/// The length the network layer packet claims to have, used to find padding and trailers
+/// after it in the frame.
+pub fn packet_length(frame_type: EthernetType, data: &[u8]) -> Option<usize> {
+    match frame_type {
+        IPV4 if data.len() >= 4 => Some(tou16(&data[2..4]) as usize),
+        IPV6 if data.len() >= 6 => Some(40 + tou16(&data[4..6]) as usize),
+        ARP | RARP if data.len() >= 6 => Some(8 + 2 * (data[4] as usize + data[5] as usize)),
+        IPX if data.len() >= 4 => Some(tou16(&data[2..4]) as usize),
+        _ => None
+    }
+}
+
+/// Whether the data holds the whole header of an IP packet, including the IPv4 options its
+/// header length announces. Other types are not checked.
+pub fn has_ip_header(frame_type: EthernetType, data: &[u8]) -> bool {
+    match frame_type {
+        IPV4 => data.len() >= 20 && data.len() >= (data[0] & 0x0f) as usize * 4,
+        IPV6 => data.len() >= 40,
+        _ => true
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table, row};
+use crate::network::{self, EthernetType};
+use crate::util::*;
+
+pub const LABEL_IPV4_EXPLICIT_NULL: u32 = 0;
+pub const LABEL_ROUTER_ALERT: u32 = 1;
+pub const LABEL_IPV6_EXPLICIT_NULL: u32 = 2;
+pub const LABEL_IMPLICIT_NULL: u32 = 3;
+
+/// A single MPLS label stack entry.
+#[derive(Debug, Clone, Copy)]
+pub struct MplsLabel {
+    label: u32,
+    traffic_class: u8,
+    bottom_of_stack: bool,
+    ttl: u8,
+}
+
+impl From<&[u8]> for MplsLabel {
+    fn from(data: &[u8]) -> Self {
+        let entry = tou32(&data[0..4]);
+        Self {
+            label: entry >> 12,
+            traffic_class: ((entry >> 9) & 0b111) as u8,
+            bottom_of_stack: entry & 0x100 != 0,
+            ttl: entry as u8,
+        }
+    }
+}
+
+/// An MPLS label stack and the packet it carries.
+#[derive(Debug)]
+pub struct MplsPacket {
+    labels: Vec<MplsLabel>,
+    packet_data: Vec<u8>
+}
+
+impl MplsPacket {
+    /// MPLS doesn't say what it carries. Explicit null labels do, otherwise guess from the IP
+    /// version nibble. `None` as well when the packet is too short for the header of its type.
+    pub fn payload_type(&self) -> Option<EthernetType> {
+        let payload_type = match self.labels.last().map(|label| label.label) {
+            Some(LABEL_IPV4_EXPLICIT_NULL) => network::IPV4,
+            Some(LABEL_IPV6_EXPLICIT_NULL) => network::IPV6,
+            _ => match self.packet_data.first().map(|byte| byte >> 4) {
+                Some(4) => network::IPV4,
+                Some(6) => network::IPV6,
+                _ => return None
+            }
+        };
+        Some(payload_type).filter(|payload_type| network::has_ip_header(*payload_type, &self.packet_data))
+    }
+}
+
+impl From<&[u8]> for MplsPacket {
+    fn from(data: &[u8]) -> Self {
+        let mut labels = Vec::new();
+        let mut offset = 0;
+        while offset + 4 <= data.len() {
+            let label = MplsLabel::from(&data[offset..offset + 4]);
+            labels.push(label);
+            offset += 4;
+            if label.bottom_of_stack {
+                break;
+            }
+        }
+
+        Self {
+            labels,
+            packet_data: Vec::from(&data[offset..])
+        }
+    }
+}
+
+impl DataContainer for MplsPacket {
+    fn data(&self) -> &[u8] {
+        self.packet_data.as_slice()
+    }
+}
+
+impl Display for MplsPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!();
+        for label in self.labels.iter() {
+            let name = match label.label {
+                LABEL_IPV4_EXPLICIT_NULL => " (ipv4 explicit null)",
+                LABEL_ROUTER_ALERT => " (router alert)",
+                LABEL_IPV6_EXPLICIT_NULL => " (ipv6 explicit null)",
+                LABEL_IMPLICIT_NULL => " (implicit null)",
+                _ => ""
+            };
+            table.add_row(row!["label", format!(
+                "{}{}, tc {}, s {}, ttl {}",
+                label.label, name, label.traffic_class, label.bottom_of_stack as u8, label.ttl
+            )]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_the_header_after_the_label_stack() {
+        // An IPv4 explicit null label, then the start of an IPv4 header with options
+        let data = unhex("00000100460000180000000040010000c0a80001c0a8000200000000").unwrap();
+        assert_eq!(MplsPacket::from(&data[..8]).payload_type(), None);
+        assert_eq!(MplsPacket::from(&data[..24]).payload_type(), None);
+        assert_eq!(MplsPacket::from(data.as_slice()).payload_type(), Some(network::IPV4));
+    }
+}
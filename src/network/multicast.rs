@@ -0,0 +1,177 @@
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+use prettytable::{format, Table, row};
+use crate::network::igmp::{self, IgmpMessage};
+use crate::network::mld::{self, MldMessage};
+
+/// Which sources of a group a host listens to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// Only these sources
+    Include(Vec<IpAddr>),
+    /// Every source but these
+    Exclude(Vec<IpAddr>),
+}
+
+/// A host's membership of a multicast group, as last reported on an interface.
+#[derive(Debug)]
+pub struct Membership {
+    interface: String,
+    host: IpAddr,
+    group: IpAddr,
+    version: u8,
+    filter: Filter,
+    last_seen: Duration,
+}
+
+impl Membership {
+    /// A host has left once it no longer wants any source.
+    #[inline]
+    pub fn joined(&self) -> bool {
+        self.filter != Filter::Include(Vec::new())
+    }
+}
+
+/// The multicast groups joined during a capture, one entry per interface, host and group.
+#[derive(Debug, Default)]
+pub struct Memberships {
+    memberships: Vec<Membership>,
+}
+
+impl Memberships {
+    /// Applies the reports and leaves of the message sent by the host.
+    pub fn update_igmp(&mut self, interface: &str, host: Ipv4Addr, message: &IgmpMessage, timestamp: Duration) {
+        let host = IpAddr::V4(host);
+        match message.igmp_type() {
+            igmp::V1_MEMBERSHIP_REPORT | igmp::V2_MEMBERSHIP_REPORT => {
+                let membership = self.membership(interface, host, IpAddr::V4(message.group()));
+                membership.filter = Filter::Exclude(Vec::new());
+                membership.version = message.version();
+                membership.last_seen = timestamp;
+            }
+            igmp::LEAVE_GROUP => {
+                let membership = self.membership(interface, host, IpAddr::V4(message.group()));
+                membership.filter = Filter::Include(Vec::new());
+                membership.last_seen = timestamp;
+            }
+            igmp::V3_MEMBERSHIP_REPORT => {
+                for record in message.records() {
+                    let sources: Vec<IpAddr> = record.sources().iter().map(|source| IpAddr::V4(*source)).collect();
+                    let membership = self.membership(interface, host, IpAddr::V4(record.group()));
+                    membership.apply(record.record_type(), sources);
+                    membership.version = 3;
+                    membership.last_seen = timestamp;
+                }
+            }
+            _ => ()
+        }
+    }
+
+    /// Applies the reports and dones of the MLD message sent by the host.
+    pub fn update_mld(&mut self, interface: &str, host: Ipv6Addr, message: &MldMessage, timestamp: Duration) {
+        let host = IpAddr::V6(host);
+        match message.mld_type() {
+            mld::V1_MULTICAST_LISTENER_REPORT => {
+                let membership = self.membership(interface, host, IpAddr::V6(message.address()));
+                membership.filter = Filter::Exclude(Vec::new());
+                membership.version = 1;
+                membership.last_seen = timestamp;
+            }
+            mld::MULTICAST_LISTENER_DONE => {
+                let membership = self.membership(interface, host, IpAddr::V6(message.address()));
+                membership.filter = Filter::Include(Vec::new());
+                membership.last_seen = timestamp;
+            }
+            mld::V2_MULTICAST_LISTENER_REPORT => {
+                for record in message.records() {
+                    let sources: Vec<IpAddr> = record.sources().iter().map(|source| IpAddr::V6(*source)).collect();
+                    let membership = self.membership(interface, host, IpAddr::V6(record.address()));
+                    membership.apply(record.record_type(), sources);
+                    membership.version = 2;
+                    membership.last_seen = timestamp;
+                }
+            }
+            _ => ()
+        }
+    }
+
+    fn membership(&mut self, interface: &str, host: IpAddr, group: IpAddr) -> &mut Membership {
+        let position = self.memberships.iter().position(|known| {
+            known.interface == interface && known.host == host && known.group == group
+        });
+        let position = position.unwrap_or_else(|| {
+            self.memberships.push(Membership {
+                interface: String::from(interface),
+                host,
+                group,
+                version: 0,
+                filter: Filter::Include(Vec::new()),
+                last_seen: Duration::ZERO,
+            });
+            self.memberships.len() - 1
+        });
+        &mut self.memberships[position]
+    }
+}
+
+impl Membership {
+    /// Merges an IGMPv3 group record or MLDv2 address record into the source filter, as in
+    /// RFC 3376 section 6.4.
+    fn apply(&mut self, record_type: igmp::RecordType, sources: Vec<IpAddr>) {
+        self.filter = match (record_type, &self.filter) {
+            (igmp::MODE_IS_INCLUDE | igmp::CHANGE_TO_INCLUDE_MODE, _) => Filter::Include(sources),
+            (igmp::MODE_IS_EXCLUDE | igmp::CHANGE_TO_EXCLUDE_MODE, _) => Filter::Exclude(sources),
+            (igmp::ALLOW_NEW_SOURCES, Filter::Include(included)) => {
+                let mut included = included.clone();
+                included.extend(sources.into_iter().filter(|source| !included.contains(source)).collect::<Vec<_>>());
+                Filter::Include(included)
+            }
+            (igmp::ALLOW_NEW_SOURCES, Filter::Exclude(excluded)) => {
+                Filter::Exclude(excluded.iter().filter(|source| !sources.contains(source)).copied().collect())
+            }
+            (igmp::BLOCK_OLD_SOURCES, Filter::Include(included)) => {
+                Filter::Include(included.iter().filter(|source| !sources.contains(source)).copied().collect())
+            }
+            (igmp::BLOCK_OLD_SOURCES, Filter::Exclude(excluded)) => {
+                let mut excluded = excluded.clone();
+                excluded.extend(sources.into_iter().filter(|source| !excluded.contains(source)).collect::<Vec<_>>());
+                Filter::Exclude(excluded)
+            }
+            _ => return
+        };
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (mode, sources) = match self {
+            Filter::Include(sources) => ("include", sources),
+            Filter::Exclude(sources) => ("exclude", sources)
+        };
+        let sources: Vec<String> = sources.iter().map(|source| source.to_string()).collect();
+        write!(f, "{} [{}]", mode, sources.join(", "))
+    }
+}
+
+impl Display for Memberships {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.set_titles(row!["interface", "host", "group", "state", "filter", "version", "last_seen"]);
+        for membership in self.memberships.iter() {
+            table.add_row(row![
+                membership.interface,
+                membership.host,
+                membership.group,
+                if membership.joined() { "joined" } else { "left" },
+                membership.filter,
+                membership.version,
+                format!("{:.3}", membership.last_seen.as_secs_f64())
+            ]);
+        }
+
+        table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::exit;
+use std::str::FromStr;
+use pnet::util::MacAddr;
+use crate::datalink::LinkType;
+use crate::datalink::ethernet::Frame;
+
+const USAGE: &str = "usage: pnettest [--interface <name> | --read <file>] [--link-type <type>] \
+                     [--count <packets>] [--destination <mac>] [--vlan <id>] [--neighbors] \
+                     [--multicast] [--associations] [--leases] \
+                     [--quic-connections] [--esp-sa <file>] [--tls-keylog <file>] \
+                     [--fingerprints] [--fingerprint-db <file>] \
+                     [--export-objects http|tftp,<dir>]... \
+                     [--proto-descriptors <file>]...";
+
+/// Command line options of the capture.
+#[derive(Debug)]
+pub struct Options {
+    interface: String,
+    read: Option<PathBuf>,
+    link_type: Option<LinkType>,
+    destination: Option<MacAddr>,
+    vlan: Option<u16>,
+    count: Option<usize>,
+    neighbors: bool,
+    multicast: bool,
+    associations: bool,
+    leases: bool,
+    quic_connections: bool,
+    esp_sa: Option<PathBuf>,
+    tls_keylog: Option<PathBuf>,
+    fingerprints: bool,
+    fingerprint_db: Option<PathBuf>,
+    export_http: Option<PathBuf>,
+    export_tftp: Option<PathBuf>,
+    proto_descriptors: Vec<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            interface: String::from("wlp0s20f3"),
+            read: None,
+            link_type: None,
+            destination: None,
+            vlan: None,
+            count: None,
+            neighbors: false,
+            multicast: false,
+            associations: false,
+            leases: false,
+            quic_connections: false,
+            esp_sa: None,
+            tls_keylog: None,
+            fingerprints: false,
+            fingerprint_db: None,
+            export_http: None,
+            export_tftp: None,
+            proto_descriptors: Vec::new(),
+        }
+    }
+}
+
+impl Options {
+    pub fn from_args() -> Self {
+        let mut options = Self::default();
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-i" | "--interface" => options.interface = value(&mut args, &arg),
+                "-r" | "--read" => options.read = Some(PathBuf::from(value(&mut args, &arg))),
+                "--link-type" => options.link_type = Some(parse(&mut args, &arg)),
+                "--destination" => options.destination = Some(parse(&mut args, &arg)),
+                "--vlan" => options.vlan = Some(parse(&mut args, &arg)),
+                "-c" | "--count" => options.count = Some(parse(&mut args, &arg)),
+                "--neighbors" => options.neighbors = true,
+                "--multicast" => options.multicast = true,
+                "--associations" => options.associations = true,
+                "--leases" => options.leases = true,
+                "--quic-connections" => options.quic_connections = true,
+                "--esp-sa" => options.esp_sa = Some(PathBuf::from(value(&mut args, &arg))),
+                "--tls-keylog" => options.tls_keylog = Some(PathBuf::from(value(&mut args, &arg))),
+                "--fingerprints" => options.fingerprints = true,
+                "--fingerprint-db" => options.fingerprint_db = Some(PathBuf::from(value(&mut args, &arg))),
+                "--export-objects" => {
+                    let value = value(&mut args, &arg);
+                    match value.split_once(',') {
+                        Some(("http", directory)) if !directory.is_empty() => {
+                            options.export_http = Some(PathBuf::from(directory));
+                        }
+                        Some(("tftp", directory)) if !directory.is_empty() => {
+                            options.export_tftp = Some(PathBuf::from(directory));
+                        }
+                        _ => usage_error(&format!("invalid value {} for {}, expected http,<dir> or tftp,<dir>", value, arg))
+                    }
+                }
+                "--proto-descriptors" => options.proto_descriptors.push(PathBuf::from(value(&mut args, &arg))),
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    exit(0);
+                }
+                _ => usage_error(&format!("unknown argument {}", arg))
+            }
+        }
+        // Live frames keep the header of the interface they arrived on, pnet has no cooked mode
+        if let (None, Some(link_type @ (LinkType::LinuxSll | LinkType::LinuxSll2))) = (&options.read, options.link_type) {
+            usage_error(&format!("--link-type {} is not available for live captures, which are never cooked", link_type.name()));
+        }
+        options
+    }
+
+    #[inline]
+    pub fn interface(&self) -> &str {
+        &self.interface
+    }
+
+    /// The capture file to read instead of capturing live.
+    #[inline]
+    pub fn read(&self) -> Option<&PathBuf> {
+        self.read.as_ref()
+    }
+
+    /// The link type to assume for live captures, ethernet unless given.
+    #[inline]
+    pub fn link_type(&self) -> LinkType {
+        self.link_type.unwrap_or(LinkType::Ethernet)
+    }
+
+    /// The number of packets to dissect before stopping.
+    #[inline]
+    pub fn count(&self) -> Option<usize> {
+        self.count
+    }
+
+    /// Whether to list the devices found through discovery protocols at the end.
+    #[inline]
+    pub fn neighbors(&self) -> bool {
+        self.neighbors
+    }
+
+    /// Whether to list the multicast groups hosts joined at the end.
+    #[inline]
+    pub fn multicast(&self) -> bool {
+        self.multicast
+    }
+
+    /// Whether to list the SCTP associations at the end.
+    #[inline]
+    pub fn associations(&self) -> bool {
+        self.associations
+    }
+
+    /// Whether to list the DHCP address assignments at the end.
+    #[inline]
+    pub fn leases(&self) -> bool {
+        self.leases
+    }
+
+    /// Whether to list the QUIC connections at the end.
+    #[inline]
+    pub fn quic_connections(&self) -> bool {
+        self.quic_connections
+    }
+
+    /// The file with the security associations used to decrypt ESP packets.
+    #[inline]
+    pub fn esp_sa(&self) -> Option<&PathBuf> {
+        self.esp_sa.as_ref()
+    }
+
+    /// The NSS key log file with the secrets used to decrypt TLS.
+    #[inline]
+    pub fn tls_keylog(&self) -> Option<&PathBuf> {
+        self.tls_keylog.as_ref()
+    }
+
+    /// Whether to list the TLS and SSH fingerprints at the end.
+    #[inline]
+    pub fn fingerprints(&self) -> bool {
+        self.fingerprints
+    }
+
+    /// The file naming known TLS fingerprints.
+    #[inline]
+    pub fn fingerprint_db(&self) -> Option<&PathBuf> {
+        self.fingerprint_db.as_ref()
+    }
+
+    /// The directory to export the objects carried over HTTP to.
+    #[inline]
+    pub fn export_http(&self) -> Option<&PathBuf> {
+        self.export_http.as_ref()
+    }
+
+    /// The directory to export the files transferred over TFTP to.
+    #[inline]
+    pub fn export_tftp(&self) -> Option<&PathBuf> {
+        self.export_tftp.as_ref()
+    }
+
+    /// The protobuf descriptor sets naming the fields of gRPC messages, the flag being repeatable.
+    #[inline]
+    pub fn proto_descriptors(&self) -> &[PathBuf] {
+        &self.proto_descriptors
+    }
+
+    /// Whether any of the filters need an ethernet frame to match against.
+    pub fn filters_frames(&self) -> bool {
+        self.destination.is_some() || self.vlan.is_some()
+    }
+
+    /// Whether the frame passes all the filters given on the command line.
+    pub fn matches(&self, frame: &Frame) -> bool {
+        if let Some(destination) = self.destination {
+            if *frame.destination() != destination {
+                return false;
+            }
+        }
+        if let Some(vlan) = self.vlan {
+            if !frame.vlan_tags().iter().any(|tag| tag.vid() == vlan) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> String {
+    args.next().unwrap_or_else(|| usage_error(&format!("{} expects a value", flag)))
+}
+
+fn parse<T: FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> T {
+    let value = value(args, flag);
+    value.parse().unwrap_or_else(|_| usage_error(&format!("invalid value {} for {}", value, flag)))
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("{}", USAGE);
+    exit(2);
+}
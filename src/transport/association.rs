@@ -0,0 +1,267 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use prettytable::{format, Table, row};
+use crate::transport::sctp::{self, Chunk, PayloadProtocol, SctpPacket};
+
+/// Largest message reassembled from fragments, longer ones being dropped.
+const MAXIMUM_MESSAGE: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssociationState {
+    CookieWait,
+    CookieEchoed,
+    Established,
+    ShutdownPending,
+    ShutdownAckSent,
+    Closed,
+    Aborted,
+}
+
+/// An SCTP association between two endpoints, followed through its handshake and shutdown.
+#[derive(Debug)]
+pub struct Association {
+    id: usize,
+    /// The endpoint that sent the INIT, or the first one seen
+    initiator: SocketAddr,
+    responder: SocketAddr,
+    state: AssociationState,
+    /// Data chunks and user data bytes sent by the initiator and the responder
+    chunks: [usize; 2],
+    bytes: [usize; 2],
+    streams: BTreeSet<u16>,
+    payload_protocols: BTreeSet<u32>,
+    first_seen: Duration,
+    last_seen: Duration,
+    /// Messages whose fragments are still coming, by direction, stream, unordered flag and
+    /// I-DATA message identifier
+    fragments: HashMap<(usize, u16, bool, Option<u32>), Fragments>,
+}
+
+/// The fragments of a message received so far.
+#[derive(Debug)]
+struct Fragments {
+    ppid: PayloadProtocol,
+    /// The TSN of a DATA fragment, or the fragment sequence number of an I-DATA one, expected
+    /// next
+    next: u32,
+    data: Vec<u8>,
+}
+
+impl Association {
+    #[inline]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    #[inline]
+    pub fn state(&self) -> AssociationState {
+        self.state
+    }
+
+    fn is_over(&self) -> bool {
+        matches!(self.state, AssociationState::Closed | AssociationState::Aborted)
+    }
+
+    fn connects(&self, source: SocketAddr, destination: SocketAddr) -> bool {
+        (self.initiator == source && self.responder == destination)
+            || (self.initiator == destination && self.responder == source)
+    }
+}
+
+/// The SCTP associations seen during a capture.
+#[derive(Debug, Default)]
+pub struct Associations {
+    associations: Vec<Association>,
+}
+
+impl Associations {
+    /// Advances the association the packet belongs to, starting a new one on an INIT or when
+    /// the endpoints were never seen together before.
+    pub fn update(&mut self, source: IpAddr, destination: IpAddr, packet: &SctpPacket, timestamp: Duration) -> &Association {
+        let source = SocketAddr::new(source, packet.source());
+        let destination = SocketAddr::new(destination, packet.destination());
+        let starts = packet.chunks().iter().any(|chunk| chunk.chunk_type() == sctp::INIT);
+
+        let existing = self.associations.iter().rposition(|association| {
+            association.connects(source, destination) && !(starts && association.is_over())
+        });
+        let position = match existing {
+            Some(position) => position,
+            None => {
+                self.associations.push(Association {
+                    id: self.associations.len() + 1,
+                    initiator: source,
+                    responder: destination,
+                    state: AssociationState::CookieWait,
+                    chunks: [0; 2],
+                    bytes: [0; 2],
+                    streams: BTreeSet::new(),
+                    payload_protocols: BTreeSet::new(),
+                    first_seen: timestamp,
+                    last_seen: timestamp,
+                    fragments: HashMap::new(),
+                });
+                self.associations.len() - 1
+            }
+        };
+
+        let association = &mut self.associations[position];
+        let direction = if association.initiator == source { 0 } else { 1 };
+        association.last_seen = timestamp;
+        for chunk in packet.chunks() {
+            association.state = match (chunk.chunk_type(), association.state) {
+                (sctp::INIT, _) => AssociationState::CookieWait,
+                (sctp::COOKIE_ECHO, _) => AssociationState::CookieEchoed,
+                (sctp::COOKIE_ACK, _) => AssociationState::Established,
+                (sctp::SHUTDOWN, _) => AssociationState::ShutdownPending,
+                (sctp::SHUTDOWN_ACK, _) => AssociationState::ShutdownAckSent,
+                (sctp::SHUTDOWN_COMPLETE, _) => AssociationState::Closed,
+                (sctp::ABORT, _) => AssociationState::Aborted,
+                // Data on an association whose handshake was not captured
+                (sctp::DATA | sctp::I_DATA, AssociationState::CookieWait) => AssociationState::Established,
+                (_, state) => state
+            };
+            if let Some(data) = chunk.user_data() {
+                association.chunks[direction] += 1;
+                association.bytes[direction] += data.len();
+            }
+            if let Some(stream_id) = chunk.stream_id() {
+                association.streams.insert(stream_id);
+            }
+            if let Some(ppid) = chunk.ppid() {
+                association.payload_protocols.insert(ppid);
+            }
+        }
+        association
+    }
+
+    /// Adds the user data of a DATA or I-DATA chunk the source sent over an association to the
+    /// message it belongs to, and returns the payload protocol and the whole message once the
+    /// last fragment came. A missing fragment drops the message.
+    pub fn reassemble(&mut self, id: usize, source: SocketAddr, chunk: &Chunk) -> Option<(PayloadProtocol, Vec<u8>)> {
+        let data = chunk.user_data()?;
+        if chunk.unfragmented() {
+            return Some((chunk.ppid()?, data.to_vec()));
+        }
+        let association = self.associations.get_mut(id.checked_sub(1)?)?;
+        let direction = if association.initiator == source { 0 } else { 1 };
+        let key = (direction, chunk.stream_id()?, chunk.is_unordered(), chunk.message_id());
+        // DATA fragments take consecutive TSNs, I-DATA ones consecutive fragment sequence numbers
+        let sequence = match chunk.chunk_type() {
+            sctp::I_DATA => chunk.fragment_sequence()?,
+            _ => chunk.tsn()?
+        };
+        if chunk.is_beginning() {
+            let fragments = Fragments {
+                ppid: chunk.ppid()?,
+                next: sequence.wrapping_add(1),
+                data: data.to_vec(),
+            };
+            association.fragments.insert(key, fragments);
+            return None;
+        }
+
+        let fragments = association.fragments.get_mut(&key)?;
+        // A retransmission of a fragment already added
+        if (sequence.wrapping_sub(fragments.next) as i32) < 0 {
+            return None;
+        }
+        if sequence != fragments.next || fragments.data.len() + data.len() > MAXIMUM_MESSAGE {
+            association.fragments.remove(&key);
+            return None;
+        }
+        fragments.data.extend_from_slice(data);
+        fragments.next = sequence.wrapping_add(1);
+        if !chunk.is_ending() {
+            return None;
+        }
+        association.fragments.remove(&key).map(|fragments| (fragments.ppid, fragments.data))
+    }
+}
+
+impl Display for AssociationState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = match self {
+            AssociationState::CookieWait => "cookie-wait",
+            AssociationState::CookieEchoed => "cookie-echoed",
+            AssociationState::Established => "established",
+            AssociationState::ShutdownPending => "shutdown-pending",
+            AssociationState::ShutdownAckSent => "shutdown-ack-sent",
+            AssociationState::Closed => "closed",
+            AssociationState::Aborted => "aborted"
+        };
+        write!(f, "{}", state)
+    }
+}
+
+impl Display for Associations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.set_titles(row![
+            "id", "initiator", "responder", "state", "data_chunks", "bytes", "streams", "ppids", "duration"
+        ]);
+        for association in self.associations.iter() {
+            let streams: Vec<String> = association.streams.iter().map(|stream| stream.to_string()).collect();
+            let ppids: Vec<String> = association.payload_protocols.iter().map(|ppid| ppid.to_string()).collect();
+            table.add_row(row![
+                association.id,
+                association.initiator,
+                association.responder,
+                association.state,
+                format!("{}/{}", association.chunks[0], association.chunks[1]),
+                format!("{}/{}", association.bytes[0], association.bytes[1]),
+                streams.join(","),
+                ppids.join(","),
+                format!("{:.3}", (association.last_seen - association.first_seen).as_secs_f64())
+            ]);
+        }
+
+        table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A packet from port 3868 to 3868 holding one DATA chunk of stream 0 with Diameter.
+    fn packet(tsn: u32, flags: u8, data: &[u8]) -> SctpPacket {
+        let mut bytes = vec![0x0f, 0x1c, 0x0f, 0x1c, 0, 0, 0, 1, 0, 0, 0, 0];
+        bytes.extend_from_slice(&[sctp::DATA, flags]);
+        bytes.extend_from_slice(&(16 + data.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&tsn.to_be_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        bytes.extend_from_slice(&sctp::PPID_DIAMETER.to_be_bytes());
+        bytes.extend_from_slice(data);
+        SctpPacket::new(&bytes).unwrap()
+    }
+
+    #[test]
+    fn reassembles_fragments_in_tsn_order() {
+        let (client, server) = ("10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap());
+        let source = SocketAddr::new(client, 3868);
+        let mut associations = Associations::default();
+        let fragments = [packet(10, 0x02, b"abc"), packet(11, 0x00, b"def"), packet(11, 0x00, b"def"), packet(12, 0x01, b"gh")];
+        let mut messages = Vec::new();
+        for packet in fragments.iter() {
+            let id = associations.update(client, server, packet, Duration::ZERO).id();
+            messages.push(associations.reassemble(id, source, &packet.chunks()[0]));
+        }
+        assert_eq!(messages, vec![None, None, None, Some((sctp::PPID_DIAMETER, b"abcdefgh".to_vec()))]);
+
+        // A gap drops the message
+        for packet in [packet(20, 0x02, b"abc"), packet(22, 0x01, b"gh")] {
+            assert_eq!(associations.reassemble(1, source, &packet.chunks()[0]), None);
+        }
+    }
+
+    #[test]
+    fn rejects_short_packets() {
+        assert!(SctpPacket::new(&[0x0f, 0x1c, 0x0f, 0x1c, 0, 0]).is_none());
+        assert!(crate::application::diameter::DiameterMessage::new(&[1, 0, 0, 20]).is_none());
+    }
+}
@@ -1,13 +1,26 @@
 pub mod udp;
 pub mod tcp;
+pub mod stream;
+pub mod sctp;
+pub mod association;
+pub mod quic;
 
 
 pub type Protocol = u8;
 
+pub const HOP_BY_HOP: Protocol = 0;
 pub const ICMP: Protocol = 1;
 pub const IGMP: Protocol = 2;
+pub const IPIP: Protocol = 4;
 pub const TCP: Protocol = 6;
+#[allow(dead_code)]
 pub const CHAOS: Protocol = 16;     // the protocol I use in my life...
 pub const UDP: Protocol = 17;
+#[allow(dead_code)]
 pub const RDP: Protocol = 27;
-pub const IPV6_ICMP: Protocol = 58;
\ No newline at end of file
+pub const IPV6: Protocol = 41;
+pub const GRE: Protocol = 47;
+pub const ESP: Protocol = 50;
+pub const AH: Protocol = 51;
+pub const IPV6_ICMP: Protocol = 58;
+pub const SCTP: Protocol = 132;
\ No newline at end of file
@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::net::SocketAddr;
+use std::time::Duration;
+use prettytable::{format, Table, row};
+use crate::application::tls::{Hello, HandshakeMessage};
+use crate::transport::quic::{Frame, PacketType, QuicPacket};
+use crate::transport::quic::crypto::PacketKeys;
+use crate::util::*;
+
+/// The CRYPTO frames of one direction, put back in order.
+#[derive(Debug, Default)]
+struct CryptoStream {
+    /// Contiguous data from offset zero not yet parsed into handshake messages
+    buffer: Vec<u8>,
+    /// The offset of the start of the buffer
+    consumed: u64,
+    /// Frames that arrived ahead of the buffer, by offset
+    pending: BTreeMap<u64, Vec<u8>>,
+}
+
+impl CryptoStream {
+    /// Adds a frame and returns the handshake messages it completed.
+    fn push(&mut self, offset: u64, data: &[u8]) -> Vec<HandshakeMessage> {
+        self.pending.insert(offset, Vec::from(data));
+        while let Some(entry) = self.pending.first_entry() {
+            let end = self.consumed + self.buffer.len() as u64;
+            if *entry.key() > end {
+                break;
+            }
+            let (offset, data) = entry.remove_entry();
+            let skip = (end - offset) as usize;
+            if skip < data.len() {
+                self.buffer.extend_from_slice(&data[skip..]);
+            }
+        }
+        let (messages, length) = HandshakeMessage::parse_all(&self.buffer);
+        self.buffer.drain(..length);
+        self.consumed += length as u64;
+        messages
+    }
+}
+
+/// The Initial keys of the client and the server for a destination connection id.
+fn initial_keys(version: u32, dcid: &[u8]) -> Option<[PacketKeys; 2]> {
+    Some([PacketKeys::initial(version, dcid, false)?, PacketKeys::initial(version, dcid, true)?])
+}
+
+/// A QUIC connection, recognised by any of the connection ids either side picked.
+#[derive(Debug)]
+pub struct Connection {
+    id: usize,
+    version: u32,
+    client: SocketAddr,
+    server: SocketAddr,
+    /// The destination connection id of the first Initial, which the Initial keys derive from
+    /// unless a Retry gave another one
+    original_dcid: Vec<u8>,
+    client_cids: Vec<Vec<u8>>,
+    server_cids: Vec<Vec<u8>>,
+    /// Initial packet keys of the client and the server
+    keys: Option<[PacketKeys; 2]>,
+    largest_packet_number: [Option<u64>; 2],
+    crypto: [CryptoStream; 2],
+    server_name: Option<String>,
+    alpn: Vec<String>,
+    packets: [usize; 2],
+    first_seen: Duration,
+    last_seen: Duration,
+}
+
+impl Connection {
+    #[inline]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+/// The QUIC connections seen during a capture.
+#[derive(Debug, Default)]
+pub struct Connections {
+    connections: Vec<Connection>,
+}
+
+impl Connections {
+    /// The length of the connection id a short header packet starts with, guessed from the ids
+    /// the peers announced in long headers.
+    pub fn short_dcid_length(&self, source: SocketAddr, destination: SocketAddr, data: &[u8]) -> usize {
+        let candidates = self.connections.iter().flat_map(|connection| {
+            connection.client_cids.iter().chain(connection.server_cids.iter())
+        });
+        let matched = candidates
+            .filter(|cid| !cid.is_empty() && data.get(1..1 + cid.len()) == Some(cid.as_slice()))
+            .map(|cid| cid.len())
+            .max();
+        matched.unwrap_or_else(|| {
+            // Fall back to the addresses, for peers that use empty connection ids
+            self.connections.iter().rev()
+                .find(|connection| connection.client == destination && connection.server == source)
+                .map(|connection| connection.client_cids.last().map_or(0, |cid| cid.len()))
+                .or_else(|| self.connections.iter().rev()
+                    .find(|connection| connection.client == source && connection.server == destination)
+                    .map(|connection| connection.server_cids.last().map_or(0, |cid| cid.len())))
+                .unwrap_or(0)
+        })
+    }
+
+    /// Finds the connection and direction of the packet, 0 from the client and 1 from the server.
+    fn find(&self, packet: &QuicPacket, source: SocketAddr, destination: SocketAddr) -> Option<(usize, usize)> {
+        let dcid = packet.dcid();
+        for (position, connection) in self.connections.iter().enumerate().rev() {
+            if !dcid.is_empty() {
+                if dcid == connection.original_dcid || connection.server_cids.iter().any(|cid| cid == dcid) {
+                    return Some((position, 0));
+                }
+                if connection.client_cids.iter().any(|cid| cid == dcid) {
+                    return Some((position, 1));
+                }
+            } else if connection.client == source && connection.server == destination {
+                return Some((position, 0));
+            } else if connection.client == destination && connection.server == source {
+                return Some((position, 1));
+            }
+        }
+        None
+    }
+
+    /// Attributes the packet to its connection, decrypting it when it is an Initial, and returns
+    /// the connection with the hellos the packet completed.
+    pub fn update(
+        &mut self, source: SocketAddr, destination: SocketAddr, packet: &mut QuicPacket, timestamp: Duration
+    ) -> Option<(&Connection, Vec<Hello>)> {
+        let (position, direction) = match self.find(packet, source, destination) {
+            Some(found) => found,
+            // Only a client Initial opens a connection
+            None if packet.packet_type() == PacketType::Initial => {
+                self.connections.push(Connection {
+                    id: self.connections.len() + 1,
+                    version: packet.version(),
+                    client: source,
+                    server: destination,
+                    original_dcid: Vec::from(packet.dcid()),
+                    client_cids: Vec::new(),
+                    server_cids: Vec::new(),
+                    keys: initial_keys(packet.version(), packet.dcid()),
+                    largest_packet_number: [None; 2],
+                    crypto: Default::default(),
+                    server_name: None,
+                    alpn: Vec::new(),
+                    packets: [0; 2],
+                    first_seen: timestamp,
+                    last_seen: timestamp,
+                });
+                (self.connections.len() - 1, 0)
+            }
+            None => return None
+        };
+
+        let connection = &mut self.connections[position];
+        connection.packets[direction] += 1;
+        connection.last_seen = timestamp;
+        if packet.is_long() {
+            let cids = if direction == 0 { &mut connection.client_cids } else { &mut connection.server_cids };
+            if !cids.iter().any(|cid| cid == packet.scid()) {
+                cids.push(Vec::from(packet.scid()));
+            }
+        }
+
+        let mut hellos = Vec::new();
+        // The client starts over with the connection id of the Retry, which the keys now derive from
+        if packet.packet_type() == PacketType::Retry && direction == 1 {
+            connection.keys = initial_keys(packet.version(), packet.scid());
+            connection.crypto = Default::default();
+        }
+        if packet.packet_type() != PacketType::Initial {
+            return Some((connection, hellos));
+        }
+        let largest = &mut connection.largest_packet_number[direction];
+        let decrypted = connection.keys.as_ref().and_then(|keys| keys[direction].decrypt(packet, *largest));
+        if let Some((packet_number, payload)) = decrypted {
+            *largest = Some(largest.map_or(packet_number, |largest| largest.max(packet_number)));
+            packet.set_payload(packet_number, &payload);
+
+            for frame in packet.frames() {
+                if let Frame::Crypto { offset, data } = frame {
+                    for message in connection.crypto[direction].push(*offset, data) {
+                        if let Some(hello) = Hello::new(&message) {
+                            if hello.is_client() {
+                                connection.server_name = hello.server_name();
+                            }
+                            if !hello.alpn().is_empty() {
+                                connection.alpn = hello.alpn();
+                            }
+                            hellos.push(hello);
+                        }
+                    }
+                }
+            }
+        }
+        Some((connection, hellos))
+    }
+}
+
+impl Display for Connections {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = Table::new();
+        table.set_titles(row!["id", "client", "server", "version", "original_dcid", "server_name", "alpn", "packets", "duration"]);
+        for connection in self.connections.iter() {
+            table.add_row(row![
+                connection.id,
+                connection.client,
+                connection.server,
+                format!("{:08X}", connection.version),
+                hex(&connection.original_dcid),
+                connection.server_name.clone().unwrap_or_default(),
+                connection.alpn.join(","),
+                format!("{}/{}", connection.packets[0], connection.packets[1]),
+                format!("{:.3}", (connection.last_seen - connection.first_seen).as_secs_f64())
+            ]);
+        }
+
+        table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use aes::Aes128;
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::cipher::generic_array::GenericArray;
+use aes_gcm::{Aes128Gcm, Nonce};
+use aes_gcm::aead::{Aead, Payload};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use crate::application::tls::cipher::HashAlgorithm;
+use crate::transport::quic::{self, QuicPacket};
+
+const INITIAL_SALT_V1: [u8; 20] = [
+    0x38, 0x76, 0x2c, 0xf7, 0xf5, 0x59, 0x34, 0xb3, 0x4d, 0x17,
+    0x9a, 0xe6, 0xa4, 0xc8, 0x0c, 0xad, 0xcc, 0xbb, 0x7f, 0x0a
+];
+const INITIAL_SALT_V2: [u8; 20] = [
+    0x0d, 0xed, 0xe3, 0xde, 0xf7, 0x00, 0xa6, 0xdb, 0x81, 0x93,
+    0x81, 0xbe, 0x6e, 0x26, 0x9d, 0xcb, 0xf9, 0xbd, 0x2e, 0xd9
+];
+
+/// The packet protection keys of one direction of a connection, derived with AES-128-GCM in mind.
+#[derive(Debug, Clone)]
+pub struct PacketKeys {
+    key: Vec<u8>,
+    iv: Vec<u8>,
+    hp: Vec<u8>,
+}
+
+impl PacketKeys {
+    /// Derives the keys from a traffic secret, with the labels of the QUIC version.
+    pub fn new(version: u32, secret: &[u8]) -> Option<Self> {
+        let prefix = if version == quic::VERSION_2 { "quicv2" } else { "quic" };
+        let hash = HashAlgorithm::Sha256;
+        Some(Self {
+            key: hash.expand_label(secret, &format!("{} key", prefix), 16)?,
+            iv: hash.expand_label(secret, &format!("{} iv", prefix), 12)?,
+            hp: hash.expand_label(secret, &format!("{} hp", prefix), 16)?,
+        })
+    }
+
+    /// The keys protecting Initial packets, which anyone can derive from the destination
+    /// connection id the client first picked. None for versions without known salts.
+    pub fn initial(version: u32, dcid: &[u8], server: bool) -> Option<Self> {
+        let salt = match version {
+            quic::VERSION_1 => &INITIAL_SALT_V1,
+            quic::VERSION_2 => &INITIAL_SALT_V2,
+            _ => return None
+        };
+        let (initial_secret, _) = Hkdf::<Sha256>::extract(Some(salt), dcid);
+        let secret = HashAlgorithm::Sha256.expand_label(&initial_secret, if server { "server in" } else { "client in" }, 32)?;
+        Self::new(version, &secret)
+    }
+
+    /// Removes header protection and decrypts the packet, giving the full packet number, expanded
+    /// from the largest one of the direction so far, and the payload. None when the packet does
+    /// not authenticate.
+    pub fn decrypt(&self, packet: &QuicPacket, largest: Option<u64>) -> Option<(u64, Vec<u8>)> {
+        let protected = packet.protected.as_slice();
+        // The sample is taken as if the packet number were 4 bytes long
+        let sample = protected.get(4..20)?;
+        let mut mask = GenericArray::clone_from_slice(sample);
+        Aes128::new_from_slice(&self.hp).ok()?.encrypt_block(&mut mask);
+
+        let first_byte = packet.first_byte ^ (mask[0] & if packet.is_long() { 0x0f } else { 0x1f });
+        let length = (first_byte & 0x03) as usize + 1;
+        let mut header = packet.header.clone();
+        header[0] = first_byte;
+        let mut truncated = 0;
+        for (index, byte) in protected.get(..length)?.iter().enumerate() {
+            let byte = byte ^ mask[1 + index];
+            header.push(byte);
+            truncated = truncated << 8 | byte as u64;
+        }
+
+        // The nonce takes the full packet number, not the bytes sent of it
+        let packet_number = decode_packet_number(largest, truncated, length);
+        let mut nonce = [0; 12];
+        nonce.copy_from_slice(&self.iv);
+        for (index, byte) in packet_number.to_be_bytes().iter().enumerate() {
+            nonce[4 + index] ^= byte;
+        }
+        let payload = Payload { msg: &protected[length..], aad: &header };
+        let plaintext = Aes128Gcm::new_from_slice(&self.key).ok()?
+            .decrypt(Nonce::from_slice(&nonce), payload)
+            .ok()?;
+        Some((packet_number, plaintext))
+    }
+}
+
+/// Expands a truncated packet number to the one closest to the next expected, as in RFC 9000
+/// appendix A.3.
+pub fn decode_packet_number(largest: Option<u64>, truncated: u64, length: usize) -> u64 {
+    let expected = largest.map_or(0, |largest| largest + 1);
+    let window = 1u64 << (length * 8);
+    let candidate = (expected & !(window - 1)) | truncated;
+    if candidate + window / 2 <= expected && candidate < (1 << 62) - window {
+        candidate + window
+    } else if candidate > expected + window / 2 && candidate >= window {
+        candidate - window
+    } else {
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{hex, unhex};
+
+    #[test]
+    fn decodes_packet_number_example() {
+        // RFC 9000 appendix A.3
+        assert_eq!(decode_packet_number(Some(0xa82f30ea), 0x9b32, 2), 0xa82f9b32);
+        assert_eq!(decode_packet_number(None, 0, 1), 0);
+        assert_eq!(decode_packet_number(Some(250), 0x2c, 1), 300);
+    }
+
+    #[test]
+    fn derives_initial_keys() {
+        // RFC 9001 appendix A.1
+        let dcid = unhex("8394C8F03E515708").unwrap();
+        let client = PacketKeys::initial(quic::VERSION_1, &dcid, false).unwrap();
+        assert_eq!(hex(&client.key), "1F369613DD76D5467730EFCBE3B1A22D");
+        assert_eq!(hex(&client.iv), "FA044B2F42A3FD3B46FB255C");
+        assert_eq!(hex(&client.hp), "9F50449E04A0E810283A1E9933ADEDD2");
+        let server = PacketKeys::initial(quic::VERSION_1, &dcid, true).unwrap();
+        assert_eq!(hex(&server.key), "CF3A5331653C364C88F0F379B6067E37");
+        assert_eq!(hex(&server.iv), "0AC1493CA1905853B0BBA03E");
+        assert_eq!(hex(&server.hp), "C206B8D9B9F0F37644430B490EEAA314");
+    }
+
+    #[test]
+    fn decrypts_server_initial() {
+        // RFC 9001 appendix A.3
+        let data = unhex(concat!(
+            "CF000000010008F067A5502A4262B5004075C0D95A482CD0991CD25B0AAC406A",
+            "5816B6394100F37A1C69797554780BB38CC5A99F5EDE4CF73C3EC2493A1839B3",
+            "DBCBA3F6EA46C5B7684DF3548E7DDEB9C3BF9C73CC3F3BDED74B562BFB19FB84",
+            "022F8EF4CDD93795D77D06EDBB7AAF2F58891850ABBDCA3D20398C276456CBC4",
+            "2158407DD074EE"
+        )).unwrap();
+        let (packet, _) = QuicPacket::parse(&data, 0).unwrap();
+        let keys = PacketKeys::initial(quic::VERSION_1, &unhex("8394C8F03E515708").unwrap(), true).unwrap();
+        let (packet_number, payload) = keys.decrypt(&packet, None).unwrap();
+        assert_eq!(packet_number, 1);
+        assert!(hex(&payload).starts_with("02000000000600405A020000560303EEFCE7F7B37BA1D1632E96677825DDF739"));
+    }
+}
@@ -0,0 +1,306 @@
+pub mod crypto;
+pub mod connection;
+
+
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table, row};
+use crate::util::*;
+
+pub const PORT: u16 = 443;
+
+pub const VERSION_NEGOTIATION: u32 = 0x00000000;
+pub const VERSION_1: u32 = 0x00000001;
+pub const VERSION_2: u32 = 0x6b3343cf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketType {
+    Initial,
+    ZeroRtt,
+    Handshake,
+    Retry,
+    VersionNegotiation,
+    OneRtt,
+}
+
+impl PacketType {
+    /// The packet number space the packet belongs to, 0-RTT and 1-RTT sharing the application one.
+    pub fn space(&self) -> &'static str {
+        match self {
+            PacketType::Initial => "initial",
+            PacketType::Handshake => "handshake",
+            PacketType::ZeroRtt | PacketType::OneRtt => "application data",
+            PacketType::Retry | PacketType::VersionNegotiation => "none"
+        }
+    }
+}
+
+/// Reads a variable length integer, whose first two bits give its length. Returns the value and
+/// the number of bytes it took.
+pub fn varint(data: &[u8]) -> Option<(u64, usize)> {
+    let length = 1 << (data.first()? >> 6);
+    let bytes = data.get(..length)?;
+    let value = bytes[1..].iter().fold((bytes[0] & 0x3f) as u64, |value, byte| value << 8 | *byte as u64);
+    Some((value, length))
+}
+
+/// A QUIC packet with a long or short header. Packets are protected, so the packet number and
+/// the frames are only known once it has been decrypted.
+#[derive(Debug)]
+pub struct QuicPacket {
+    packet_type: PacketType,
+    first_byte: u8,
+    version: u32,
+    dcid: Vec<u8>,
+    scid: Vec<u8>,
+    token: Vec<u8>,
+    supported_versions: Vec<u32>,
+    /// The header up to the packet number
+    header: Vec<u8>,
+    /// The packet number and the payload, still protected
+    protected: Vec<u8>,
+    packet_number: Option<u64>,
+    frames: Vec<Frame>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Frame {
+    Padding(usize),
+    Ping,
+    Ack { largest: u64, delay: u64, ranges: u64 },
+    Crypto { offset: u64, data: Vec<u8> },
+    ConnectionClose { error_code: u64, reason: String },
+    /// A frame not allowed in the packet types that can be decrypted, which ends parsing
+    Other(u64),
+}
+
+impl QuicPacket {
+    /// Parses the packet at the start of a datagram, returning it and its length since packets
+    /// with long headers can be coalesced. Short headers do not tell the destination connection
+    /// id length, which has to come from the connections seen so far.
+    pub fn parse(data: &[u8], short_dcid_length: usize) -> Option<(Self, usize)> {
+        let first_byte = *data.first()?;
+        if first_byte & 0x80 == 0 {
+            let dcid = Vec::from(data.get(1..1 + short_dcid_length)?);
+            return Some((Self {
+                packet_type: PacketType::OneRtt,
+                first_byte,
+                version: 0,
+                dcid,
+                scid: Vec::new(),
+                token: Vec::new(),
+                supported_versions: Vec::new(),
+                header: Vec::from(&data[..1 + short_dcid_length]),
+                protected: Vec::from(&data[1 + short_dcid_length..]),
+                packet_number: None,
+                frames: Vec::new(),
+            }, data.len()));
+        }
+
+        let version = tou32(data.get(1..5)?);
+        let dcid_length = *data.get(5)? as usize;
+        let dcid = Vec::from(data.get(6..6 + dcid_length)?);
+        let mut offset = 6 + dcid_length;
+        let scid_length = *data.get(offset)? as usize;
+        let scid = Vec::from(data.get(offset + 1..offset + 1 + scid_length)?);
+        offset += 1 + scid_length;
+
+        let mut packet = Self {
+            packet_type: PacketType::VersionNegotiation,
+            first_byte,
+            version,
+            dcid,
+            scid,
+            token: Vec::new(),
+            supported_versions: Vec::new(),
+            header: Vec::new(),
+            protected: Vec::new(),
+            packet_number: None,
+            frames: Vec::new(),
+        };
+        if version == VERSION_NEGOTIATION {
+            packet.supported_versions = data[offset..].chunks_exact(4).map(tou32).collect();
+            return Some((packet, data.len()));
+        }
+
+        // Version 2 shuffled the type bits around
+        let bits = (first_byte >> 4) & 0x03;
+        packet.packet_type = match (version == VERSION_2, bits) {
+            (false, 0) | (true, 1) => PacketType::Initial,
+            (false, 1) | (true, 2) => PacketType::ZeroRtt,
+            (false, 2) | (true, 3) => PacketType::Handshake,
+            _ => PacketType::Retry
+        };
+        if packet.packet_type == PacketType::Retry {
+            packet.token = Vec::from(&data[offset..data.len().saturating_sub(16).max(offset)]);
+            return Some((packet, data.len()));
+        }
+        if packet.packet_type == PacketType::Initial {
+            let (length, size) = varint(&data[offset..])?;
+            offset += size;
+            packet.token = Vec::from(data.get(offset..offset + length as usize)?);
+            offset += length as usize;
+        }
+        let (length, size) = varint(&data[offset..])?;
+        offset += size;
+        let end = (offset + length as usize).min(data.len());
+        packet.header = Vec::from(&data[..offset]);
+        packet.protected = Vec::from(&data[offset..end]);
+        Some((packet, end))
+    }
+
+    #[inline]
+    pub fn packet_type(&self) -> PacketType {
+        self.packet_type
+    }
+
+    #[inline]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    #[inline]
+    pub fn dcid(&self) -> &[u8] {
+        &self.dcid
+    }
+
+    #[inline]
+    pub fn scid(&self) -> &[u8] {
+        &self.scid
+    }
+
+    #[inline]
+    pub fn is_long(&self) -> bool {
+        self.first_byte & 0x80 != 0
+    }
+
+    #[inline]
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Parses the frames of the decrypted payload.
+    fn set_payload(&mut self, packet_number: u64, payload: &[u8]) {
+        self.packet_number = Some(packet_number);
+        let mut rest = payload;
+        while let Some((frame_type, size)) = varint(rest) {
+            rest = &rest[size..];
+            let frame = match frame_type {
+                0x00 => {
+                    let count = rest.iter().take_while(|byte| **byte == 0).count();
+                    rest = &rest[count..];
+                    Frame::Padding(count + 1)
+                }
+                0x01 => Frame::Ping,
+                0x02 | 0x03 => {
+                    let mut fields = [0; 4];
+                    for field in fields.iter_mut() {
+                        let Some((value, size)) = varint(rest) else { break };
+                        *field = value;
+                        rest = &rest[size..];
+                    }
+                    let [largest, delay, ranges, _first_range] = fields;
+                    // Skip the gap and length of every further range, and the ECN counts
+                    let skip = ranges * 2 + if frame_type == 0x03 { 3 } else { 0 };
+                    for _ in 0..skip {
+                        let Some((_, size)) = varint(rest) else { break };
+                        rest = &rest[size..];
+                    }
+                    Frame::Ack { largest, delay, ranges: ranges + 1 }
+                }
+                0x06 => {
+                    let Some((offset, size)) = varint(rest) else { break };
+                    rest = &rest[size..];
+                    let Some((length, size)) = varint(rest) else { break };
+                    let length = (length as usize).min(rest.len() - size);
+                    let data = Vec::from(&rest[size..size + length]);
+                    rest = &rest[size + length..];
+                    Frame::Crypto { offset, data }
+                }
+                0x1c | 0x1d => {
+                    let Some((error_code, size)) = varint(rest) else { break };
+                    rest = &rest[size..];
+                    if frame_type == 0x1c {
+                        let Some((_, size)) = varint(rest) else { break };
+                        rest = &rest[size..];
+                    }
+                    let Some((length, size)) = varint(rest) else { break };
+                    let length = (length as usize).min(rest.len() - size);
+                    let reason = String::from_utf8_lossy(&rest[size..size + length]).into_owned();
+                    rest = &rest[size + length..];
+                    Frame::ConnectionClose { error_code, reason }
+                }
+                other => {
+                    self.frames.push(Frame::Other(other));
+                    break;
+                }
+            };
+            self.frames.push(frame);
+        }
+    }
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Frame::Padding(length) => write!(f, "padding {} bytes", length),
+            Frame::Ping => write!(f, "ping"),
+            Frame::Ack { largest, delay, ranges } => write!(f, "ack largest {} delay {} ranges {}", largest, delay, ranges),
+            Frame::Crypto { offset, data } => write!(f, "crypto offset {} length {}", offset, data.len()),
+            Frame::ConnectionClose { error_code, reason } => write!(f, "connection close {:X} {}", error_code, reason),
+            Frame::Other(frame_type) => write!(f, "{:02X}", frame_type)
+        }
+    }
+}
+
+impl Display for QuicPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let packet_type = match self.packet_type {
+            PacketType::Initial => "initial",
+            PacketType::ZeroRtt => "0-rtt",
+            PacketType::Handshake => "handshake",
+            PacketType::Retry => "retry",
+            PacketType::VersionNegotiation => "version negotiation",
+            PacketType::OneRtt => "1-rtt"
+        };
+        let version = match self.version {
+            VERSION_1 => String::from("1"),
+            VERSION_2 => String::from("2"),
+            version if version & 0x0f0f0f0f == 0x0a0a0a0a => String::from("reserved"),
+            version => format!("{:08X}", version)
+        };
+
+        let mut table = table!(
+            ["header_form", if self.is_long() { "long" } else { "short" }],
+            ["packet_type", packet_type],
+            ["packet_number_space", self.packet_type.space()]
+        );
+        if self.is_long() {
+            table.add_row(row!["version", version]);
+        }
+        table.add_row(row!["dcid", hex(&self.dcid)]);
+        if self.is_long() {
+            table.add_row(row!["scid", hex(&self.scid)]);
+        }
+        if !self.token.is_empty() {
+            table.add_row(row!["token", hex(&self.token)]);
+        }
+        for supported_version in self.supported_versions.iter() {
+            table.add_row(row!["supported_version", format!("{:08X}", supported_version)]);
+        }
+        if !self.protected.is_empty() {
+            table.add_row(row!["length", self.protected.len()]);
+        }
+        if let Some(packet_number) = self.packet_number {
+            table.add_row(row!["packet_number", packet_number]);
+        }
+        for frame in self.frames.iter() {
+            table.add_row(row!["frame", frame]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
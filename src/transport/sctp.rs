@@ -0,0 +1,363 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table, row};
+use crate::util::*;
+
+pub type ChunkType = u8;
+
+pub const DATA: ChunkType = 0;
+pub const INIT: ChunkType = 1;
+pub const INIT_ACK: ChunkType = 2;
+pub const SACK: ChunkType = 3;
+pub const HEARTBEAT: ChunkType = 4;
+pub const HEARTBEAT_ACK: ChunkType = 5;
+pub const ABORT: ChunkType = 6;
+pub const SHUTDOWN: ChunkType = 7;
+pub const SHUTDOWN_ACK: ChunkType = 8;
+pub const ERROR: ChunkType = 9;
+pub const COOKIE_ECHO: ChunkType = 10;
+pub const COOKIE_ACK: ChunkType = 11;
+pub const ECNE: ChunkType = 12;
+pub const CWR: ChunkType = 13;
+pub const SHUTDOWN_COMPLETE: ChunkType = 14;
+pub const AUTH: ChunkType = 15;
+pub const I_DATA: ChunkType = 64;
+pub const ASCONF_ACK: ChunkType = 128;
+pub const RE_CONFIG: ChunkType = 130;
+pub const PAD: ChunkType = 132;
+pub const FORWARD_TSN: ChunkType = 192;
+pub const ASCONF: ChunkType = 193;
+pub const I_FORWARD_TSN: ChunkType = 194;
+
+pub type PayloadProtocol = u32;
+
+pub const PPID_M3UA: PayloadProtocol = 3;
+pub const PPID_S1AP: PayloadProtocol = 18;
+pub const PPID_X2AP: PayloadProtocol = 27;
+pub const PPID_DIAMETER: PayloadProtocol = 46;
+pub const PPID_DIAMETER_DTLS: PayloadProtocol = 47;
+pub const PPID_NGAP: PayloadProtocol = 60;
+
+fn payload_protocol_name(ppid: PayloadProtocol) -> &'static str {
+    match ppid {
+        PPID_M3UA => "m3ua",
+        PPID_S1AP => "s1ap",
+        PPID_X2AP => "x2ap",
+        PPID_DIAMETER => "diameter",
+        PPID_DIAMETER_DTLS => "diameter over dtls",
+        PPID_NGAP => "ngap",
+        _ => "unidentified"
+    }
+}
+
+/// An SCTP packet: the common header followed by a bundle of chunks.
+#[derive(Debug)]
+pub struct SctpPacket {
+    source_port: u16,
+    destination_port: u16,
+    verification_tag: u32,
+    checksum: u32,
+    checksum_valid: bool,
+    chunks: Vec<Chunk>,
+}
+
+#[derive(Debug)]
+pub struct Chunk {
+    chunk_type: ChunkType,
+    flags: u8,
+    value: Vec<u8>,
+}
+
+impl SctpPacket {
+    #[inline]
+    pub fn source(&self) -> u16 {
+        self.source_port
+    }
+
+    #[inline]
+    pub fn destination(&self) -> u16 {
+        self.destination_port
+    }
+
+    #[inline]
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+}
+
+/// The length of the common header before the chunks.
+const HEADER_LENGTH: usize = 12;
+
+impl SctpPacket {
+    /// Parses a packet, None when the data is shorter than the common header.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < HEADER_LENGTH {
+            return None;
+        }
+        // The checksum is computed with its own field zeroed, and sent in little endian
+        let mut zeroed = Vec::from(data);
+        zeroed[8..12].fill(0);
+        let checksum = u32::from_le_bytes([data[8], data[9], data[10], data[11]]);
+
+        let mut chunks = Vec::new();
+        let mut rest = &data[HEADER_LENGTH..];
+        while rest.len() >= 4 {
+            let length = (tou16(&rest[2..4]) as usize).clamp(4, rest.len());
+            chunks.push(Chunk {
+                chunk_type: rest[0],
+                flags: rest[1],
+                value: Vec::from(&rest[4..length]),
+            });
+            // Chunks are padded to 4 bytes, except maybe the last one
+            rest = &rest[(length.div_ceil(4) * 4).min(rest.len())..];
+        }
+
+        Some(Self {
+            source_port: tou16(&data[0..2]),
+            destination_port: tou16(&data[2..4]),
+            verification_tag: tou32(&data[4..8]),
+            checksum,
+            checksum_valid: crc32c(&zeroed) == checksum,
+            chunks,
+        })
+    }
+}
+
+impl Chunk {
+    #[inline]
+    pub fn chunk_type(&self) -> ChunkType {
+        self.chunk_type
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.chunk_type {
+            DATA => "data",
+            INIT => "init",
+            INIT_ACK => "init ack",
+            SACK => "sack",
+            HEARTBEAT => "heartbeat",
+            HEARTBEAT_ACK => "heartbeat ack",
+            ABORT => "abort",
+            SHUTDOWN => "shutdown",
+            SHUTDOWN_ACK => "shutdown ack",
+            ERROR => "error",
+            COOKIE_ECHO => "cookie echo",
+            COOKIE_ACK => "cookie ack",
+            ECNE => "ecne",
+            CWR => "cwr",
+            SHUTDOWN_COMPLETE => "shutdown complete",
+            AUTH => "auth",
+            I_DATA => "i-data",
+            ASCONF_ACK => "asconf ack",
+            RE_CONFIG => "re-config",
+            PAD => "pad",
+            FORWARD_TSN => "forward tsn",
+            ASCONF => "asconf",
+            I_FORWARD_TSN => "i-forward tsn",
+            _ => "unidentified"
+        }
+    }
+
+    /// The transmission sequence number of a DATA or I-DATA chunk.
+    pub fn tsn(&self) -> Option<u32> {
+        match self.chunk_type {
+            DATA | I_DATA if self.value.len() >= 4 => Some(tou32(&self.value[0..4])),
+            _ => None
+        }
+    }
+
+    /// The stream a DATA or I-DATA chunk belongs to.
+    pub fn stream_id(&self) -> Option<u16> {
+        match self.chunk_type {
+            DATA | I_DATA if self.value.len() >= 6 => Some(tou16(&self.value[4..6])),
+            _ => None
+        }
+    }
+
+    /// The payload protocol identifier of a DATA chunk, or of the first fragment of an I-DATA chunk.
+    pub fn ppid(&self) -> Option<PayloadProtocol> {
+        match self.chunk_type {
+            DATA if self.value.len() >= 12 => Some(tou32(&self.value[8..12])),
+            I_DATA if self.value.len() >= 16 && self.flags & 0x02 != 0 => Some(tou32(&self.value[12..16])),
+            _ => None
+        }
+    }
+
+    /// The user data of a DATA or I-DATA chunk.
+    pub fn user_data(&self) -> Option<&[u8]> {
+        match self.chunk_type {
+            DATA => self.value.get(12..),
+            I_DATA => self.value.get(16..),
+            _ => None
+        }
+    }
+
+    /// Whether the user data is a whole message, with both the beginning and ending flags set.
+    #[inline]
+    pub fn unfragmented(&self) -> bool {
+        self.flags & 0x03 == 0x03
+    }
+
+    /// Whether the user data is the first fragment of a message.
+    #[inline]
+    pub fn is_beginning(&self) -> bool {
+        self.flags & 0x02 != 0
+    }
+
+    /// Whether the user data is the last fragment of a message.
+    #[inline]
+    pub fn is_ending(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+
+    /// Whether the message is delivered out of the order of its stream.
+    #[inline]
+    pub fn is_unordered(&self) -> bool {
+        self.flags & 0x04 != 0
+    }
+
+    /// The message identifier of an I-DATA chunk, which its fragments share.
+    pub fn message_id(&self) -> Option<u32> {
+        match self.chunk_type {
+            I_DATA if self.value.len() >= 12 => Some(tou32(&self.value[8..12])),
+            _ => None
+        }
+    }
+
+    /// The place of an I-DATA fragment in its message, 0 for the first one whose field holds
+    /// the payload protocol instead.
+    pub fn fragment_sequence(&self) -> Option<u32> {
+        match self.chunk_type {
+            I_DATA if self.is_beginning() => Some(0),
+            I_DATA if self.value.len() >= 16 => Some(tou32(&self.value[12..16])),
+            _ => None
+        }
+    }
+
+    /// A one line description of the fields of the chunk.
+    pub fn summary(&self) -> String {
+        let value = self.value.as_slice();
+        match self.chunk_type {
+            DATA | I_DATA if value.len() >= 12 => {
+                let flags = format!(
+                    "{}{}{}{}",
+                    if self.flags & 0x08 != 0 { "I" } else { "" },
+                    if self.flags & 0x04 != 0 { "U" } else { "" },
+                    if self.flags & 0x02 != 0 { "B" } else { "" },
+                    if self.flags & 0x01 != 0 { "E" } else { "" }
+                );
+                let sequence = if self.chunk_type == DATA {
+                    format!("ssn {}", tou16(&value[6..8]))
+                } else {
+                    format!("mid {}", tou32(&value[8..12]))
+                };
+                let ppid = self.ppid()
+                    .map(|ppid| format!(" ppid {} ({})", ppid, payload_protocol_name(ppid)))
+                    .unwrap_or_default();
+                format!(
+                    "tsn {} stream {} {}{} flags {} length {}",
+                    tou32(&value[0..4]), tou16(&value[4..6]), sequence, ppid, flags,
+                    self.user_data().map(|data| data.len()).unwrap_or_default()
+                )
+            }
+            INIT | INIT_ACK if value.len() >= 16 => {
+                let parameters: Vec<String> = parameters(&value[16..]).iter()
+                    .map(|(parameter_type, parameter)| parameter_name(*parameter_type, parameter))
+                    .collect();
+                format!(
+                    "initiate_tag {:08X} a_rwnd {} streams {}/{} initial_tsn {} [{}]",
+                    tou32(&value[0..4]), tou32(&value[4..8]), tou16(&value[8..10]),
+                    tou16(&value[10..12]), tou32(&value[12..16]), parameters.join(", ")
+                )
+            }
+            SACK if value.len() >= 12 => {
+                let gaps = tou16(&value[8..10]) as usize;
+                let duplicates = tou16(&value[10..12]) as usize;
+                let blocks: Vec<String> = value[12..].chunks_exact(4).take(gaps)
+                    .map(|block| format!("{}-{}", tou16(&block[0..2]), tou16(&block[2..4])))
+                    .collect();
+                format!(
+                    "cumulative_tsn_ack {} a_rwnd {} gaps [{}] duplicates {}",
+                    tou32(&value[0..4]), tou32(&value[4..8]), blocks.join(", "), duplicates
+                )
+            }
+            SHUTDOWN if value.len() >= 4 => format!("cumulative_tsn_ack {}", tou32(&value[0..4])),
+            FORWARD_TSN if value.len() >= 4 => format!("new_cumulative_tsn {}", tou32(&value[0..4])),
+            ABORT | ERROR => {
+                let causes: Vec<String> = parameters(value).iter()
+                    .map(|(cause, _)| cause_name(*cause).to_string())
+                    .collect();
+                let tag = if self.flags & 0x01 != 0 { " (tag reflected)" } else { "" };
+                format!("causes [{}]{}", causes.join(", "), tag)
+            }
+            HEARTBEAT | HEARTBEAT_ACK | COOKIE_ECHO => format!("length {}", value.len()),
+            _ => String::new()
+        }
+    }
+}
+
+/// Splits type-length-value parameters or error causes, padded to 4 bytes.
+fn parameters(mut data: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut parameters = Vec::new();
+    while data.len() >= 4 {
+        let length = (tou16(&data[2..4]) as usize).clamp(4, data.len());
+        parameters.push((tou16(&data[0..2]), &data[4..length]));
+        data = &data[(length.div_ceil(4) * 4).min(data.len())..];
+    }
+    parameters
+}
+
+fn parameter_name(parameter_type: u16, value: &[u8]) -> String {
+    match (parameter_type, value) {
+        (5, [a, b, c, d]) => format!("ipv4 {}.{}.{}.{}", a, b, c, d),
+        (6, address) if address.len() == 16 => {
+            format!("ipv6 {}", std::net::Ipv6Addr::from(tou128(address)))
+        }
+        (7, _) => String::from("state cookie"),
+        (9, _) => String::from("cookie preservative"),
+        (11, _) => format!("hostname {}", String::from_utf8_lossy(value)),
+        (12, _) => String::from("supported address types"),
+        (0x8000, _) => String::from("ecn capable"),
+        (0x8008, _) => String::from("supported extensions"),
+        (0xC000, _) => String::from("forward tsn supported"),
+        _ => format!("{:04X}", parameter_type)
+    }
+}
+
+fn cause_name(cause: u16) -> &'static str {
+    match cause {
+        1 => "invalid stream identifier",
+        2 => "missing mandatory parameter",
+        3 => "stale cookie",
+        4 => "out of resource",
+        5 => "unresolvable address",
+        6 => "unrecognized chunk type",
+        7 => "invalid mandatory parameter",
+        8 => "unrecognized parameters",
+        9 => "no user data",
+        10 => "cookie received while shutting down",
+        11 => "restart with new addresses",
+        12 => "user initiated abort",
+        13 => "protocol violation",
+        _ => "unidentified"
+    }
+}
+
+impl Display for SctpPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["source_port", self.source_port],
+            ["destination_port", self.destination_port],
+            ["verification_tag", format!("{:08X}", self.verification_tag)],
+            ["checksum", format!("{:08X} ({})", self.checksum, if self.checksum_valid { "valid" } else { "invalid" })]
+        );
+        for chunk in self.chunks.iter() {
+            table.add_row(row![format!("chunk {}", chunk.name()), chunk.summary()]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
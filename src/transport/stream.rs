@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use crate::transport::tcp::{self, TcpSegment};
+use crate::util::DataContainer;
+
+/// Most bytes held back behind a missing segment, before giving up on it.
+const MAXIMUM_PENDING: usize = 1 << 20;
+
+/// One direction of a TCP connection, delivering bytes in sequence order.
+#[derive(Debug, Default)]
+struct Direction {
+    /// The sequence number of the first byte, unknown until the first segment
+    origin: Option<u32>,
+    /// The offset of the next byte to deliver from the first one
+    next: u32,
+    /// Segments ahead of the next byte, by offset
+    pending: BTreeMap<u32, Vec<u8>>,
+    /// The bytes held in pending
+    buffered: usize,
+    /// The offset the FIN takes, once seen
+    fin: Option<u32>,
+    finished: bool,
+}
+
+impl Direction {
+    /// Adds a segment and returns the bytes that became contiguous, along with how many missing
+    /// bytes were skipped before them.
+    fn push(&mut self, segment: &TcpSegment, reset: bool) -> (usize, Vec<u8>) {
+        let mut sequence = segment.sequence_number();
+        if segment.has_flag(tcp::FLAG_SYN) {
+            sequence = sequence.wrapping_add(1);
+        }
+        let origin = *self.origin.get_or_insert(sequence);
+        let data = segment.data();
+        if segment.has_flag(tcp::FLAG_FIN) {
+            self.fin = Some(sequence.wrapping_sub(origin).wrapping_add(data.len() as u32));
+        }
+        let ahead = sequence.wrapping_sub(origin).wrapping_sub(self.next) as i32;
+        // A retransmission overlapping what was already delivered keeps only its new bytes
+        let skip = ahead.min(0).unsigned_abs() as usize;
+        if skip < data.len() {
+            let offset = self.next.wrapping_add(ahead.max(0) as u32);
+            let data = &data[skip..];
+            let buffered = self.pending.get(&offset).map_or(0, Vec::len);
+            if buffered < data.len() {
+                self.buffered += data.len() - buffered;
+                self.pending.insert(offset, Vec::from(data));
+            }
+        }
+
+        let mut gap = 0;
+        let mut delivered = Vec::new();
+        loop {
+            while let Some(entry) = self.pending.first_entry() {
+                if *entry.key() > self.next {
+                    break;
+                }
+                let (offset, data) = entry.remove_entry();
+                self.buffered -= data.len();
+                let skip = self.next.wrapping_sub(offset) as usize;
+                if skip < data.len() {
+                    delivered.extend_from_slice(&data[skip..]);
+                    self.next = self.next.wrapping_add((data.len() - skip) as u32);
+                }
+            }
+            // Skip a segment that never arrived once too much waits behind it, or nothing more
+            // can come to fill it
+            let ended = reset || self.pending.last_key_value()
+                .is_some_and(|(offset, data)| Some(offset.wrapping_add(data.len() as u32)) == self.fin);
+            match self.pending.first_key_value() {
+                Some((&offset, _)) if ended || self.buffered > MAXIMUM_PENDING => {
+                    gap += offset.wrapping_sub(self.next) as usize;
+                    self.next = offset;
+                }
+                _ => break
+            }
+        }
+        if self.fin == Some(self.next) {
+            self.finished = true;
+        }
+        (gap, delivered)
+    }
+}
+
+/// A TCP connection, its client being the side that sent the SYN when it was captured.
+#[derive(Debug)]
+pub struct TcpStream {
+    id: usize,
+    client: SocketAddr,
+    server: SocketAddr,
+    directions: [Direction; 2],
+    reset: bool,
+}
+
+impl TcpStream {
+    #[inline]
+    pub fn client(&self) -> SocketAddr {
+        self.client
+    }
+
+    #[inline]
+    pub fn server(&self) -> SocketAddr {
+        self.server
+    }
+
+    /// Whether the side finished sending, 0 being the client and 1 the server.
+    #[inline]
+    pub fn finished(&self, direction: usize) -> bool {
+        self.reset || self.directions[direction].finished
+    }
+
+    fn is_over(&self) -> bool {
+        self.reset || (self.directions[0].finished && self.directions[1].finished)
+    }
+}
+
+/// What a segment added to its stream.
+#[derive(Debug)]
+pub struct StreamData {
+    pub stream: usize,
+    /// 0 from the client, 1 from the server
+    pub direction: usize,
+    pub data: Vec<u8>,
+    /// Bytes missing before the data, which never arrived
+    pub gap: usize,
+    /// Whether the sender finished its side with this segment
+    pub finished: bool,
+}
+
+/// Puts the segments of the TCP connections of a capture back in order.
+#[derive(Debug, Default)]
+pub struct TcpStreams {
+    streams: Vec<TcpStream>,
+}
+
+impl TcpStreams {
+    pub fn update(&mut self, source: SocketAddr, destination: SocketAddr, segment: &TcpSegment) -> StreamData {
+        let existing = self.streams.iter().rposition(|stream| {
+            (stream.client == source && stream.server == destination)
+                || (stream.client == destination && stream.server == source)
+        });
+        let syn = segment.has_flag(tcp::FLAG_SYN) && !segment.has_flag(tcp::FLAG_ACK);
+        let position = match existing {
+            // A new SYN on a finished connection reuses its ports for a new one
+            Some(position) if !(syn && self.streams[position].is_over()) => position,
+            _ => {
+                // Without the handshake, guess the server is the side with the lower port
+                let client_sends = if segment.has_flag(tcp::FLAG_SYN) {
+                    syn
+                } else {
+                    source.port() > destination.port()
+                };
+                let (client, server) = if client_sends { (source, destination) } else { (destination, source) };
+                self.streams.push(TcpStream {
+                    id: self.streams.len() + 1,
+                    client,
+                    server,
+                    directions: Default::default(),
+                    reset: false,
+                });
+                self.streams.len() - 1
+            }
+        };
+
+        let stream = &mut self.streams[position];
+        let direction = if stream.client == source { 0 } else { 1 };
+        let finished_before = stream.finished(direction);
+        if segment.has_flag(tcp::FLAG_RST) {
+            stream.reset = true;
+        }
+        let (gap, data) = stream.directions[direction].push(segment, stream.reset);
+        StreamData {
+            stream: stream.id,
+            direction,
+            data,
+            gap,
+            finished: !finished_before && stream.finished(direction),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, id: usize) -> Option<&TcpStream> {
+        self.streams.get(id - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A segment from port 40000 to 80 with a 20 byte header.
+    fn segment(sequence: u32, flags: u8, data: &[u8]) -> TcpSegment {
+        let mut bytes = vec![0x9c, 0x40, 0, 80];
+        bytes.extend_from_slice(&sequence.to_be_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0x50, flags, 0xff, 0xff, 0, 0, 0, 0]);
+        bytes.extend_from_slice(data);
+        TcpSegment::new(&bytes).unwrap()
+    }
+
+    #[test]
+    fn reorders_and_trims_retransmissions() {
+        let mut direction = Direction::default();
+        assert_eq!(direction.push(&segment(u32::MAX, tcp::FLAG_SYN, b""), false), (0, Vec::new()));
+        assert_eq!(direction.push(&segment(3, 0, b"def"), false), (0, Vec::new()));
+        assert_eq!(direction.push(&segment(0, 0, b"abc"), false), (0, b"abcdef".to_vec()));
+        assert_eq!(direction.push(&segment(4, tcp::FLAG_FIN, b"efgh"), false), (0, b"gh".to_vec()));
+        assert!(direction.finished);
+        assert!(direction.pending.is_empty());
+    }
+
+    #[test]
+    fn skips_missing_segments() {
+        // The FIN leaves nothing to fill the gap
+        let mut direction = Direction::default();
+        direction.push(&segment(100, 0, b"abc"), false);
+        assert_eq!(direction.push(&segment(108, tcp::FLAG_FIN, b"xyz"), false), (5, b"xyz".to_vec()));
+        assert!(direction.finished);
+
+        // Nor does too much waiting behind it
+        let mut direction = Direction::default();
+        direction.push(&segment(100, 0, b"abc"), false);
+        let data = vec![0; MAXIMUM_PENDING];
+        assert_eq!(direction.push(&segment(110, 0, &data), false), (0, Vec::new()));
+        assert_eq!(direction.push(&segment(110 + MAXIMUM_PENDING as u32, 0, b"z"), false).0, 7);
+        assert_eq!(direction.buffered, 0);
+    }
+}
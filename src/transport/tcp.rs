@@ -1,17 +1,45 @@
 use std::fmt::{Display, Formatter};
-use prettytable::{format, table, row, cell};
+use prettytable::{format, table};
 use crate::util::*;
 
+pub const FLAG_FIN: u8 = 0x01;
+pub const FLAG_SYN: u8 = 0x02;
+pub const FLAG_RST: u8 = 0x04;
+pub const FLAG_PSH: u8 = 0x08;
+pub const FLAG_ACK: u8 = 0x10;
+pub const FLAG_URG: u8 = 0x20;
+
 pub struct TcpSegment {
     source_port: u16,
     destination_port: u16,
     sequence_number: u32,
     acknowledgment_number: u32,
     data_offset: u8,
+    flags: u8,
     checksum: u16,
+    data: Vec<u8>
 }
 
 impl TcpSegment {
+    /// Parses a segment, or returns `None` if it is shorter than its header or the data offset
+    /// points outside of it.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        let header_length = (*data.get(12)? >> 4) as usize * 4;
+        if header_length < 20 || header_length > data.len() {
+            return None;
+        }
+        Some(Self {
+            source_port: tou16(&data[0..2]),
+            destination_port: tou16(&data[2..4]),
+            sequence_number: tou32(&data[4..8]),
+            acknowledgment_number: tou32(&data[8..12]),
+            data_offset: data[12] >> 4,
+            flags: data[13],
+            checksum: tou16(&data[16..18]),
+            data: Vec::from(&data[header_length..])
+        })
+    }
+
     #[inline]
     pub fn source(&self) -> u16 {
         self.source_port
@@ -27,43 +55,51 @@ impl TcpSegment {
         self.sequence_number
     }
 
+    #[allow(dead_code)]
     #[inline]
     pub fn acknowledgment_number(&self) -> u32 {
         self.acknowledgment_number
     }
 
+    #[allow(dead_code)]
     #[inline]
     pub fn data_offset(&self) -> u8 {
         self.data_offset
     }
 
+    #[inline]
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+
+    #[allow(dead_code)]
     #[inline]
     pub fn checksum(&self) -> u16 {
         self.checksum
     }
 }
 
-impl From<&[u8]> for TcpSegment {
-    fn from(data: &[u8]) -> Self {
-        Self {
-            source_port: tou16(&data[0..2]),
-            destination_port: tou16(&data[2..4]),
-            sequence_number: tou32(&data[4..8]),
-            acknowledgment_number: tou32(&data[8..12]),
-            data_offset: data[12] >> 4,
-            checksum: tou16(&data[16..18]),
-        }
+impl DataContainer for TcpSegment {
+    fn data(&self) -> &[u8] {
+        self.data.as_slice()
     }
 }
 
 impl Display for TcpSegment {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let names = [(FLAG_SYN, "syn"), (FLAG_ACK, "ack"), (FLAG_PSH, "psh"), (FLAG_FIN, "fin"), (FLAG_RST, "rst"), (FLAG_URG, "urg")];
+        let flags: Vec<&str> = names.iter()
+            .filter(|(flag, _)| self.has_flag(*flag))
+            .map(|(_, name)| *name)
+            .collect();
+
         let mut table = table!(
             ["source_port", self.source_port],
             ["destination_port", self.destination_port],
             ["sequence_number", self.sequence_number],
             ["acknowledgment_number", self.acknowledgment_number],
             ["data_offset", self.data_offset],
+            ["flags", flags.join(", ")],
             ["checksum", self.checksum]
         );
 
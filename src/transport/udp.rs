@@ -1,5 +1,5 @@
 use std::fmt::{Display, Formatter};
-use prettytable::{format, table, row, cell};
+use prettytable::{format, table};
 use crate::util::*;
 
 #[derive(Debug)]
@@ -12,6 +12,20 @@ pub struct UDPSegment {
 }
 
 impl UDPSegment {
+    /// Parses a datagram, or returns `None` if it is shorter than its header.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+        Some(Self {
+            source_port: tou16(&data[0..2]),
+            destination_port: tou16(&data[2..4]),
+            length: tou16(&data[4..6]),
+            checksum: tou16(&data[6..8]),
+            data: Vec::from(&data[8..])
+        })
+    }
+
     #[inline]
     pub fn source(&self) -> u16 {
         self.source_port
@@ -22,29 +36,19 @@ impl UDPSegment {
         self.destination_port
     }
     
+    #[allow(dead_code)]
     #[inline]
     pub fn length(&self) -> u16 {
         self.length
     }
     
+    #[allow(dead_code)]
     #[inline]
     pub fn checksum(&self) -> u16 {
         self.checksum
     }
 }
 
-impl From<&[u8]> for UDPSegment {
-    fn from(data: &[u8]) -> Self {
-        Self {
-            source_port: tou16(&data[0..2]),
-            destination_port: tou16(&data[2..4]),
-            length: tou16(&data[4..6]),
-            checksum: tou16(&data[6..8]),
-            data: Vec::from(&data[64..])
-        }
-    }
-}
-
 impl DataContainer for UDPSegment {
     fn data(&self) -> &[u8] {
         self.data.as_slice()
@@ -63,7 +67,7 @@ impl Display for UDPSegment {
         table.set_format(*format::consts::FORMAT_CLEAN);
         table.get_format().padding(5, 5);
 
-        writeln!(f, "{}", table);
+        writeln!(f, "{}", table).unwrap();
         Ok(())
     }
 }
\ No newline at end of file
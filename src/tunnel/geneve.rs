@@ -0,0 +1,93 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table, row};
+use crate::network::EthernetType;
+use crate::util::*;
+
+pub const PORT: u16 = 6081;
+
+/// A GENEVE header with its variable length options.
+#[derive(Debug)]
+pub struct GeneveHeader {
+    version: u8,
+    oam: bool,
+    critical: bool,
+    protocol_type: EthernetType,
+    vni: u32,
+    options: Vec<GeneveOption>,
+    packet_data: Vec<u8>
+}
+
+#[derive(Debug)]
+pub struct GeneveOption {
+    class: u16,
+    option_type: u8,
+    value: Vec<u8>,
+}
+
+impl GeneveHeader {
+    /// Parses the header, or returns `None` if the data is shorter than its fixed 8 bytes.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+        // Option lengths count 4 byte words
+        let options_end = (8 + (data[0] & 0x3f) as usize * 4).min(data.len());
+        let mut options = Vec::new();
+        let mut rest = &data[8..options_end];
+        while rest.len() >= 4 {
+            let length = ((rest[3] & 0x1f) as usize * 4).min(rest.len() - 4);
+            options.push(GeneveOption {
+                class: tou16(&rest[0..2]),
+                option_type: rest[2],
+                value: Vec::from(&rest[4..4 + length]),
+            });
+            rest = &rest[4 + length..];
+        }
+
+        Some(Self {
+            version: data[0] >> 6,
+            oam: data[1] & 0x80 != 0,
+            critical: data[1] & 0x40 != 0,
+            protocol_type: tou16(&data[2..4]),
+            vni: tou32(&[0, data[4], data[5], data[6]]),
+            options,
+            packet_data: Vec::from(&data[options_end..])
+        })
+    }
+
+    #[inline]
+    pub fn protocol_type(&self) -> EthernetType {
+        self.protocol_type
+    }
+}
+
+impl DataContainer for GeneveHeader {
+    fn data(&self) -> &[u8] {
+        self.packet_data.as_slice()
+    }
+}
+
+impl Display for GeneveHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["version", self.version],
+            ["oam", self.oam],
+            ["critical", self.critical],
+            ["protocol_type", format!("{:04X}", self.protocol_type)],
+            ["vni", self.vni]
+        );
+        for option in self.options.iter() {
+            // The high bit of the type marks options that must be understood
+            table.add_row(row![
+                format!("option {:04X}/{:02X}", option.class, option.option_type),
+                format!("{:02X?}{}", option.value, if option.option_type & 0x80 != 0 { " (critical)" } else { "" })
+            ]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
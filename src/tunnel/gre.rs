@@ -0,0 +1,245 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table, row};
+use crate::network::{self, EthernetType};
+use crate::tunnel;
+use crate::util::*;
+
+pub const FLAG_CHECKSUM: u16 = 0x8000;
+pub const FLAG_ROUTING: u16 = 0x4000;
+pub const FLAG_KEY: u16 = 0x2000;
+pub const FLAG_SEQUENCE: u16 = 0x1000;
+pub const FLAG_ACKNOWLEDGMENT: u16 = 0x0080;
+
+/// A GRE header, either plain (RFC 2784/2890) or the enhanced version 1 used by PPTP.
+#[derive(Debug)]
+pub struct GrePacket {
+    flags: u16,
+    version: u8,
+    protocol_type: EthernetType,
+    checksum: Option<u16>,
+    key: Option<u32>,
+    sequence_number: Option<u32>,
+    acknowledgment_number: Option<u32>,
+    packet_data: Vec<u8>
+}
+
+impl GrePacket {
+    /// Parses the header, or returns `None` if the data ends before the optional fields its
+    /// flags announce.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        let flags = tou16(data.get(0..2)?);
+        let mut packet = Self {
+            flags,
+            version: (flags & 0b111) as u8,
+            protocol_type: tou16(data.get(2..4)?),
+            checksum: None,
+            key: None,
+            sequence_number: None,
+            acknowledgment_number: None,
+            packet_data: Vec::new()
+        };
+
+        let mut offset = 4;
+        // The checksum is followed by 16 reserved bits, also present when only routing is set
+        if flags & (FLAG_CHECKSUM | FLAG_ROUTING) != 0 {
+            packet.checksum = Some(tou16(data.get(4..8)?));
+            offset += 4;
+        }
+        if flags & FLAG_KEY != 0 {
+            packet.key = Some(tou32(data.get(offset..offset + 4)?));
+            offset += 4;
+        }
+        if flags & FLAG_SEQUENCE != 0 {
+            packet.sequence_number = Some(tou32(data.get(offset..offset + 4)?));
+            offset += 4;
+        }
+        if packet.version == 1 && flags & FLAG_ACKNOWLEDGMENT != 0 {
+            packet.acknowledgment_number = Some(tou32(data.get(offset..offset + 4)?));
+            offset += 4;
+        }
+        packet.packet_data = Vec::from(&data[offset..]);
+        Some(packet)
+    }
+
+    #[inline]
+    pub fn protocol_type(&self) -> EthernetType {
+        self.protocol_type
+    }
+
+    /// NVGRE uses the key as a 24 bit virtual subnet id and an 8 bit flow id.
+    pub fn nvgre(&self) -> Option<(u32, u8)> {
+        if self.protocol_type == tunnel::TRANSPARENT_ETHERNET_BRIDGING && self.version == 0 {
+            self.key.map(|key| (key >> 8, key as u8))
+        } else {
+            None
+        }
+    }
+}
+
+impl DataContainer for GrePacket {
+    fn data(&self) -> &[u8] {
+        self.packet_data.as_slice()
+    }
+}
+
+impl Display for GrePacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let protocol_type = match self.protocol_type {
+            network::IPV4 => "ipv4",
+            network::IPV6 => "ipv6",
+            network::MPLS => "mpls",
+            tunnel::TRANSPARENT_ETHERNET_BRIDGING => "ethernet",
+            tunnel::ERSPAN_TYPE_II => "erspan type ii",
+            tunnel::ERSPAN_TYPE_III => "erspan type iii",
+            tunnel::PPP => "ppp",
+            _ => "unidentified"
+        };
+
+        let mut table = table!(
+            ["flags", format!("{:016b}", self.flags)],
+            ["version", self.version],
+            ["protocol_type", format!("{:04X} ({})", self.protocol_type, protocol_type)]
+        );
+        if let Some(checksum) = self.checksum {
+            table.add_row(row!["checksum", format!("{:X}", checksum)]);
+        }
+        match (self.nvgre(), self.key) {
+            (Some((vsid, flow_id)), _) => {
+                table.add_row(row!["vsid", vsid]);
+                table.add_row(row!["flow_id", flow_id]);
+            }
+            (None, Some(key)) if self.version == 1 => {
+                // PPTP splits the key into payload length and call id
+                table.add_row(row!["payload_length", key >> 16]);
+                table.add_row(row!["call_id", key & 0xffff]);
+            }
+            (None, Some(key)) => {
+                table.add_row(row!["key", format!("{:08X}", key)]);
+            }
+            _ => ()
+        }
+        if let Some(sequence_number) = self.sequence_number {
+            table.add_row(row!["sequence_number", sequence_number]);
+        }
+        if let Some(acknowledgment_number) = self.acknowledgment_number {
+            table.add_row(row!["acknowledgment_number", acknowledgment_number]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+/// The ERSPAN header between GRE and the mirrored ethernet frame.
+#[derive(Debug)]
+pub struct ErspanHeader {
+    version: u8,
+    vlan: u16,
+    cos: u8,
+    truncated: bool,
+    session_id: u16,
+    index: Option<u32>,
+    timestamp: Option<u32>,
+    security_group_tag: Option<u16>,
+    hardware_id: Option<u8>,
+    direction: Option<bool>,
+    frame_data: Vec<u8>
+}
+
+impl ErspanHeader {
+    /// Decodes the header of either ERSPAN type, told apart by the GRE protocol type. Returns
+    /// `None` if the data is shorter than the fixed header of the type.
+    pub fn new(protocol_type: EthernetType, data: &[u8]) -> Option<Self> {
+        if data.len() < if protocol_type == tunnel::ERSPAN_TYPE_III { 12 } else { 8 } {
+            return None;
+        }
+        let mut header = Self {
+            version: data[0] >> 4,
+            vlan: tou16(&data[0..2]) & 0x0fff,
+            cos: data[2] >> 5,
+            truncated: data[2] & 0x04 != 0,
+            session_id: tou16(&data[2..4]) & 0x03ff,
+            index: None,
+            timestamp: None,
+            security_group_tag: None,
+            hardware_id: None,
+            direction: None,
+            frame_data: Vec::new()
+        };
+
+        let length = if protocol_type == tunnel::ERSPAN_TYPE_III {
+            header.timestamp = Some(tou32(&data[4..8]));
+            header.security_group_tag = Some(tou16(&data[8..10]));
+            header.hardware_id = Some((tou16(&data[10..12]) >> 4) as u8 & 0x3f);
+            header.direction = Some(data[11] & 0x08 != 0);
+            // The O flag announces an 8 byte platform specific subheader
+            if data[11] & 0x01 != 0 { 20 } else { 12 }
+        } else {
+            header.index = Some(tou32(&data[4..8]) & 0x000fffff);
+            8
+        };
+        header.frame_data = Vec::from(&data[length.min(data.len())..]);
+        Some(header)
+    }
+}
+
+impl DataContainer for ErspanHeader {
+    fn data(&self) -> &[u8] {
+        self.frame_data.as_slice()
+    }
+}
+
+impl Display for ErspanHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut table = table!(
+            ["version", self.version],
+            ["vlan", self.vlan],
+            ["cos", self.cos],
+            ["truncated", self.truncated],
+            ["session_id", self.session_id]
+        );
+        if let Some(index) = self.index {
+            table.add_row(row!["index", index]);
+        }
+        if let Some(timestamp) = self.timestamp {
+            table.add_row(row!["timestamp", timestamp]);
+        }
+        if let Some(security_group_tag) = self.security_group_tag {
+            table.add_row(row!["security_group_tag", security_group_tag]);
+        }
+        if let Some(hardware_id) = self.hardware_id {
+            table.add_row(row!["hardware_id", hardware_id]);
+        }
+        if let Some(direction) = self.direction {
+            table.add_row(row!["direction", if direction { "egress" } else { "ingress" }]);
+        }
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_headers_shorter_than_their_flags() {
+        // Key and sequence number present, NVGRE with virtual subnet 0x123456 and flow 0x78
+        let data = unhex("30006558123456780000000a").unwrap();
+        assert!(GrePacket::new(&data[..1]).is_none());
+        assert!(GrePacket::new(&data[..6]).is_none());
+        assert!(GrePacket::new(&data[..10]).is_none());
+
+        let packet = GrePacket::new(&data).unwrap();
+        assert_eq!(packet.nvgre(), Some((0x123456, 0x78)));
+        assert_eq!(packet.sequence_number, Some(10));
+        assert!(packet.data().is_empty());
+    }
+}
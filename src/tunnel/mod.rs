@@ -0,0 +1,12 @@
+pub mod gre;
+pub mod vxlan;
+pub mod geneve;
+
+
+use crate::network::EthernetType;
+
+/// Protocol type of tunnels carrying whole ethernet frames.
+pub const TRANSPARENT_ETHERNET_BRIDGING: EthernetType = 0x6558;
+pub const ERSPAN_TYPE_II: EthernetType = 0x88BE;
+pub const ERSPAN_TYPE_III: EthernetType = 0x22EB;
+pub const PPP: EthernetType = 0x880B;
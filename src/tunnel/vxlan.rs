@@ -0,0 +1,52 @@
+use std::fmt::{Display, Formatter};
+use prettytable::{format, table};
+use crate::util::*;
+
+pub const PORT: u16 = 4789;
+
+pub const FLAG_VNI: u8 = 0x08;
+
+/// A VXLAN header, followed by the encapsulated ethernet frame.
+#[derive(Debug)]
+pub struct VxlanHeader {
+    flags: u8,
+    vni: u32,
+    frame_data: Vec<u8>
+}
+
+impl VxlanHeader {
+    /// Parses the header, or returns `None` if the data is shorter than its 8 bytes.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+        Some(Self {
+            flags: data[0],
+            vni: tou32(&[0, data[4], data[5], data[6]]),
+            frame_data: Vec::from(&data[8..])
+        })
+    }
+}
+
+impl DataContainer for VxlanHeader {
+    fn data(&self) -> &[u8] {
+        self.frame_data.as_slice()
+    }
+}
+
+impl Display for VxlanHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let vni = if self.flags & FLAG_VNI != 0 { self.vni.to_string() } else { String::from("invalid") };
+
+        let mut table = table!(
+            ["flags", format!("{:08b}", self.flags)],
+            ["vni", vni]
+        );
+
+        table.set_format(*format::consts::FORMAT_CLEAN);
+        table.get_format().padding(5, 5);
+
+        writeln!(f, "{}", table).unwrap();
+        Ok(())
+    }
+}
@@ -22,3 +22,77 @@ pub fn tou64(data: &[u8]) -> u64 {
 pub fn tou128(data: &[u8]) -> u128 {
     (tou64(&data[0..8]) as u128) << 64 | (tou64(&data[8..16]) as u128)
 }
+
+/// The IEEE 802.3 CRC-32, as used in the frame check sequence of ethernet frames.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffffffff_u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// The Castagnoli CRC-32C, as used in the checksum of SCTP packets.
+pub fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = 0xffffffff_u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0x82f63b78 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Formats bytes as one continuous run of hex digits.
+pub fn hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+/// Parses a run of hex digits, with or without a leading 0x, back into bytes.
+pub fn unhex(text: &str) -> Option<Vec<u8>> {
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok()).collect()
+}
+
+/// Encodes bytes as standard base64, padded with = to a multiple of four characters.
+pub fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| bits | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+/// Formats seconds and nanoseconds since the Unix epoch as a UTC date and time.
+pub fn utc_time(seconds: i64, nanoseconds: u32) -> String {
+    // Days to a civil date, after Howard Hinnant's days_from_civil inverse
+    let (days, time) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:09} UTC",
+        year, month, day, time / 3600, time % 3600 / 60, time % 60, nanoseconds
+    )
+}